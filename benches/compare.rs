@@ -0,0 +1,134 @@
+//! Compares transforms through a `Replacinator` against straightforward allocating equivalents
+//!
+//! Run with `cargo bench --features disable_safety_checks`. Without that feature, every view of
+//! the remainder is validated as UTF-8, which dominates the timings.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use replacinator::{percent, transforms, unescape, Replacinator};
+
+fn json_text() -> String {
+    r#"Line one\nLine \"two\"\té中 and some plain text which needs no escapes \\ "#.repeat(2_000)
+}
+
+fn percent_text() -> String {
+    "path%2Fto%2Fa%20file%20with%20%C3%A9%20and%20plain-segments/".repeat(2_000)
+}
+
+fn whitespace_text() -> String {
+    "  lorem   ipsum\t\tdolor\n\n  sit amet,   consectetur \r\n adipiscing  ".repeat(2_000)
+}
+
+fn replace_text() -> String {
+    "the quick brown fox jumps over the lazy dog; ".repeat(2_000)
+}
+
+/// Decode a JSON string's contents into a new `String`
+fn unescape_json_alloc(text: &str) -> Option<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        out.push(match chars.next()? {
+            '"' => '"',
+            '\\' => '\\',
+            '/' => '/',
+            'b' => '\x08',
+            'f' => '\x0c',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'u' => {
+                let hex: String = chars.by_ref().take(4).collect();
+                char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?
+            }
+            _ => return None,
+        });
+    }
+    Some(out)
+}
+
+/// Decode `%XX` sequences into a new `String`
+fn percent_decode_alloc(text: &str) -> Option<String> {
+    let mut out = Vec::with_capacity(text.len());
+    let mut bytes = text.bytes();
+    while let Some(b) = bytes.next() {
+        if b != b'%' {
+            out.push(b);
+            continue;
+        }
+        let hex = [bytes.next()?, bytes.next()?];
+        out.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+    }
+    String::from_utf8(out).ok()
+}
+
+/// Benchmark `in_place` against `allocating`, each run over a fresh copy of `text`
+///
+/// Both must produce output of the same length, as a check that they do the same work.
+fn compare(
+    c: &mut Criterion,
+    name: &str,
+    text: String,
+    in_place: impl Fn(&mut Replacinator<'_>) -> usize,
+    allocating: impl Fn(&str) -> usize,
+) {
+    assert_eq!(
+        Replacinator::new_in(&mut text.clone(), &in_place),
+        allocating(&text),
+        "{} and its allocating equivalent disagree",
+        name
+    );
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    group.bench_function("replacinator", |b| {
+        b.iter_batched_ref(
+            || text.clone(),
+            |text| Replacinator::new_in(text, &in_place),
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("allocating", |b| b.iter(|| allocating(&text)));
+    group.finish();
+}
+
+fn bench(c: &mut Criterion) {
+    compare(
+        c,
+        "unescape_json",
+        json_text(),
+        |src| unescape::unescape_json(src).unwrap().len(),
+        |text| unescape_json_alloc(text).unwrap().len(),
+    );
+    compare(
+        c,
+        "percent_decode",
+        percent_text(),
+        |src| percent::decode_in_place(src).unwrap().len(),
+        |text| percent_decode_alloc(text).unwrap().len(),
+    );
+    compare(
+        c,
+        "collapse_whitespace",
+        whitespace_text(),
+        |src| transforms::collapse_whitespace(src, transforms::Whitespace::Ascii).len(),
+        |text| {
+            text.split_ascii_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .len()
+        },
+    );
+    compare(
+        c,
+        "replace_literal",
+        replace_text(),
+        |src| src.replace_literal("the", "a").unwrap().written_len(),
+        |text| text.replace("the", "a").len(),
+    );
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -0,0 +1,72 @@
+//! Measures the per-character cursor methods, which should be inlined into tight loops
+//!
+//! Run with `cargo bench --features disable_safety_checks --bench cursor`. `tests/codegen.rs`
+//! checks the inlining itself; these give the throughput of the loops which rely on it.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use replacinator::{ByteReplacinator, Replacinator};
+
+fn text() -> String {
+    "the-quick brown-fox jumps over-the lazy-dog; é中 ".repeat(2_000)
+}
+
+fn cursor(c: &mut Criterion) {
+    let text = text();
+    let mut group = c.benchmark_group("cursor");
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    group.bench_function("skip_char", |b| {
+        b.iter_batched_ref(
+            || text.clone(),
+            |s| Replacinator::new_in(s, |src| while src.skip_char().is_some() {}),
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("discard_spaces", |b| {
+        b.iter_batched_ref(
+            || text.clone(),
+            |s| {
+                Replacinator::new_in(s, |src| {
+                    while let Some(c) = src.peek() {
+                        if c == ' ' {
+                            src.discard_char();
+                        } else {
+                            src.skip_char();
+                        }
+                    }
+                })
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("read_write_char", |b| {
+        b.iter_batched_ref(
+            || text.clone(),
+            |s| {
+                Replacinator::new_in(s, |src| {
+                    while let Some(c) = src.read_char() {
+                        src.write_char(if c == '-' { '_' } else { c });
+                    }
+                })
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("read_write_byte", |b| {
+        b.iter_batched_ref(
+            || text.clone().into_bytes(),
+            |bytes| {
+                let mut src = ByteReplacinator::new(bytes);
+                while let Some(b) = src.read_byte() {
+                    if b != b' ' {
+                        src.write_byte(b);
+                    }
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, cursor);
+criterion_main!(benches);
@@ -0,0 +1,54 @@
+//! Small transforms built from the per-character cursor methods, for checking their codegen
+//!
+//! `tests/codegen.rs` builds this in release mode and checks that the cursor methods were
+//! inlined into these functions. It can also be inspected by hand with
+//! `cargo rustc --release --example codegen -- --emit=asm`.
+
+use std::hint::black_box;
+
+use replacinator::{ByteReplacinator, Replacinator};
+
+/// Remove the spaces from the remainder, one character at a time
+#[no_mangle]
+#[inline(never)]
+pub fn codegen_remove_spaces(src: &mut Replacinator<'_>) -> usize {
+    let mut removed = 0;
+    while let Some(c) = src.peek() {
+        if c == ' ' {
+            src.discard_char();
+            removed += 1;
+        } else {
+            src.skip_char();
+        }
+    }
+    removed
+}
+
+/// Replace each `-` in the remainder with `_`, one character at a time
+#[no_mangle]
+#[inline(never)]
+pub fn codegen_replace_dashes(src: &mut Replacinator<'_>) {
+    while let Some(c) = src.read_char() {
+        src.write_char(if c == '-' { '_' } else { c });
+    }
+}
+
+/// Remove the NUL bytes from the remainder, one byte at a time
+#[no_mangle]
+#[inline(never)]
+pub fn codegen_remove_nuls(src: &mut ByteReplacinator<'_>) {
+    while let Some(b) = src.read_byte() {
+        if b != 0 {
+            src.write_byte(b);
+        }
+    }
+}
+
+fn main() {
+    let mut text = String::from(black_box("a - b - c"));
+    let removed = Replacinator::new_in(&mut text, |src| codegen_remove_spaces(src));
+    Replacinator::new_in(&mut text, |src| codegen_replace_dashes(src));
+    let mut bytes = black_box(*b"a\0b");
+    codegen_remove_nuls(&mut ByteReplacinator::new(&mut bytes));
+    println!("{} {:?} {:?}", removed, text, bytes);
+}
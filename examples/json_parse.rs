@@ -1,11 +1,6 @@
 use std::{fs::File, io::Read};
 
-use replacinator::Replacinator;
-
-#[derive(Debug)]
-struct JsonArray<'a> {
-    values: Vec<&'a mut str>,
-}
+use replacinator::{json, segments::Segments, Replacinator};
 
 fn main() {
     let mut buf = String::new();
@@ -14,67 +9,15 @@ fn main() {
         .read_to_string(&mut buf)
         .unwrap();
 
-    let json_values = Replacinator::new_in(&mut buf, parse_json_array);
-    dbg!(&json_values);
-    println!("Buffer is now: {:?}", buf);
-}
-
-fn parse_json_array<'a>(src: &mut Replacinator<'a>) -> JsonArray<'a> {
-    let mut values = Vec::new();
-    assert_eq!(src.skip_char(), Some('['));
-    loop {
-        match src.skip_char() {
-            Some('"') => {
-                // Reset the replacinator to the beginning of this string
-                let _ = src.take_start();
-                loop {
-                    match src
-                        .read_char()
-                        .expect("JSON value should not end in the middle of a string")
-                    {
-                        '\\' => match src
-                            .read_char()
-                            .expect("JSON value should not end in the middle of an escape sequence")
-                        {
-                            '"' => src.write_char('"'),
-                            '\\' => src.write_char('\\'),
-                            '/' => src.write_char('/'),
-                            'b' => src.write_char('\x08'),
-                            'f' => src.write_char('\x0c'),
-                            'n' => src.write_char('\n'),
-                            'r' => src.write_char('\r'),
-                            't' => src.write_char('\t'),
-                            'u' => {
-                                let mut res = 0;
-                                for _ in 0..4 {
-                                    let v = src
-                                        .read_char()
-                                        .expect("String ended in unicode")
-                                        .to_digit(16)
-                                        .expect("Invalid hex digit in escape");
-                                    res = res * 16 + v;
-                                }
-                                src.write_char(
-                                    std::char::from_u32(res).expect("Valid character code"),
-                                )
-                            }
-                            other => panic!("Invalid escape {:?}", other),
-                        },
-                        '"' => {
-                            values.push(src.take_start());
-                            break;
-                        }
-                        other => src.write_char(other),
-                    }
-                }
-            }
-            Some(']') => break,
-            Some(' ') | Some('\n') | Some('\t') => (),
-            res => panic!(
-                r#"JSON array should be continued with '"' or ']', got {:?}"#,
-                res
-            ),
+    let values = Replacinator::new_in(&mut buf, |src| {
+        let mut values = Segments::<'_, 16>::new();
+        for value in json::parse_string_array(src).expect("JSON array should be valid") {
+            values
+                .push(value)
+                .expect("JSON array should have at most 16 values");
         }
-    }
-    JsonArray { values }
+        values
+    });
+    dbg!(&values);
+    println!("Buffer is now: {:?}", buf);
 }
@@ -0,0 +1,28 @@
+use replacinator::{
+    parse::{discard_while1, skip_while1, tag, token, Parser},
+    Replacinator,
+};
+
+fn main() {
+    let mut buf = String::from("name = replacinator; version=0.2 ; license = MIT");
+
+    let pairs = Replacinator::new_in(&mut buf, |src| {
+        let mut pairs = Vec::new();
+        let space = || discard_while1(char::is_whitespace).optional();
+        let word = || token(skip_while1(|c: char| c.is_alphanumeric() || c == '.'));
+        let mut pair = space()
+            .then(word())
+            .then(space())
+            .then(tag("="))
+            .then(space())
+            .then(word())
+            .then(space())
+            .then(tag(";").optional())
+            .map(|(((((((_, key), _), _), _), value), _), _)| (key, value));
+        while let Some(pair) = pair.parse(src) {
+            pairs.push(pair);
+        }
+        pairs
+    });
+    println!("{:?}", pairs);
+}
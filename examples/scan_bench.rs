@@ -0,0 +1,69 @@
+//! Compares the fast paths for scanning against per-character loops
+//!
+//! Run with `cargo run --release --features disable_safety_checks --example scan_bench`. Without
+//! that feature, every view of the remainder is validated as UTF-8, which dominates the timings.
+
+use std::time::{Duration, Instant};
+
+use replacinator::Replacinator;
+
+fn time(name: &str, text: &str, mut f: impl FnMut(&mut Replacinator<'_>) -> usize) {
+    let mut total = Duration::ZERO;
+    let mut scanned = 0;
+    for _ in 0..20 {
+        let mut text = text.to_string();
+        let start = Instant::now();
+        Replacinator::new_in(&mut text, |src| {
+            while src.peek().is_some() {
+                scanned += f(src);
+                src.skip_char();
+            }
+        });
+        total += start.elapsed();
+    }
+    println!(
+        "{:<32} {:>10.2?} ({} bytes scanned)",
+        name,
+        total / 20,
+        scanned / 20
+    );
+}
+
+fn main() {
+    let mut indented = String::new();
+    for i in 0..100_000 {
+        indented.push_str(&" ".repeat(4 * (i % 8)));
+        indented.push_str("key: value\n");
+    }
+    let mut words = String::new();
+    for _ in 0..200_000 {
+        words.push_str("lorem ipsum dolor sit amet ");
+    }
+
+    println!("Indentation, {} bytes", indented.len());
+    time("skip_while(is_ascii_whitespace)", &indented, |src| {
+        src.skip_while(|c| c.is_ascii_whitespace())
+    });
+    time("skip_ascii_whitespace", &indented, |src| {
+        src.skip_ascii_whitespace()
+    });
+    time("manual skip_char loop", &indented, |src| {
+        let mut len = 0;
+        while src.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            len += src.skip_char().map_or(0, char::len_utf8);
+        }
+        len
+    });
+
+    println!("Words, {} bytes", words.len());
+    time("skip_while(is_alphabetic)", &words, |src| {
+        src.skip_while(char::is_alphabetic)
+    });
+    time("manual skip_char loop", &words, |src| {
+        let mut len = 0;
+        while src.peek().is_some_and(char::is_alphabetic) {
+            len += src.skip_char().map_or(0, char::len_utf8);
+        }
+        len
+    });
+}
@@ -0,0 +1,66 @@
+//! Applies a sequence of operations decoded from the input to a `Replacinator`, and checks that
+//! the string is valid UTF-8 afterwards, even if an operation panicked
+//!
+//! Run with `cargo fuzz run operations` from the repository root.
+#![no_main]
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use libfuzzer_sys::fuzz_target;
+use replacinator::Replacinator;
+
+fuzz_target!(|data: &[u8]| {
+    let (&split, data) = match data.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    let (text, ops) = data.split_at(usize::from(split).min(data.len()));
+    let mut value = String::from_utf8_lossy(text).into_owned();
+    let len = value.len();
+
+    let _ = catch_unwind(AssertUnwindSafe(|| {
+        Replacinator::new_in(&mut value, |src| {
+            let mut checkpoint = None;
+            for &op in ops {
+                let arg = char::from(op & 0x7F);
+                match op >> 4 {
+                    0 => {
+                        src.skip_char();
+                    }
+                    1 => {
+                        src.read_char();
+                    }
+                    2 => src.write_char(arg),
+                    3 => src.write_str("\u{e9}"),
+                    4 => {
+                        src.skip_while(|c| c != arg);
+                    }
+                    5 => {
+                        src.discard_while(|c| c == arg);
+                    }
+                    6 => {
+                        src.map_chars(|c| if c == arg { None } else { Some('\u{20AC}') });
+                    }
+                    7 => src.synchronise(),
+                    8 => checkpoint = Some(src.checkpoint()),
+                    9 => {
+                        if let Some(checkpoint) = checkpoint {
+                            let _ = src.rewind(checkpoint);
+                        }
+                    }
+                    10 => {
+                        src.take_start();
+                    }
+                    11 => {
+                        let _ = src.advance_read_to(src.read_offset() + usize::from(op & 0xF));
+                    }
+                    _ => panic!("A transform panicked"),
+                }
+                assert!(src.write_offset() <= src.read_offset());
+                assert_eq!(src.read_offset() + src.remainder().len(), len);
+            }
+        })
+    }));
+    assert!(std::str::from_utf8(value.as_bytes()).is_ok());
+    assert_eq!(value.len(), len);
+});
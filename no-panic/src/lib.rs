@@ -0,0 +1,53 @@
+//! A `no_std` library which uses the non-panicking API of `replacinator`
+//!
+//! The panic handler calls a function which is never defined, so this only links if every
+//! panicking path has been removed by the optimiser. Build it in release mode from this
+//! directory, so that the linker flags in `.cargo/config.toml` are used:
+//!
+//! ```text
+//! cd no-panic && cargo build --release
+//! ```
+#![no_std]
+
+use replacinator::Replacinator;
+
+extern "C" {
+    /// Deliberately undefined, so that any reachable panic fails to link
+    fn replacinator_can_panic() -> !;
+}
+
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo<'_>) -> ! {
+    // Safety: This is never called, as otherwise linking fails
+    unsafe { replacinator_can_panic() }
+}
+
+/// Remove the spaces from the UTF-8 text at `ptr`, returning its new length, or `usize::MAX` if
+/// the text is not valid UTF-8 or can't be rewritten
+///
+/// # Safety
+/// `ptr` must be valid for reads and writes of `len` bytes
+#[no_mangle]
+pub unsafe extern "C" fn remove_spaces(ptr: *mut u8, len: usize) -> usize {
+    let bytes = core::slice::from_raw_parts_mut(ptr, len);
+    let result = Replacinator::from_utf8_in(bytes, |src| {
+        loop {
+            match src.try_read_char() {
+                Ok(Some(' ')) => {}
+                Ok(Some(c)) => {
+                    if src.try_write_char(c).is_err() {
+                        return usize::MAX;
+                    }
+                }
+                Ok(None) => break,
+                Err(_) => return usize::MAX,
+            }
+        }
+        let written = src.written_len();
+        match src.try_synchronise() {
+            Ok(()) => written,
+            Err(_) => usize::MAX,
+        }
+    });
+    result.unwrap_or(usize::MAX)
+}
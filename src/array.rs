@@ -0,0 +1,142 @@
+//! Rewriting a string stored in a fixed-capacity buffer
+
+use core::{convert::Infallible, fmt};
+
+use crate::{
+    raw::{unchecked_from_utf8, unchecked_from_utf8_mut},
+    Replacinator,
+};
+
+/// A string stored in a fixed-capacity buffer, which can be rewritten using a [`Replacinator`]
+///
+/// This allows text to be copied onto the stack and transformed in place, without an allocator,
+/// and without producing a `&mut str` from a byte buffer.
+#[derive(Clone)]
+pub struct ArrayReplacinator<const N: usize> {
+    buf: [u8; N],
+    /// The length of the string at the start of `buf`, which is always valid UTF-8
+    len: usize,
+}
+
+impl<const N: usize> ArrayReplacinator<N> {
+    /// Create an empty string
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+        }
+    }
+
+    /// Create a copy of `text`, or `None` if it is longer than `N` bytes
+    pub fn copy_from(text: &str) -> Option<Self> {
+        let mut it = Self::new();
+        it.buf
+            .get_mut(..text.len())?
+            .copy_from_slice(text.as_bytes());
+        it.len = text.len();
+        Some(it)
+    }
+
+    /// The most bytes which can be stored
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// The length of the string in bytes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the string is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// View the string
+    pub fn as_str(&self) -> &str {
+        // Safety: The first `len` bytes are always valid UTF-8
+        unsafe { unchecked_from_utf8(&self.buf[..self.len]) }
+    }
+
+    /// View the string mutably
+    pub fn as_mut_str(&mut self) -> &mut str {
+        // Safety: The first `len` bytes are always valid UTF-8, and stay so as this is a `&mut str`
+        unsafe { unchecked_from_utf8_mut(&mut self.buf[..self.len]) }
+    }
+
+    /// Operate on the string using a [`Replacinator`] within the given function
+    ///
+    /// As with [`Replacinator::new_in`], the string is valid UTF-8 again afterwards, and the same
+    /// length. Use [`ArrayReplacinator::transform`] to keep only the result of a transform.
+    pub fn with<'s, R>(&'s mut self, with: impl FnMut(&mut Replacinator<'s>) -> R) -> R {
+        Replacinator::new_in(self.as_mut_str(), with)
+    }
+
+    /// Apply `transform` to the string, then shrink the string to the result of `transform`
+    ///
+    /// # Panics
+    /// If `transform` returns a string which isn't part of this string
+    pub fn transform<F>(&mut self, transform: F)
+    where
+        F: for<'a> FnOnce(&mut Replacinator<'a>) -> &'a mut str,
+    {
+        match self.try_transform(|src| Ok::<_, Infallible>(transform(src))) {
+            Ok(()) => {}
+            Err(never) => match never {},
+        }
+    }
+
+    /// Apply the fallible `transform` to the string, then shrink the string to its result if it
+    /// succeeded
+    ///
+    /// If `transform` fails, the string is left valid, but its contents are unspecified.
+    ///
+    /// # Panics
+    /// If `transform` returns a string which isn't part of this string
+    pub fn try_transform<F, E>(&mut self, transform: F) -> Result<(), E>
+    where
+        F: for<'a> FnOnce(&mut Replacinator<'a>) -> Result<&'a mut str, E>,
+    {
+        let (start, end) = transform_range(self.as_mut_str(), transform)?;
+        // The result is a `str`, so the bytes moved to the start are valid UTF-8
+        self.buf.copy_within(start..end, 0);
+        self.len = end - start;
+        Ok(())
+    }
+}
+
+impl<const N: usize> Default for ArrayReplacinator<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> fmt::Debug for ArrayReplacinator<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ArrayReplacinator")
+            .field(&self.as_str())
+            .finish()
+    }
+}
+
+/// Apply `transform` to `text`, returning the range of `text` which holds its result
+///
+/// # Panics
+/// If `transform` returns a string which isn't part of `text`
+pub(crate) fn transform_range<F, E>(text: &mut str, transform: F) -> Result<(usize, usize), E>
+where
+    F: for<'a> FnOnce(&mut Replacinator<'a>) -> Result<&'a mut str, E>,
+{
+    let contents = text.as_ptr() as usize;
+    let len = text.len();
+    let mut transform = Some(transform);
+    Replacinator::new_in(text, |src| {
+        let transform = transform.take().expect("`new_in` calls its function once");
+        let result = transform(src)?;
+        let start = (result.as_ptr() as usize).checked_sub(contents);
+        match start.map(|start| (start, start + result.len())) {
+            Some((start, end)) if end <= len => Ok((start, end)),
+            _ => panic!("The transform must return a part of the string"),
+        }
+    })
+}
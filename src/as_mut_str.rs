@@ -0,0 +1,45 @@
+//! Borrowing string buffers of other types as the `&mut str` which a [`Replacinator`] works on
+//!
+//! [`Replacinator`]: crate::Replacinator
+
+/// A mutable borrow of a string buffer, which can be worked on in place
+///
+/// This is accepted by [`Replacinator::new_in`], the other functions which create a
+/// [`Replacinator`] for the duration of a closure, and the helpers in
+/// [`owned`](crate::owned), so that a buffer doesn't need to be converted to a `&mut str` at
+/// each call site. It is implemented for a mutable borrow of any type which implements
+/// [`AsMut<str>`], which includes `str`, `String` and `Box<str>`, without needing the `alloc`
+/// feature.
+///
+/// The simplest way to accept your own string type is to implement [`AsMut<str>`] for it. A type
+/// which doesn't can instead implement this for a mutable borrow of itself.
+///
+/// A string type from a crate which doesn't depend on this one, such as one allocated in an
+/// arena, can't implement either trait outside of its own crate. Instead, wrap a mutable borrow
+/// of it in a type of your own, and implement this for the wrapper itself, which doesn't
+/// overlap with the implementation for borrows:
+///
+/// ```ignore
+/// struct Bump<'a, 'b>(&'a mut bumpalo::collections::String<'b>);
+///
+/// impl<'a> AsMutStr<'a> for Bump<'a, '_> {
+///     fn into_mut_str(self) -> &'a mut str {
+///         self.0.as_mut_str()
+///     }
+/// }
+///
+/// Replacinator::new_in(Bump(&mut text), |src| ...);
+/// ```
+///
+/// [`Replacinator`]: crate::Replacinator
+/// [`Replacinator::new_in`]: crate::Replacinator::new_in
+pub trait AsMutStr<'a> {
+    /// Borrow the buffer as a string slice for all of `'a`
+    fn into_mut_str(self) -> &'a mut str;
+}
+
+impl<'a, T: AsMut<str> + ?Sized> AsMutStr<'a> for &'a mut T {
+    fn into_mut_str(self) -> &'a mut str {
+        self.as_mut()
+    }
+}
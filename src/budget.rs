@@ -0,0 +1,145 @@
+//! Limiting the amount of text written to a [`Replacinator`]
+//!
+//! A [`Replacinator`] can always write as much text as it has read. For formats with a hard limit
+//! on their length, such as SMS messages, a [`Budget`] limits the output independently of how much
+//! input has been consumed.
+
+use core::fmt;
+
+use crate::{NoObserver, Observer, Position, Replacinator};
+
+/// An error encountered whilst writing within a [`Budget`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    position: Position,
+}
+
+impl Error {
+    /// The kind of error which occurred
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The position in the original string at which the error was detected
+    pub fn position(&self) -> Position {
+        self.position
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::Exhausted => write!(f, "output budget exhausted"),
+        }?;
+        write!(f, " at byte {}", self.position.byte())
+    }
+}
+
+/// The kinds of [`Error`] which can occur whilst writing within a [`Budget`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// Writing the text would have exceeded the budget
+    Exhausted,
+}
+
+/// A view of a [`Replacinator`] which writes at most a fixed number of bytes
+///
+/// The budget counts everything added to the first section after the [`Budget`] was created,
+/// including text written through [`Budget::replacinator`]. Writes which would exceed it fail
+/// without writing anything, so a character is never split.
+pub struct Budget<'r, 'a, O = NoObserver> {
+    src: &'r mut Replacinator<'a, O>,
+    /// The write offset when this budget was created
+    start: usize,
+    limit: usize,
+}
+
+impl<'r, 'a, O: Observer> Budget<'r, 'a, O> {
+    /// Create a budget which allows `limit` bytes to be written to `src`
+    pub fn new(src: &'r mut Replacinator<'a, O>, limit: usize) -> Self {
+        let start = src.write_offset();
+        Self { src, start, limit }
+    }
+
+    /// The number of bytes written since this budget was created
+    pub fn written(&self) -> usize {
+        self.src.write_offset() - self.start
+    }
+
+    /// The number of bytes which can still be written
+    ///
+    /// This is zero if more than the budget was written through [`Budget::replacinator`].
+    pub fn remaining(&self) -> usize {
+        self.limit.saturating_sub(self.written())
+    }
+
+    /// Write `c` to the end of the first section, if it fits within the budget
+    ///
+    /// # Panics
+    /// Like [`Replacinator::write_char`], if there is no room before the read position
+    pub fn write_char(&mut self, c: char) -> Result<(), Error> {
+        self.reserve(c.len_utf8())?;
+        self.src.write_char(c);
+        Ok(())
+    }
+
+    /// Write `s` to the end of the first section, if it fits within the budget
+    ///
+    /// # Panics
+    /// Like [`Replacinator::write_str`], if there is no room before the read position
+    pub fn write_str(&mut self, s: &str) -> Result<(), Error> {
+        self.reserve(s.len())?;
+        self.src.write_str(s);
+        Ok(())
+    }
+
+    /// Read and write back the next character, if it fits within the budget
+    ///
+    /// If an error is returned, the character has not been read.
+    pub fn skip_char(&mut self) -> Result<Option<char>, Error> {
+        match self.src.peek() {
+            Some(c) => self.reserve(c.len_utf8())?,
+            None => return Ok(None),
+        }
+        Ok(self.src.skip_char())
+    }
+
+    /// Read and write back `expected` if the remainder starts with it and it fits within the budget
+    ///
+    /// Returns whether `expected` was skipped. If an error is returned, nothing has been read.
+    pub fn skip_str(&mut self, expected: &str) -> Result<bool, Error> {
+        if !self.src.starts_with(expected) {
+            return Ok(false);
+        }
+        self.reserve(expected.len())?;
+        Ok(self.src.skip_str(expected))
+    }
+
+    /// The [`Replacinator`] being written to, for reading and discarding text
+    pub fn replacinator(&mut self) -> &mut Replacinator<'a, O> {
+        self.src
+    }
+
+    /// The [`Replacinator`] being written to
+    pub fn into_inner(self) -> &'r mut Replacinator<'a, O> {
+        self.src
+    }
+
+    fn reserve(&self, len: usize) -> Result<(), Error> {
+        if len <= self.remaining() {
+            Ok(())
+        } else {
+            Err(error(self.src, ErrorKind::Exhausted))
+        }
+    }
+}
+
+#[cold]
+fn error<O: Observer>(src: &Replacinator<'_, O>, kind: ErrorKind) -> Error {
+    Error {
+        kind,
+        position: src.position(),
+    }
+}
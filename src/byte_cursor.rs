@@ -0,0 +1,98 @@
+//! Moving the cursors by byte counts and writing raw bytes, for callers which search the string
+//! as bytes
+
+use core::fmt;
+
+use crate::{raw::from_utf8_checked_if, Observer, Replacinator, WriteError};
+
+/// The error returned by [`Replacinator::advance_read_bytes`], [`Replacinator::read_exact_bytes`]
+/// and [`Replacinator::write_bytes`] when nothing could be done
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ByteError {
+    /// The remainder is shorter than the number of bytes given
+    PastEnd,
+    /// The bytes would end within a character, which continues to this offset in the string
+    NotCharBoundary(usize),
+    /// The bytes to write aren't valid UTF-8, from this index into them
+    InvalidUtf8(usize),
+    /// The bytes are valid UTF-8, but couldn't be written
+    Write(WriteError),
+}
+
+impl From<WriteError> for ByteError {
+    fn from(e: WriteError) -> Self {
+        ByteError::Write(e)
+    }
+}
+
+impl fmt::Display for ByteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ByteError::PastEnd => write!(f, "there are not enough bytes in the remainder"),
+            ByteError::NotCharBoundary(offset) => {
+                write!(f, "a character would be split, ending at byte {}", offset)
+            }
+            ByteError::InvalidUtf8(index) => {
+                write!(f, "the bytes are not valid UTF-8 at byte {}", index)
+            }
+            ByteError::Write(e) => e.fmt(f),
+        }
+    }
+}
+
+impl<'a, O: Observer> Replacinator<'a, O> {
+    /// Read and write back the next `n` bytes of the remainder
+    ///
+    /// This is [`Replacinator::advance_read_to`] with a length rather than an offset, for
+    /// callers driving the [`Replacinator`] by byte-oriented searches of the remainder. Nothing
+    /// is read if the remainder is shorter than `n`, or if `n` bytes would end within a
+    /// character.
+    pub fn advance_read_bytes(&mut self, n: usize) -> Result<(), ByteError> {
+        self.check_byte_len(n)?;
+        self.skip_bytes(n);
+        Ok(())
+    }
+
+    /// Read the next `n` bytes of the remainder without writing them, returning their text
+    ///
+    /// This is for fields whose length is known in advance, such as in length-prefixed protocols.
+    /// Nothing is read if the remainder is shorter than `n`, or if `n` bytes would end within a
+    /// character.
+    pub fn read_exact_bytes(&mut self, n: usize) -> Result<&str, ByteError> {
+        self.check_byte_len(n)?;
+        let start = self.inner.read_position;
+        self.advance_read(n);
+        self.check_invariants();
+        // Safety: These bytes were part of the remainder, and nothing can be written over them
+        // whilst the returned borrow of `self` is live
+        Ok(unsafe { from_utf8_checked_if(self.checks, &self.inner.contents[start..start + n]) })
+    }
+
+    /// Check that the remainder has at least `n` bytes, and that they end on a character boundary
+    fn check_byte_len(&self, n: usize) -> Result<(), ByteError> {
+        let remainder = self.remainder();
+        if n > remainder.len() {
+            return Err(ByteError::PastEnd);
+        }
+        if !remainder.is_char_boundary(n) {
+            let end = (n..remainder.len())
+                .find(|&end| remainder.is_char_boundary(end))
+                .unwrap_or(remainder.len());
+            return Err(ByteError::NotCharBoundary(self.read_offset() + end));
+        }
+        Ok(())
+    }
+
+    /// Write `bytes` to the end of the first section, if they are valid UTF-8
+    ///
+    /// As `bytes` must be valid UTF-8 on their own, they can't split a character, so the first
+    /// section stays valid. Nothing is written if `bytes` are invalid, or if
+    /// [`Replacinator::try_write_str`] would fail.
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), ByteError> {
+        let text =
+            core::str::from_utf8(bytes).map_err(|e| ByteError::InvalidUtf8(e.valid_up_to()))?;
+        self.try_write_str(text)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,335 @@
+//! The byte-level cursors which underlie [`Replacinator`](crate::Replacinator)
+
+use core::{mem::replace, ops::Range};
+
+use crate::Position;
+
+/// A partially updated byte slice
+///
+/// This has the same three sections as a [`Replacinator`](crate::Replacinator), but places no
+/// requirements on their contents. As there is nothing to restore, this can be created safely.
+pub struct ByteReplacinator<'a> {
+    pub(crate) contents: &'a mut [u8],
+    pub(crate) read_position: usize,
+    pub(crate) write_position: usize,
+    /// The furthest point which has been written to since the start of `contents`
+    ///
+    /// Bytes after this point are unchanged from when they were read, which allows
+    /// [`Replacinator::rewind`](crate::Replacinator::rewind) to re-read them. Skipping bytes
+    /// whilst the second section is empty leaves them where they are, so doesn't count as writing
+    /// to them.
+    pub(crate) write_high_water: usize,
+    pub(crate) poisoned: bool,
+    /// The position of the read position in the original slice
+    pub(crate) position: Position,
+    /// The addresses of the first byte which has been changed and of the byte after the last,
+    /// which stay correct as `contents` is split
+    pub(crate) dirty: Option<(usize, usize)>,
+}
+
+impl<'a> ByteReplacinator<'a> {
+    /// Create a new [`ByteReplacinator`] for the given byte slice
+    pub fn new(from: &'a mut [u8]) -> Self {
+        Self {
+            contents: from,
+            read_position: 0,
+            write_position: 0,
+            write_high_water: 0,
+            poisoned: false,
+            position: Position::default(),
+            dirty: None,
+        }
+    }
+
+    /// View the bytes of the 'third section'
+    #[inline]
+    pub fn remainder_bytes(&self) -> &[u8] {
+        &self.contents[self.read_position..]
+    }
+
+    /// View the bytes of the 'third section' mutably
+    #[inline]
+    pub fn remainder_bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.contents[self.read_position..]
+    }
+
+    /// View the bytes of the first section
+    #[inline]
+    pub fn start_bytes(&self) -> &[u8] {
+        &self.contents[..self.write_position]
+    }
+
+    /// View the bytes of the first section mutably
+    #[inline]
+    pub fn start_bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.contents[..self.write_position]
+    }
+
+    /// Take the first section as a mutable view
+    ///
+    /// The second section is discarded, without its contents being changed.
+    pub fn take_start(&mut self) -> &'a mut [u8] {
+        let inner = &mut [];
+        // Juggle the lifetimes, to avoid unneeded unsafe code
+        let contents = replace(&mut self.contents, inner);
+        let (start, end) = contents.split_at_mut(self.read_position);
+        self.contents = end;
+
+        let start = &mut start[..self.write_position];
+        self.read_position = 0;
+        self.write_position = 0;
+        self.write_high_water = 0;
+        self.check_invariants();
+        start
+    }
+
+    /// Take the third section as a mutable view, leaving nothing left to read
+    pub fn take_remainder(&mut self) -> &'a mut [u8] {
+        let inner = &mut [];
+        let contents = replace(&mut self.contents, inner);
+        let (start, end) = contents.split_at_mut(self.read_position);
+        self.contents = start;
+        self.check_invariants();
+        end
+    }
+
+    #[inline]
+    pub fn peek_byte(&self) -> Option<u8> {
+        self.remainder_bytes().first().copied()
+    }
+
+    #[inline]
+    pub fn read_byte(&mut self) -> Option<u8> {
+        let value = self.peek_byte();
+        if value.is_some() {
+            self.advance_read(1);
+        }
+        self.check_invariants();
+        value
+    }
+
+    #[inline]
+    pub fn write_byte(&mut self, b: u8) {
+        self.write_bytes(&[b]);
+    }
+
+    /// Write `bytes` to the end of the first section
+    ///
+    /// # Panics
+    /// If there is not enough space between the write position and the read position
+    #[inline(always)]
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        assert!(
+            bytes.len() <= self.read_position - self.write_position,
+            "Not enough room to write {} bytes before the read position",
+            bytes.len()
+        );
+        let end = self.write_position + bytes.len();
+        let region = &mut self.invalid_region()[..bytes.len()];
+        if let Some(changed) = differing(region, bytes.iter().copied()) {
+            region.copy_from_slice(bytes);
+            self.mark_dirty(self.write_position + changed.start..self.write_position + changed.end);
+        }
+        // The invariants were checked by `invalid_region`, and the assertion keeps the write
+        // position at or before the read position, so they still hold
+        self.write_position = end;
+        self.write_high_water = self.write_high_water.max(self.write_position);
+    }
+
+    #[inline]
+    pub fn skip_byte(&mut self) -> Option<u8> {
+        let value = self.read_byte();
+        if let Some(b) = value {
+            self.write_byte(b)
+        }
+        value
+    }
+
+    /// Move the next `len` bytes of the remainder to the end of the first section
+    ///
+    /// # Panics
+    /// If fewer than `len` bytes remain
+    #[inline]
+    pub fn skip_bytes(&mut self, len: usize) {
+        let read_start = self.read_position;
+        let read_end = read_start + len;
+        assert!(read_end <= self.contents.len());
+        // The position is advanced first, as the copy can overwrite the bytes which were read
+        self.advance_read(len);
+        if self.write_position != read_start {
+            let write = self.write_position;
+            let source = self.contents[read_start..read_end].iter().copied();
+            if let Some(changed) = differing(&self.contents[write..write + len], source) {
+                self.contents.copy_within(read_start..read_end, write);
+                self.mark_dirty(write + changed.start..write + changed.end);
+            }
+            self.write_high_water = self.write_high_water.max(write + len);
+        }
+        self.write_position += len;
+        self.check_invariants();
+    }
+
+    /// Read the next `len` bytes without writing them
+    ///
+    /// # Panics
+    /// If fewer than `len` bytes remain
+    #[inline]
+    pub fn discard_bytes(&mut self, len: usize) {
+        assert!(len <= self.remainder_bytes().len());
+        self.advance_read(len);
+        self.check_invariants();
+    }
+
+    /// XOR each byte of the remainder with `key`, which is repeated from the read position
+    ///
+    /// Applying the same key again restores the original bytes. This doesn't move the read or
+    /// write positions. An empty key leaves the remainder unchanged.
+    pub fn xor_remaining(&mut self, key: &[u8]) {
+        if key.is_empty() {
+            return;
+        }
+        let keys = key.iter().cycle();
+        let remainder = self.remainder_bytes();
+        let xored = remainder
+            .iter()
+            .enumerate()
+            .map(|(i, b)| b ^ key[i % key.len()]);
+        if let Some(changed) = differing(remainder, xored) {
+            for (b, k) in self.remainder_bytes_mut().iter_mut().zip(keys) {
+                *b ^= k;
+            }
+            let start = self.read_position;
+            self.mark_dirty(start + changed.start..start + changed.end);
+        }
+    }
+
+    /// Add `delta` to each byte of the remainder, wrapping around at 256
+    ///
+    /// Adding `delta.wrapping_neg()` restores the original bytes. This doesn't move the read or
+    /// write positions.
+    pub fn wrapping_add_remaining(&mut self, delta: u8) {
+        if delta == 0 {
+            return;
+        }
+        for b in self.remainder_bytes_mut() {
+            *b = b.wrapping_add(delta);
+        }
+        self.mark_dirty(self.read_position..self.contents.len());
+    }
+
+    /// The position in the original slice of the next byte to be read
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// Move the read position forward by `len` bytes, keeping track of the [`Position`]
+    #[inline]
+    pub(crate) fn advance_read(&mut self, len: usize) {
+        let end = self.read_position + len;
+        // If `len` is out of bounds, the invariants no longer hold, which the caller checks
+        self.position.advance(
+            self.contents
+                .get(self.read_position..end)
+                .unwrap_or_default(),
+        );
+        self.read_position = end;
+    }
+
+    /// Fill the second section with `byte`, and move the write position to the read position
+    pub fn synchronise_with(&mut self, byte: u8) {
+        self.fill_range(self.write_position..self.read_position, byte);
+        self.write_position = self.read_position;
+        self.write_high_water = self.write_high_water.max(self.write_position);
+        self.check_invariants();
+    }
+
+    /// Fill `range` of the contents with `byte`, recording any bytes which changed
+    pub(crate) fn fill_range(&mut self, range: Range<usize>, byte: u8) {
+        let region = &mut self.contents[range.start..range.end];
+        if let Some(changed) = differing_from(region, byte) {
+            region.fill(byte);
+            self.mark_dirty(range.start + changed.start..range.start + changed.end);
+        }
+    }
+
+    /// Record that the bytes in `range` of the contents have been changed
+    #[inline]
+    pub(crate) fn mark_dirty(&mut self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+        let base = self.contents.as_ptr() as usize;
+        let (start, end) = (base + range.start, base + range.end);
+        self.dirty = Some(match self.dirty {
+            Some((first, last)) => (first.min(start), last.max(end)),
+            None => (start, end),
+        });
+    }
+
+    #[inline]
+    pub(crate) fn invalid_region(&mut self) -> &mut [u8] {
+        self.check_invariants();
+        &mut self.contents[self.write_position..self.read_position]
+    }
+
+    /// Whether an internal invariant was found to be violated
+    ///
+    /// A poisoned [`ByteReplacinator`] panics when used, rather than risking operating on
+    /// inconsistent positions. Its owner fills the whole slice when returning it to valid UTF-8.
+    #[inline]
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Whether the internal invariants are correct
+    #[inline]
+    pub(crate) fn invariants_hold(&self) -> bool {
+        self.write_position <= self.read_position
+            && self.write_high_water <= self.read_position
+            && self.read_position <= self.contents.len()
+    }
+
+    /// Checks internal invariants are correct, poisoning `self` if they are not
+    ///
+    /// # Panics
+    /// If `self` is poisoned
+    #[inline]
+    pub(crate) fn check_invariants(&mut self) {
+        if self.poisoned || !self.invariants_hold() {
+            self.poison();
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn poison(&mut self) -> ! {
+        self.poisoned = true;
+        panic!("An internal invariant of `replacinator` was violated, so the replacinator was poisoned");
+    }
+}
+
+/// The smallest range of indices at which `old` differs from `new`, comparing only as many bytes
+/// as the shorter of the two
+///
+/// This is on the path of every write, so rather than comparing every byte, it stops at the
+/// first difference from each end. Text which has been moved usually differs from what it
+/// overwrites almost immediately, so this is much cheaper than the copy itself.
+pub(crate) fn differing<I>(old: &[u8], new: I) -> Option<Range<usize>>
+where
+    I: IntoIterator<Item = u8>,
+    I::IntoIter: DoubleEndedIterator + ExactSizeIterator,
+{
+    let mut pairs = old.iter().zip(new).enumerate();
+    let (start, _) = pairs.find(|&(_, (&old, new))| old != new)?;
+    let end = pairs
+        .rfind(|&(_, (&old, new))| old != new)
+        .map_or(start, |(end, _)| end);
+    Some(start..end + 1)
+}
+
+/// The smallest range of indices at which `old` differs from a run of `byte`
+pub(crate) fn differing_from(old: &[u8], byte: u8) -> Option<Range<usize>> {
+    let start = old.iter().position(|&b| b != byte)?;
+    let end = old.iter().rposition(|&b| b != byte).unwrap_or(start);
+    Some(start..end + 1)
+}
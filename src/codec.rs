@@ -0,0 +1,141 @@
+//! In-place decoding of binary-to-text encodings
+//!
+//! The decoded bytes may not be valid UTF-8, so these decode through a [`ByteReplacinator`].
+//! Each encoding is strictly shrinking, so the decoded bytes are written over the text which
+//! encoded them, without a second buffer.
+
+use core::fmt;
+
+use crate::{ByteReplacinator, Position};
+
+/// An error encountered whilst decoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    position: Position,
+}
+
+impl Error {
+    /// The kind of error which occurred
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The position in the original slice at which the error was detected
+    pub fn position(&self) -> Position {
+        self.position
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::Incomplete => write!(f, "encoded text ended partway through a byte"),
+            ErrorKind::InvalidPadding => write!(f, "invalid base64 padding"),
+        }?;
+        write!(f, " at byte {}", self.position.byte())
+    }
+}
+
+/// The kinds of [`Error`] which can occur whilst decoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The encoded text ended with too few digits to encode a whole byte
+    Incomplete,
+    /// A base64 group was followed by the wrong number of `=`s
+    InvalidPadding,
+}
+
+/// Decode the pairs of hex digits at the start of the remainder of `src`
+///
+/// Upper and lower case digits are both accepted. Decoding stops at the first byte which is not
+/// a hex digit, which is left in the remainder with the rest of the text.
+///
+/// Returns the decoded bytes. Like [`ByteReplacinator::take_start`], this includes anything
+/// which was written before, and the first section of `src` is no longer available afterwards.
+pub fn decode_hex_in_place<'a>(src: &mut ByteReplacinator<'a>) -> Result<&'a mut [u8], Error> {
+    while let Some(high) = src.peek_byte().and_then(hex_value) {
+        src.read_byte();
+        let low = src
+            .peek_byte()
+            .and_then(hex_value)
+            .ok_or_else(|| error(src, ErrorKind::Incomplete))?;
+        src.read_byte();
+        src.write_byte(high << 4 | low);
+    }
+    Ok(src.take_start())
+}
+
+/// Decode the base64 at the start of the remainder of `src`
+///
+/// This uses the standard alphabet of RFC 4648, with `+` and `/`. The final group may be padded
+/// with `=`, or unpadded. Decoding stops at the first byte which is not part of the alphabet,
+/// which is left in the remainder with the rest of the text, or after a final group which is
+/// shorter than four digits.
+///
+/// Returns the decoded bytes. Like [`ByteReplacinator::take_start`], this includes anything
+/// which was written before, and the first section of `src` is no longer available afterwards.
+pub fn decode_base64_in_place<'a>(src: &mut ByteReplacinator<'a>) -> Result<&'a mut [u8], Error> {
+    loop {
+        let mut group = 0u32;
+        let mut len = 0;
+        while len < 4 {
+            match src.peek_byte().and_then(base64_value) {
+                Some(value) => {
+                    src.read_byte();
+                    group = group << 6 | u32::from(value);
+                    len += 1;
+                }
+                None => break,
+            }
+        }
+        match len {
+            0 if src.peek_byte() == Some(b'=') => {
+                return Err(error(src, ErrorKind::InvalidPadding))
+            }
+            0 => break,
+            1 => return Err(error(src, ErrorKind::Incomplete)),
+            _ => {}
+        }
+        let bytes = group << (6 * (4 - len)) << 8;
+        src.write_bytes(&bytes.to_be_bytes()[..len - 1]);
+        if len < 4 {
+            if src.peek_byte() == Some(b'=') {
+                for _ in len..4 {
+                    if src.peek_byte() != Some(b'=') {
+                        return Err(error(src, ErrorKind::InvalidPadding));
+                    }
+                    src.read_byte();
+                }
+            }
+            break;
+        }
+    }
+    Ok(src.take_start())
+}
+
+/// The value of the hex digit `b`
+fn hex_value(b: u8) -> Option<u8> {
+    (b as char).to_digit(16).map(|digit| digit as u8)
+}
+
+/// The value of `b` in the standard base64 alphabet
+pub(crate) fn base64_value(b: u8) -> Option<u8> {
+    match b {
+        b'A'..=b'Z' => Some(b - b'A'),
+        b'a'..=b'z' => Some(b - b'a' + 26),
+        b'0'..=b'9' => Some(b - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+#[cold]
+fn error(src: &ByteReplacinator<'_>, kind: ErrorKind) -> Error {
+    Error {
+        kind,
+        position: src.position(),
+    }
+}
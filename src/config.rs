@@ -0,0 +1,181 @@
+//! In-place parsing of the `key = value` lines of TOML and INI files
+//!
+//! Each line is split into its key and value without allocating. Quoted keys and values are
+//! unquoted, and TOML basic strings are unescaped, so the parts are ready to use. Sections,
+//! arrays and inline tables are left to the caller.
+
+use core::fmt;
+
+use crate::{
+    unescape::{self, consume_quoted, unescape_toml},
+    Observer, Position, Replacinator,
+};
+
+/// An error encountered whilst parsing a line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    position: Position,
+}
+
+impl Error {
+    /// The kind of error which occurred
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The position in the line at which the error was detected
+    pub fn position(&self) -> Position {
+        self.position
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::EmptyKey => write!(f, "empty key"),
+            ErrorKind::MissingEquals => write!(f, "expected `=` after the key"),
+            ErrorKind::UnexpectedChar(c) => write!(f, "unexpected character {:?}", c),
+            ErrorKind::InvalidEscape => write!(f, "invalid escape sequence in string"),
+            ErrorKind::Unterminated => write!(f, "unterminated quoted string"),
+        }?;
+        write!(f, " at byte {}", self.position.byte())
+    }
+}
+
+/// The kinds of [`Error`] which can occur whilst parsing a line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The line has no key before the `=`
+    EmptyKey,
+    /// The key isn't followed by `=`
+    MissingEquals,
+    /// A character was found which is not valid at this point in the line, such as after a
+    /// quoted value, or an unescaped control character in a basic string
+    UnexpectedChar(char),
+    /// A basic string contained an invalid escape sequence
+    InvalidEscape,
+    /// A quoted key or value has no closing quote
+    Unterminated,
+}
+
+impl From<unescape::Error> for Error {
+    fn from(e: unescape::Error) -> Self {
+        let kind = match e.kind() {
+            unescape::ErrorKind::ControlCharacter(c) => ErrorKind::UnexpectedChar(c),
+            unescape::ErrorKind::Unterminated | unescape::ErrorKind::UnexpectedEnd => {
+                ErrorKind::Unterminated
+            }
+            _ => ErrorKind::InvalidEscape,
+        };
+        Self {
+            kind,
+            position: e.position(),
+        }
+    }
+}
+
+/// Whether `line` has no key and value, as it is blank or a comment starting with `#` or `;`
+pub fn is_comment(line: &str) -> bool {
+    matches!(
+        line.trim_start_matches(is_space).chars().next(),
+        None | Some('#' | ';')
+    )
+}
+
+/// Split `line` into its key and value, which are unquoted and unescaped in place
+///
+/// A key or value in `"` is a TOML basic string, which is unescaped using
+/// [`unescape_toml`], and one in `'` is a literal string, which is used as is. Otherwise, the
+/// key is everything before the `=`, and the value is everything after it up to a comment,
+/// each without surrounding whitespace. A comment starts with `#` or `;`, at the start of the
+/// value or after whitespace. A line terminator at the end of `line` is ignored.
+///
+/// If an error is returned, `line` is left valid, but its contents are unspecified.
+pub fn key_value(line: &mut str) -> Result<(&mut str, &mut str), Error> {
+    Replacinator::new_in(line, |src| {
+        src.discard_while(is_space);
+        let key = match src.peek() {
+            Some(quote @ ('"' | '\'')) => quoted(src, quote)?,
+            _ => {
+                let mark = src.mark();
+                src.skip_while(|c| c != '=');
+                let key = trim_end(
+                    src.take_marked(mark)
+                        .expect("The mark was made from this Replacinator"),
+                );
+                if key.is_empty() {
+                    return Err(error(src, ErrorKind::EmptyKey));
+                }
+                key
+            }
+        };
+        src.discard_while(is_space);
+        if src.peek() != Some('=') {
+            return Err(error(src, ErrorKind::MissingEquals));
+        }
+        src.discard_char();
+        src.discard_while(is_space);
+        let value = match src.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                let value = quoted(src, quote)?;
+                src.discard_while(is_space);
+                match src.peek() {
+                    None | Some('#' | ';') => value,
+                    Some(c) => return Err(error(src, ErrorKind::UnexpectedChar(c))),
+                }
+            }
+            _ => {
+                let mark = src.mark();
+                let mut after_space = true;
+                src.skip_while(|c| {
+                    let comment = after_space && (c == '#' || c == ';');
+                    after_space = is_space(c);
+                    !comment
+                });
+                trim_end(
+                    src.take_marked(mark)
+                        .expect("The mark was made from this Replacinator"),
+                )
+            }
+        };
+        Ok((key, value))
+    })
+}
+
+/// Read a string quoted by `quote`, unescaping it if it is a basic string
+fn quoted<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+    quote: char,
+) -> Result<&'a mut str, Error> {
+    Ok(if quote == '"' {
+        consume_quoted(src, quote, unescape_toml)?
+    } else {
+        consume_quoted(src, quote, |src| {
+            let mark = src.mark();
+            src.skip_while(|c| c != quote);
+            Ok(src
+                .take_marked(mark)
+                .expect("The mark was made from this Replacinator"))
+        })?
+    })
+}
+
+fn trim_end(text: &mut str) -> &mut str {
+    let len = text.trim_end_matches(is_space).len();
+    &mut text[..len]
+}
+
+/// Whether `c` is whitespace within a line, or its terminator
+fn is_space(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\r' | '\n')
+}
+
+#[cold]
+fn error<O: Observer>(src: &Replacinator<'_, O>, kind: ErrorKind) -> Error {
+    Error {
+        kind,
+        position: src.position(),
+    }
+}
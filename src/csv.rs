@@ -0,0 +1,369 @@
+//! In-place processing of comma-separated values
+//!
+//! The functions in this module follow [RFC 4180](https://tools.ietf.org/html/rfc4180), and
+//! accept either `\n` or `\r\n` as the record terminator.
+
+use core::fmt;
+
+use crate::{NoObserver, Observer, Position, Replacinator};
+
+/// An error encountered whilst processing CSV
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    position: Position,
+}
+
+impl Error {
+    /// The kind of error which occurred
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The position in the original document at which the error was detected
+    pub fn position(&self) -> Position {
+        self.position
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::UnterminatedQuote => write!(f, "unterminated quoted field"),
+            ErrorKind::UnexpectedChar(c) => write!(f, "unexpected character {:?}", c),
+            ErrorKind::Unrepresentable(c) => {
+                write!(f, "field contains {:?}, which cannot be represented", c)
+            }
+        }?;
+        write!(f, " at byte {}", self.position.byte())
+    }
+}
+
+/// The kinds of [`Error`] which can occur whilst processing CSV
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The document ended inside a quoted field
+    UnterminatedQuote,
+    /// A character was found which is not valid at this point in the document
+    UnexpectedChar(char),
+    /// A field contains a character which cannot be represented in the output format
+    Unrepresentable(char),
+}
+
+/// Convert `document` from CSV to tab-separated values
+///
+/// Separating commas are replaced with tabs, and quoted fields are unquoted. As TSV has no way to
+/// quote fields, an error is returned if any field contains a tab or a line break.
+///
+/// The converted document is `&document[..len]`, where `len` is the returned value; the rest of
+/// the document is filled with spaces. If an error is returned, the document may have been
+/// partially converted, but is still valid UTF-8.
+pub fn to_tsv(document: &mut str) -> Result<usize, Error> {
+    Replacinator::new_in(document, |src| {
+        while src.peek().is_some() {
+            if src.peek() == Some('"') {
+                src.read_char();
+                loop {
+                    match src.peek() {
+                        Some('"') if src.peek_nth(1) == Some('"') => {
+                            src.read_char();
+                            src.skip_char();
+                        }
+                        Some('"') => {
+                            src.read_char();
+                            break;
+                        }
+                        Some(c @ ('\t' | '\n' | '\r')) => {
+                            return Err(error(src, ErrorKind::Unrepresentable(c)))
+                        }
+                        Some(_) => {
+                            src.skip_char();
+                        }
+                        None => return Err(error(src, ErrorKind::UnterminatedQuote)),
+                    }
+                }
+            } else {
+                src.skip_while(|c| !matches!(c, ',' | '\n' | '\r' | '\t' | '"'));
+            }
+            match src.peek() {
+                Some(',') => {
+                    src.read_char();
+                    src.write_char('\t');
+                }
+                Some('\n') => {
+                    src.skip_char();
+                }
+                Some('\r') if src.peek_nth(1) == Some('\n') => {
+                    src.skip_str("\r\n");
+                }
+                Some('\t') => return Err(error(src, ErrorKind::Unrepresentable('\t'))),
+                Some(c) => return Err(error(src, ErrorKind::UnexpectedChar(c))),
+                None => {}
+            }
+        }
+        Ok(src.written_len())
+    })
+}
+
+/// Keep only the given `columns` of each record in `document`, in their original order
+///
+/// Columns are numbered from zero; [`header_index`] can be used to find a column by its name.
+/// Fields are kept unchanged, including any quotes.
+///
+/// The projected document is `&document[..len]`, where `len` is the returned value; the rest of
+/// the document is filled with spaces. If an error is returned, the document may have been
+/// partially projected, but is still valid UTF-8.
+pub fn project(document: &mut str, columns: &[usize]) -> Result<usize, Error> {
+    Replacinator::new_in(document, |src| {
+        let mut column = 0;
+        let mut record_empty = true;
+        loop {
+            let field_start = src.written_len();
+            let keep = columns.contains(&column);
+            if keep && !record_empty {
+                // The separator from the input has always been read by this point
+                src.write_char(',');
+            }
+            skip_field(src)?;
+            if keep {
+                record_empty = false;
+            } else {
+                src.truncate_start(field_start);
+            }
+            match src.peek() {
+                Some(',') => {
+                    src.read_char();
+                    column += 1;
+                    continue;
+                }
+                Some('\n') => {
+                    src.skip_char();
+                }
+                Some('\r') if src.peek_nth(1) == Some('\n') => {
+                    src.skip_str("\r\n");
+                }
+                Some(c) => return Err(error(src, ErrorKind::UnexpectedChar(c))),
+                None => return Ok(src.written_len()),
+            }
+            column = 0;
+            record_empty = true;
+        }
+    })
+}
+
+/// Find the index of the column called `name`, using the header record of `document`
+pub fn header_index(document: &str, name: &str) -> Option<usize> {
+    let mut chars = document.chars().peekable();
+    for index in 0.. {
+        let mut expected = name.chars();
+        let mut matches = true;
+        let mut check = |c: char| matches &= expected.next() == Some(c);
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next()? {
+                    '"' if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        check('"');
+                    }
+                    '"' => break,
+                    c => check(c),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if matches!(c, ',' | '\n' | '\r') {
+                    break;
+                }
+                chars.next();
+                check(c);
+            }
+        }
+        if matches && expected.next().is_none() {
+            return Some(index);
+        }
+        if chars.next() != Some(',') {
+            break;
+        }
+    }
+    None
+}
+
+/// The characters which separate and quote fields, used by [`fields`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dialect {
+    /// The character between the fields of a record
+    pub delimiter: char,
+    /// The character which starts and ends a quoted field, and is doubled to include it in one
+    pub quote: char,
+}
+
+impl Dialect {
+    /// Comma-separated values, as in RFC 4180
+    pub const CSV: Self = Self {
+        delimiter: ',',
+        quote: '"',
+    };
+
+    /// Semicolon-separated values, as produced by spreadsheets in locales which use a decimal
+    /// comma
+    pub const SEMICOLON: Self = Self {
+        delimiter: ';',
+        quote: '"',
+    };
+}
+
+/// A field found by [`fields`]
+#[derive(Debug)]
+pub struct Field<'a> {
+    text: &'a mut str,
+    ends_record: bool,
+}
+
+impl<'a> Field<'a> {
+    /// View the unquoted text of this field
+    pub fn text(&self) -> &str {
+        self.text
+    }
+
+    /// Whether this is the last field of its record
+    pub fn ends_record(&self) -> bool {
+        self.ends_record
+    }
+
+    /// Take the unquoted text of this field as a mutable view
+    pub fn into_text(self) -> &'a mut str {
+        self.text
+    }
+}
+
+/// Iterate over the fields of the records in the remainder of `src`, unquoting them in place
+///
+/// Quoted fields have their quotes removed, and each doubled quote within them replaced with a
+/// single quote. [`Field::ends_record`] marks the last field of each record. A final record
+/// terminator is optional, but an empty line at the end is not yielded as a record.
+///
+/// Each field is taken from `src` as it is yielded, as with [`Replacinator::take_start`]. After an
+/// error, no more fields are yielded.
+pub fn fields<'r, 'a, O: Observer>(
+    src: &'r mut Replacinator<'a, O>,
+    dialect: Dialect,
+) -> Fields<'r, 'a, O> {
+    Fields {
+        src,
+        dialect,
+        record_start: true,
+        done: false,
+    }
+}
+
+/// An iterator over the fields of some CSV
+///
+/// Created by [`fields`].
+pub struct Fields<'r, 'a, O = NoObserver> {
+    src: &'r mut Replacinator<'a, O>,
+    dialect: Dialect,
+    /// Whether the next field is the first of a record
+    record_start: bool,
+    done: bool,
+}
+
+impl<'r, 'a, O: Observer> Fields<'r, 'a, O> {
+    /// Read and write back a field, without its quotes
+    fn unquote_field(&mut self) -> Result<(), Error> {
+        let Dialect { delimiter, quote } = self.dialect;
+        let src = &mut *self.src;
+        if src.peek() != Some(quote) {
+            src.skip_while(|c| c != delimiter && c != quote && c != '\n' && c != '\r');
+            return Ok(());
+        }
+        src.read_char();
+        loop {
+            src.skip_while(|c| c != quote);
+            if src.read_char().is_none() {
+                return Err(error(src, ErrorKind::UnterminatedQuote));
+            }
+            if src.peek() != Some(quote) {
+                return Ok(());
+            }
+            src.skip_char();
+        }
+    }
+
+    /// Read the delimiter or record terminator after a field, returning whether it ended the record
+    fn end_field(&mut self) -> Result<bool, Error> {
+        let src = &mut *self.src;
+        match src.peek() {
+            Some(c) if c == self.dialect.delimiter => {
+                src.read_char();
+                Ok(false)
+            }
+            Some('\n') => {
+                src.read_char();
+                Ok(true)
+            }
+            Some('\r') if src.peek_nth(1) == Some('\n') => {
+                src.discard_bytes("\r\n".len());
+                Ok(true)
+            }
+            Some(c) => Err(error(src, ErrorKind::UnexpectedChar(c))),
+            None => {
+                self.done = true;
+                Ok(true)
+            }
+        }
+    }
+}
+
+impl<'r, 'a, O: Observer> Iterator for Fields<'r, 'a, O> {
+    type Item = Result<Field<'a>, Error>;
+
+    fn next(&mut self) -> Option<Result<Field<'a>, Error>> {
+        if self.done || (self.record_start && self.src.peek().is_none()) {
+            return None;
+        }
+        let mark = self.src.mark();
+        let ends_record = match self.unquote_field().and_then(|()| self.end_field()) {
+            Ok(ends_record) => ends_record,
+            Err(e) => {
+                self.done = true;
+                return Some(Err(e));
+            }
+        };
+        self.record_start = ends_record;
+        let text = self
+            .src
+            .take_marked(mark)
+            .expect("The mark was made from this Replacinator");
+        Some(Ok(Field { text, ends_record }))
+    }
+}
+
+impl<'r, 'a, O: Observer> core::iter::FusedIterator for Fields<'r, 'a, O> {}
+
+/// Skip a field unchanged, including any quotes
+fn skip_field<O: Observer>(src: &mut Replacinator<'_, O>) -> Result<(), Error> {
+    if !src.skip_str("\"") {
+        src.skip_while(|c| !matches!(c, ',' | '\n' | '\r' | '"'));
+        return Ok(());
+    }
+    loop {
+        src.skip_while(|c| c != '"');
+        if src.skip_str("\"\"") {
+            continue;
+        }
+        if src.skip_str("\"") {
+            return Ok(());
+        }
+        return Err(error(src, ErrorKind::UnterminatedQuote));
+    }
+}
+
+#[cold]
+fn error<O: Observer>(src: &Replacinator<'_, O>, kind: ErrorKind) -> Error {
+    Error {
+        kind,
+        position: src.position(),
+    }
+}
@@ -0,0 +1,111 @@
+use core::marker::PhantomData;
+
+use crate::{NoObserver, Observer, Position, Replacinator, WriteError};
+
+impl<'a, O: Observer> Replacinator<'a, O> {
+    /// Split this into a [`Reader`], which moves the read position, and a [`Writer`], which moves
+    /// the write position
+    ///
+    /// The two handles can be given to separate components, such as the consumer and producer
+    /// of a decoding pipeline. Writing never passes the read position: the [`Writer`] can only
+    /// use the space which the [`Reader`] has already freed.
+    pub fn split_cursors(&mut self) -> (Reader<'_, 'a, O>, Writer<'_, 'a, O>) {
+        let src: *mut Replacinator<'a, O> = self;
+        (
+            Reader {
+                src,
+                _borrow: PhantomData,
+            },
+            Writer {
+                src,
+                _borrow: PhantomData,
+            },
+        )
+    }
+}
+
+/// The reading half of a [`Replacinator`]
+///
+/// Created by [`Replacinator::split_cursors`]. Nothing is borrowed from the remainder between
+/// calls, as the [`Writer`] may be used in between.
+pub struct Reader<'r, 'a, O = NoObserver> {
+    src: *mut Replacinator<'a, O>,
+    _borrow: PhantomData<&'r mut Replacinator<'a, O>>,
+}
+
+impl<'r, 'a, O: Observer> Reader<'r, 'a, O> {
+    fn with<R>(&mut self, f: impl FnOnce(&mut Replacinator<'a, O>) -> R) -> R {
+        // Safety: `src` is borrowed mutably for `'r` by `split_cursors`. `Reader` and `Writer`
+        // are not `Send`, and neither holds a reference into it between calls, so this is the
+        // only reference to it which exists during `f`
+        f(unsafe { &mut *self.src })
+    }
+
+    /// The next character of the remainder, without reading it
+    pub fn peek(&mut self) -> Option<char> {
+        self.with(|src| src.peek())
+    }
+
+    /// Read the next character of the remainder
+    ///
+    /// This frees its bytes for the [`Writer`].
+    pub fn read_char(&mut self) -> Option<char> {
+        self.with(|src| src.read_char())
+    }
+
+    /// Whether the remainder starts with `prefix`
+    pub fn starts_with(&mut self, prefix: &str) -> bool {
+        self.with(|src| src.starts_with(prefix))
+    }
+
+    /// The length in bytes of the remainder
+    pub fn remaining(&mut self) -> usize {
+        self.with(|src| src.remainder().len())
+    }
+
+    /// The position in the original string of the next character to be read
+    pub fn position(&mut self) -> Position {
+        self.with(|src| src.position())
+    }
+}
+
+/// The writing half of a [`Replacinator`]
+///
+/// Created by [`Replacinator::split_cursors`].
+pub struct Writer<'r, 'a, O = NoObserver> {
+    src: *mut Replacinator<'a, O>,
+    _borrow: PhantomData<&'r mut Replacinator<'a, O>>,
+}
+
+impl<'r, 'a, O: Observer> Writer<'r, 'a, O> {
+    fn with<R>(&mut self, f: impl FnOnce(&mut Replacinator<'a, O>) -> R) -> R {
+        // Safety: As in `Reader::with`
+        f(unsafe { &mut *self.src })
+    }
+
+    /// The number of bytes which can be written before reaching the read position
+    pub fn available(&mut self) -> usize {
+        self.with(|src| src.capacity_for_write())
+    }
+
+    /// Write `s` to the end of the first section
+    ///
+    /// # Panics
+    /// As for [`Replacinator::write_str`]
+    pub fn write_str(&mut self, s: &str) {
+        self.with(|src| src.write_str(s))
+    }
+
+    /// Write `c` to the end of the first section
+    ///
+    /// # Panics
+    /// As for [`Replacinator::write_char`]
+    pub fn write_char(&mut self, c: char) {
+        self.with(|src| src.write_char(c))
+    }
+
+    /// Write `s` to the end of the first section, or return an error without writing anything
+    pub fn try_write_str(&mut self, s: &str) -> Result<(), WriteError> {
+        self.with(|src| src.try_write_str(s))
+    }
+}
@@ -0,0 +1,264 @@
+//! Rewriting a string slice from both ends at once
+
+use crate::{
+    raw::{unchecked_from_utf8, unchecked_from_utf8_mut},
+    AsMutStr,
+};
+
+/// A partially updated string slice, which is read and written from both ends
+///
+/// This combines a [`Replacinator`](crate::Replacinator) and a
+/// [`RevReplacinator`](crate::RevReplacinator) over the same string, with five sections:
+/// - The start, a valid UTF-8 string containing the characters written to the front, in order
+/// - The front gap, with arbitrary contents
+/// - The characters which have not yet been read, as valid UTF-8
+/// - The back gap, with arbitrary contents
+/// - The end, a valid UTF-8 string containing the characters written to the back. Each write
+///   is added to the front of this section.
+///
+/// Characters can be read from either end of the remainder and written to either end of the
+/// string. Each write must fit in the gap on its own side. If it does not, the remainder can be
+/// moved to give all of the space to one side, using [`DoubleReplacinator::shift_gap_to_front`]
+/// or [`DoubleReplacinator::shift_gap_to_back`]. As everything written has been read, the gaps
+/// together always have room for the character which was last read.
+///
+/// When this is dropped, both gaps are filled with spaces to return them to valid UTF-8.
+pub struct DoubleReplacinator<'a> {
+    contents: &'a mut [u8],
+    /// The end of the start
+    front_write: usize,
+    /// The start of the remainder
+    front_read: usize,
+    /// The end of the remainder
+    back_read: usize,
+    /// The start of the end
+    back_write: usize,
+}
+
+impl<'a> DoubleReplacinator<'a> {
+    /// Create a new [`DoubleReplacinator`] for the given string slice, and operate on it within
+    /// the given function
+    ///
+    /// This function can be safe because it ensures that `value` is returned to a valid string
+    /// slice, by ensuring that [`Drop`] is called as required.
+    pub fn new_in<R>(
+        value: impl AsMutStr<'a>,
+        mut with: impl FnMut(&mut DoubleReplacinator<'a>) -> R,
+    ) -> R {
+        // Safety: Because we create a new scope, `it` is always dropped,
+        // so the data behind value returns to being utf8 by the end of the borrow.
+        let mut it = unsafe { Self::construct(value.into_mut_str()) };
+        with(&mut it)
+    }
+
+    /// Create a new [`DoubleReplacinator`] from the given string
+    ///
+    /// # Safety
+    /// Before 'a ends, the resulting [`DoubleReplacinator`] must be dropped
+    pub unsafe fn construct(from: &'a mut str) -> Self {
+        let len = from.len();
+        Self {
+            // SAFETY: By the time this borrow ends, the memory contents are back to being utf8,
+            // as both gaps are filled when this is dropped
+            contents: unsafe { from.as_bytes_mut() },
+            front_write: 0,
+            front_read: 0,
+            back_read: len,
+            back_write: len,
+        }
+    }
+
+    /// View the characters which have not yet been read
+    #[inline]
+    pub fn remainder(&self) -> &str {
+        unsafe { unchecked_from_utf8(&self.contents[self.front_read..self.back_read]) }
+    }
+
+    /// View the characters which have not yet been read mutably
+    #[inline]
+    pub fn remainder_mut(&mut self) -> &mut str {
+        unsafe { unchecked_from_utf8_mut(&mut self.contents[self.front_read..self.back_read]) }
+    }
+
+    /// View the characters written to the front
+    #[inline]
+    pub fn start(&self) -> &str {
+        unsafe { unchecked_from_utf8(&self.contents[..self.front_write]) }
+    }
+
+    /// View the characters written to the front mutably
+    #[inline]
+    pub fn start_mut(&mut self) -> &mut str {
+        unsafe { unchecked_from_utf8_mut(&mut self.contents[..self.front_write]) }
+    }
+
+    /// View the characters written to the back
+    #[inline]
+    pub fn end(&self) -> &str {
+        unsafe { unchecked_from_utf8(&self.contents[self.back_write..]) }
+    }
+
+    /// View the characters written to the back mutably
+    #[inline]
+    pub fn end_mut(&mut self) -> &mut str {
+        unsafe { unchecked_from_utf8_mut(&mut self.contents[self.back_write..]) }
+    }
+
+    /// The number of bytes which can be written to the front without moving the remainder
+    pub fn front_room(&self) -> usize {
+        self.front_read - self.front_write
+    }
+
+    /// The number of bytes which can be written to the back without moving the remainder
+    pub fn back_room(&self) -> usize {
+        self.back_write - self.back_read
+    }
+
+    /// The first character of the remainder, without reading it
+    #[inline]
+    pub fn peek_front(&self) -> Option<char> {
+        self.remainder().chars().next()
+    }
+
+    /// The last character of the remainder, without reading it
+    #[inline]
+    pub fn peek_back(&self) -> Option<char> {
+        self.remainder().chars().next_back()
+    }
+
+    /// Read the first character of the remainder, without writing it
+    #[inline]
+    pub fn read_front(&mut self) -> Option<char> {
+        let c = self.peek_front()?;
+        self.front_read += c.len_utf8();
+        self.check_invariants();
+        Some(c)
+    }
+
+    /// Read the last character of the remainder, without writing it
+    #[inline]
+    pub fn read_back(&mut self) -> Option<char> {
+        let c = self.peek_back()?;
+        self.back_read -= c.len_utf8();
+        self.check_invariants();
+        Some(c)
+    }
+
+    /// Read the first character of the remainder, and write it to the front
+    #[inline]
+    pub fn skip_front(&mut self) -> Option<char> {
+        let c = self.read_front()?;
+        self.write_front_char(c);
+        Some(c)
+    }
+
+    /// Read the last character of the remainder, and write it to the back
+    #[inline]
+    pub fn skip_back(&mut self) -> Option<char> {
+        let c = self.read_back()?;
+        self.write_back_char(c);
+        Some(c)
+    }
+
+    /// Write `c` to the end of the start
+    ///
+    /// # Panics
+    /// If there are fewer than `c.len_utf8()` bytes of [`DoubleReplacinator::front_room`]
+    #[inline]
+    pub fn write_front_char(&mut self, c: char) {
+        self.write_front_str(c.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Write `s` to the end of the start
+    ///
+    /// # Panics
+    /// If `s` is longer than [`DoubleReplacinator::front_room`]
+    #[inline]
+    pub fn write_front_str(&mut self, s: &str) {
+        assert!(
+            s.len() <= self.front_room(),
+            "Cannot write more bytes than there is room for at the front"
+        );
+        let end = self.front_write + s.len();
+        self.contents[self.front_write..end].copy_from_slice(s.as_bytes());
+        self.front_write = end;
+        self.check_invariants();
+    }
+
+    /// Write `c` to the start of the end
+    ///
+    /// # Panics
+    /// If there are fewer than `c.len_utf8()` bytes of [`DoubleReplacinator::back_room`]
+    #[inline]
+    pub fn write_back_char(&mut self, c: char) {
+        self.write_back_str(c.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Write `s` to the start of the end
+    ///
+    /// # Panics
+    /// If `s` is longer than [`DoubleReplacinator::back_room`]
+    #[inline]
+    pub fn write_back_str(&mut self, s: &str) {
+        assert!(
+            s.len() <= self.back_room(),
+            "Cannot write more bytes than there is room for at the back"
+        );
+        let start = self.back_write - s.len();
+        self.contents[start..self.back_write].copy_from_slice(s.as_bytes());
+        self.back_write = start;
+        self.check_invariants();
+    }
+
+    /// Move the remainder to the back, so that all of the free space is at the front
+    ///
+    /// This takes time proportional to the length of the remainder.
+    pub fn shift_gap_to_front(&mut self) {
+        let len = self.back_read - self.front_read;
+        let start = self.back_write - len;
+        self.contents
+            .copy_within(self.front_read..self.back_read, start);
+        self.front_read = start;
+        self.back_read = self.back_write;
+        self.check_invariants();
+    }
+
+    /// Move the remainder to the front, so that all of the free space is at the back
+    ///
+    /// This takes time proportional to the length of the remainder.
+    pub fn shift_gap_to_back(&mut self) {
+        let len = self.back_read - self.front_read;
+        self.contents
+            .copy_within(self.front_read..self.back_read, self.front_write);
+        self.front_read = self.front_write;
+        self.back_read = self.front_write + len;
+        self.check_invariants();
+    }
+
+    #[inline]
+    fn invariants_hold(&self) -> bool {
+        self.front_write <= self.front_read
+            && self.front_read <= self.back_read
+            && self.back_read <= self.back_write
+            && self.back_write <= self.contents.len()
+    }
+
+    #[inline]
+    fn check_invariants(&self) {
+        assert!(
+            self.invariants_hold(),
+            "An internal invariant of `replacinator` was violated"
+        );
+    }
+}
+
+impl<'a> Drop for DoubleReplacinator<'a> {
+    fn drop(&mut self) {
+        if self.invariants_hold() {
+            self.contents[self.front_write..self.front_read].fill(b' ');
+            self.contents[self.back_read..self.back_write].fill(b' ');
+        } else {
+            self.contents.fill(b' ');
+        }
+    }
+}
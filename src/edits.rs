@@ -0,0 +1,251 @@
+//! Recording the edits made through a [`Replacinator`], to map offsets between the original and
+//! the rewritten text
+//!
+//! An [`EditLog`] is an [`Observer`] which keeps a list of the ranges of the string which were
+//! removed or replaced. Tools which report diagnostics against rewritten text can use it to
+//! point at the corresponding part of the text they were given, using [`EditLog::to_original`].
+//! Create a logged [`Replacinator`] with [`Replacinator::new_in_logged`].
+//!
+//! Offsets in the rewritten text are offsets in the string the [`Replacinator`] was created
+//! from, as given by [`Replacinator::write_offset`]. Bytes used to fill the second section, such
+//! as the spaces left by [`FillStrategy::Space`], count as inserted text.
+//!
+//! This module requires the `alloc` feature.
+
+use alloc::vec::Vec;
+use core::{mem, ops::Range};
+
+use crate::{AsMutStr, FillStrategy, Observer, Replacinator};
+
+/// A part of the original string which was removed or replaced
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    /// The bytes of the original string which were removed
+    pub original: Range<usize>,
+    /// The bytes of the rewritten string which were written in their place
+    pub output: Range<usize>,
+}
+
+/// The edits made through a [`Replacinator`], in the order they were made
+///
+/// Text which is read and written back unchanged, such as by [`Replacinator::skip_char`], is not
+/// an edit. Adjacent edits are merged, so no two edits touch in both the original and the
+/// rewritten string.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EditLog {
+    edits: Vec<Edit>,
+    /// The offset in the original string of the read position
+    read: usize,
+    /// The offset in the string of the write position
+    write: usize,
+    /// The number of bytes the remainder has been moved towards the start by compaction
+    compacted: usize,
+    /// The last read, which is not an edit if the same text is written next
+    pending: Option<PendingRead>,
+}
+
+/// The state of an [`EditLog`] before a read was recorded as a removal
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PendingRead {
+    /// The address of the text which was read
+    address: usize,
+    len: usize,
+    /// A copy of the text which was read, if it is a single character
+    char: [u8; 4],
+    edits: usize,
+    last: Option<Edit>,
+}
+
+impl PendingRead {
+    /// Whether `text` is the text which was read
+    ///
+    /// Text written back in bulk is the same slice which was read, whereas a single character
+    /// is written back from a copy.
+    fn matches(&self, text: &str) -> bool {
+        self.len == text.len()
+            && (self.address == text.as_ptr() as usize
+                || text.len() <= self.char.len() && self.char[..text.len()] == *text.as_bytes())
+    }
+}
+
+impl EditLog {
+    /// Create an empty log
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The edits recorded so far
+    pub fn edits(&self) -> &[Edit] {
+        &self.edits
+    }
+
+    /// The offset in the rewritten string of the text at `offset` in the original string
+    ///
+    /// An offset within removed text maps to the start of the text which replaced it.
+    pub fn to_output(&self, offset: usize) -> usize {
+        map(&self.edits, offset, |edit| (&edit.original, &edit.output))
+    }
+
+    /// The offset in the original string of the text at `offset` in the rewritten string
+    ///
+    /// An offset within inserted text maps to the start of the text which it replaced.
+    pub fn to_original(&self, offset: usize) -> usize {
+        map(&self.edits, offset, |edit| (&edit.output, &edit.original))
+    }
+
+    /// Record that `removed` bytes were read and `inserted` bytes were written in their place
+    fn record(&mut self, removed: usize, inserted: usize) {
+        let (read, write) = (self.read, self.write);
+        self.read += removed;
+        self.write += inserted;
+        match self.edits.last_mut() {
+            Some(last) if last.original.end == read && last.output.end == write => {
+                last.original.end = self.read;
+                last.output.end = self.write;
+            }
+            _ => self.edits.push(Edit {
+                original: read..self.read,
+                output: write..self.write,
+            }),
+        }
+    }
+
+    /// Remove the output after `write_offset`, so that the original text it was written for is
+    /// removed or replaced by what is left of the output
+    fn truncate(&mut self, write_offset: usize) {
+        while let Some(last) = self.edits.last() {
+            if last.output.start < write_offset {
+                break;
+            }
+            self.edits.pop();
+        }
+        match self.edits.last_mut() {
+            Some(last) if last.output.end > write_offset => {
+                last.original.end = self.read;
+                last.output.end = write_offset;
+                self.write = write_offset;
+            }
+            _ => {
+                let original = map(&self.edits, write_offset, |edit| {
+                    (&edit.output, &edit.original)
+                });
+                let read = mem::replace(&mut self.read, original);
+                self.write = write_offset;
+                if original < read {
+                    self.record(read - original, 0);
+                }
+            }
+        }
+    }
+
+    /// Move the write offset to the read offset, as when the second section is filled
+    fn fill(&mut self, fill: FillStrategy) {
+        self.pending = None;
+        if fill == FillStrategy::Compact {
+            self.compacted = self.read - self.write;
+        } else {
+            self.record(0, self.read - self.compacted - self.write);
+        }
+    }
+}
+
+/// Map `offset` from the first range of each edit given by `ranges` to the second
+fn map(
+    edits: &[Edit],
+    offset: usize,
+    ranges: impl Fn(&Edit) -> (&Range<usize>, &Range<usize>),
+) -> usize {
+    let index = edits.partition_point(|edit| ranges(edit).0.start <= offset);
+    let (from, to) = match index.checked_sub(1) {
+        Some(index) => ranges(&edits[index]),
+        None => return offset,
+    };
+    if offset >= from.end {
+        to.end + (offset - from.end)
+    } else {
+        to.start
+    }
+}
+
+impl Observer for EditLog {
+    fn read(&mut self, text: &str) {
+        let mut char = [0; 4];
+        if let Some(char) = char.get_mut(..text.len()) {
+            char.copy_from_slice(text.as_bytes());
+        }
+        self.pending = Some(PendingRead {
+            address: text.as_ptr() as usize,
+            len: text.len(),
+            char,
+            edits: self.edits.len(),
+            last: self.edits.last().cloned(),
+        });
+        self.record(text.len(), 0);
+    }
+
+    fn write(&mut self, text: &str) {
+        match self.pending.take() {
+            // The text which was just read is being written back unchanged
+            Some(pending) if pending.matches(text) => {
+                self.edits.truncate(pending.edits);
+                if let Some(last) = pending.last {
+                    self.edits[pending.edits - 1] = last;
+                }
+                self.write += text.len();
+            }
+            _ => self.record(0, text.len()),
+        }
+    }
+
+    fn take_start(&mut self, _: &str) {
+        self.fill(if cfg!(feature = "compact_on_take") {
+            FillStrategy::Compact
+        } else {
+            FillStrategy::DEFAULT
+        });
+    }
+
+    fn take_remainder(&mut self, _: &str) {
+        self.pending = None;
+    }
+
+    fn synchronise(&mut self, fill: FillStrategy) {
+        self.fill(fill);
+    }
+
+    fn rewind(&mut self, read_offset: usize, write_offset: usize) {
+        self.pending = None;
+        if read_offset == self.read {
+            self.truncate(write_offset);
+            return;
+        }
+        while let Some(last) = self.edits.last() {
+            if last.original.start < read_offset || last.output.start < write_offset {
+                break;
+            }
+            self.edits.pop();
+        }
+        if let Some(last) = self.edits.last_mut() {
+            last.original.end = last.original.end.min(read_offset);
+            last.output.end = last.output.end.min(write_offset);
+        }
+        self.read = read_offset;
+        self.write = write_offset;
+    }
+}
+
+impl<'a> Replacinator<'a, EditLog> {
+    /// Create a new [`Replacinator`] which records its edits, and operate on it within the given
+    /// function, returning the [`EditLog`] afterwards
+    ///
+    /// This is [`Replacinator::new_in_observed`] with an [`EditLog`].
+    pub fn new_in_logged<R>(
+        value: impl AsMutStr<'a>,
+        mut with: impl FnMut(&mut Replacinator<'a, EditLog>) -> R,
+    ) -> (R, EditLog) {
+        Replacinator::new_in_observed(value, EditLog::new(), |src| {
+            let result = with(src);
+            (result, mem::take(src.observer_mut()))
+        })
+    }
+}
@@ -0,0 +1,173 @@
+//! Splitting of fixed-width records into their fields
+
+use crate::{NoObserver, Observer, Replacinator};
+
+/// How the widths of fields are measured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    /// Widths are measured in bytes
+    ///
+    /// If a field would end partway through a character, no further fields are yielded.
+    Bytes,
+    /// Widths are measured in characters
+    ///
+    /// This matches the display width for text which doesn't contain wide or combining characters.
+    Chars,
+}
+
+/// Split `record` into fields with the given `widths`, with surrounding whitespace trimmed
+///
+/// If `record` is shorter than the sum of `widths`, the fields past its end are empty. Any
+/// part of `record` after the final field is available from [`Fields::into_remainder`].
+pub fn fields<'a, 'w>(record: &'a mut str, widths: &'w [usize], unit: Unit) -> Fields<'a, 'w> {
+    Fields {
+        rest: record,
+        widths: widths.iter(),
+        unit,
+    }
+}
+
+/// An iterator over the fields of a fixed-width record
+///
+/// Created by [`fields`].
+#[derive(Debug)]
+pub struct Fields<'a, 'w> {
+    rest: &'a mut str,
+    widths: core::slice::Iter<'w, usize>,
+    unit: Unit,
+}
+
+impl<'a, 'w> Fields<'a, 'w> {
+    /// The part of the record which has not been split into fields
+    pub fn into_remainder(self) -> &'a mut str {
+        self.rest
+    }
+}
+
+impl<'a, 'w> Iterator for Fields<'a, 'w> {
+    type Item = &'a mut str;
+
+    fn next(&mut self) -> Option<&'a mut str> {
+        let width = *self.widths.clone().next()?;
+        let len = match self.unit {
+            Unit::Bytes => {
+                let len = width.min(self.rest.len());
+                if !self.rest.is_char_boundary(len) {
+                    return None;
+                }
+                len
+            }
+            Unit::Chars => self
+                .rest
+                .char_indices()
+                .nth(width)
+                .map_or(self.rest.len(), |(i, _)| i),
+        };
+        self.widths.next();
+        let (field, rest) = core::mem::take(&mut self.rest).split_at_mut(len);
+        self.rest = rest;
+        Some(trim_mut(field))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.widths.len()))
+    }
+}
+
+/// Read fields with the given `widths` from the start of the remainder of `src`
+///
+/// Unlike [`fields`], the record doesn't need to be split off first, so this can read fields
+/// from a stream of records or a text protocol, and the remainder of `src` continues after the
+/// last field. Fields are not trimmed unless [`FixedWidthReader::trim_pad`] is used.
+pub fn reader<'r, 'a, 'w, O: Observer>(
+    src: &'r mut Replacinator<'a, O>,
+    widths: &'w [usize],
+    unit: Unit,
+) -> FixedWidthReader<'r, 'a, 'w, O> {
+    FixedWidthReader {
+        src,
+        widths: widths.iter(),
+        unit,
+        pad: None,
+    }
+}
+
+/// An iterator over fixed-width fields read from a [`Replacinator`]
+///
+/// Created by [`reader`]. Each field is taken from the [`Replacinator`] as it is yielded, as with
+/// [`Replacinator::take_start`]. If the remainder is shorter than the sum of the widths, the
+/// fields past its end are empty.
+pub struct FixedWidthReader<'r, 'a, 'w, O = NoObserver> {
+    src: &'r mut Replacinator<'a, O>,
+    widths: core::slice::Iter<'w, usize>,
+    unit: Unit,
+    pad: Option<char>,
+}
+
+impl<'r, 'a, 'w, O: Observer> FixedWidthReader<'r, 'a, 'w, O> {
+    /// Remove `pad` from both ends of each field
+    ///
+    /// The padding is read without being written, so it doesn't need to be trimmed afterwards.
+    pub fn trim_pad(self, pad: char) -> Self {
+        Self {
+            pad: Some(pad),
+            ..self
+        }
+    }
+}
+
+impl<'r, 'a, 'w, O: Observer> Iterator for FixedWidthReader<'r, 'a, 'w, O> {
+    type Item = &'a mut str;
+
+    fn next(&mut self) -> Option<&'a mut str> {
+        let width = *self.widths.clone().next()?;
+        let remainder = self.src.remainder();
+        let len = match self.unit {
+            Unit::Bytes => {
+                let len = width.min(remainder.len());
+                if !remainder.is_char_boundary(len) {
+                    return None;
+                }
+                len
+            }
+            Unit::Chars => remainder
+                .char_indices()
+                .nth(width)
+                .map_or(remainder.len(), |(i, _)| i),
+        };
+        self.widths.next();
+        let field = &remainder[..len];
+        let (leading, kept) = match self.pad {
+            Some(pad) => {
+                let trimmed = field.trim_start_matches(pad);
+                (len - trimmed.len(), trimmed.trim_end_matches(pad).len())
+            }
+            None => (0, len),
+        };
+        let mark = self.src.mark();
+        self.src.discard_bytes(leading);
+        self.src.skip_bytes(kept);
+        self.src.discard_bytes(len - leading - kept);
+        Some(
+            self.src
+                .take_marked(mark)
+                .expect("The mark was made from this Replacinator"),
+        )
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.widths.len()))
+    }
+}
+
+impl<'r, 'a, 'w, O: Observer> core::iter::FusedIterator for FixedWidthReader<'r, 'a, 'w, O> {}
+
+/// Trim whitespace from both ends of a mutable string slice
+fn trim_mut(s: &mut str) -> &mut str {
+    let start = s.len() - s.trim_start().len();
+    let end = s.trim_end().len();
+    if start >= end {
+        return &mut s[..0];
+    }
+    &mut s[start..end]
+}
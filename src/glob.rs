@@ -0,0 +1,114 @@
+//! Analysis of glob patterns
+//!
+//! The metacharacters recognised are `*`, `?`, `[`, `]`, `{` and `}`. A character preceded by
+//! `\` is always literal.
+
+use core::ops::Range;
+
+use crate::Replacinator;
+
+/// Find the spans of `pattern` which contain glob metacharacters
+///
+/// A bracket expression such as `[a-z]` is reported as a single span. Every other metacharacter
+/// is reported individually, including an unmatched `[`.
+pub fn metacharacters(pattern: &str) -> Metacharacters<'_> {
+    Metacharacters {
+        pattern,
+        position: 0,
+    }
+}
+
+/// An iterator over the spans of a glob pattern which contain metacharacters
+///
+/// Created by [`metacharacters`].
+#[derive(Debug, Clone)]
+pub struct Metacharacters<'p> {
+    pattern: &'p str,
+    position: usize,
+}
+
+impl<'p> Iterator for Metacharacters<'p> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        let mut chars = self.pattern[self.position..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            let start = self.position + i;
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '[' => {
+                    let end =
+                        bracket_end(&self.pattern[start..]).map_or(start + 1, |end| start + end);
+                    self.position = end;
+                    return Some(start..end);
+                }
+                '*' | '?' | ']' | '{' | '}' => {
+                    self.position = start + 1;
+                    return Some(start..start + 1);
+                }
+                _ => {}
+            }
+        }
+        self.position = self.pattern.len();
+        None
+    }
+}
+
+impl<'p> core::iter::FusedIterator for Metacharacters<'p> {}
+
+/// Whether `pattern` contains any glob metacharacters
+pub fn is_literal(pattern: &str) -> bool {
+    metacharacters(pattern).next().is_none()
+}
+
+/// Remove all metacharacters from `pattern`, and the `\` from escaped characters
+///
+/// Returns the resulting literal text, which is at the start of `pattern`. Bracket expressions
+/// are removed entirely. The rest of `pattern` is filled with spaces.
+pub fn strip(pattern: &mut str) -> &mut str {
+    Replacinator::new_in(pattern, |src| {
+        while let Some(c) = src.peek() {
+            match c {
+                '\\' => {
+                    src.discard_char();
+                    src.skip_char();
+                }
+                '[' => {
+                    let len = bracket_end(src.remainder()).unwrap_or(1);
+                    src.discard_bytes(len);
+                }
+                '*' | '?' | ']' | '{' | '}' => {
+                    src.discard_char();
+                }
+                _ => {
+                    src.skip_while(|c| !matches!(c, '\\' | '[' | '*' | '?' | ']' | '{' | '}'));
+                }
+            }
+        }
+        src.take_start()
+    })
+}
+
+/// Find the end of the bracket expression at the start of `pattern`, if it is closed
+fn bracket_end(pattern: &str) -> Option<usize> {
+    let mut chars = pattern.char_indices().skip(1).peekable();
+    // A `]` immediately after the opening `[` (or `[!`) is part of the expression
+    if let Some((_, '!' | '^')) = chars.peek() {
+        chars.next();
+    }
+    if let Some((_, ']')) = chars.peek() {
+        chars.next();
+    }
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            ']' => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
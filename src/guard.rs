@@ -0,0 +1,177 @@
+use core::fmt;
+
+use crate::{
+    raw::unchecked_from_utf8_mut, AsMutStr, ByteReplacinator, Position, Replacinator, State,
+    StateError,
+};
+
+/// An owned [`Replacinator`], which can be stored without using [`Replacinator::new_in`]
+///
+/// A `ReplacinatorGuard` cannot rely on its `Drop` impl being called, as it may be leaked.
+/// Instead, it returns the borrowed string to valid UTF-8 whenever control returns to the caller.
+/// This means that the [`Replacinator`] can only be used mutably within [`ReplacinatorGuard::with`].
+pub struct ReplacinatorGuard<'a> {
+    inner: Replacinator<'a>,
+}
+
+impl<'a> ReplacinatorGuard<'a> {
+    /// Create a new guard for the given string slice
+    pub fn new(value: impl AsMutStr<'a>) -> Self {
+        Self {
+            // Safety: The second section is returned to valid UTF-8 at the end of every call to `with`,
+            // and is initially empty
+            inner: unsafe { Replacinator::construct(value.into_mut_str()) },
+        }
+    }
+
+    /// Create a new guard for a part of a string, which starts at `position` in the original string
+    pub(crate) fn starting_at(value: &'a mut str, position: Position) -> Self {
+        let mut guard = Self::new(value);
+        guard.inner.inner.position = position;
+        guard.inner.taken = position;
+        guard
+    }
+
+    /// Operate on the [`Replacinator`] within the given function
+    ///
+    /// Once `with` returns (or panics), the second section is filled with spaces so that the
+    /// borrowed string is valid UTF-8. This takes time proportional to the length of the
+    /// second section, so the guard should not be used for a single character at a time.
+    pub fn with<R>(&mut self, with: impl FnOnce(&mut Replacinator<'a>) -> R) -> R {
+        struct Restore<'r, 'a>(&'r mut Replacinator<'a>);
+
+        impl Drop for Restore<'_, '_> {
+            fn drop(&mut self) {
+                self.0.fill_invalid_region();
+            }
+        }
+
+        let restore = Restore(&mut self.inner);
+        with(&mut *restore.0)
+    }
+
+    /// Operate on the [`Replacinator`] within the given function, unless it is poisoned
+    ///
+    /// A [`Replacinator`] is poisoned if an internal invariant was violated during an earlier
+    /// call, which would have panicked. The borrowed string has already been filled with spaces.
+    pub fn try_with<R>(
+        &mut self,
+        with: impl FnOnce(&mut Replacinator<'a>) -> R,
+    ) -> Result<R, Poisoned> {
+        if self.inner.is_poisoned() {
+            return Err(Poisoned);
+        }
+        Ok(self.with(with))
+    }
+
+    /// View the [`Replacinator`]
+    pub fn get(&self) -> &Replacinator<'a> {
+        &self.inner
+    }
+
+    /// Synchronise the [`Replacinator`], and return the string which it has not yet handed out
+    ///
+    /// This is the whole original string, unless [`Replacinator::take_start`] was used.
+    ///
+    /// If the [`Replacinator`] is poisoned, the returned string is filled with spaces.
+    pub fn finish(mut self) -> &'a mut str {
+        self.inner.restore();
+        let contents =
+            core::mem::replace(&mut self.inner.inner, ByteReplacinator::new(&mut [])).contents;
+        // Safety: All of `contents` is valid UTF-8 after restoring
+        unsafe { unchecked_from_utf8_mut(contents) }
+    }
+
+    /// Move the remainder of the [`Replacinator`] to directly after its first section, and return
+    /// them together with the number of bytes of padding which follow them
+    ///
+    /// See [`Replacinator::take_compacted`].
+    pub fn finish_compacted(mut self) -> (&'a mut str, usize) {
+        self.with(Replacinator::take_compacted)
+    }
+
+    /// Create a guard for `value` which continues from `state`
+    ///
+    /// `value` must be the string which `state` was saved from, in its state after the
+    /// [`Replacinator`] was dropped. Slices taken before `state` was saved are not part of the
+    /// resumed [`Replacinator`]. The gap between the first section and the remainder is still
+    /// filled, so text read before `state` was saved cannot be rewound to.
+    ///
+    /// Only the lengths and character boundaries of `value` can be checked. If it has been
+    /// changed in other ways, the result is valid UTF-8, but is otherwise unspecified.
+    pub fn resume(value: impl AsMutStr<'a>, state: State) -> Result<Self, StateError> {
+        let value = value.into_mut_str();
+        let read = state.read_index();
+        if state.end > value.len() {
+            return Err(StateError::TooShort);
+        }
+        for &offset in &[state.base, state.write, read, state.end] {
+            if !value.is_char_boundary(offset) {
+                return Err(StateError::NotCharBoundary(offset));
+            }
+        }
+        let origin = value.as_ptr() as usize;
+        let value = &mut value[state.base..state.end];
+        let mut guard = Self::new(value);
+        let src = &mut guard.inner;
+        src.inner = ByteReplacinator {
+            contents: core::mem::take(&mut src.inner.contents),
+            read_position: read - state.base,
+            write_position: state.write - state.base,
+            // The gap has been filled, so must not be read again
+            write_high_water: read - state.base,
+            poisoned: false,
+            position: state.position,
+            dirty: state
+                .dirty
+                .map(|(start, end)| (origin + start, origin + end)),
+        };
+        src.compacted = state.compacted;
+        src.padding = state.padding;
+        src.fill = state.fill;
+        src.taken = state.taken;
+        src.check_invariants();
+        Ok(guard)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'a> Replacinator<'a> {
+    /// Create a new [`ReplacinatorGuard`] for `value`, and await the future returned by `with`
+    ///
+    /// This is the counterpart of [`Replacinator::new_in`] for transforms which need to await
+    /// between operations, such as to wait for more data. The [`Replacinator`] can only be used
+    /// within [`ReplacinatorGuard::with`], which is synchronous, so `value` is valid UTF-8 at
+    /// every `.await`. The guard can be finished to take the transformed string.
+    ///
+    /// This means that `value` is valid UTF-8 however the future ends. If it is dropped before it
+    /// completes, the guard inside it is dropped, which fills the second section again, so
+    /// `value` holds the text written so far, then the padding, then the unread text. If it is
+    /// leaked instead, the second section was already filled when `with` last returned. If it is
+    /// dropped before it is first polled, `with` is never called, so `value` is unchanged.
+    ///
+    /// This requires the `async` feature.
+    pub async fn new_in_async<R, F>(
+        value: impl AsMutStr<'a>,
+        with: impl FnOnce(ReplacinatorGuard<'a>) -> F,
+    ) -> R
+    where
+        F: core::future::Future<Output = R>,
+    {
+        with(ReplacinatorGuard::new(value)).await
+    }
+}
+
+/// The error returned by [`ReplacinatorGuard::try_with`] and the `try_` methods of
+/// [`Replacinator`] when it is poisoned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Poisoned;
+
+impl fmt::Display for Poisoned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the replacinator was poisoned by a violated internal invariant"
+        )
+    }
+}
@@ -0,0 +1,145 @@
+//! In-place parsing of HTTP header lines and `application/x-www-form-urlencoded` text, such as
+//! query strings and form bodies
+
+use core::iter::FusedIterator;
+
+use crate::{NoObserver, Observer, Replacinator};
+
+/// Split a header line such as `Content-Type: text/html` into its name and value
+///
+/// Whitespace around the value is removed, as is a line terminator at the end of `line`.
+/// Returns `None` if there is no `:`, or the name is empty or contains whitespace.
+pub fn split_header(line: &mut str) -> Option<(&mut str, &mut str)> {
+    let colon = line.find(':')?;
+    let (name, value) = line.split_at_mut(colon);
+    if name.is_empty() || name.contains(|c: char| c.is_ascii_whitespace()) {
+        return None;
+    }
+    let value = &mut value[1..];
+    let start = value.len() - value.trim_start_matches(is_space).len();
+    let value = &mut value[start..];
+    let len = value.trim_end_matches(is_space).len();
+    Some((name, &mut value[..len]))
+}
+
+/// Split the remainder of `src` into its `name=value` pairs, decoding each name and value in
+/// place
+///
+/// This follows the `application/x-www-form-urlencoded` parser of the URL Standard: pairs are
+/// separated by `&`, empty pairs are skipped, and a pair without `=` has an empty value. `+` is
+/// decoded as a space. Decoding never fails: a `%` which isn't followed by two hex digits is
+/// left as it is, and bytes which aren't valid UTF-8 are replaced with U+FFFD.
+///
+/// Each name and value is taken from `src` as it is yielded, as with
+/// [`Replacinator::take_start`].
+pub fn decode_form_urlencoded<'r, 'a, O: Observer>(
+    src: &'r mut Replacinator<'a, O>,
+) -> FormPairs<'r, 'a, O> {
+    FormPairs { src }
+}
+
+/// An iterator over the decoded pairs of some `application/x-www-form-urlencoded` text
+///
+/// Created by [`decode_form_urlencoded`].
+pub struct FormPairs<'r, 'a, O = NoObserver> {
+    src: &'r mut Replacinator<'a, O>,
+}
+
+impl<'r, 'a, O: Observer> FormPairs<'r, 'a, O> {
+    /// Decode text up to the next `&`, or `=` if `name` is set, and take it from `src`
+    fn decode(&mut self, name: bool) -> &'a mut str {
+        let src = &mut *self.src;
+        let mark = src.mark();
+        loop {
+            src.skip_while(|c| !(matches!(c, '%' | '+' | '&') || name && c == '='));
+            match src.peek() {
+                Some('+') => {
+                    src.read_char();
+                    src.write_char(' ');
+                }
+                Some('%') => decode_percent(src),
+                _ => break,
+            }
+        }
+        src.take_marked(mark)
+            .expect("The mark was made from this Replacinator")
+    }
+}
+
+impl<'r, 'a, O: Observer> Iterator for FormPairs<'r, 'a, O> {
+    type Item = (&'a mut str, &'a mut str);
+
+    fn next(&mut self) -> Option<(&'a mut str, &'a mut str)> {
+        while self.src.peek() == Some('&') {
+            self.src.discard_char();
+        }
+        self.src.peek()?;
+        let name = self.decode(true);
+        if self.src.peek() == Some('=') {
+            self.src.discard_char();
+        }
+        let value = self.decode(false);
+        Some((name, value))
+    }
+}
+
+impl<'r, 'a, O: Observer> FusedIterator for FormPairs<'r, 'a, O> {}
+
+/// Decode the character encoded by the `%XX` sequences at the start of the remainder of `src`
+///
+/// A `%` which doesn't start a sequence is written back unchanged. The bytes of an invalid or
+/// incomplete character are replaced by U+FFFD, as in the URL Standard, which is never longer
+/// than the sequences it replaces.
+fn decode_percent<O: Observer>(src: &mut Replacinator<'_, O>) {
+    let remainder = src.remainder().as_bytes();
+    let lead = match percent_byte(remainder) {
+        Some(lead) => lead,
+        None => {
+            src.skip_char();
+            return;
+        }
+    };
+    let len = match lead {
+        0x00..=0x7F => 1,
+        0xC2..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF4 => 4,
+        _ => 1,
+    };
+    let mut bytes = [lead, 0, 0, 0];
+    let mut decoded = 1;
+    while decoded < len {
+        match percent_byte(&remainder[decoded * 3..]) {
+            Some(byte @ 0x80..=0xBF) => bytes[decoded] = byte,
+            _ => break,
+        }
+        decoded += 1;
+    }
+    let (c, used) = match core::str::from_utf8(&bytes[..decoded]) {
+        Ok(text) => (
+            text.chars()
+                .next()
+                .expect("A decoded character is not empty"),
+            decoded,
+        ),
+        Err(e) => ('\u{FFFD}', e.error_len().unwrap_or(decoded)),
+    };
+    src.discard_bytes(used * 3);
+    src.write_char(c);
+}
+
+/// The byte encoded by the `%XX` sequence at the start of `bytes`
+fn percent_byte(bytes: &[u8]) -> Option<u8> {
+    match bytes {
+        [b'%', high, low, ..] => {
+            let digit = |b: u8| char::from(b).to_digit(16);
+            Some((digit(*high)? * 16 + digit(*low)?) as u8)
+        }
+        _ => None,
+    }
+}
+
+/// Whether `c` is optional whitespace in a header, or its line terminator
+fn is_space(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\r' | '\n')
+}
@@ -0,0 +1,113 @@
+//! Adapters between a [`Replacinator`] and the [`std::io`] traits
+//!
+//! This module requires the `std` feature.
+
+use std::io;
+
+use crate::{bytes::differing, NoObserver, Observer, Replacinator};
+
+/// An [`io::Write`] sink which writes to the end of the first section of a [`Replacinator`]
+///
+/// The bytes written must be valid UTF-8, but characters may be split between calls to `write`.
+/// A character is only added to the first section once all of its bytes have been written.
+/// If invalid UTF-8 is written, `write` returns an error of kind [`io::ErrorKind::InvalidData`],
+/// after adding the characters before the invalid bytes.
+/// As with [`Replacinator::write_str`], there must be room before the read position: if there
+/// is none, `write` returns `Ok(0)`.
+pub struct Writer<'r, 'a, O = NoObserver> {
+    src: &'r mut Replacinator<'a, O>,
+    /// The number of bytes of an incomplete character which have been written after the write position
+    partial: usize,
+}
+
+impl<'r, 'a, O: Observer> Writer<'r, 'a, O> {
+    /// Create a writer for `src`
+    pub fn new(src: &'r mut Replacinator<'a, O>) -> Self {
+        Self { src, partial: 0 }
+    }
+
+    /// The [`Replacinator`] being written to
+    ///
+    /// Any incomplete character which has been written is discarded.
+    pub fn into_inner(self) -> &'r mut Replacinator<'a, O> {
+        self.src
+    }
+}
+
+impl<'r, 'a, O: Observer> io::Write for Writer<'r, 'a, O> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let inner = &mut self.src.inner;
+        let start = inner.write_position + self.partial;
+        let len = buf.len().min(inner.read_position - start);
+        let region = &mut inner.contents[start..start + len];
+        if let Some(changed) = differing(region, buf[..len].iter().copied()) {
+            region.copy_from_slice(&buf[..len]);
+            inner.mark_dirty(start + changed.start..start + changed.end);
+        }
+        inner.write_high_water = inner.write_high_water.max(start + len);
+
+        let written = start + len - inner.write_position;
+        let (valid, result) =
+            match core::str::from_utf8(&inner.contents[inner.write_position..][..written]) {
+                Ok(text) => (text.len(), Ok(len)),
+                Err(e) if e.error_len().is_none() => (e.valid_up_to(), Ok(len)),
+                Err(e) => (
+                    e.valid_up_to(),
+                    Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "stream did not contain valid UTF-8",
+                    )),
+                ),
+            };
+        // SAFETY: The first `valid` bytes were checked to be valid UTF-8 above
+        unsafe { self.src.advance_write(valid) };
+        self.partial = match result {
+            Ok(_) => written - valid,
+            Err(_) => 0,
+        };
+        result
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// An [`io::Read`] source which reads from the remainder of a [`Replacinator`], without writing
+///
+/// Characters may be split between calls to `read`. The read position of the [`Replacinator`]
+/// only moves past a character once all of its bytes have been read.
+pub struct Reader<'r, 'a, O = NoObserver> {
+    src: &'r mut Replacinator<'a, O>,
+    /// The number of bytes of the next character which have already been read
+    partial: usize,
+}
+
+impl<'r, 'a, O: Observer> Reader<'r, 'a, O> {
+    /// Create a reader for `src`
+    pub fn new(src: &'r mut Replacinator<'a, O>) -> Self {
+        Self { src, partial: 0 }
+    }
+
+    /// The [`Replacinator`] being read from
+    ///
+    /// A character which has been partially read remains in the remainder.
+    pub fn into_inner(self) -> &'r mut Replacinator<'a, O> {
+        self.src
+    }
+}
+
+impl<'r, 'a, O: Observer> io::Read for Reader<'r, 'a, O> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remainder = &self.src.remainder().as_bytes()[self.partial..];
+        let len = buf.len().min(remainder.len());
+        buf[..len].copy_from_slice(&remainder[..len]);
+        let mut read = self.partial + len;
+        while !self.src.remainder().is_char_boundary(read) {
+            read -= 1;
+        }
+        self.src.discard_bytes(read);
+        self.partial = self.partial + len - read;
+        Ok(len)
+    }
+}
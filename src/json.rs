@@ -0,0 +1,1286 @@
+//! In-place processing of JSON documents
+//!
+//! The functions in this module walk a JSON document using a [`Replacinator`], without
+//! building a tree of values or allocating.
+
+use core::fmt;
+
+use crate::{
+    unescape::{self, UnicodeEscape},
+    NoObserver, Observer, Position, Replacinator,
+};
+
+/// An error encountered whilst walking a JSON document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    position: Position,
+}
+
+impl Error {
+    /// The kind of error which occurred
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The position in the original document at which the error was detected
+    pub fn position(&self) -> Position {
+        self.position
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        describe(self.kind, f)?;
+        write!(f, " at byte {}", self.position.byte())
+    }
+}
+
+/// Describe an error of kind `kind`, without its position
+pub(crate) fn describe(kind: ErrorKind, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match kind {
+        ErrorKind::UnexpectedEnd => write!(f, "unexpected end of JSON input"),
+        ErrorKind::UnexpectedChar(c) => write!(f, "unexpected character {:?}", c),
+        ErrorKind::InvalidEscape => write!(f, "invalid escape sequence in string"),
+        ErrorKind::InvalidMask => write!(f, "mask character cannot be used in a JSON string"),
+    }
+}
+
+/// The kinds of [`Error`] which can occur whilst walking a JSON document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The document ended before a complete value was read
+    UnexpectedEnd,
+    /// A character was found which is not valid at this point in the document
+    UnexpectedChar(char),
+    /// A string contained an invalid escape sequence
+    InvalidEscape,
+    /// The character requested for masking would need to be escaped
+    InvalidMask,
+}
+
+/// Mask the values in `document` whose key paths match any of `patterns`
+///
+/// Each pattern is a sequence of segments separated by `.`, which is matched against the
+/// full path from the root of the document to the value. A segment is either an object key,
+/// an array index, `*` (which matches any single segment) or `**` (which matches any number of
+/// segments). For example, `**.password` matches a `password` key at any depth, and `*.token`
+/// matches the `token` key of any top-level object.
+///
+/// Every character of a matched string is replaced with `mask`, or with a `*` for each of its
+/// bytes if it is shorter than `mask`, and matched numbers are replaced with `0`. If a matched
+/// value is an object or array, every string and number within it is masked. Booleans and `null`
+/// are left unchanged. As masking never grows the document, any space which is freed is filled
+/// with whitespace between tokens.
+///
+/// Returns the number of values which were masked. If an error is returned, the document may have
+/// been partially redacted, but is still valid UTF-8.
+///
+/// This function requires the `serde` feature.
+#[cfg(feature = "serde")]
+pub fn redact(document: &mut str, patterns: &[&str], mask: char) -> Result<usize, Error> {
+    Replacinator::new_in(document, |src| {
+        let mut walker = Walker::new(src);
+        if mask == '"' || mask == '\\' || mask.is_control() {
+            return Err(walker.error(ErrorKind::InvalidMask));
+        }
+        let mut redactor = Redactor {
+            patterns,
+            mask,
+            masked: 0,
+        };
+        walker.skip_whitespace();
+        redactor.value(&mut walker, None)?;
+        walker.skip_whitespace();
+        walker.end()?;
+        Ok(redactor.masked)
+    })
+}
+
+/// Find the value in `document` identified by the [JSON Pointer](https://tools.ietf.org/html/rfc6901) `pointer`
+///
+/// If the target is a string, its contents are unescaped in place and returned without the
+/// surrounding quotes. Otherwise, the text of the value is returned unchanged. Note that unescaping
+/// the string may leave `document` as invalid JSON.
+///
+/// Returns `None` if there is no such value, or if the document is not valid JSON up to the target.
+pub fn pointer<'a>(document: &'a mut str, pointer: &str) -> Option<&'a mut str> {
+    if !(pointer.is_empty() || pointer.starts_with('/')) {
+        return None;
+    }
+    Replacinator::new_in(document, |src| {
+        let mut walker = Walker::new(src);
+        walker.skip_whitespace();
+        for token in pointer.split('/').skip(1) {
+            match walker.peek().ok()? {
+                '{' => {
+                    walker.expect('{').ok()?;
+                    walker.skip_whitespace();
+                    if walker.eat('}') {
+                        return None;
+                    }
+                    loop {
+                        let key = walker.key().ok()?;
+                        if Unescape::new(key).eq(pointer_token(token)) {
+                            break;
+                        }
+                        walker.skip_value().ok()?;
+                        if !walker.separator('}').ok()? {
+                            return None;
+                        }
+                    }
+                }
+                '[' => {
+                    // Leading zeroes are not permitted in array indices
+                    if token.len() > 1 && token.starts_with('0') {
+                        return None;
+                    }
+                    let index: usize = token.parse().ok()?;
+                    walker.expect('[').ok()?;
+                    walker.skip_whitespace();
+                    if walker.eat(']') {
+                        return None;
+                    }
+                    for _ in 0..index {
+                        walker.skip_value().ok()?;
+                        if !walker.separator(']').ok()? {
+                            return None;
+                        }
+                    }
+                }
+                _ => return None,
+            }
+        }
+        if walker.peek().ok()? == '"' {
+            walker.unescape_string().ok()
+        } else {
+            walker.take_start();
+            walker.skip_value().ok()?;
+            Some(walker.take_start())
+        }
+    })
+}
+
+/// The characters of a JSON Pointer reference token, with `~0` and `~1` unescaped
+///
+/// Yields `None` for an invalid escape sequence
+fn pointer_token(token: &str) -> impl Iterator<Item = Option<char>> + '_ {
+    let mut chars = token.chars();
+    core::iter::from_fn(move || {
+        Some(match chars.next()? {
+            '~' => match chars.next() {
+                Some('0') => Some('~'),
+                Some('1') => Some('/'),
+                _ => None,
+            },
+            c => Some(c),
+        })
+    })
+}
+
+/// Rewrite `document` towards the [JSON Canonicalization Scheme](https://tools.ietf.org/html/rfc8785)
+///
+/// This removes all insignificant whitespace, rewrites strings using the minimal escapes, and
+/// normalises numbers where this can be done without growing them (for example, `1.50E+01` becomes
+/// `1.5e1`). The canonical form of some documents cannot be produced in place, such as when object
+/// keys need to be reordered; the returned [`Canonicalization`] reports whether this was the case.
+///
+/// The canonical document is `&document[..len]`, where `len` is [`Canonicalization::len`].
+/// The rest of the document is filled with whitespace.
+pub fn canonicalize(document: &mut str) -> Result<Canonicalization, Error> {
+    Replacinator::new_in(document, |src| {
+        let mut walker = Walker::new(src);
+        let mut result = Canonicalization {
+            len: 0,
+            keys_sorted: true,
+            numbers_canonical: true,
+        };
+        walker.discard_whitespace();
+        result.value(&mut walker)?;
+        walker.discard_whitespace();
+        walker.end()?;
+        result.len = walker.src.written_len();
+        Ok(result)
+    })
+}
+
+/// The outcome of [`canonicalize`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Canonicalization {
+    len: usize,
+    keys_sorted: bool,
+    numbers_canonical: bool,
+}
+
+impl Canonicalization {
+    /// The length in bytes of the rewritten document
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the rewritten document is empty, which is never the case for valid JSON
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether the keys of every object were already in canonical order
+    pub fn keys_sorted(&self) -> bool {
+        self.keys_sorted
+    }
+
+    /// Whether every number is known to be in its canonical form
+    ///
+    /// Numbers which use an exponent or have more than 15 significant digits may need to be
+    /// reformatted, which is not attempted.
+    pub fn numbers_canonical(&self) -> bool {
+        self.numbers_canonical
+    }
+
+    /// Whether the rewritten document is in canonical form
+    pub fn is_canonical(&self) -> bool {
+        self.keys_sorted && self.numbers_canonical
+    }
+
+    fn value(&mut self, walker: &mut Walker<'_, '_>) -> Result<(), Error> {
+        match walker.peek()? {
+            '{' => {
+                walker.expect('{')?;
+                walker.discard_whitespace();
+                if walker.eat('}') {
+                    return Ok(());
+                }
+                // The range of the previous key within the written section
+                let mut previous: Option<(usize, usize)> = None;
+                loop {
+                    let start = walker.src.written_len() + 1;
+                    self.string(walker)?;
+                    let key = (start, walker.src.written_len() - 1);
+                    if let Some(previous) = previous {
+                        let written = walker.src.start();
+                        let previous = &written[previous.0..previous.1];
+                        let key = &written[key.0..key.1];
+                        if utf16_units(previous).cmp(utf16_units(key)) != core::cmp::Ordering::Less
+                        {
+                            self.keys_sorted = false;
+                        }
+                    }
+                    previous = Some(key);
+                    walker.discard_whitespace();
+                    walker.expect(':')?;
+                    walker.discard_whitespace();
+                    self.value(walker)?;
+                    walker.discard_whitespace();
+                    if !walker.eat(',') {
+                        return walker.expect('}');
+                    }
+                    walker.discard_whitespace();
+                }
+            }
+            '[' => {
+                walker.expect('[')?;
+                walker.discard_whitespace();
+                if walker.eat(']') {
+                    return Ok(());
+                }
+                loop {
+                    self.value(walker)?;
+                    walker.discard_whitespace();
+                    if !walker.eat(',') {
+                        return walker.expect(']');
+                    }
+                    walker.discard_whitespace();
+                }
+            }
+            '"' => self.string(walker),
+            '-' | '0'..='9' => {
+                let start = walker.src.written_len();
+                walker.skip_number()?;
+                let end = walker.src.written_len();
+                let number = &mut walker.src.inner.contents[start..end];
+                let upper_exponent = number.contains(&b'E');
+                let (len, canonical) = canonicalize_number(number);
+                // Otherwise, the number was already in its normal form, so is unchanged
+                if len != number.len() || upper_exponent {
+                    walker.src.inner.mark_dirty(start..start + len);
+                    walker.src.truncate_start(start);
+                    // SAFETY: `canonicalize_number` only moves ASCII bytes within the number
+                    unsafe { walker.src.advance_write(len) };
+                }
+                self.numbers_canonical &= canonical;
+                Ok(())
+            }
+            _ => walker.skip_value(),
+        }
+    }
+
+    /// Rewrite a string using the minimal escapes
+    fn string(&mut self, walker: &mut Walker<'_, '_>) -> Result<(), Error> {
+        walker.expect('"')?;
+        loop {
+            let c = match walker.peek()? {
+                '"' => break,
+                '\\' => {
+                    walker.read()?;
+                    walker.read_escape()?
+                }
+                c if c < ' ' => return Err(walker.error(ErrorKind::UnexpectedChar(c))),
+                c => {
+                    walker.read()?;
+                    c
+                }
+            };
+            let escape = match c {
+                '"' => '"',
+                '\\' => '\\',
+                '\x08' => 'b',
+                '\x0c' => 'f',
+                '\n' => 'n',
+                '\r' => 'r',
+                '\t' => 't',
+                c if c < ' ' => {
+                    const HEX: &[u8; 16] = b"0123456789abcdef";
+                    walker.src.write_char('\\');
+                    walker.src.write_char('u');
+                    walker.src.write_char('0');
+                    walker.src.write_char('0');
+                    walker.src.write_char(HEX[c as usize >> 4].into());
+                    walker.src.write_char(HEX[c as usize & 0xF].into());
+                    continue;
+                }
+                c => {
+                    walker.src.write_char(c);
+                    continue;
+                }
+            };
+            walker.src.write_char('\\');
+            walker.src.write_char(escape);
+        }
+        walker.expect('"')
+    }
+}
+
+/// The UTF-16 code units of the contents of an escaped JSON string, as used to order keys
+fn utf16_units(escaped: &str) -> impl Iterator<Item = u16> + '_ {
+    Unescape::new(escaped).flat_map(|c| {
+        let mut units = [0; 2];
+        let len = c.map_or(0, |c| c.encode_utf16(&mut units).len());
+        IntoIterator::into_iter(units).take(len)
+    })
+}
+
+/// Normalise a valid JSON number in place, without growing it
+///
+/// Returns the new length of the number, and whether it is known to be canonical.
+fn canonicalize_number(number: &mut [u8]) -> (usize, bool) {
+    let mantissa_end = number
+        .iter()
+        .position(|&b| b == b'e' || b == b'E')
+        .unwrap_or(number.len());
+    let point = number[..mantissa_end].iter().position(|&b| b == b'.');
+    let mut len = mantissa_end;
+    if let Some(point) = point {
+        // Remove trailing zeroes from the fraction, and the point if the fraction is empty
+        while number[len - 1] == b'0' {
+            len -= 1;
+        }
+        if len == point + 1 {
+            len = point;
+        }
+    }
+    let is_zero = number[..len]
+        .iter()
+        .all(|&b| !b.is_ascii_digit() || b == b'0');
+    if is_zero {
+        number[0] = b'0';
+        return (1, true);
+    }
+    let significant = number[..len]
+        .iter()
+        .filter(|b| b.is_ascii_digit())
+        .skip_while(|&&b| b == b'0')
+        .count();
+    let mut canonical = significant <= 15;
+    if mantissa_end < number.len() {
+        // Normalise the exponent, removing any `+` and leading zeroes
+        let mut exponent = mantissa_end + 1;
+        let negative = number[exponent] == b'-';
+        if matches!(number[exponent], b'+' | b'-') {
+            exponent += 1;
+        }
+        while exponent < number.len() - 1 && number[exponent] == b'0' {
+            exponent += 1;
+        }
+        if number[exponent..] != *b"0" {
+            canonical = false;
+            number[len] = b'e';
+            len += 1;
+            if negative {
+                number[len] = b'-';
+                len += 1;
+            }
+            number.copy_within(exponent.., len);
+            len += number.len() - exponent;
+        }
+    }
+    let integer_digits = number[..len]
+        .iter()
+        .take_while(|&&b| b != b'.' && b != b'e')
+        .filter(|b| b.is_ascii_digit())
+        .count();
+    // Very large and very small numbers are written using an exponent
+    let fraction_zeroes = match point {
+        Some(point) if integer_digits == 1 && number[point - 1] == b'0' => number[point + 1..len]
+            .iter()
+            .take_while(|&&b| b == b'0')
+            .count(),
+        _ => 0,
+    };
+    canonical &= integer_digits <= 21 && fraction_zeroes < 6;
+    (len, canonical)
+}
+
+/// Remove the members of the top-level object in `document` whose keys are not in `keep`
+///
+/// This can be used with [`records`] to project each record of newline-delimited JSON.
+/// Whitespace between the members of the object is removed, and any space which is freed
+/// is filled with whitespace after the object.
+///
+/// Returns the number of members which were removed.
+pub fn project(document: &mut str, keep: &[&str]) -> Result<usize, Error> {
+    Replacinator::new_in(document, |src| {
+        let mut walker = Walker::new(src);
+        let mut removed = 0;
+        walker.skip_whitespace();
+        walker.expect('{')?;
+        walker.discard_whitespace();
+        if !walker.eat('}') {
+            let mut first = true;
+            loop {
+                let member_start = walker.src.written_len();
+                if !first {
+                    // The separator from the input has always been read by this point
+                    walker.src.write_char(',');
+                }
+                let key_start = walker.src.written_len() + 1;
+                walker.skip_string()?;
+                let key = &walker.src.start()[key_start..walker.src.written_len() - 1];
+                let kept = keep
+                    .iter()
+                    .any(|name| Unescape::new(key).eq(name.chars().map(Some)));
+                walker.skip_whitespace();
+                walker.expect(':')?;
+                walker.skip_whitespace();
+                walker.skip_value()?;
+                if kept {
+                    first = false;
+                } else {
+                    walker.src.truncate_start(member_start);
+                    removed += 1;
+                }
+                walker.discard_whitespace();
+                if walker.src.peek() == Some(',') {
+                    walker.read()?;
+                    walker.discard_whitespace();
+                } else {
+                    walker.expect('}')?;
+                    break;
+                }
+            }
+        }
+        walker.skip_whitespace();
+        walker.end()?;
+        Ok(removed)
+    })
+}
+
+/// Parse a JSON array of strings from the start of the remainder of `src`, unescaping each
+/// string in place
+///
+/// Whitespace is allowed before the array and between its tokens, and every escape sequence is
+/// supported, including surrogate pairs written as two `\uXXXX` escapes. The whole array is
+/// checked before anything is changed, so if an error is returned, nothing has been read from
+/// `src`. Otherwise, the returned iterator unescapes and yields each string in turn, and once
+/// it is exhausted, the remainder of `src` starts after the closing `]`.
+///
+/// The text between the strings, such as the quotes and commas, is taken along with them, so is
+/// no longer available from `src`. The strings can be collected without an allocator using
+/// [`Segments`](crate::segments::Segments).
+pub fn parse_string_array<'r, 'a, O: Observer>(
+    src: &'r mut Replacinator<'a, O>,
+) -> Result<StringArray<'r, 'a, O>, Error> {
+    let mut checker = Checker {
+        text: src.remainder(),
+        offset: 0,
+    };
+    if let Err(kind) = checker.string_array() {
+        let offset = checker.offset;
+        return Err(Error {
+            kind,
+            position: src.position().after(&src.remainder()[..offset]),
+        });
+    }
+    let mut walker = Walker::new(src);
+    walker.skip_whitespace();
+    walker.src.skip_char();
+    walker.skip_whitespace();
+    let remaining = !walker.eat(']');
+    Ok(StringArray { walker, remaining })
+}
+
+/// An iterator over the strings of a JSON array, which are unescaped as they are yielded
+///
+/// Created by [`parse_string_array`].
+pub struct StringArray<'r, 'a, O = NoObserver> {
+    walker: Walker<'r, 'a, O>,
+    remaining: bool,
+}
+
+impl<'r, 'a, O: Observer> Iterator for StringArray<'r, 'a, O> {
+    type Item = &'a mut str;
+
+    fn next(&mut self) -> Option<&'a mut str> {
+        if !self.remaining {
+            return None;
+        }
+        let string = self
+            .walker
+            .unescape_string()
+            .expect("The array was checked by `parse_string_array`");
+        self.remaining = self
+            .walker
+            .separator(']')
+            .expect("The array was checked by `parse_string_array`");
+        Some(string)
+    }
+}
+
+impl<'r, 'a, O: Observer> core::iter::FusedIterator for StringArray<'r, 'a, O> {}
+
+/// A cursor which checks JSON without changing it, for when a document must be known to be
+/// valid before it is rewritten
+///
+/// This accepts exactly the text which the corresponding methods of [`Walker`] accept. After an
+/// error, `offset` is the offset in `text` at which it was detected.
+struct Checker<'t> {
+    text: &'t str,
+    offset: usize,
+}
+
+impl<'t> Checker<'t> {
+    fn peek(&self) -> Result<char, ErrorKind> {
+        self.text[self.offset..]
+            .chars()
+            .next()
+            .ok_or(ErrorKind::UnexpectedEnd)
+    }
+
+    fn read(&mut self) -> Result<char, ErrorKind> {
+        let c = self.peek()?;
+        self.offset += c.len_utf8();
+        Ok(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ErrorKind> {
+        match self.peek()? {
+            c if c == expected => {
+                self.offset += c.len_utf8();
+                Ok(())
+            }
+            c => Err(ErrorKind::UnexpectedChar(c)),
+        }
+    }
+
+    fn whitespace(&mut self) {
+        while let Ok(' ' | '\t' | '\n' | '\r') = self.peek() {
+            self.offset += 1;
+        }
+    }
+
+    fn string_array(&mut self) -> Result<(), ErrorKind> {
+        self.whitespace();
+        self.expect('[')?;
+        self.whitespace();
+        if self.expect(']').is_ok() {
+            return Ok(());
+        }
+        loop {
+            self.string()?;
+            self.whitespace();
+            if self.expect(',').is_ok() {
+                self.whitespace();
+            } else {
+                return self.expect(']');
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<(), ErrorKind> {
+        self.expect('"')?;
+        loop {
+            match self.peek()? {
+                '"' => return self.expect('"'),
+                '\\' => {
+                    self.offset += 1;
+                    self.escape()?;
+                }
+                c if c < ' ' => return Err(ErrorKind::UnexpectedChar(c)),
+                c => self.offset += c.len_utf8(),
+            }
+        }
+    }
+
+    /// Check the remainder of an escape sequence after the `\`
+    fn escape(&mut self) -> Result<(), ErrorKind> {
+        let c = self.read()?;
+        if c != 'u' {
+            return unescape_simple(c).map(drop).ok_or(ErrorKind::InvalidEscape);
+        }
+        match self.hex()? {
+            0xD800..=0xDBFF => {
+                for expected in ['\\', 'u'] {
+                    if self.read()? != expected {
+                        return Err(ErrorKind::InvalidEscape);
+                    }
+                }
+                match self.hex()? {
+                    0xDC00..=0xDFFF => Ok(()),
+                    _ => Err(ErrorKind::InvalidEscape),
+                }
+            }
+            0xDC00..=0xDFFF => Err(ErrorKind::InvalidEscape),
+            _ => Ok(()),
+        }
+    }
+
+    fn hex(&mut self) -> Result<u32, ErrorKind> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self.peek()?.to_digit(16).ok_or(ErrorKind::InvalidEscape)?;
+            self.offset += 1;
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+}
+
+/// Split a buffer of newline-delimited JSON into its records
+///
+/// Each record is yielded without its line terminator (either `\n` or `\r\n`), and can be
+/// processed in place using the other functions in this module or a [`Replacinator`].
+/// Lines which only contain whitespace are skipped.
+///
+/// If the buffer does not end with a newline, the final line is yielded as a complete record.
+/// Use [`streaming_records`] if `buffer` may end partway through a record.
+pub fn records(buffer: &mut str) -> Records<'_> {
+    Records {
+        rest: buffer,
+        streaming: false,
+    }
+}
+
+/// Split a chunk of a stream of newline-delimited JSON into its complete records
+///
+/// This is the same as [`records`], except that a final line which is not terminated by a newline
+/// is not yielded. It can instead be retrieved using [`Records::into_partial`], to be prepended
+/// to the next chunk of the stream.
+pub fn streaming_records(buffer: &mut str) -> Records<'_> {
+    Records {
+        rest: buffer,
+        streaming: true,
+    }
+}
+
+/// An iterator over the records in a buffer of newline-delimited JSON
+///
+/// Created by [`records`] or [`streaming_records`].
+#[derive(Debug)]
+pub struct Records<'a> {
+    rest: &'a mut str,
+    streaming: bool,
+}
+
+impl<'a> Records<'a> {
+    /// The part of the buffer which has not yet been yielded
+    ///
+    /// Once a [`streaming_records`] iterator is exhausted, this is the incomplete final line.
+    pub fn into_partial(self) -> &'a mut str {
+        self.rest
+    }
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = &'a mut str;
+
+    fn next(&mut self) -> Option<&'a mut str> {
+        loop {
+            let line = match self.rest.find('\n') {
+                Some(end) => {
+                    let (line, rest) = core::mem::take(&mut self.rest).split_at_mut(end);
+                    self.rest = &mut rest[1..];
+                    match line.strip_suffix('\r') {
+                        Some(stripped) => {
+                            let len = stripped.len();
+                            &mut line[..len]
+                        }
+                        None => line,
+                    }
+                }
+                None if self.streaming || self.rest.is_empty() => return None,
+                None => core::mem::take(&mut self.rest),
+            };
+            if !line.trim().is_empty() {
+                return Some(line);
+            }
+        }
+    }
+}
+
+impl<'a> core::iter::FusedIterator for Records<'a> {}
+
+/// A segment of the path from the root of a document to a value
+#[cfg(feature = "serde")]
+#[derive(Clone, Copy)]
+enum Segment<'a> {
+    /// An object key, in its escaped form
+    Key(&'a str),
+    Index(usize),
+}
+
+/// A path to a value, as a linked list from the value towards the root
+#[cfg(feature = "serde")]
+struct Path<'p, 'a> {
+    segment: Segment<'a>,
+    parent: Option<&'p Path<'p, 'a>>,
+}
+
+#[cfg(feature = "serde")]
+struct Redactor<'p> {
+    patterns: &'p [&'p str],
+    mask: char,
+    masked: usize,
+}
+
+#[cfg(feature = "serde")]
+impl<'p> Redactor<'p> {
+    fn value(
+        &mut self,
+        walker: &mut Walker<'_, '_>,
+        path: Option<&Path<'_, '_>>,
+    ) -> Result<(), Error> {
+        if path.is_some()
+            && self
+                .patterns
+                .iter()
+                .any(|pattern| pattern_matches(pattern, path))
+        {
+            return self.mask(walker);
+        }
+        match walker.peek()? {
+            '{' => {
+                walker.expect('{')?;
+                walker.skip_whitespace();
+                if walker.eat('}') {
+                    return Ok(());
+                }
+                loop {
+                    let key = walker.key()?;
+                    let inner = Path {
+                        segment: Segment::Key(key),
+                        parent: path,
+                    };
+                    self.value(walker, Some(&inner))?;
+                    if !walker.separator('}')? {
+                        return Ok(());
+                    }
+                }
+            }
+            '[' => {
+                walker.expect('[')?;
+                walker.skip_whitespace();
+                if walker.eat(']') {
+                    return Ok(());
+                }
+                for index in 0.. {
+                    let inner = Path {
+                        segment: Segment::Index(index),
+                        parent: path,
+                    };
+                    self.value(walker, Some(&inner))?;
+                    if !walker.separator(']')? {
+                        break;
+                    }
+                }
+                Ok(())
+            }
+            _ => walker.skip_value(),
+        }
+    }
+
+    fn mask(&mut self, walker: &mut Walker<'_, '_>) -> Result<(), Error> {
+        match walker.peek()? {
+            '"' => {
+                walker.expect('"')?;
+                loop {
+                    let start = walker.src.inner.read_position;
+                    match walker.read()? {
+                        '"' => break,
+                        '\\' => {
+                            walker.read_escape()?;
+                        }
+                        _ => {}
+                    }
+                    // A wider mask would overwrite the rest of the document
+                    let len = walker.src.inner.read_position - start;
+                    if self.mask.len_utf8() <= len {
+                        walker.src.write_char(self.mask);
+                    } else {
+                        for _ in 0..len {
+                            walker.src.write_char('*');
+                        }
+                    }
+                }
+                walker.src.write_char('"');
+                self.masked += 1;
+                Ok(())
+            }
+            '-' | '0'..='9' => {
+                walker.discard_number()?;
+                walker.src.write_char('0');
+                self.masked += 1;
+                Ok(())
+            }
+            '{' => {
+                walker.expect('{')?;
+                walker.skip_whitespace();
+                if walker.eat('}') {
+                    return Ok(());
+                }
+                loop {
+                    walker.skip_key()?;
+                    self.mask(walker)?;
+                    if !walker.separator('}')? {
+                        return Ok(());
+                    }
+                }
+            }
+            '[' => {
+                walker.expect('[')?;
+                walker.skip_whitespace();
+                if walker.eat(']') {
+                    return Ok(());
+                }
+                loop {
+                    self.mask(walker)?;
+                    if !walker.separator(']')? {
+                        return Ok(());
+                    }
+                }
+            }
+            _ => walker.skip_value(),
+        }
+    }
+}
+
+/// Check whether `pattern` matches the full path to a value
+#[cfg(feature = "serde")]
+fn pattern_matches(pattern: &str, path: Option<&Path<'_, '_>>) -> bool {
+    /// Match the segments of `pattern` from the end, where `None` means no segments remain
+    fn matches_rev(pattern: Option<&str>, path: Option<&Path<'_, '_>>) -> bool {
+        let pattern = match pattern {
+            Some(pattern) => pattern,
+            None => return path.is_none(),
+        };
+        let (rest, last) = match pattern.rsplit_once('.') {
+            Some((rest, last)) => (Some(rest), last),
+            None => (None, pattern),
+        };
+        match path {
+            // Either `**` matches no further segments, or it consumes this one
+            _ if last == "**" => {
+                matches_rev(rest, path)
+                    || path.is_some_and(|path| matches_rev(Some(pattern), path.parent))
+            }
+            Some(path) => segment_matches(last, path.segment) && matches_rev(rest, path.parent),
+            None => false,
+        }
+    }
+    matches_rev(Some(pattern), path)
+}
+
+#[cfg(feature = "serde")]
+fn segment_matches(pattern: &str, segment: Segment<'_>) -> bool {
+    if pattern == "*" {
+        return true;
+    }
+    match segment {
+        Segment::Key(key) => Unescape::new(key).eq(pattern.chars().map(Some)),
+        Segment::Index(index) => pattern.parse() == Ok(index),
+    }
+}
+
+/// An iterator over the characters of the contents of an escaped JSON string
+///
+/// Yields `None` for an invalid escape sequence
+struct Unescape<'s> {
+    chars: core::str::Chars<'s>,
+}
+
+impl<'s> Unescape<'s> {
+    fn new(escaped: &'s str) -> Self {
+        Self {
+            chars: escaped.chars(),
+        }
+    }
+
+    fn hex(&mut self) -> Option<u32> {
+        let mut res = 0;
+        for _ in 0..4 {
+            res = res * 16 + self.chars.next()?.to_digit(16)?;
+        }
+        Some(res)
+    }
+}
+
+impl<'s> Iterator for Unescape<'s> {
+    type Item = Option<char>;
+
+    fn next(&mut self) -> Option<Option<char>> {
+        let c = self.chars.next()?;
+        if c != '\\' {
+            return Some(Some(c));
+        }
+        Some(match self.chars.next() {
+            Some('u') => self.hex().and_then(|high| {
+                if (0xD800..0xDC00).contains(&high) {
+                    if self.chars.next() != Some('\\') || self.chars.next() != Some('u') {
+                        return None;
+                    }
+                    let low = self.hex()?;
+                    if !(0xDC00..0xE000).contains(&low) {
+                        return None;
+                    }
+                    core::char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+                } else {
+                    core::char::from_u32(high)
+                }
+            }),
+            Some(c) => unescape_simple(c),
+            None => None,
+        })
+    }
+}
+
+/// Decode a single-character escape sequence, given the character after the `\`
+fn unescape_simple(c: char) -> Option<char> {
+    Some(match c {
+        '"' => '"',
+        '\\' => '\\',
+        '/' => '/',
+        'b' => '\x08',
+        'f' => '\x0c',
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        _ => return None,
+    })
+}
+
+/// A cursor over a JSON document
+pub(crate) struct Walker<'r, 'a, O = NoObserver> {
+    src: &'r mut Replacinator<'a, O>,
+}
+
+impl<'r, 'a, O: Observer> Walker<'r, 'a, O> {
+    pub(crate) fn new(src: &'r mut Replacinator<'a, O>) -> Self {
+        Self { src }
+    }
+
+    #[cold]
+    pub(crate) fn error(&self, kind: ErrorKind) -> Error {
+        Error {
+            kind,
+            position: self.src.position(),
+        }
+    }
+
+    /// The position in the original document of the next character
+    #[cfg(feature = "serde")]
+    pub(crate) fn position(&self) -> Position {
+        self.src.position()
+    }
+
+    pub(crate) fn take_start(&mut self) -> &'a mut str {
+        self.src.take_start()
+    }
+
+    pub(crate) fn peek(&self) -> Result<char, Error> {
+        self.src
+            .peek()
+            .ok_or_else(|| self.error(ErrorKind::UnexpectedEnd))
+    }
+
+    /// Read a character without writing it
+    fn read(&mut self) -> Result<char, Error> {
+        self.src
+            .read_char()
+            .ok_or_else(|| self.error(ErrorKind::UnexpectedEnd))
+    }
+
+    /// Skip the next character, which must be `expected`
+    pub(crate) fn expect(&mut self, expected: char) -> Result<(), Error> {
+        let c = self.peek()?;
+        if c != expected {
+            return Err(self.error(ErrorKind::UnexpectedChar(c)));
+        }
+        self.src.skip_char();
+        Ok(())
+    }
+
+    /// Skip the next character if it is `expected`
+    pub(crate) fn eat(&mut self, expected: char) -> bool {
+        if self.src.peek() == Some(expected) {
+            self.src.skip_char();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Check that the document has been fully read
+    pub(crate) fn end(&self) -> Result<(), Error> {
+        match self.src.peek() {
+            None => Ok(()),
+            Some(c) => Err(self.error(ErrorKind::UnexpectedChar(c))),
+        }
+    }
+
+    /// Read whitespace without writing it
+    fn discard_whitespace(&mut self) {
+        self.src
+            .discard_while(|c| matches!(c, ' ' | '\t' | '\n' | '\r'));
+    }
+
+    pub(crate) fn skip_whitespace(&mut self) {
+        self.src
+            .skip_while(|c| matches!(c, ' ' | '\t' | '\n' | '\r'));
+    }
+
+    /// After a value in a container, skip either a `,` (returning `true`) or `close`
+    pub(crate) fn separator(&mut self, close: char) -> Result<bool, Error> {
+        self.skip_whitespace();
+        if self.eat(',') {
+            self.skip_whitespace();
+            Ok(true)
+        } else {
+            self.expect(close)?;
+            Ok(false)
+        }
+    }
+
+    /// Skip an object key and the following `:`, returning the escaped contents of the key
+    ///
+    /// The returned key is split off from the [`Replacinator`], so that it remains available
+    /// whilst the value is processed.
+    fn key(&mut self) -> Result<&'a str, Error> {
+        self.take_start();
+        self.skip_string()?;
+        let key = self.take_start();
+        let key = &key[1..key.len() - 1];
+        self.skip_whitespace();
+        self.expect(':')?;
+        self.skip_whitespace();
+        Ok(key)
+    }
+
+    /// Skip an object key and the following `:` unchanged
+    fn skip_key(&mut self) -> Result<(), Error> {
+        self.skip_string()?;
+        self.skip_whitespace();
+        self.expect(':')?;
+        self.skip_whitespace();
+        Ok(())
+    }
+
+    /// Skip a value unchanged
+    pub(crate) fn skip_value(&mut self) -> Result<(), Error> {
+        match self.peek()? {
+            '"' => self.skip_string(),
+            '-' | '0'..='9' => self.skip_number(),
+            't' => self.skip_literal("true"),
+            'f' => self.skip_literal("false"),
+            'n' => self.skip_literal("null"),
+            '{' => {
+                self.expect('{')?;
+                self.skip_whitespace();
+                if self.eat('}') {
+                    return Ok(());
+                }
+                loop {
+                    self.skip_key()?;
+                    self.skip_value()?;
+                    if !self.separator('}')? {
+                        return Ok(());
+                    }
+                }
+            }
+            '[' => {
+                self.expect('[')?;
+                self.skip_whitespace();
+                if self.eat(']') {
+                    return Ok(());
+                }
+                loop {
+                    self.skip_value()?;
+                    if !self.separator(']')? {
+                        return Ok(());
+                    }
+                }
+            }
+            c => Err(self.error(ErrorKind::UnexpectedChar(c))),
+        }
+    }
+
+    pub(crate) fn skip_literal(&mut self, literal: &str) -> Result<(), Error> {
+        if self.src.skip_str(literal) {
+            return Ok(());
+        }
+        for c in literal.chars() {
+            self.expect(c)?;
+        }
+        unreachable!("The literal was not found, so `expect` must have failed")
+    }
+
+    /// Skip a string, including its quotes, unchanged
+    fn skip_string(&mut self) -> Result<(), Error> {
+        self.expect('"')?;
+        loop {
+            match self.peek()? {
+                '"' => break,
+                '\\' => {
+                    self.src.skip_char();
+                    self.skip_escape()?;
+                }
+                c if c < ' ' => return Err(self.error(ErrorKind::UnexpectedChar(c))),
+                _ => {
+                    self.src.skip_char();
+                }
+            }
+        }
+        self.expect('"')
+    }
+
+    /// Unescape a string in place, returning its contents without the quotes
+    pub(crate) fn unescape_string(&mut self) -> Result<&'a mut str, Error> {
+        self.expect('"')?;
+        self.take_start();
+        loop {
+            match self.peek()? {
+                '"' => break,
+                '\\' => {
+                    self.read()?;
+                    let c = self.read_escape()?;
+                    self.src.write_char(c);
+                }
+                c if c < ' ' => return Err(self.error(ErrorKind::UnexpectedChar(c))),
+                _ => {
+                    self.src.skip_char();
+                }
+            }
+        }
+        let contents = self.take_start();
+        self.expect('"')?;
+        Ok(contents)
+    }
+
+    /// Skip the remainder of an escape sequence after the `\` unchanged
+    fn skip_escape(&mut self) -> Result<(), Error> {
+        match self.peek()? {
+            'u' => {
+                self.src.skip_char();
+                for _ in 0..4 {
+                    if !self.peek()?.is_ascii_hexdigit() {
+                        return Err(self.error(ErrorKind::InvalidEscape));
+                    }
+                    self.src.skip_char();
+                }
+                Ok(())
+            }
+            c if unescape_simple(c).is_some() => {
+                self.src.skip_char();
+                Ok(())
+            }
+            _ => Err(self.error(ErrorKind::InvalidEscape)),
+        }
+    }
+
+    /// Skip a number unchanged
+    pub(crate) fn skip_number(&mut self) -> Result<(), Error> {
+        self.number(true)
+    }
+
+    /// Read a number without writing it
+    #[cfg(feature = "serde")]
+    fn discard_number(&mut self) -> Result<(), Error> {
+        self.number(false)
+    }
+
+    /// Read a number, writing it back if `keep` is set
+    fn number(&mut self, keep: bool) -> Result<(), Error> {
+        if self.peek()? == '-' {
+            self.number_char(keep)?;
+        }
+        if self.peek()? == '0' {
+            self.number_char(keep)?;
+        } else {
+            self.digits(keep)?;
+        }
+        if self.src.peek() == Some('.') {
+            self.number_char(keep)?;
+            self.digits(keep)?;
+        }
+        if let Some('e' | 'E') = self.src.peek() {
+            self.number_char(keep)?;
+            if let Some('+' | '-') = self.src.peek() {
+                self.number_char(keep)?;
+            }
+            self.digits(keep)?;
+        }
+        Ok(())
+    }
+
+    /// Read one or more digits, writing them back if `keep` is set
+    fn digits(&mut self, keep: bool) -> Result<(), Error> {
+        let c = self.peek()?;
+        if !c.is_ascii_digit() {
+            return Err(self.error(ErrorKind::UnexpectedChar(c)));
+        }
+        while self.src.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.number_char(keep)?;
+        }
+        Ok(())
+    }
+
+    fn number_char(&mut self, keep: bool) -> Result<(), Error> {
+        let c = self.read()?;
+        if keep {
+            self.src.write_char(c);
+        }
+        Ok(())
+    }
+
+    /// Read the remainder of an escape sequence after the `\`, returning the escaped character
+    fn read_escape(&mut self) -> Result<char, Error> {
+        let c = self.read()?;
+        if c != 'u' {
+            return unescape_simple(c).ok_or_else(|| self.error(ErrorKind::InvalidEscape));
+        }
+        UnicodeEscape::JSON.read(self.src).map_err(|e| Error {
+            kind: match e.kind() {
+                unescape::ErrorKind::UnexpectedEnd => ErrorKind::UnexpectedEnd,
+                _ => ErrorKind::InvalidEscape,
+            },
+            position: e.position(),
+        })
+    }
+}
@@ -0,0 +1,225 @@
+//! Tables of the precomposed Latin letters, shared by the transforms which compose or strip
+//! their diacritics
+
+/// The precomposed letters in the Latin-1 Supplement and Latin Extended-A blocks which are an
+/// ASCII letter followed by one combining mark, with that letter and mark, sorted by letter
+const DECOMPOSITIONS: &[(char, char, char)] = &[
+    ('\u{C0}', 'A', '\u{300}'),
+    ('\u{C1}', 'A', '\u{301}'),
+    ('\u{C2}', 'A', '\u{302}'),
+    ('\u{C3}', 'A', '\u{303}'),
+    ('\u{C4}', 'A', '\u{308}'),
+    ('\u{C5}', 'A', '\u{30A}'),
+    ('\u{C7}', 'C', '\u{327}'),
+    ('\u{C8}', 'E', '\u{300}'),
+    ('\u{C9}', 'E', '\u{301}'),
+    ('\u{CA}', 'E', '\u{302}'),
+    ('\u{CB}', 'E', '\u{308}'),
+    ('\u{CC}', 'I', '\u{300}'),
+    ('\u{CD}', 'I', '\u{301}'),
+    ('\u{CE}', 'I', '\u{302}'),
+    ('\u{CF}', 'I', '\u{308}'),
+    ('\u{D1}', 'N', '\u{303}'),
+    ('\u{D2}', 'O', '\u{300}'),
+    ('\u{D3}', 'O', '\u{301}'),
+    ('\u{D4}', 'O', '\u{302}'),
+    ('\u{D5}', 'O', '\u{303}'),
+    ('\u{D6}', 'O', '\u{308}'),
+    ('\u{D9}', 'U', '\u{300}'),
+    ('\u{DA}', 'U', '\u{301}'),
+    ('\u{DB}', 'U', '\u{302}'),
+    ('\u{DC}', 'U', '\u{308}'),
+    ('\u{DD}', 'Y', '\u{301}'),
+    ('\u{E0}', 'a', '\u{300}'),
+    ('\u{E1}', 'a', '\u{301}'),
+    ('\u{E2}', 'a', '\u{302}'),
+    ('\u{E3}', 'a', '\u{303}'),
+    ('\u{E4}', 'a', '\u{308}'),
+    ('\u{E5}', 'a', '\u{30A}'),
+    ('\u{E7}', 'c', '\u{327}'),
+    ('\u{E8}', 'e', '\u{300}'),
+    ('\u{E9}', 'e', '\u{301}'),
+    ('\u{EA}', 'e', '\u{302}'),
+    ('\u{EB}', 'e', '\u{308}'),
+    ('\u{EC}', 'i', '\u{300}'),
+    ('\u{ED}', 'i', '\u{301}'),
+    ('\u{EE}', 'i', '\u{302}'),
+    ('\u{EF}', 'i', '\u{308}'),
+    ('\u{F1}', 'n', '\u{303}'),
+    ('\u{F2}', 'o', '\u{300}'),
+    ('\u{F3}', 'o', '\u{301}'),
+    ('\u{F4}', 'o', '\u{302}'),
+    ('\u{F5}', 'o', '\u{303}'),
+    ('\u{F6}', 'o', '\u{308}'),
+    ('\u{F9}', 'u', '\u{300}'),
+    ('\u{FA}', 'u', '\u{301}'),
+    ('\u{FB}', 'u', '\u{302}'),
+    ('\u{FC}', 'u', '\u{308}'),
+    ('\u{FD}', 'y', '\u{301}'),
+    ('\u{FF}', 'y', '\u{308}'),
+    ('\u{100}', 'A', '\u{304}'),
+    ('\u{101}', 'a', '\u{304}'),
+    ('\u{102}', 'A', '\u{306}'),
+    ('\u{103}', 'a', '\u{306}'),
+    ('\u{104}', 'A', '\u{328}'),
+    ('\u{105}', 'a', '\u{328}'),
+    ('\u{106}', 'C', '\u{301}'),
+    ('\u{107}', 'c', '\u{301}'),
+    ('\u{108}', 'C', '\u{302}'),
+    ('\u{109}', 'c', '\u{302}'),
+    ('\u{10A}', 'C', '\u{307}'),
+    ('\u{10B}', 'c', '\u{307}'),
+    ('\u{10C}', 'C', '\u{30C}'),
+    ('\u{10D}', 'c', '\u{30C}'),
+    ('\u{10E}', 'D', '\u{30C}'),
+    ('\u{10F}', 'd', '\u{30C}'),
+    ('\u{112}', 'E', '\u{304}'),
+    ('\u{113}', 'e', '\u{304}'),
+    ('\u{114}', 'E', '\u{306}'),
+    ('\u{115}', 'e', '\u{306}'),
+    ('\u{116}', 'E', '\u{307}'),
+    ('\u{117}', 'e', '\u{307}'),
+    ('\u{118}', 'E', '\u{328}'),
+    ('\u{119}', 'e', '\u{328}'),
+    ('\u{11A}', 'E', '\u{30C}'),
+    ('\u{11B}', 'e', '\u{30C}'),
+    ('\u{11C}', 'G', '\u{302}'),
+    ('\u{11D}', 'g', '\u{302}'),
+    ('\u{11E}', 'G', '\u{306}'),
+    ('\u{11F}', 'g', '\u{306}'),
+    ('\u{120}', 'G', '\u{307}'),
+    ('\u{121}', 'g', '\u{307}'),
+    ('\u{122}', 'G', '\u{327}'),
+    ('\u{123}', 'g', '\u{327}'),
+    ('\u{124}', 'H', '\u{302}'),
+    ('\u{125}', 'h', '\u{302}'),
+    ('\u{128}', 'I', '\u{303}'),
+    ('\u{129}', 'i', '\u{303}'),
+    ('\u{12A}', 'I', '\u{304}'),
+    ('\u{12B}', 'i', '\u{304}'),
+    ('\u{12C}', 'I', '\u{306}'),
+    ('\u{12D}', 'i', '\u{306}'),
+    ('\u{12E}', 'I', '\u{328}'),
+    ('\u{12F}', 'i', '\u{328}'),
+    ('\u{130}', 'I', '\u{307}'),
+    ('\u{134}', 'J', '\u{302}'),
+    ('\u{135}', 'j', '\u{302}'),
+    ('\u{136}', 'K', '\u{327}'),
+    ('\u{137}', 'k', '\u{327}'),
+    ('\u{139}', 'L', '\u{301}'),
+    ('\u{13A}', 'l', '\u{301}'),
+    ('\u{13B}', 'L', '\u{327}'),
+    ('\u{13C}', 'l', '\u{327}'),
+    ('\u{13D}', 'L', '\u{30C}'),
+    ('\u{13E}', 'l', '\u{30C}'),
+    ('\u{143}', 'N', '\u{301}'),
+    ('\u{144}', 'n', '\u{301}'),
+    ('\u{145}', 'N', '\u{327}'),
+    ('\u{146}', 'n', '\u{327}'),
+    ('\u{147}', 'N', '\u{30C}'),
+    ('\u{148}', 'n', '\u{30C}'),
+    ('\u{14C}', 'O', '\u{304}'),
+    ('\u{14D}', 'o', '\u{304}'),
+    ('\u{14E}', 'O', '\u{306}'),
+    ('\u{14F}', 'o', '\u{306}'),
+    ('\u{150}', 'O', '\u{30B}'),
+    ('\u{151}', 'o', '\u{30B}'),
+    ('\u{154}', 'R', '\u{301}'),
+    ('\u{155}', 'r', '\u{301}'),
+    ('\u{156}', 'R', '\u{327}'),
+    ('\u{157}', 'r', '\u{327}'),
+    ('\u{158}', 'R', '\u{30C}'),
+    ('\u{159}', 'r', '\u{30C}'),
+    ('\u{15A}', 'S', '\u{301}'),
+    ('\u{15B}', 's', '\u{301}'),
+    ('\u{15C}', 'S', '\u{302}'),
+    ('\u{15D}', 's', '\u{302}'),
+    ('\u{15E}', 'S', '\u{327}'),
+    ('\u{15F}', 's', '\u{327}'),
+    ('\u{160}', 'S', '\u{30C}'),
+    ('\u{161}', 's', '\u{30C}'),
+    ('\u{162}', 'T', '\u{327}'),
+    ('\u{163}', 't', '\u{327}'),
+    ('\u{164}', 'T', '\u{30C}'),
+    ('\u{165}', 't', '\u{30C}'),
+    ('\u{168}', 'U', '\u{303}'),
+    ('\u{169}', 'u', '\u{303}'),
+    ('\u{16A}', 'U', '\u{304}'),
+    ('\u{16B}', 'u', '\u{304}'),
+    ('\u{16C}', 'U', '\u{306}'),
+    ('\u{16D}', 'u', '\u{306}'),
+    ('\u{16E}', 'U', '\u{30A}'),
+    ('\u{16F}', 'u', '\u{30A}'),
+    ('\u{170}', 'U', '\u{30B}'),
+    ('\u{171}', 'u', '\u{30B}'),
+    ('\u{172}', 'U', '\u{328}'),
+    ('\u{173}', 'u', '\u{328}'),
+    ('\u{174}', 'W', '\u{302}'),
+    ('\u{175}', 'w', '\u{302}'),
+    ('\u{176}', 'Y', '\u{302}'),
+    ('\u{177}', 'y', '\u{302}'),
+    ('\u{178}', 'Y', '\u{308}'),
+    ('\u{179}', 'Z', '\u{301}'),
+    ('\u{17A}', 'z', '\u{301}'),
+    ('\u{17B}', 'Z', '\u{307}'),
+    ('\u{17C}', 'z', '\u{307}'),
+    ('\u{17D}', 'Z', '\u{30C}'),
+    ('\u{17E}', 'z', '\u{30C}'),
+];
+
+/// The precomposed letter made of `base` followed by `mark`, if it is in [`DECOMPOSITIONS`]
+#[cfg(feature = "latin_composition")]
+pub(crate) fn compose(base: char, mark: char) -> Option<char> {
+    DECOMPOSITIONS
+        .iter()
+        .find(|&&(_, b, m)| b == base && m == mark)
+        .map(|&(composed, _, _)| composed)
+}
+
+/// Whether `c` is in the Combining Diacritical Marks block
+#[cfg(any(feature = "latin_composition", feature = "deunicode"))]
+pub(crate) fn is_combining_mark(c: char) -> bool {
+    matches!(c, '\u{300}'..='\u{36F}')
+}
+
+/// The ASCII letters which `c` is written with once its diacritics are removed
+///
+/// This covers the letters of [`DECOMPOSITIONS`], and the other letters of the Latin-1
+/// Supplement and Latin Extended-A blocks which have a conventional ASCII spelling no longer
+/// than themselves, such as `ß` as `ss` and `ł` as `l`.
+pub(crate) fn ascii_base(c: char) -> Option<&'static str> {
+    const LETTERS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+    if let Ok(index) = DECOMPOSITIONS.binary_search_by_key(&c, |&(composed, _, _)| composed) {
+        let base = DECOMPOSITIONS[index].1 as usize;
+        let offset = if base >= usize::from(b'a') {
+            base - usize::from(b'a') + 26
+        } else {
+            base - usize::from(b'A')
+        };
+        return Some(&LETTERS[offset..offset + 1]);
+    }
+    Some(match c {
+        'Æ' => "AE",
+        'æ' => "ae",
+        'Ð' | 'Đ' => "D",
+        'ð' | 'đ' => "d",
+        'Ø' => "O",
+        'ø' => "o",
+        'Þ' => "Th",
+        'þ' => "th",
+        'ß' => "ss",
+        'Ħ' => "H",
+        'ħ' => "h",
+        'ı' => "i",
+        'Ĳ' => "IJ",
+        'ĳ' => "ij",
+        'Ŀ' | 'Ł' => "L",
+        'ŀ' | 'ł' => "l",
+        'Œ' => "OE",
+        'œ' => "oe",
+        'Ŧ' => "T",
+        'ŧ' => "t",
+        'ſ' => "s",
+        _ => return None,
+    })
+}
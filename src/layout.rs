@@ -0,0 +1,135 @@
+//! Tracking of indentation, for layout-sensitive formats such as YAML and Python
+
+use core::fmt;
+
+use crate::{Observer, Position, Replacinator};
+
+/// An error encountered whilst tracking indentation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    position: Position,
+}
+
+impl Error {
+    /// The kind of error which occurred
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The position in the original string at which the error was detected
+    pub fn position(&self) -> Position {
+        self.position
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::InconsistentDedent => {
+                write!(f, "dedent does not match any outer indentation level")
+            }
+            ErrorKind::TooDeep => write!(f, "too many levels of indentation"),
+        }?;
+        write!(f, " at byte {}", self.position.byte())
+    }
+}
+
+/// The kinds of [`Error`] which can occur whilst tracking indentation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A line was indented less than the previous line, but by an amount which doesn't match any
+    /// enclosing line
+    InconsistentDedent,
+    /// There were more levels of indentation than fit in the stack given to [`Indentation::new`]
+    TooDeep,
+}
+
+/// How the indentation of a line compares to the lines before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    /// The line is indented by the same amount as the previous line
+    Same,
+    /// The line is indented further than the previous line, opening a new level
+    Indent,
+    /// The line is indented less than the previous line, closing the given number of levels
+    Dedent(usize),
+    /// The line contains only whitespace, so doesn't affect the indentation
+    Blank,
+}
+
+/// A tracker of the levels of indentation of the lines read from a [`Replacinator`]
+///
+/// Call [`Indentation::line`] at the start of each line, and [`Indentation::finish`] at the end
+/// of the input to close any open levels.
+#[derive(Debug)]
+pub struct Indentation<'s> {
+    /// The widths of the open levels of indentation, outermost first
+    stack: &'s mut [usize],
+    depth: usize,
+}
+
+impl<'s> Indentation<'s> {
+    /// Create a tracker which can hold as many levels of indentation as `stack` has elements
+    ///
+    /// As no allocation is available, `stack` is used to store the levels.
+    pub fn new(stack: &'s mut [usize]) -> Self {
+        Self { stack, depth: 0 }
+    }
+
+    /// The number of open levels of indentation
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The width of the current level of indentation
+    pub fn width(&self) -> usize {
+        self.depth.checked_sub(1).map_or(0, |top| self.stack[top])
+    }
+
+    /// Read and write back the indentation at the start of a line of `src`, and compare it to the
+    /// current level
+    ///
+    /// The width of the indentation is the number of spaces and tabs. A line containing only
+    /// spaces and tabs is [`Change::Blank`], and its line ending is not read.
+    pub fn line<O: Observer>(&mut self, src: &mut Replacinator<'_, O>) -> Result<Change, Error> {
+        let position = src.position();
+        let width = src.skip_while(|c| c == ' ' || c == '\t');
+        if matches!(src.peek(), None | Some('\n') | Some('\r')) {
+            return Ok(Change::Blank);
+        }
+        let current = self.width();
+        if width > current {
+            let top = self.stack.get_mut(self.depth).ok_or(Error {
+                kind: ErrorKind::TooDeep,
+                position,
+            })?;
+            *top = width;
+            self.depth += 1;
+            return Ok(Change::Indent);
+        }
+        let levels = self.stack[..self.depth]
+            .iter()
+            .rev()
+            .take_while(|&&level| level > width)
+            .count();
+        let depth = self.depth - levels;
+        if depth.checked_sub(1).map_or(0, |top| self.stack[top]) != width {
+            return Err(Error {
+                kind: ErrorKind::InconsistentDedent,
+                position,
+            });
+        }
+        self.depth = depth;
+        Ok(match levels {
+            0 => Change::Same,
+            levels => Change::Dedent(levels),
+        })
+    }
+
+    /// Close all open levels of indentation, returning how many there were
+    pub fn finish(&mut self) -> usize {
+        core::mem::take(&mut self.depth)
+    }
+}
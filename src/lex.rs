@@ -0,0 +1,156 @@
+//! Splitting of text into simple typed tokens, for small domain specific languages
+
+/// A set of characters, used to define the tokens recognised by [`scan`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharClass<'s> {
+    /// ASCII decimal digits
+    Digit,
+    /// ASCII hexadecimal digits
+    HexDigit,
+    /// Alphabetic characters, as defined by [`char::is_alphabetic`]
+    Alphabetic,
+    /// Alphanumeric characters, as defined by [`char::is_alphanumeric`]
+    Alphanumeric,
+    /// Whitespace characters, as defined by [`char::is_whitespace`]
+    Whitespace,
+    /// The characters of the given string
+    AnyOf(&'s str),
+    /// The characters in any of the given classes
+    Union(&'s [CharClass<'s>]),
+}
+
+impl<'s> CharClass<'s> {
+    /// Whether `c` is in this class
+    pub fn matches(&self, c: char) -> bool {
+        match *self {
+            CharClass::Digit => c.is_ascii_digit(),
+            CharClass::HexDigit => c.is_ascii_hexdigit(),
+            CharClass::Alphabetic => c.is_alphabetic(),
+            CharClass::Alphanumeric => c.is_alphanumeric(),
+            CharClass::Whitespace => c.is_whitespace(),
+            CharClass::AnyOf(chars) => chars.contains(c),
+            CharClass::Union(classes) => classes.iter().any(|class| class.matches(c)),
+        }
+    }
+}
+
+/// The character classes which make up each kind of token
+///
+/// Classes are tried in the order whitespace, number, then identifier. Any other character is
+/// a [`TokenKind::Punct`] token on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Classes<'s> {
+    /// The characters which make up whitespace
+    pub whitespace: CharClass<'s>,
+    /// The characters which can start a number
+    pub number_start: CharClass<'s>,
+    /// The characters which can continue a number
+    pub number_continue: CharClass<'s>,
+    /// The characters which can start an identifier
+    pub ident_start: CharClass<'s>,
+    /// The characters which can continue an identifier
+    pub ident_continue: CharClass<'s>,
+}
+
+impl Classes<'static> {
+    /// Classes similar to those of most programming languages
+    ///
+    /// Identifiers are made of alphanumeric characters and `_`, and can't start with a digit.
+    /// Numbers start with a digit, and continue with alphanumeric characters, `.` and `_`, so
+    /// that `0x1F`, `1.5e3` and `1_000` are each a single token.
+    pub const DEFAULT: Self = Self {
+        whitespace: CharClass::Whitespace,
+        number_start: CharClass::Digit,
+        number_continue: CharClass::Union(&[CharClass::Alphanumeric, CharClass::AnyOf("._")]),
+        ident_start: CharClass::Union(&[CharClass::Alphabetic, CharClass::AnyOf("_")]),
+        ident_continue: CharClass::Union(&[CharClass::Alphanumeric, CharClass::AnyOf("_")]),
+    };
+}
+
+/// The kind of a [`Token`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// An identifier
+    Ident,
+    /// A number
+    Number,
+    /// A single character which is not part of any other kind of token
+    Punct,
+    /// A run of whitespace
+    Whitespace,
+}
+
+/// A token found by [`scan`]
+#[derive(Debug)]
+pub struct Token<'a> {
+    kind: TokenKind,
+    text: &'a mut str,
+}
+
+impl<'a> Token<'a> {
+    /// The kind of this token
+    pub fn kind(&self) -> TokenKind {
+        self.kind
+    }
+
+    /// View the text of this token
+    pub fn text(&self) -> &str {
+        self.text
+    }
+
+    /// Take the text of this token as a mutable view
+    pub fn into_text(self) -> &'a mut str {
+        self.text
+    }
+}
+
+/// Split `text` into tokens, using `classes` to determine where each token ends
+///
+/// The tokens cover all of `text`, including its whitespace.
+pub fn scan<'a, 's>(text: &'a mut str, classes: Classes<'s>) -> Tokens<'a, 's> {
+    Tokens {
+        rest: text,
+        classes,
+    }
+}
+
+/// An iterator over the tokens of some text
+///
+/// Created by [`scan`].
+#[derive(Debug)]
+pub struct Tokens<'a, 's> {
+    rest: &'a mut str,
+    classes: Classes<'s>,
+}
+
+impl<'a, 's> Tokens<'a, 's> {
+    /// The part of the text which has not been split into tokens
+    pub fn into_remainder(self) -> &'a mut str {
+        self.rest
+    }
+}
+
+impl<'a, 's> Iterator for Tokens<'a, 's> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let first = self.rest.chars().next()?;
+        let classes = &self.classes;
+        let (kind, continues) = if classes.whitespace.matches(first) {
+            (TokenKind::Whitespace, &classes.whitespace)
+        } else if classes.number_start.matches(first) {
+            (TokenKind::Number, &classes.number_continue)
+        } else if classes.ident_start.matches(first) {
+            (TokenKind::Ident, &classes.ident_continue)
+        } else {
+            (TokenKind::Punct, &CharClass::AnyOf(""))
+        };
+        let after = &self.rest[first.len_utf8()..];
+        let len = self.rest.len() - after.trim_start_matches(|c| continues.matches(c)).len();
+        let (text, rest) = core::mem::take(&mut self.rest).split_at_mut(len);
+        self.rest = rest;
+        Some(Token { kind, text })
+    }
+}
+
+impl<'a, 's> core::iter::FusedIterator for Tokens<'a, 's> {}
@@ -4,13 +4,95 @@
 #![deny(unsafe_op_in_unsafe_fn)]
 #![no_std]
 
-use core::{convert::TryInto, mem::replace};
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+mod array;
+mod as_mut_str;
+pub mod budget;
+mod byte_cursor;
+mod bytes;
+pub mod codec;
+pub mod config;
+pub mod csv;
+mod cursors;
+mod double;
+#[cfg(feature = "alloc")]
+pub mod edits;
+pub mod fixed_width;
+pub mod glob;
+mod guard;
+pub mod html;
+pub mod http;
+#[cfg(feature = "std")]
+pub mod io;
+pub mod json;
+mod latin;
+pub mod layout;
+pub mod lex;
+pub mod mime;
+pub mod nesting;
+#[cfg(feature = "number_formatting")]
+mod number;
+mod observer;
+#[cfg(feature = "alloc")]
+pub mod owned;
+pub mod parallel;
+pub mod parse;
+mod pattern;
+pub mod percent;
+pub mod pipeline;
+mod position;
+pub mod raw;
+#[cfg(feature = "regex")]
+pub mod regex;
+#[cfg(feature = "std")]
+pub mod replay;
+mod rev;
+pub mod rewrite;
+pub mod sanitize;
+mod scan;
+pub mod segments;
+#[cfg(feature = "serde")]
+pub mod serde;
+mod state;
+pub mod stream;
+pub mod template;
+#[cfg(feature = "std")]
+pub mod testing;
+pub mod text;
+#[cfg(feature = "trace")]
+pub mod trace;
+pub mod transforms;
+pub mod unescape;
+mod validate;
+
+pub use array::ArrayReplacinator;
+pub use as_mut_str::AsMutStr;
+pub use byte_cursor::ByteError;
+pub use bytes::ByteReplacinator;
+pub use cursors::{Reader, Writer};
+pub use double::DoubleReplacinator;
+pub use guard::{Poisoned, ReplacinatorGuard};
+pub use observer::{NoObserver, Observer};
+#[cfg(feature = "case_folding")]
+pub use pattern::CaseFolded;
+pub use pattern::{IgnoreAsciiCase, Pattern, ReplaceError, ReplaceErrorKind};
+pub use position::{CharIndices, Position, Span};
+pub use rev::RevReplacinator;
+pub use state::{State, StateError};
+pub use stream::ReplacinatorStream;
+pub use validate::InvariantError;
+
+use raw::{from_utf8_checked_if, from_utf8_mut_checked_if, unchecked_from_utf8_mut};
 
 /// A partially updated string slice
 ///
 /// Conceptually, this is a wrapper around a region of bytes containing three consecutive segments:
 /// - The first section of the memory is a valid UTF-8 string, containing exactly the
-///  characters which have been 'written' to the string in order
+///   characters which have been 'written' to the string in order
 /// - The second section of memory has arbitrary contents
 /// - The third section is the characters which have not yet been read, as valid UTF-8
 ///
@@ -19,179 +101,2027 @@ use core::{convert::TryInto, mem::replace};
 ///
 /// This is ensured by the `Drop` impl for Replacinator. However, this is not guaranteed to run
 /// in an arbitrary user controlled stack - [`Replacinator::new_in`] provides a safe wrapper around this.
-pub struct Replacinator<'a> {
-    contents: &'a mut [u8],
-    read_position: usize,
-    write_position: usize,
+///
+/// The [`Observer`] `O` is notified as the [`Replacinator`] is used.
+pub struct Replacinator<'a, O = NoObserver> {
+    inner: ByteReplacinator<'a>,
+    observer: O,
+    /// Whether [`Replacinator::abort`] was called, after which nothing can be written
+    aborted: bool,
+    /// How the second section is returned to valid UTF-8
+    fill: FillStrategy,
+    /// The number of bytes the remainder has been moved towards the start by [`FillStrategy::Compact`]
+    compacted: usize,
+    /// The number of bytes of padding left by [`FillStrategy::Compact`] directly after the contents
+    padding: usize,
+    /// What happens when a write doesn't fit before the read position
+    overflow: OverflowPolicy,
+    /// Whether a write hasn't fit since [`Replacinator::clear_overflowed`] was last called
+    overflowed: bool,
+    /// The read position when the first section was last taken, which is where the text written
+    /// since was read from
+    taken: Position,
+    /// Whether views of the string are checked to be valid UTF-8 as they are created
+    checks: bool,
 }
 
 impl<'a> Replacinator<'a> {
     /// Create a new [`Replacinator`] for the given string slice, and operate on it within the given function.
     /// This function can be safe because it ensures that `value` is returned to a valid string slice,
     /// by ensuring that [`Drop`] is called as required.
-    pub fn new_in<R>(value: &'a mut str, mut with: impl FnMut(&mut Replacinator<'a>) -> R) -> R {
+    pub fn new_in<R>(
+        value: impl AsMutStr<'a>,
+        mut with: impl FnMut(&mut Replacinator<'a>) -> R,
+    ) -> R {
         // Safety: Because we create a new scope, `it` is always dropped,
         // so the data behind value returns to being utf8 by the end of the borrow.
-        let mut it = unsafe { Self::construct(value) };
+        let mut it = unsafe { Self::construct(value.into_mut_str()) };
         with(&mut it)
     }
 
+    /// Create a new [`Replacinator`] which always checks that the string is valid UTF-8, and
+    /// operate on it within the given function
+    ///
+    /// This is [`Replacinator::new_in`], but each view of the string is checked as it is created,
+    /// even if [`raw::SAFETY_CHECKS`] is disabled. This guards against a bug in this crate for
+    /// untrusted input, at the cost of time proportional to the length of each view.
+    pub fn new_in_checked<R>(
+        value: impl AsMutStr<'a>,
+        mut with: impl FnMut(&mut Replacinator<'a>) -> R,
+    ) -> R {
+        Self::new_in(value, |it| {
+            it.checks = true;
+            with(it)
+        })
+    }
+
+    /// Create a new [`Replacinator`] which doesn't check that the string is valid UTF-8, and
+    /// operate on it within the given function
+    ///
+    /// This is [`Replacinator::new_in`], but views of the string are created without checking
+    /// them, even in debug builds, unless the `paranoid` feature is enabled. This is still safe to
+    /// call, as the checks should never fail; it removes their cost from a hot path.
+    pub fn new_in_unchecked_fastpath<R>(
+        value: impl AsMutStr<'a>,
+        mut with: impl FnMut(&mut Replacinator<'a>) -> R,
+    ) -> R {
+        Self::new_in(value, |it| {
+            it.checks = false;
+            with(it)
+        })
+    }
+
+    /// Create a new [`Replacinator`] for the given string slice, and operate on it within the given
+    /// function, catching any panic
+    ///
+    /// If `with` panics, the payload of the panic is returned as the error. Like
+    /// [`Replacinator::new_in`], `value` is always returned to a valid string slice, so it can still be
+    /// used afterwards. As after [`Replacinator::abort`], it then contains the first section,
+    /// the second section filled according to [`Replacinator::fill`], then the remainder.
+    ///
+    /// This function requires the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn new_in_catching<R>(
+        value: impl AsMutStr<'a>,
+        with: impl FnMut(&mut Replacinator<'a>) -> R + std::panic::UnwindSafe,
+    ) -> Result<R, std::boxed::Box<dyn core::any::Any + Send>> {
+        // `value` can't be observed in a broken state, as the `Replacinator` restores it when dropped
+        let value = std::panic::AssertUnwindSafe(value.into_mut_str());
+        std::panic::catch_unwind(move || Self::new_in(value.0, with))
+    }
+
+    /// Create a new [`Replacinator`] which treats the first `gap` bytes of `value` as scratch space,
+    /// and operate on it within the given function
+    ///
+    /// The first `gap` bytes are discarded without being read, so up to `gap` bytes more can be
+    /// written than have been read. This allows replacements which grow the text, where the caller
+    /// knows the maximum growth up front and has padded `value` to provide room for it. Positions
+    /// still refer to `value`, so reading starts at byte `gap`.
+    ///
+    /// # Errors
+    /// If `gap` is not on a character boundary of `value`, before `with` is called. `value` is
+    /// left unchanged.
+    pub fn new_in_with_headroom<R>(
+        value: impl AsMutStr<'a>,
+        gap: usize,
+        mut with: impl FnMut(&mut Replacinator<'a>) -> R,
+    ) -> Result<R, HeadroomError> {
+        let value = value.into_mut_str();
+        if !value.is_char_boundary(gap) {
+            return Err(HeadroomError { gap });
+        }
+        Ok(Self::new_in(value, |it| {
+            it.discard_bytes(gap);
+            with(it)
+        }))
+    }
+
+    /// Create a new [`Replacinator`] for `bytes`, if they are valid UTF-8, and operate on it
+    /// within the given function
+    ///
+    /// `bytes` are validated once, up front, then used as in [`Replacinator::new_in`]. As there,
+    /// they are valid UTF-8 again once this returns.
+    pub fn from_utf8_in<R>(
+        bytes: &'a mut [u8],
+        with: impl FnMut(&mut Replacinator<'a>) -> R,
+    ) -> Result<R, core::str::Utf8Error> {
+        Ok(Self::new_in(core::str::from_utf8_mut(bytes)?, with))
+    }
+
+    /// Create a new [`Replacinator`] for `bytes`, first repairing any invalid UTF-8 in place, and
+    /// operate on it within the given function
+    ///
+    /// Each invalid sequence is replaced with [`char::REPLACEMENT_CHARACTER`] if it is at least
+    /// three bytes long, and otherwise with `?`. Any bytes left over are filled with spaces, so
+    /// the length is unchanged.
+    pub fn from_utf8_lossy_in_place<R>(
+        bytes: &'a mut [u8],
+        with: impl FnMut(&mut Replacinator<'a>) -> R,
+    ) -> R {
+        let mut start = 0;
+        while let Err(e) = core::str::from_utf8(&bytes[start..]) {
+            let invalid = start + e.valid_up_to();
+            let end = e.error_len().map_or(bytes.len(), |len| invalid + len);
+            let replacement = if end - invalid >= char::REPLACEMENT_CHARACTER.len_utf8() {
+                char::REPLACEMENT_CHARACTER
+            } else {
+                '?'
+            };
+            let written = replacement.encode_utf8(&mut bytes[invalid..end]).len();
+            bytes[invalid + written..end].fill(b' ');
+            start = end;
+        }
+        // Safety: Every invalid sequence was replaced
+        Self::new_in(unsafe { unchecked_from_utf8_mut(bytes) }, with)
+    }
+
     /// Create a new [`Replacinator`] from the given string
     ///
     /// # Safety
     /// Before 'a ends, the resulting Replacinator must be synchonr
     pub unsafe fn construct(from: &'a mut str) -> Self {
+        // Safety: The requirements are passed on to our caller
+        unsafe { Replacinator::construct_observed(from, NoObserver) }
+    }
+}
+
+impl<'a, O: Observer> Replacinator<'a, O> {
+    /// Create a new [`Replacinator`] which notifies `observer` as it is used, and operate on it
+    /// within the given function
+    ///
+    /// Like [`Replacinator::new_in`], this ensures that `value` is returned to a valid string slice.
+    pub fn new_in_observed<R>(
+        value: impl AsMutStr<'a>,
+        observer: O,
+        mut with: impl FnMut(&mut Replacinator<'a, O>) -> R,
+    ) -> R {
+        // Safety: As in `new_in`, `it` is always dropped before the borrow of `value` ends
+        let mut it = unsafe { Self::construct_observed(value.into_mut_str(), observer) };
+        with(&mut it)
+    }
+
+    /// Create a new [`Replacinator`] from the given string, which notifies `observer` as it is used
+    ///
+    /// # Safety
+    /// As for [`Replacinator::construct`]
+    unsafe fn construct_observed(from: &'a mut str, observer: O) -> Self {
         Self {
             // SAFETY: By the time this borrow ends, the memory contents are back to being utf8.
             // This is the key line of unsafety which the rest of this module ensures is kept safe
-            contents: unsafe { from.as_bytes_mut() },
-            read_position: 0,
-            write_position: 0,
+            inner: ByteReplacinator::new(unsafe { from.as_bytes_mut() }),
+            observer,
+            aborted: false,
+            fill: FillStrategy::DEFAULT,
+            compacted: 0,
+            padding: 0,
+            overflow: OverflowPolicy::Panic,
+            overflowed: false,
+            taken: Position::default(),
+            checks: raw::SAFETY_CHECKS,
         }
     }
 
+    /// The [`Observer`] which is notified as this is used
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    /// The [`Observer`] which is notified as this is used, mutably
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    /// Whether views of the string are checked to be valid UTF-8 as they are created
+    ///
+    /// This is [`raw::SAFETY_CHECKS`], unless this was created by
+    /// [`Replacinator::new_in_checked`] or [`Replacinator::new_in_unchecked_fastpath`]. With the
+    /// `paranoid` feature, this is always `true`.
+    pub fn checks_utf8(&self) -> bool {
+        self.checks || raw::PARANOID
+    }
+
     /// View the string contents of the 'third section'
+    #[inline]
     pub fn remainder(&self) -> &str {
-        unsafe { unchecked_from_utf8(&self.contents[self.read_position..]) }
+        unsafe {
+            from_utf8_checked_if(
+                self.checks,
+                &self.inner.contents[self.inner.read_position..],
+            )
+        }
+    }
+
+    /// Find the first match of `pattern` in the remainder, without reading it
+    ///
+    /// The returned [`Span`] is in the original string, so its start is at least
+    /// [`Replacinator::position`].
+    pub fn find(&self, mut pattern: impl Pattern) -> Option<Span> {
+        let remainder = self.remainder();
+        let found = pattern.find_in(remainder)?;
+        let start = self.position().after(&remainder[..found.start]);
+        Some(Span::new(start, start.after(&remainder[found])))
+    }
+
+    /// The byte offset in the remainder of the first `c`, without reading it
+    pub fn find_char(&self, c: char) -> Option<usize> {
+        self.remainder().find(c)
+    }
+
+    /// The byte offset in the remainder of the first occurrence of `needle`, without reading it
+    pub fn find_str(&self, needle: &str) -> Option<usize> {
+        self.remainder().find(needle)
+    }
+
+    /// Whether the remainder contains a match of `pattern`
+    pub fn contains(&self, mut pattern: impl Pattern) -> bool {
+        pattern.find_in(self.remainder()).is_some()
+    }
+
+    /// Read the remainder up to the next match of `pattern` without writing it, returning the
+    /// text which was read
+    ///
+    /// The match itself is not read. If the remainder doesn't contain a match, nothing is read
+    /// and `None` is returned.
+    pub fn read_until(&mut self, mut pattern: impl Pattern) -> Option<&str> {
+        let len = pattern.find_in(self.remainder())?.start;
+        let start = self.inner.read_position;
+        self.advance_read(len);
+        self.check_invariants();
+        // Safety: These bytes were part of the remainder, and nothing can be written over them
+        // whilst the returned borrow of `self` is live
+        Some(unsafe { from_utf8_checked_if(self.checks, &self.inner.contents[start..start + len]) })
+    }
+
+    /// Read and write back the remainder up to the next match of `pattern`, returning the text
+    /// which was skipped
+    ///
+    /// The match itself is not read. If the remainder doesn't contain a match, nothing is read
+    /// and `None` is returned.
+    pub fn skip_until(&mut self, mut pattern: impl Pattern) -> Option<&str> {
+        let len = pattern.find_in(self.remainder())?.start;
+        let start = self.inner.write_position;
+        self.skip_bytes(len);
+        Some(&self.start()[start..])
+    }
+
+    /// Iterate over the characters of the remainder with their positions in the original string,
+    /// without reading them
+    pub fn char_indices(&self) -> CharIndices<'_> {
+        CharIndices::new(self.remainder(), self.position())
     }
 
     /// View the string contents of the 'third section' mutably
+    #[inline]
     pub fn remainder_mut(&mut self) -> &mut str {
-        unsafe { unchecked_from_utf8_mut(&mut self.contents[self.read_position..]) }
+        unsafe {
+            from_utf8_mut_checked_if(
+                self.checks,
+                &mut self.inner.contents[self.inner.read_position..],
+            )
+        }
     }
 
     /// View the string contents of the first section
+    #[inline]
     pub fn start(&self) -> &str {
-        unsafe { unchecked_from_utf8(&self.contents[..self.write_position]) }
+        unsafe {
+            from_utf8_checked_if(
+                self.checks,
+                &self.inner.contents[..self.inner.write_position],
+            )
+        }
     }
 
     /// View the string contents of the first section mutably.
+    ///
+    /// As this is a `&mut str`, any changes made through it keep the first section valid UTF-8,
+    /// so no revalidation is needed afterwards. [`Mark`]s and [`Checkpoint`]s made before such
+    /// changes are still checked against character boundaries when they are used.
+    #[inline]
     pub fn start_mut(&mut self) -> &mut str {
-        unsafe { unchecked_from_utf8_mut(&mut self.contents[..self.write_position]) }
+        unsafe {
+            from_utf8_mut_checked_if(
+                self.checks,
+                &mut self.inner.contents[..self.inner.write_position],
+            )
+        }
     }
 
     /// Take the first section as a mutable view
+    ///
+    /// The second section is filled according to [`Replacinator::fill`], and is no longer part of
+    /// either view. With the `compact_on_take` feature, the remainder is instead moved to directly
+    /// after the first section, as with [`FillStrategy::Compact`], so that successive slices taken
+    /// are adjacent in the string. This takes time proportional to the length of the remainder.
     pub fn take_start(&mut self) -> &'a mut str {
-        let inner = &mut [];
-        // Juggle the lifetimes, to avoid unneeded unsafe code
-        let pre_synchronised_end = self.write_position;
-        self.synchronise();
-        let contents = replace(&mut self.contents, inner);
-        let (start, end) = contents.split_at_mut(self.read_position);
-        self.contents = end;
+        // The second section is split off with the start, so must be returned to valid UTF-8
+        #[cfg(not(feature = "compact_on_take"))]
+        self.fill_invalid_region();
+        #[cfg(feature = "compact_on_take")]
+        {
+            self.inner.check_invariants();
+            self.synchronise_unobserved(FillStrategy::Compact);
+        }
+        let start = unsafe { from_utf8_mut_checked_if(self.checks, self.inner.take_start()) };
+        self.observer.take_start(start);
+        self.taken = self.inner.position;
+        start
+    }
 
-        self.read_position = 0;
-        self.write_position = 0;
-        self.check_invariants();
-        unsafe { unchecked_from_utf8_mut(&mut start[..pre_synchronised_end]) }
+    /// Take the first section as a mutable view, along with the part of the original string
+    /// which was read since the first section was last taken
+    ///
+    /// This is [`Replacinator::take_start`], for a caller which needs to point at where a
+    /// token came from, such as to report an error in it.
+    pub fn take_start_spanned(&mut self) -> (Span, &'a mut str) {
+        let end = self.position();
+        let span = Span::new(self.taken.min(end), end);
+        (span, self.take_start())
     }
 
+    /// Move the remainder to directly after the first section, and take them together as a
+    /// mutable view, leaving nothing left to read
+    ///
+    /// This is the result of a transform which may have shrunk the text, without a gap of padding
+    /// between what was written and what was left unread. The number of bytes of padding which
+    /// directly follow the view in the string is also returned. If the [`Replacinator`] was created
+    /// from a whole `String`, it can be truncated to remove this padding, so long as only
+    /// [`FillStrategy::Compact`] was used to synchronise.
+    pub fn take_compacted(&mut self) -> (&'a mut str, usize) {
+        self.compact();
+        self.skip_bytes(self.remainder().len());
+        (self.take_start(), self.padding)
+    }
+
+    /// Take the third section as a mutable view, leaving nothing left to read
+    ///
+    /// The first section is unaffected, and can still be accessed or taken.
+    pub fn take_remainder(&mut self) -> &'a mut str {
+        let remainder =
+            unsafe { from_utf8_mut_checked_if(self.checks, self.inner.take_remainder()) };
+        self.padding = 0;
+        self.observer.take_remainder(remainder);
+        remainder
+    }
+
+    /// Split the third section into two independent halves, at the byte index `mid`
+    ///
+    /// This leaves nothing left to read. Returns `None` if `mid` is not on a character boundary
+    /// of the remainder.
+    pub fn split_at(
+        &mut self,
+        mid: usize,
+    ) -> Option<(ReplacinatorGuard<'a>, ReplacinatorGuard<'a>)> {
+        if !self.remainder().is_char_boundary(mid) {
+            return None;
+        }
+        let position = self.position();
+        let (left, right) = self.take_remainder().split_at_mut(mid);
+        let mut right_position = position;
+        right_position.advance(left.as_bytes());
+        Some((
+            ReplacinatorGuard::starting_at(left, position),
+            ReplacinatorGuard::starting_at(right, right_position),
+        ))
+    }
+
+    /// Split the third section into two independent halves, either side of the first `delim`
+    ///
+    /// `delim` itself is not included in either half. This leaves nothing left to read.
+    /// Returns `None` if the remainder does not contain `delim`.
+    pub fn split_at_char(
+        &mut self,
+        delim: char,
+    ) -> Option<(ReplacinatorGuard<'a>, ReplacinatorGuard<'a>)> {
+        self.split_at_pattern(delim)
+    }
+
+    /// Split the third section into two independent halves, either side of the first match of
+    /// `pattern`
+    ///
+    /// The match itself is not included in either half. This leaves nothing left to read.
+    /// Returns `None` if the remainder does not contain a match.
+    pub fn split_at_pattern(
+        &mut self,
+        mut pattern: impl Pattern,
+    ) -> Option<(ReplacinatorGuard<'a>, ReplacinatorGuard<'a>)> {
+        let found = pattern.find_in(self.remainder())?;
+        let position = self.position();
+        let (left, right) = self.take_remainder().split_at_mut(found.start);
+        let (delim, right) = right.split_at_mut(found.len());
+        let mut right_position = position;
+        right_position.advance(left.as_bytes());
+        right_position.advance(delim.as_bytes());
+        Some((
+            ReplacinatorGuard::starting_at(left, position),
+            ReplacinatorGuard::starting_at(right, right_position),
+        ))
+    }
+
+    #[inline]
     pub fn skip_char(&mut self) -> Option<char> {
-        let value = self.read_char();
-        if let Some(c) = value {
-            self.write_char(c)
+        let c = self.peek()?;
+        self.skip_bytes(c.len_utf8());
+        Some(c)
+    }
+
+    /// Read and write back characters while they match `pred`, returning the number of bytes skipped
+    ///
+    /// This is equivalent to calling [`Replacinator::skip_char`] repeatedly, but moves the
+    /// characters in bulk. ASCII characters are passed to `pred` without being decoded.
+    pub fn skip_while(&mut self, pred: impl FnMut(char) -> bool) -> usize {
+        let len = scan::prefix_len(self.remainder(), pred);
+        self.skip_bytes(len);
+        len
+    }
+
+    /// Read and write back ASCII whitespace, returning the number of bytes skipped
+    ///
+    /// This is the same as `skip_while(|c| c.is_ascii_whitespace())`, but checks eight bytes at a
+    /// time, so is faster for long runs of whitespace such as indentation.
+    pub fn skip_ascii_whitespace(&mut self) -> usize {
+        let len = scan::ascii_whitespace_len(self.remainder().as_bytes());
+        self.skip_bytes(len);
+        len
+    }
+
+    /// Read and write back `expected`, if the remainder starts with it
+    ///
+    /// Returns whether `expected` was skipped.
+    pub fn skip_str(&mut self, expected: &str) -> bool {
+        if self.starts_with(expected) {
+            self.skip_bytes(expected.len());
+            true
+        } else {
+            false
         }
-        value
     }
 
+    /// Read and write back the keyword `kw`, if the remainder starts with it and it isn't
+    /// followed by an identifier character
+    ///
+    /// Identifier characters are alphanumeric characters and `_`, so `if` is skipped from
+    /// `if (x)` but not from `iffy`. Returns whether `kw` was skipped.
+    pub fn skip_keyword(&mut self, kw: &str) -> bool {
+        let matches = self.starts_with(kw)
+            && !self.remainder()[kw.len()..].starts_with(|c: char| c.is_alphanumeric() || c == '_');
+        if matches {
+            self.skip_bytes(kw.len());
+        }
+        matches
+    }
+
+    /// Read and write back whitespace and comments, returning the number of bytes skipped
+    ///
+    /// Comments are recognised using the line and block comment markers of `syntax`. An
+    /// unterminated block comment is skipped to the end of the remainder.
+    ///
+    /// # Errors
+    /// If a line comment marker or block comment opener of `syntax` is empty, before anything
+    /// is read
+    pub fn skip_ignorable(
+        &mut self,
+        syntax: transforms::CommentSyntax<'_>,
+    ) -> Result<usize, transforms::EmptyMarker> {
+        syntax.check()?;
+        let start = self.inner.read_position;
+        loop {
+            self.skip_while(char::is_whitespace);
+            if let Some(marker) = syntax.line.iter().find(|&&marker| self.starts_with(marker)) {
+                self.skip_bytes(marker.len());
+                self.skip_while(|c| c != '\n');
+            } else if let Some((open, close)) =
+                syntax.block.iter().find(|(open, _)| self.starts_with(open))
+            {
+                let remainder = &self.remainder()[open.len()..];
+                let len = remainder
+                    .find(close)
+                    .map_or(remainder.len(), |end| end + close.len());
+                self.skip_bytes(open.len() + len);
+            } else {
+                break;
+            }
+        }
+        Ok(self.inner.read_position - start)
+    }
+
+    /// Read and write back a balanced region, from an `open` delimiter to its matching `close`
+    ///
+    /// Nested pairs of delimiters are skipped over, and a character preceded by `escape` is never
+    /// treated as a delimiter. Returns the region, including both delimiters. Like
+    /// [`Replacinator::take_marked`], the first section is no longer available afterwards.
+    ///
+    /// Returns `None` if the remainder doesn't start with `open`, or ends before the region is
+    /// balanced. In the latter case, the whole remainder has been skipped.
+    pub fn consume_balanced(
+        &mut self,
+        open: char,
+        close: char,
+        escape: Option<char>,
+    ) -> Option<&'a mut str> {
+        if self.peek() != Some(open) {
+            return None;
+        }
+        let mark = self.mark();
+        let mut depth = 0_usize;
+        loop {
+            self.skip_while(|c| c != open && c != close && Some(c) != escape);
+            match self.skip_char()? {
+                c if Some(c) == escape => {
+                    self.skip_char()?;
+                }
+                c if c == close && depth > 0 => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => depth += 1,
+            }
+        }
+        self.take_marked(mark)
+    }
+
+    /// Replace every match of `from` in the remainder with `to`, reporting the number of
+    /// matches replaced
+    ///
+    /// The whole remainder is read and written back. For a string, occurrences are found using
+    /// [`str::find`], which uses the Two-Way algorithm, so the text between them is moved in bulk.
+    ///
+    /// # Errors
+    /// If a match is empty, or `to` is longer than a match. This is checked before anything is
+    /// replaced, so the remainder is unchanged. If `from` gives its [`Pattern::match_len`], this
+    /// is checked without searching. Otherwise, the remainder is searched twice, first to check
+    /// every match.
+    pub fn replace_literal(
+        &mut self,
+        mut from: impl Pattern,
+        to: &str,
+    ) -> Result<transforms::TransformReport, ReplaceError> {
+        let check = |match_len: usize| match match_len {
+            0 => Err(ReplaceErrorKind::EmptyMatch),
+            _ if to.len() > match_len => Err(ReplaceErrorKind::TooLong {
+                match_len,
+                replacement_len: to.len(),
+            }),
+            _ => Ok(()),
+        };
+        match from.match_len() {
+            Some(match_len) => {
+                check(match_len).map_err(|kind| ReplaceError::new(kind, self.position()))?
+            }
+            None => {
+                let remainder = self.remainder();
+                let mut checked = 0;
+                while let Some(found) = from.find_in(&remainder[checked..]) {
+                    let start = checked + found.start;
+                    check(found.len()).map_err(|kind| {
+                        ReplaceError::new(kind, self.position().after(&remainder[..start]))
+                    })?;
+                    checked += found.end;
+                }
+            }
+        }
+        let (read_offset, write_offset) = (self.read_offset(), self.write_offset());
+        let mut count = 0;
+        while let Some(found) = from.find_in(self.remainder()) {
+            self.skip_bytes(found.start);
+            self.discard_bytes(found.len());
+            self.write_str(to);
+            count += 1;
+        }
+        self.skip_bytes(self.remainder().len());
+        Ok(transforms::TransformReport::since(
+            self,
+            read_offset,
+            write_offset,
+            count,
+        ))
+    }
+
+    /// Replace every occurrence of `from` in the remainder with `to`, ignoring ASCII case
+    ///
+    /// This is [`Replacinator::replace_literal`] with [`IgnoreAsciiCase`].
+    ///
+    /// # Errors
+    /// If `from` is empty, or `to` is longer than `from`, before anything is replaced
+    pub fn replace_literal_ignore_ascii_case(
+        &mut self,
+        from: &str,
+        to: &str,
+    ) -> Result<transforms::TransformReport, ReplaceError> {
+        self.replace_literal(IgnoreAsciiCase(from), to)
+    }
+
+    /// Replace every occurrence of `from` in the remainder with `to`, comparing characters after
+    /// Unicode simple case folding
+    ///
+    /// This is [`Replacinator::replace_literal`] with [`CaseFolded`], and requires the
+    /// `case_folding` feature.
+    ///
+    /// # Errors
+    /// If `from` is empty, or `to` is longer than a match, before anything is replaced. A match
+    /// may be shorter than `from`, such as `k` matching U+212A KELVIN SIGN, so every match is
+    /// checked first.
+    #[cfg(feature = "case_folding")]
+    pub fn replace_literal_case_folded(
+        &mut self,
+        from: &str,
+        to: &str,
+    ) -> Result<transforms::TransformReport, ReplaceError> {
+        self.replace_literal(CaseFolded(from), to)
+    }
+
+    /// Read and write back the longest entry of `table` which the remainder starts with
+    ///
+    /// `table` must be sorted, otherwise matches may be missed. Returns the index of the entry
+    /// which was skipped, or `None` if the remainder starts with none of them.
+    pub fn match_keyword(&mut self, table: &[&str]) -> Option<usize> {
+        let remainder = self.remainder().as_bytes();
+        let (mut lo, mut hi) = (0, table.len());
+        let mut longest = None;
+        for depth in 0..=remainder.len() {
+            // Every entry in `lo..hi` starts with `remainder[..depth]`
+            if lo < hi && table[lo].len() == depth {
+                longest = Some(lo);
+            }
+            while lo < hi && table[lo].len() == depth {
+                lo += 1;
+            }
+            if lo == hi || depth == remainder.len() {
+                break;
+            }
+            let byte = remainder[depth];
+            let candidates = &table[lo..hi];
+            // In a sorted table, every entry in `lo..hi` is longer than `depth`, but an unsorted
+            // table may break this
+            let at = |entry: &&str| entry.as_bytes().get(depth).copied();
+            hi = lo + candidates.partition_point(|entry| at(entry) <= Some(byte));
+            lo += candidates.partition_point(|entry| at(entry) < Some(byte));
+        }
+        let index = longest.filter(|&index| remainder.starts_with(table[index].as_bytes()))?;
+        self.skip_bytes(table[index].len());
+        Some(index)
+    }
+
+    /// Read the next character without writing it, removing it from the output
+    ///
+    /// This is the same as [`Replacinator::read_char`], but makes the intent clear.
+    #[inline]
+    pub fn discard_char(&mut self) -> Option<char> {
+        self.read_char()
+    }
+
+    /// Read the next `n` characters without writing them, returning the number of characters discarded
+    ///
+    /// This is less than `n` if the remainder ends first.
+    pub fn discard_n(&mut self, n: usize) -> usize {
+        let remainder = self.remainder();
+        let (count, len) = remainder
+            .chars()
+            .take(n)
+            .fold((0, 0), |(count, len), c| (count + 1, len + c.len_utf8()));
+        self.advance_read(len);
+        self.check_invariants();
+        count
+    }
+
+    /// Read characters without writing them while they match `pred`, returning the number of bytes discarded
+    ///
+    /// ASCII characters are passed to `pred` without being decoded.
+    pub fn discard_while(&mut self, pred: impl FnMut(char) -> bool) -> usize {
+        let len = scan::prefix_len(self.remainder(), pred);
+        self.advance_read(len);
+        self.check_invariants();
+        len
+    }
+
+    /// Read ASCII whitespace without writing it, returning the number of bytes discarded
+    ///
+    /// Like [`Replacinator::skip_ascii_whitespace`], this checks eight bytes at a time.
+    pub fn discard_ascii_whitespace(&mut self) -> usize {
+        let len = scan::ascii_whitespace_len(self.remainder().as_bytes());
+        self.advance_read(len);
+        self.check_invariants();
+        len
+    }
+
+    #[inline]
     pub fn peek(&self) -> Option<char> {
         self.remainder().chars().next()
     }
 
+    /// Look at the character `n` characters ahead of the read position, without reading it
+    ///
+    /// `peek_nth(0)` is equivalent to [`Replacinator::peek`].
+    #[inline]
+    pub fn peek_nth(&self, n: usize) -> Option<char> {
+        self.remainder().chars().nth(n)
+    }
+
+    /// View the next `n` characters of the remainder, without reading them
+    ///
+    /// Returns `None` if fewer than `n` characters remain.
+    pub fn peek_str(&self, n: usize) -> Option<&str> {
+        let remainder = self.remainder();
+        let mut chars = remainder.chars();
+        let mut end = 0;
+        for _ in 0..n {
+            end += chars.next()?.len_utf8();
+        }
+        Some(&remainder[..end])
+    }
+
+    /// View up to the next `n` characters of the remainder, without reading them
+    ///
+    /// Unlike [`Replacinator::peek_str`], this returns the whole remainder if fewer than `n`
+    /// characters remain.
+    pub fn peek_window(&self, n: usize) -> &str {
+        let remainder = self.remainder();
+        let end = remainder
+            .char_indices()
+            .nth(n)
+            .map_or(remainder.len(), |(end, _)| end);
+        &remainder[..end]
+    }
+
+    /// Whether the remainder starts with `prefix`
+    #[inline]
+    pub fn starts_with(&self, prefix: &str) -> bool {
+        self.remainder().starts_with(prefix)
+    }
+
+    /// Convert the remainder to ASCII lowercase in place, without moving the read position
+    pub fn make_ascii_lowercase_remaining(&mut self) {
+        let lower = self.remainder().bytes().map(|b| b.to_ascii_lowercase());
+        if let Some(changed) = bytes::differing(self.inner.remainder_bytes(), lower) {
+            let start = self.inner.read_position;
+            self.inner.remainder_bytes_mut()[changed.clone()].make_ascii_lowercase();
+            self.inner
+                .mark_dirty(start + changed.start..start + changed.end);
+        }
+    }
+
+    /// Convert the remainder to ASCII uppercase in place, without moving the read position
+    pub fn make_ascii_uppercase_remaining(&mut self) {
+        let upper = self.remainder().bytes().map(|b| b.to_ascii_uppercase());
+        if let Some(changed) = bytes::differing(self.inner.remainder_bytes(), upper) {
+            let start = self.inner.read_position;
+            self.inner.remainder_bytes_mut()[changed.clone()].make_ascii_uppercase();
+            self.inner
+                .mark_dirty(start + changed.start..start + changed.end);
+        }
+    }
+
+    /// Convert the remainder to lowercase in place, without moving the read position
+    ///
+    /// Characters whose lowercase form is several characters, or has a different length in
+    /// UTF-8 (such as `K`, the Kelvin sign), are left unchanged. Returns the number of such
+    /// characters which were skipped.
+    pub fn make_lowercase_remaining(&mut self) -> usize {
+        self.map_remaining_same_len(char::to_lowercase)
+    }
+
+    /// Convert the remainder to uppercase in place, without moving the read position
+    ///
+    /// Characters whose uppercase form is several characters (such as `ß`), or has a different
+    /// length in UTF-8, are left unchanged. Returns the number of such characters which were skipped.
+    pub fn make_uppercase_remaining(&mut self) -> usize {
+        self.map_remaining_same_len(char::to_uppercase)
+    }
+
+    /// Pass the bytes of the logical content to `f`, in one or two calls
+    ///
+    /// The first section is passed followed by the remainder, skipping the second section. This
+    /// allows a checksum or hash to be computed over the text which [`Replacinator::synchronise`]
+    /// would produce, without the filler. Empty sections are skipped.
+    pub fn fold_bytes(&self, mut f: impl FnMut(&[u8])) {
+        for section in [self.inner.start_bytes(), self.inner.remainder_bytes()] {
+            if !section.is_empty() {
+                f(section);
+            }
+        }
+    }
+
+    /// The edit distance between the logical content and `other`
+    ///
+    /// The logical content is the first section followed by the remainder, which is the
+    /// string [`Replacinator::synchronise`] would produce, without the filler. The distance is the
+    /// number of single character insertions, deletions and substitutions needed to turn one into
+    /// the other.
+    ///
+    /// As no allocation is available, `row` is used as scratch space. Returns `None` if `row` is
+    /// shorter than one more than the number of characters in `other`.
+    pub fn levenshtein(&self, other: &str, row: &mut [usize]) -> Option<usize> {
+        let row = row.get_mut(..other.chars().count() + 1)?;
+        for (j, cell) in row.iter_mut().enumerate() {
+            *cell = j;
+        }
+        for (i, a) in self.content_chars().enumerate() {
+            let mut diagonal = row[0];
+            row[0] = i + 1;
+            for (j, b) in other.chars().enumerate() {
+                let substitution = diagonal + usize::from(a != b);
+                diagonal = row[j + 1];
+                row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+            }
+        }
+        row.last().copied()
+    }
+
+    /// The similarity between the logical content and `other`, from `0.0` (entirely different)
+    /// to `1.0` (equal)
+    ///
+    /// This is the [`Replacinator::levenshtein`] distance relative to the length of the longer
+    /// string in characters, and returns `None` under the same conditions.
+    pub fn similarity(&self, other: &str, row: &mut [usize]) -> Option<f64> {
+        let distance = self.levenshtein(other, row)?;
+        let longest = self.content_chars().count().max(other.chars().count());
+        if longest == 0 {
+            return Some(1.0);
+        }
+        Some(1.0 - distance as f64 / longest as f64)
+    }
+
+    #[inline(always)]
     pub fn read_char(&mut self) -> Option<char> {
         let value = self.remainder().chars().next();
         if let Some(c) = value {
-            self.read_position += c.len_utf8();
+            self.advance_read(c.len_utf8());
         }
         self.check_invariants();
         value
     }
 
-    pub fn write_char(&mut self, c: char) {
-        c.encode_utf8(self.invalid_region());
-        self.write_position += c.len_utf8();
+    /// The first extended grapheme cluster of the remainder, without reading it
+    ///
+    /// This requires the `unicode-segmentation` feature.
+    #[cfg(feature = "unicode-segmentation")]
+    pub fn peek_grapheme(&self) -> Option<&str> {
+        use unicode_segmentation::UnicodeSegmentation;
+        self.remainder().graphemes(true).next()
+    }
+
+    /// Read the next extended grapheme cluster without writing it
+    ///
+    /// A grapheme cluster is what a user would consider a single character, such as a letter
+    /// followed by combining marks, or an emoji sequence joined with zero width joiners. Reading
+    /// these whole avoids splitting them when redacting or truncating text.
+    ///
+    /// This requires the `unicode-segmentation` feature.
+    #[cfg(feature = "unicode-segmentation")]
+    pub fn read_grapheme(&mut self) -> Option<&str> {
+        let len = self.peek_grapheme()?.len();
+        let start = self.inner.read_position;
+        self.advance_read(len);
+        self.check_invariants();
+        // Safety: These bytes were part of the remainder, and nothing can be written over them
+        // whilst the returned borrow of `self` is live
+        Some(unsafe { from_utf8_checked_if(self.checks, &self.inner.contents[start..start + len]) })
+    }
+
+    /// Read and write back the next extended grapheme cluster, returning it
+    ///
+    /// This requires the `unicode-segmentation` feature.
+    #[cfg(feature = "unicode-segmentation")]
+    pub fn skip_grapheme(&mut self) -> Option<&str> {
+        let len = self.peek_grapheme()?.len();
+        let start = self.inner.write_position;
+        self.skip_bytes(len);
+        Some(&self.start()[start..])
+    }
+
+    /// Read the next line without writing it, returning its text
+    ///
+    /// The line is read up to and including the next `\n`, or to the end of the remainder if there
+    /// is none. The returned text excludes the `\n` or `\r\n` which ended the line. The line feed
+    /// is found using [`str::find`], so long lines are scanned in bulk.
+    pub fn read_line(&mut self) -> Option<&str> {
+        let (len, text_len) = self.line_len()?;
+        let start = self.inner.read_position;
+        self.advance_read(len);
         self.check_invariants();
+        // Safety: These bytes were part of the remainder, and nothing can be written over them
+        // whilst the returned borrow of `self` is live
+        Some(unsafe {
+            from_utf8_checked_if(self.checks, &self.inner.contents[start..start + text_len])
+        })
+    }
+
+    /// Read and write back the next line, returning its text
+    ///
+    /// This reads the same line as [`Replacinator::read_line`], including its line ending, which
+    /// is written back but excluded from the returned text.
+    pub fn skip_line(&mut self) -> Option<&str> {
+        let (len, text_len) = self.line_len()?;
+        let start = self.inner.write_position;
+        self.skip_bytes(len);
+        Some(&self.start()[start..start + text_len])
+    }
+
+    /// An iterator over the lines of the remainder, without reading them
+    ///
+    /// This is [`str::lines`], so the lines exclude their line endings.
+    pub fn remainder_lines(&self) -> core::str::Lines<'_> {
+        self.remainder().lines()
+    }
+
+    /// Read the next segment of the remainder between Unicode word boundaries without writing it
+    ///
+    /// Segments are found using [Unicode Standard Annex #29], so a segment is either a word, or a
+    /// run of whitespace, or a single punctuation character. This requires the
+    /// `unicode-segmentation` feature.
+    ///
+    /// [Unicode Standard Annex #29]: https://www.unicode.org/reports/tr29/
+    #[cfg(feature = "unicode-segmentation")]
+    pub fn read_word(&mut self) -> Option<&str> {
+        use unicode_segmentation::UnicodeSegmentation;
+        let len = self.remainder().split_word_bounds().next()?.len();
+        let start = self.inner.read_position;
+        self.advance_read(len);
+        self.check_invariants();
+        // Safety: These bytes were part of the remainder, and nothing can be written over them
+        // whilst the returned borrow of `self` is live
+        Some(unsafe { from_utf8_checked_if(self.checks, &self.inner.contents[start..start + len]) })
+    }
+
+    /// Read and write back the next segment of the remainder between Unicode word boundaries
+    ///
+    /// This reads the same segment as [`Replacinator::read_word`], and requires the
+    /// `unicode-segmentation` feature.
+    #[cfg(feature = "unicode-segmentation")]
+    pub fn skip_word(&mut self) -> Option<&str> {
+        use unicode_segmentation::UnicodeSegmentation;
+        let len = self.remainder().split_word_bounds().next()?.len();
+        let start = self.inner.write_position;
+        self.skip_bytes(len);
+        Some(&self.start()[start..])
     }
 
+    /// Create an iterator which reads characters from the remainder, advancing the read position
+    ///
+    /// Characters yielded by this iterator are not written back; use [`Replacinator::write_char`]
+    /// to keep them. Note that adapters such as [`Iterator::take_while`] will read (and therefore
+    /// drop) the first character which does not match.
+    pub fn reader<'r>(&'r mut self) -> ReadChars<'r, 'a, O> {
+        ReadChars { inner: self }
+    }
+
+    /// Transform the remainder in place, replacing each character with the result of `f`,
+    /// or removing it if `f` returns `None`
+    ///
+    /// Returns the first section, which contains the transformed characters. If a replacement
+    /// character would not fit in the space available, the transformation stops before the
+    /// character it replaces, which remains in the remainder.
+    pub fn map_chars(&mut self, mut f: impl FnMut(char) -> Option<char>) -> &mut str {
+        while let Some(c) = self.peek() {
+            let replacement = f(c);
+            if let Some(replacement) = replacement {
+                let available = self.gap_len() + c.len_utf8();
+                if replacement.len_utf8() > available {
+                    break;
+                }
+            }
+            self.read_char();
+            if let Some(replacement) = replacement {
+                self.write_char(replacement);
+            }
+        }
+        self.start_mut()
+    }
+
+    #[inline]
+    pub fn write_char(&mut self, c: char) {
+        self.write_str(c.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Write `s` to the end of the first section
+    ///
+    /// If `s` is longer than the space between the write position and the read position, what
+    /// happens is decided by [`Replacinator::overflow_policy`].
+    ///
+    /// # Panics
+    /// If `s` doesn't fit and the policy is [`OverflowPolicy::Panic`], or if
+    /// [`Replacinator::abort`] was called
+    #[inline(always)]
+    pub fn write_str(&mut self, s: &str) {
+        assert!(!self.aborted, "Cannot write to an aborted replacinator");
+        let available = self.gap_len();
+        if s.len() > available && !matches!(self.overflow, OverflowPolicy::Panic) {
+            self.overflow_str(s, available);
+            return;
+        }
+        // The observer is only notified once the write succeeds, as it panics if `s` doesn't fit
+        self.inner.write_bytes(s.as_bytes());
+        self.observer.write(s);
+    }
+
+    /// Handle `s` not fitting in the `available` bytes, according to the overflow policy
+    #[cold]
+    fn overflow_str(&mut self, s: &str, available: usize) {
+        self.overflowed = true;
+        match self.overflow {
+            OverflowPolicy::Panic => unreachable!("A write which doesn't fit would have panicked"),
+            OverflowPolicy::Saturate => {
+                let end = (0..=available)
+                    .rev()
+                    .find(|&end| s.is_char_boundary(end))
+                    .unwrap_or(0);
+                let fits = &s[..end];
+                self.inner.write_bytes(fits.as_bytes());
+                self.observer.write(fits);
+            }
+            OverflowPolicy::Error => {}
+            OverflowPolicy::Callback(callback) => callback(s, available),
+        }
+    }
+
+    /// Write each character of `chars` to the end of the first section, returning the number of
+    /// characters written
+    ///
+    /// If a character doesn't fit before the read position, `overflow` decides what happens.
+    /// With [`Overflow::Panic`], which the [`Extend`] impls use, this is left to the
+    /// [`OverflowPolicy`]. That panics by default, but the other policies write nothing of a
+    /// character which doesn't fit, so it isn't counted.
+    pub fn write_chars(
+        &mut self,
+        chars: impl IntoIterator<Item = char>,
+        overflow: Overflow,
+    ) -> usize {
+        let mut count = 0;
+        for c in chars {
+            if c.len_utf8() > self.gap_len() {
+                match overflow {
+                    Overflow::Panic => {}
+                    Overflow::Stop => break,
+                    Overflow::Skip => continue,
+                }
+            }
+            let write_offset = self.write_offset();
+            self.write_char(c);
+            count += usize::from(self.write_offset() != write_offset);
+        }
+        count
+    }
+
+    /// Read the next character without writing it, or return an error if `self` is poisoned
+    ///
+    /// This is [`Replacinator::read_char`], without its panic.
+    pub fn try_read_char(&mut self) -> Result<Option<char>, Poisoned> {
+        self.try_check_invariants()?;
+        let value = self.remainder().chars().next();
+        if let Some(c) = value {
+            self.advance_read(c.len_utf8());
+        }
+        self.try_check_invariants()?;
+        Ok(value)
+    }
+
+    /// Write `s` to the end of the first section, or return an error without writing anything
+    ///
+    /// This is [`Replacinator::write_str`], without its panics.
+    pub fn try_write_str(&mut self, s: &str) -> Result<(), WriteError> {
+        if self.try_check_invariants().is_err() {
+            return Err(WriteError::Poisoned);
+        }
+        if self.aborted {
+            return Err(WriteError::Aborted);
+        }
+        if s.len() > self.gap_len() {
+            return Err(WriteError::NoRoom);
+        }
+        self.write_str(s);
+        Ok(())
+    }
+
+    /// Write `c` to the end of the first section, or return an error without writing anything
+    ///
+    /// This is [`Replacinator::write_char`], without its panics.
+    pub fn try_write_char(&mut self, c: char) -> Result<(), WriteError> {
+        self.try_write_str(c.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Synchronise, or return an error if `self` is poisoned
+    ///
+    /// This is [`Replacinator::synchronise`], without its panic. If `self` is poisoned, the whole
+    /// slice is filled with spaces instead.
+    pub fn try_synchronise(&mut self) -> Result<(), Poisoned> {
+        self.try_check_invariants()?;
+        self.synchronise();
+        Ok(())
+    }
+
+    /// The position in the original string of the next character to be read
+    #[inline]
+    pub fn position(&self) -> Position {
+        self.inner.position()
+    }
+
+    /// The byte offset in the original string of the next character to be read
+    ///
+    /// This is the same as `position().byte()`.
+    pub fn read_offset(&self) -> usize {
+        self.inner.position.byte()
+    }
+
+    /// The byte offset in the original string at which the next character will be written
+    ///
+    /// After [`FillStrategy::Compact`] is used, this is before [`Replacinator::read_offset`] by more
+    /// than the length of the second section, as the remainder has moved towards the start.
+    pub fn write_offset(&self) -> usize {
+        self.read_offset() - self.compacted - self.gap_len()
+    }
+
+    /// The byte offset in the string of `text`, which must be a part of the string taken from
+    /// this, such as by [`Replacinator::take_start`]
+    ///
+    /// This is measured in the same way as [`Replacinator::write_offset`]. Returns `None` if
+    /// `text` is not within the string, or comes after the end of the remainder.
+    pub fn offset_of(&self, text: &str) -> Option<usize> {
+        let contents = self.inner.contents.as_ptr() as usize;
+        let start = contents.checked_sub(self.write_offset() - self.inner.write_position)?;
+        let offset = (text.as_ptr() as usize).checked_sub(start)?;
+        let end = contents + self.inner.contents.len();
+        if text.as_ptr() as usize + text.len() > end {
+            return None;
+        }
+        Some(offset)
+    }
+
+    /// Whether any byte of the string has been changed
+    ///
+    /// Each write is compared with the bytes it overwrites, so a transform which writes back
+    /// exactly what it read leaves the string clean, and the string doesn't need to be saved.
+    /// The second section is counted as it will be once it is returned to valid UTF-8. Changes
+    /// made through [`Replacinator::start_mut`] and [`Replacinator::remainder_mut`] are not seen.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty_range().is_some()
+    }
+
+    /// The smallest range of byte offsets in the string containing every byte which has been
+    /// changed, as described by [`Replacinator::is_dirty`]
+    ///
+    /// The offsets are measured in the same way as [`Replacinator::write_offset`], and include
+    /// changes to sections which have been taken from this.
+    pub fn dirty_range(&self) -> Option<core::ops::Range<usize>> {
+        let inner = &self.inner;
+        // The address of the start of the string, from which the changes are measured
+        let base = self.read_offset() - self.compacted - inner.read_position;
+        let start = inner.contents.as_ptr() as usize;
+        let origin = start - base;
+        let pending = if inner.poisoned || !inner.invariants_hold() {
+            bytes::differing_from(inner.contents, Self::FILL)
+        } else {
+            self.fill_changes(self.fill)
+        };
+        let pending = pending.map(|changed| (start + changed.start, start + changed.end));
+        let (first, last) = match (inner.dirty, pending) {
+            (Some(dirty), Some(pending)) => (dirty.0.min(pending.0), dirty.1.max(pending.1)),
+            (dirty, pending) => dirty.or(pending)?,
+        };
+        Some(first - origin..last - origin)
+    }
+
+    /// The length in bytes of the first section, which has been written since the last call to
+    /// [`Replacinator::take_start`]
+    ///
+    /// This is how much of the string is meaningful, once the remainder has been processed.
+    #[inline]
+    pub fn written_len(&self) -> usize {
+        self.inner.write_position
+    }
+
+    /// The length in bytes of the first and second sections, which have been read since the last
+    /// call to [`Replacinator::take_start`]
+    #[inline]
+    pub fn consumed_len(&self) -> usize {
+        self.inner.read_position
+    }
+
+    /// The length in bytes of the remainder, which is left to be read
+    #[inline]
+    pub fn remaining_len(&self) -> usize {
+        self.inner.contents.len() - self.inner.read_position
+    }
+
+    /// The length in bytes of the second section, between the first section and the remainder
+    ///
+    /// This is how much more has been read than written since the last call to
+    /// [`Replacinator::take_start`].
+    #[inline]
+    pub fn gap_len(&self) -> usize {
+        self.inner.read_position - self.inner.write_position
+    }
+
+    /// The most bytes which can be written before reaching the read position
+    ///
+    /// This is [`Replacinator::gap_len`], unless nothing can be written because `self` has been
+    /// aborted or poisoned. A transform which would grow the text, such as by escaping, can use
+    /// this to decide whether to write in place, or to give up before writing anything.
+    #[inline]
+    pub fn capacity_for_write(&self) -> usize {
+        if self.aborted || self.inner.poisoned {
+            0
+        } else {
+            self.gap_len()
+        }
+    }
+
+    /// The length in bytes of the logical content, which is the first section followed by the remainder
+    pub fn len(&self) -> usize {
+        self.inner.write_position + self.remainder().len()
+    }
+
+    /// Whether the first section and the remainder are both empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Truncate the logical content to at most `len` bytes, returning the length it was truncated to
+    ///
+    /// If `len` is not on a character boundary, the content is truncated to the boundary before it,
+    /// so the result is never more than `len` bytes. The remainder is read entirely, and the space
+    /// after the truncated content is filled with spaces when it is returned to valid UTF-8. Nothing
+    /// is changed if the content is no longer than `len`.
+    pub fn truncate_buffer(&mut self, len: usize) -> usize {
+        if len >= self.len() {
+            return self.len();
+        }
+        let written = self.inner.write_position;
+        match len.checked_sub(written) {
+            Some(mut keep) => {
+                while !self.remainder().is_char_boundary(keep) {
+                    keep -= 1;
+                }
+                self.skip_bytes(keep);
+            }
+            None => {
+                let mut keep = len;
+                while !self.start().is_char_boundary(keep) {
+                    keep -= 1;
+                }
+                self.truncate_start(keep);
+            }
+        }
+        self.discard_bytes(self.remainder().len());
+        self.inner.write_position
+    }
+
+    /// Read and write back the remainder up to the byte offset `offset` in the original string
+    ///
+    /// This allows the [`Replacinator`] to be driven by byte ranges found in the original
+    /// string, such as from a regex match. Returns `false` without reading anything if `offset`
+    /// is before [`Replacinator::read_offset`], past the end of the remainder, or not on a
+    /// character boundary.
+    #[must_use]
+    pub fn advance_read_to(&mut self, offset: usize) -> bool {
+        let len = match offset.checked_sub(self.read_offset()) {
+            Some(len) if self.remainder().is_char_boundary(len) => len,
+            _ => return false,
+        };
+        self.skip_bytes(len);
+        true
+    }
+
+    /// Whether an internal invariant was found to be violated
+    ///
+    /// This indicates a bug in this crate. A poisoned [`Replacinator`] panics when used, and its
+    /// whole slice is filled with spaces when it is returned to valid UTF-8.
+    /// [`ReplacinatorGuard::try_with`] can be used to recover from this.
+    #[inline]
+    pub fn is_poisoned(&self) -> bool {
+        self.inner.is_poisoned()
+    }
+
+    /// Return the second section to valid UTF-8 using [`Replacinator::fill`], and move the write
+    /// position to the read position
     pub fn synchronise(&mut self) {
-        let bytes = self.invalid_region();
-        let code: u32 = ' '.into();
-        bytes.fill(code.try_into().unwrap());
-        self.write_position = self.read_position;
+        self.synchronise_with(self.fill);
+    }
+
+    /// Return the second section to valid UTF-8 using `fill`, and move the write position to the
+    /// read position
+    pub fn synchronise_with(&mut self, fill: FillStrategy) {
+        self.inner.check_invariants();
+        self.observer.synchronise(fill);
+        self.synchronise_unobserved(fill);
+    }
+
+    /// Move the remainder to directly after the first section, leaving padding at the end
+    ///
+    /// This is [`Replacinator::synchronise_with`] using [`FillStrategy::Compact`].
+    pub fn compact(&mut self) {
+        self.synchronise_with(FillStrategy::Compact);
+    }
+
+    /// How the second section is returned to valid UTF-8 when synchronising, including when
+    /// this is dropped
+    #[inline]
+    pub fn fill(&self) -> FillStrategy {
+        self.fill
+    }
+
+    /// Set how the second section is returned to valid UTF-8 when synchronising, including when
+    /// this is dropped
+    ///
+    /// [`FillStrategy::Space`] is used by default. With [`FillStrategy::Compact`], the string
+    /// ends as the first section followed by the remainder, then padding.
+    #[inline]
+    pub fn set_fill(&mut self, fill: FillStrategy) {
+        self.fill = fill;
+    }
+
+    /// What happens when a write doesn't fit before the read position
+    #[inline]
+    pub fn overflow_policy(&self) -> OverflowPolicy {
+        self.overflow
+    }
+
+    /// Set what happens when a write doesn't fit before the read position
+    ///
+    /// [`OverflowPolicy::Panic`] is used by default. This affects every write, including those
+    /// made by the transforms in this crate's modules.
+    #[inline]
+    pub fn set_overflow_policy(&mut self, overflow: OverflowPolicy) {
+        self.overflow = overflow;
+    }
+
+    /// Whether a write hasn't fit since this was created, or since
+    /// [`Replacinator::clear_overflowed`] was last called
+    ///
+    /// This is only set by the policies other than [`OverflowPolicy::Panic`].
+    #[inline]
+    pub fn overflowed(&self) -> bool {
+        self.overflowed
+    }
+
+    /// Reset [`Replacinator::overflowed`] to `false`
+    #[inline]
+    pub fn clear_overflowed(&mut self) {
+        self.overflowed = false;
+    }
+
+    /// Stop changing the string, leaving it as the first section followed by the remainder
+    ///
+    /// This synchronises, then leaves nothing to read. Any later attempt to write panics, so
+    /// code which carries on after an error can't write any more text. The first section
+    /// can still be viewed and taken. [`Replacinator::new_in`] already restores the string if its
+    /// function panics, so this is only needed to give up part way through without panicking.
+    pub fn abort(&mut self) {
+        self.synchronise();
+        self.inner.take_remainder();
+        self.padding = 0;
+        self.aborted = true;
+    }
+
+    /// Whether [`Replacinator::abort`] was called
+    #[inline]
+    pub fn is_aborted(&self) -> bool {
+        self.aborted
+    }
+
+    /// Mark the current write position as the start of a token, to later extract it using [`Replacinator::take_marked`]
+    ///
+    /// The mark also records the current read [`Position`], for use with
+    /// [`Replacinator::span_since`].
+    #[inline]
+    pub fn mark(&self) -> Mark {
+        Mark {
+            contents: self.inner.contents.as_ptr() as usize,
+            write_position: self.inner.write_position,
+            position: self.inner.position,
+        }
+    }
+
+    /// The part of the original string which has been read since `mark` was made
+    ///
+    /// This is where the text written since `mark` came from, if it was read after the mark was
+    /// made. If the read position has since moved before `mark` (using [`Replacinator::rewind`]),
+    /// the span is empty, at the current position.
+    pub fn span_since(&self, mark: Mark) -> Span {
+        let end = self.position();
+        Span::new(mark.position.min(end), end)
+    }
+
+    /// Take the characters written since `mark` as a mutable view
+    ///
+    /// Like [`Replacinator::take_start`], the first section is no longer available afterwards.
+    ///
+    /// Returns `None` if `mark` was not created from this [`Replacinator`] since the
+    /// last call to [`Replacinator::take_start`], or if the write position has since moved
+    /// before it (using [`Replacinator::rewind`]).
+    pub fn take_marked(&mut self, mark: Mark) -> Option<&'a mut str> {
+        if mark.contents != self.inner.contents.as_ptr() as usize
+            || !self.start().is_char_boundary(mark.write_position)
+        {
+            return None;
+        }
+        Some(&mut self.take_start()[mark.write_position..])
+    }
+
+    /// Take the characters written since `mark` as a mutable view, along with the part of the
+    /// original string which was read since `mark` was made
+    ///
+    /// This combines [`Replacinator::take_marked`] and [`Replacinator::span_since`], returning
+    /// `None` in the same cases as [`Replacinator::take_marked`].
+    pub fn take_marked_spanned(&mut self, mark: Mark) -> Option<(Span, &'a mut str)> {
+        let span = self.span_since(mark);
+        Some((span, self.take_marked(mark)?))
+    }
+
+    /// Record the current read and write positions, to later return to them using [`Replacinator::rewind`]
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            contents: self.inner.contents.as_ptr() as usize,
+            compacted: self.compacted,
+            read_position: self.inner.read_position,
+            write_position: self.inner.write_position,
+            position: self.inner.position,
+        }
+    }
+
+    /// Return to the read and write positions recorded in `checkpoint`
+    ///
+    /// Everything read since the checkpoint will be read again, and everything written
+    /// since the checkpoint is discarded. This is only possible if nothing has been written
+    /// past the read position of the checkpoint, as the characters there would have been
+    /// overwritten. The checkpoint must also have been created from this [`Replacinator`] since the
+    /// last call to [`Replacinator::take_start`], and must not be ahead of the current positions.
+    ///
+    /// Returns whether the positions were restored. If `false` is returned, this is unchanged.
+    #[must_use]
+    pub fn rewind(&mut self, checkpoint: Checkpoint) -> bool {
+        let valid = checkpoint.contents == self.inner.contents.as_ptr() as usize
+            && checkpoint.compacted == self.compacted
+            && checkpoint.write_position <= self.inner.write_position
+            && self.inner.write_high_water <= checkpoint.read_position
+            && checkpoint.read_position <= self.inner.read_position
+            // These two checks guard against checkpoints which were made obsolete
+            // by rewinding to an earlier checkpoint
+            && self.start().is_char_boundary(checkpoint.write_position)
+            && !matches!(
+                self.inner.contents.get(checkpoint.read_position),
+                Some(&b) if is_utf8_continuation(b)
+            );
+        if valid {
+            self.inner.read_position = checkpoint.read_position;
+            self.inner.position = checkpoint.position;
+            self.inner.write_position = checkpoint.write_position;
+            self.check_invariants();
+            let (read_offset, write_offset) = (self.read_offset(), self.write_offset());
+            self.observer.rewind(read_offset, write_offset);
+        }
+        valid
+    }
+
+    /// Move the next `len` bytes of the remainder to the end of the first section
+    ///
+    /// `len` must be on a character boundary of the remainder
+    #[inline]
+    fn skip_bytes(&mut self, len: usize) {
+        assert!(self.remainder().is_char_boundary(len));
+        let (observer, text) = self.observe(len);
+        observer.read(text);
+        observer.write(text);
+        self.inner.skip_bytes(len);
+    }
+
+    /// Read the next `len` bytes of the remainder without writing them
+    ///
+    /// `len` must be on a character boundary of the remainder
+    #[inline]
+    fn discard_bytes(&mut self, len: usize) {
+        let (observer, text) = self.observe(len);
+        observer.read(text);
+        self.inner.discard_bytes(len);
+    }
+
+    /// Remove the end of the first section, keeping its first `len` bytes
+    ///
+    /// `len` must be on a character boundary of the first section
+    fn truncate_start(&mut self, len: usize) {
+        assert!(self.start().is_char_boundary(len));
+        self.inner.write_position = len;
         self.check_invariants();
+        let (read_offset, write_offset) = (self.read_offset(), self.write_offset());
+        self.observer.rewind(read_offset, write_offset);
     }
 
-    fn invalid_region(&mut self) -> &mut [u8] {
+    /// Add the next `len` bytes of the second section, which have already been written in
+    /// place, to the end of the first section
+    ///
+    /// # Safety
+    /// The bytes must be valid UTF-8
+    unsafe fn advance_write(&mut self, len: usize) {
+        let start = self.inner.write_position;
+        assert!(len <= self.inner.read_position - start);
+        let text =
+            unsafe { from_utf8_checked_if(self.checks, &self.inner.contents[start..start + len]) };
+        self.observer.write(text);
+        self.inner.write_position += len;
+        self.inner.write_high_water = self.inner.write_high_water.max(self.inner.write_position);
         self.check_invariants();
-        &mut self.contents[self.write_position..self.read_position]
     }
 
-    /// Checks internal invariants are correct
-    fn check_invariants(&self) {
-        assert!(self.write_position <= self.read_position);
-        if !(self.read_position <= self.contents.len()) {
-            unreachable!("The read position was outside of the ");
+    /// The observer, and the next `len` bytes of the remainder to notify it of
+    #[inline]
+    fn observe(&mut self, len: usize) -> (&mut O, &str) {
+        let remainder = unsafe { from_utf8_checked_if(self.checks, self.inner.remainder_bytes()) };
+        // This is only out of bounds if the invariants no longer hold, which the caller checks
+        (&mut self.observer, remainder.get(..len).unwrap_or_default())
+    }
+
+    /// Move the read position forward by `len` bytes, without checking the invariants
+    ///
+    /// `len` must be on a character boundary of the remainder
+    #[inline]
+    fn advance_read(&mut self, len: usize) {
+        let (observer, text) = self.observe(len);
+        observer.read(text);
+        self.inner.advance_read(len);
+    }
+
+    /// Replace each character of the remainder with its mapping under `f`, where that is a
+    /// single character of the same length, returning the number of characters left unchanged
+    fn map_remaining_same_len<I: Iterator<Item = char>>(&mut self, f: impl Fn(char) -> I) -> usize {
+        let mut skipped = 0;
+        let mut i = 0;
+        while let Some(c) = self.remainder()[i..].chars().next() {
+            let len = c.len_utf8();
+            if !c.is_ascii() || c.is_ascii_alphabetic() {
+                let mut mapped = f(c);
+                match (mapped.next(), mapped.next()) {
+                    (Some(m), None) if m.len_utf8() == len => {
+                        if m != c {
+                            m.encode_utf8(&mut self.inner.remainder_bytes_mut()[i..i + len]);
+                            let start = self.inner.read_position + i;
+                            self.inner.mark_dirty(start..start + len);
+                        }
+                    }
+                    _ => skipped += 1,
+                }
+            }
+            i += len;
+        }
+        skipped
+    }
+
+    /// The length of the next line of the remainder including its line ending, and the length of
+    /// its text, or `None` if the remainder is empty
+    fn line_len(&self) -> Option<(usize, usize)> {
+        let remainder = self.remainder();
+        if remainder.is_empty() {
+            return None;
+        }
+        Some(match remainder.find('\n') {
+            Some(end) if remainder[..end].ends_with('\r') => (end + 1, end - 1),
+            Some(end) => (end + 1, end),
+            None => (remainder.len(), remainder.len()),
+        })
+    }
+
+    /// The characters of the first section followed by those of the remainder
+    fn content_chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.start().chars().chain(self.remainder().chars())
+    }
+
+    /// Check the internal invariants which can be checked cheaply, poisoning `self` if they don't
+    /// hold
+    ///
+    /// # Panics
+    /// If `self` is poisoned
+    #[inline]
+    fn check_invariants(&mut self) {
+        self.inner.check_invariants();
+        if self.check_boundaries().is_err() {
+            self.inner.poison();
+        }
+    }
+
+    /// Check the same invariants as [`Replacinator::check_invariants`], but if they don't hold,
+    /// poison `self` and fill the whole slice with spaces rather than panicking
+    #[inline]
+    fn try_check_invariants(&mut self) -> Result<(), Poisoned> {
+        if self.inner.poisoned || !self.inner.invariants_hold() || self.check_boundaries().is_err()
+        {
+            self.inner.poisoned = true;
+            self.fill_invalid_region();
+            return Err(Poisoned);
         }
+        Ok(())
     }
 }
 
-// `'a` may not dangle, since it is invalid to use the source string
-// until `'a` ends
-impl<'a> Drop for Replacinator<'a> {
-    fn drop(&mut self) {
-        self.synchronise();
+impl<'a, O> Replacinator<'a, O> {
+    /// The byte used to fill the whole slice if it is poisoned
+    const FILL: u8 = FillStrategy::POISONED.pad_char() as u8;
+
+    /// Return the second section to valid UTF-8, without moving the write position
+    ///
+    /// [`FillStrategy::Compact`] can't be used without moving the write position, so spaces are
+    /// used instead. If `self` is poisoned, the positions can't be trusted, so the whole slice is
+    /// filled with spaces instead.
+    ///
+    /// This never panics, so it can be used when dropping and by the `try_` methods.
+    fn fill_invalid_region(&mut self) {
+        let inner = &mut self.inner;
+        let (write, read) = (inner.write_position, inner.read_position);
+        if inner.poisoned || !inner.invariants_hold() {
+            inner.poisoned = true;
+            let len = inner.contents.len();
+            inner.fill_range(0..len, Self::FILL);
+            return;
+        }
+        let fill = match self.fill {
+            FillStrategy::Compact => FillStrategy::Space,
+            fill => fill,
+        };
+        if let Some(changed) = self.fill_changes(fill) {
+            self.inner.mark_dirty(changed);
+        }
+        if let Some(region) = self.inner.contents.get_mut(write..read) {
+            self.fill.fill_bytes(region);
+        }
+    }
+
+    /// Return the second section to valid UTF-8 using `fill`, and move the write position to the
+    /// read position, without notifying the observer
+    ///
+    /// If `self` is poisoned, the whole slice is filled with spaces instead, as in
+    /// [`Replacinator::fill_invalid_region`]. This never panics.
+    fn synchronise_unobserved(&mut self, fill: FillStrategy) {
+        if self.inner.poisoned || !self.inner.invariants_hold() {
+            self.fill_invalid_region();
+            return;
+        }
+        if let Some(changed) = self.fill_changes(fill) {
+            self.inner.mark_dirty(changed);
+        }
+        let inner = &mut self.inner;
+        let (write, read) = (inner.write_position, inner.read_position);
+        if fill != FillStrategy::Compact {
+            if let Some(region) = inner.contents.get_mut(write..read) {
+                fill.fill_bytes(region);
+            }
+            inner.write_position = read;
+            inner.write_high_water = read;
+            return;
+        }
+        let gap = read - write;
+        if gap == 0 {
+            return;
+        }
+        let end = inner.contents.len() - gap;
+        inner.contents.copy_within(read.., write);
+        if let Some(padding) = inner.contents.get_mut(end..) {
+            padding.fill(b' ');
+        }
+        // The padding is split off, so it is never read or written again
+        let contents = core::mem::take(&mut inner.contents);
+        inner.contents = contents.get_mut(..end).unwrap_or_default();
+        inner.read_position = write;
+        inner.write_high_water = write;
+        self.compacted += gap;
+        self.padding += gap;
+    }
+
+    /// The smallest range of the contents which returning the second section to valid UTF-8
+    /// using `fill` would change, or `None` if nothing would change or the positions are invalid
+    fn fill_changes(&self, fill: FillStrategy) -> Option<core::ops::Range<usize>> {
+        let inner = &self.inner;
+        let (write, read) = (inner.write_position, inner.read_position);
+        let shift = |changed: core::ops::Range<usize>, by| changed.start + by..changed.end + by;
+        if fill != FillStrategy::Compact {
+            let region = inner.contents.get(write..read)?;
+            return bytes::differing(region, fill.pad_bytes(region.len()))
+                .map(|changed| shift(changed, write));
+        }
+        let end = inner.contents.len().checked_sub(read.checked_sub(write)?)?;
+        let moved = inner.contents.get(read..)?;
+        let moved = bytes::differing(inner.contents.get(write..end)?, moved.iter().copied())
+            .map(|changed| shift(changed, write));
+        let padding = bytes::differing_from(inner.contents.get(end..)?, b' ')
+            .map(|changed| shift(changed, end));
+        match (moved, padding) {
+            (Some(moved), Some(padding)) => Some(moved.start..padding.end),
+            (moved, padding) => moved.or(padding),
+        }
+    }
+
+    /// Return the whole slice to valid UTF-8 without panicking, synchronising unless `self` is poisoned
+    fn restore(&mut self) {
+        self.synchronise_unobserved(self.fill);
     }
 }
 
-/// Convert a byte slice into a string slice
-///
-/// This function uses a safe path if the safety checks are enabled:
+/// An iterator over the characters of the remainder of a [`Replacinator`]
 ///
-/// - When debug_assertions are enabled (default in a debug build)
-/// - The `"disable_safety_checks"` feature for this crate is enabled.
-///
-/// Note that this safe path is `O(len(v))`
-///
-/// ## Safety
+/// Created by [`Replacinator::reader`]. Each call to `next` advances the read position.
+pub struct ReadChars<'r, 'a, O = NoObserver> {
+    inner: &'r mut Replacinator<'a, O>,
+}
+
+impl<'r, 'a, O: Observer> ReadChars<'r, 'a, O> {
+    /// Access the underlying [`Replacinator`], for example to write characters whilst iterating
+    pub fn replacinator(&mut self) -> &mut Replacinator<'a, O> {
+        self.inner
+    }
+}
+
+impl<'r, 'a, O: Observer> Iterator for ReadChars<'r, 'a, O> {
+    type Item = char;
+
+    #[inline]
+    fn next(&mut self) -> Option<char> {
+        self.inner.read_char()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.inner.remainder().len();
+        (len.div_ceil(4), Some(len))
+    }
+}
+
+impl<'r, 'a, O: Observer> core::iter::FusedIterator for ReadChars<'r, 'a, O> {}
+
+impl<'a, O: Observer> Extend<char> for Replacinator<'a, O> {
+    /// Write each character to the end of the first section
+    ///
+    /// # Panics
+    /// If a character doesn't fit before the read position
+    fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+        self.write_chars(iter, Overflow::Panic);
+    }
+}
+
+impl<'a, 's, O: Observer> Extend<&'s str> for Replacinator<'a, O> {
+    /// Write each string to the end of the first section
+    ///
+    /// # Panics
+    /// If a string doesn't fit before the read position
+    fn extend<I: IntoIterator<Item = &'s str>>(&mut self, iter: I) {
+        for s in iter {
+            self.write_str(s);
+        }
+    }
+}
+
+impl<'a, O: Observer> core::fmt::Debug for Replacinator<'a, O> {
+    /// Shows the first section and the remainder, and the length of the second section between
+    /// them
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.inner.poisoned {
+            return f
+                .debug_struct("Replacinator")
+                .field("poisoned", &true)
+                .finish_non_exhaustive();
+        }
+        let gap = self.gap_len();
+        f.debug_struct("Replacinator")
+            .field("start", &self.start())
+            .field("gap", &format_args!("{} bytes", gap))
+            .field("remainder", &self.remainder())
+            .finish()
+    }
+}
+
+impl<'a, O: Observer> core::fmt::Display for Replacinator<'a, O> {
+    /// Shows the logical content, which is the first section followed by the remainder
+    ///
+    /// Nothing is shown if `self` is poisoned.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.inner.poisoned {
+            return Ok(());
+        }
+        f.write_str(self.start())?;
+        f.write_str(self.remainder())
+    }
+}
+
+/// How the second section of a [`Replacinator`] is returned to valid UTF-8
 ///
-/// Calling [`core::str::from_utf8_unchecked`] on the slice must be safe
-unsafe fn unchecked_from_utf8(v: &[u8]) -> &str {
-    #[cfg(any(debug_assertions, not(feature = "disable_safety_checks"), test))]
-    {
-        core::str::from_utf8(v).expect(
-            "`replacinator` internally tried to create a string slice which would contain invalid UTF-8.
-This indicates a soundness hole; this assertion should be unreachable.
-Please report this at the issue page: https://github.com/DJMcNab/replacinator/issues.",
-        )
+/// Set using [`Replacinator::set_fill`], or for a single call using
+/// [`Replacinator::synchronise_with`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FillStrategy {
+    /// Fill with spaces
+    #[default]
+    Space,
+    /// Fill with NUL characters
+    Nul,
+    /// Fill with copies of the character
+    ///
+    /// If the second section isn't a multiple of the character's length, the bytes left over at
+    /// its end are filled with spaces.
+    Char(char),
+    /// Move the remainder to directly after the first section, then fill the bytes freed at the
+    /// end of the slice with spaces
+    ///
+    /// This takes time proportional to the length of the remainder, rather than of the second
+    /// section. The padding is no longer part of the [`Replacinator`], so the logical content
+    /// ([`Replacinator::len`] bytes) is always at the start of the string.
+    Compact,
+}
+
+impl FillStrategy {
+    /// The strategy used by a new [`Replacinator`]
+    pub const DEFAULT: FillStrategy = FillStrategy::Space;
+
+    /// The strategy used for the whole slice of a [`Replacinator`] which is poisoned
+    ///
+    /// See [`Replacinator::is_poisoned`].
+    pub const POISONED: FillStrategy = FillStrategy::Space;
+
+    /// The character which padding added by this strategy consists of
+    ///
+    /// For [`FillStrategy::Compact`], this is the padding moved to the end of the slice. Padding
+    /// may also end with up to three spaces for [`FillStrategy::Char`], where the padding isn't a
+    /// multiple of the character's length.
+    pub const fn pad_char(self) -> char {
+        match self {
+            FillStrategy::Space | FillStrategy::Compact => ' ',
+            FillStrategy::Nul => '\0',
+            FillStrategy::Char(c) => c,
+        }
+    }
+
+    /// Whether `c` could be part of the padding added by this strategy
+    ///
+    /// This allows padding to be trimmed, such as using `text.trim_end_matches(|c| fill.is_padding(c))`.
+    pub fn is_padding(self, c: char) -> bool {
+        c == self.pad_char() || c == ' '
+    }
+
+    /// The bytes which [`FillStrategy::fill_bytes`] writes over a region of `len` bytes
+    fn pad_bytes(self, len: usize) -> impl DoubleEndedIterator<Item = u8> + ExactSizeIterator {
+        let mut buf = [0; 4];
+        let width = self.pad_char().encode_utf8(&mut buf).len();
+        let whole = len - len % width;
+        (0..len).map(move |i| if i < whole { buf[i % width] } else { b' ' })
+    }
+
+    /// Fill `region` with valid UTF-8 according to this strategy, treating
+    /// [`FillStrategy::Compact`] as [`FillStrategy::Space`]
+    fn fill_bytes(self, region: &mut [u8]) {
+        let c = self.pad_char();
+        let mut buf = [0; 4];
+        let encoded = c.encode_utf8(&mut buf).as_bytes();
+        if encoded.len() == 1 {
+            region.fill(encoded[0]);
+            return;
+        }
+        let mut chunks = region.chunks_exact_mut(encoded.len());
+        for chunk in &mut chunks {
+            chunk.copy_from_slice(encoded);
+        }
+        chunks.into_remainder().fill(b' ');
+    }
+}
+
+/// What [`Replacinator::write_chars`] does when a character doesn't fit before the read position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Write the character anyway, like [`Replacinator::write_char`], which panics unless the
+    /// [`OverflowPolicy`] says otherwise
+    Panic,
+    /// Stop writing, discarding the character which didn't fit and leaving the rest of the
+    /// iterator unconsumed
+    Stop,
+    /// Skip the character, and continue with the rest of the iterator, which may fit
+    Skip,
+}
+
+/// The error returned by [`Replacinator::try_write_str`] when nothing could be written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WriteError {
+    /// The text doesn't fit before the read position
+    NoRoom,
+    /// [`Replacinator::abort`] was called
+    Aborted,
+    /// The [`Replacinator`] was poisoned by a violated internal invariant
+    Poisoned,
+}
+
+impl core::fmt::Display for WriteError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            WriteError::NoRoom => write!(f, "the text does not fit before the read position"),
+            WriteError::Aborted => write!(f, "the replacinator was aborted"),
+            WriteError::Poisoned => write!(
+                f,
+                "the replacinator was poisoned by a violated internal invariant"
+            ),
+        }
     }
+}
 
-    #[cfg(not(any(debug_assertions, not(feature = "disable_safety_checks"), test)))]
-    unsafe {
-        // Safety: Calling this function is safe as guaranteed by the caller
-        core::str::from_utf8_unchecked(v)
+/// The error returned by [`Replacinator::new_in_with_headroom`] and
+/// [`RevReplacinator::new_in_with_headroom`] when the headroom is longer than the string, or
+/// would split a character
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeadroomError {
+    gap: usize,
+}
+
+impl HeadroomError {
+    /// The length of the headroom in bytes
+    pub fn gap(&self) -> usize {
+        self.gap
     }
 }
 
-/// Convert an exclusive byte slice into an exclusive string slice
+impl core::fmt::Display for HeadroomError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "a headroom of {} bytes would split a character or is longer than the string",
+            self.gap
+        )
+    }
+}
+
+/// What [`Replacinator::write_str`] and the methods which use it do when the text doesn't fit
+/// before the read position
 ///
-/// The same caveats regarding the safe path used apply as in [`unchecked_from_utf8`].
+/// Set using [`Replacinator::set_overflow_policy`]. Every policy other than
+/// [`OverflowPolicy::Panic`] sets [`Replacinator::overflowed`].
+#[derive(Debug, Clone, Copy, Default)]
+pub enum OverflowPolicy {
+    /// Panic
+    #[default]
+    Panic,
+    /// Write as much of the text as fits, ending on a character boundary, and drop the rest
+    Saturate,
+    /// Write none of the text
+    Error,
+    /// Call the function with the text and the number of bytes available, and write none of it
+    Callback(fn(&str, usize)),
+}
+
+/// The read and write positions of a [`Replacinator`] at a point in time
 ///
-/// ## Safety
+/// Created by [`Replacinator::checkpoint`], and used with [`Replacinator::rewind`]
+/// to support backtracking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// The address of the contents when the checkpoint was made, to detect use after [`Replacinator::take_start`]
+    contents: usize,
+    /// The amount the remainder had been moved when the checkpoint was made, to detect use after
+    /// [`FillStrategy::Compact`]
+    compacted: usize,
+    read_position: usize,
+    write_position: usize,
+    position: Position,
+}
+
+/// A write position of a [`Replacinator`], marking the start of a token
 ///
-/// Calling [`core::str::from_utf8_unchecked_mut`] on the input slice must be safe
-unsafe fn unchecked_from_utf8_mut(v: &mut [u8]) -> &mut str {
-    #[cfg(any(debug_assertions, not(feature = "disable_safety_checks"), test))]
-    {
-        core::str::from_utf8_mut(v).expect(
-            "`replacinator` internally tried to create a string slice which would contain invalid UTF-8.
-This indicates a soundness hole; this assertion should be unreachable.
-Please report this at the issue page: https://github.com/DJMcNab/replacinator/issues.",
-        )
+/// Created by [`Replacinator::mark`], and used with [`Replacinator::take_marked`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mark {
+    /// The address of the contents when the mark was made, to detect use after [`Replacinator::take_start`]
+    contents: usize,
+    write_position: usize,
+    position: Position,
+}
+
+impl Mark {
+    /// The read position when this mark was made
+    pub fn position(&self) -> Position {
+        self.position
     }
+}
 
-    #[cfg(not(any(debug_assertions, not(feature = "disable_safety_checks"), test)))]
-    unsafe {
-        // Safety: Calling this function is safe as guaranteed by the caller
-        core::str::from_utf8_unchecked_mut(v)
+// `'a` may not dangle, since it is invalid to use the source string
+// until `'a` ends
+impl<'a, O> Drop for Replacinator<'a, O> {
+    fn drop(&mut self) {
+        self.restore();
     }
 }
+
+/// Whether `byte` is a continuation byte in UTF-8, i.e. not the first byte of a character
+fn is_utf8_continuation(byte: u8) -> bool {
+    byte & 0b1100_0000 == 0b1000_0000
+}
@@ -0,0 +1,328 @@
+//! Decoding of the encodings used in email: quoted-printable bodies, and encoded words in headers
+//!
+//! Only text in UTF-8 (or its subset US-ASCII) can be decoded in place, as the result must be
+//! valid UTF-8. Both encodings are strictly shrinking.
+
+use core::fmt;
+
+use crate::{codec::base64_value, Observer, Position, Replacinator};
+
+/// An error encountered whilst decoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    position: Position,
+}
+
+impl Error {
+    /// The kind of error which occurred
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The position in the original string at which the error was detected
+    pub fn position(&self) -> Position {
+        self.position
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::UnexpectedEnd => write!(f, "unexpected end of encoded byte"),
+            ErrorKind::InvalidDigit(c) => write!(f, "invalid digit {:?}", c),
+            ErrorKind::InvalidUtf8 => write!(f, "encoded bytes are not valid UTF-8"),
+        }?;
+        write!(f, " at byte {}", self.position.byte())
+    }
+}
+
+/// The kinds of [`Error`] which can occur whilst decoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The input ended partway through an encoded byte
+    UnexpectedEnd,
+    /// An encoded byte contained a character which is not a hex or base64 digit
+    InvalidDigit(char),
+    /// The decoded bytes are not valid UTF-8
+    InvalidUtf8,
+}
+
+/// Decode the remainder of `src` in place as a quoted-printable body, as in RFC 2045
+///
+/// Each `=XX` is decoded as a byte, and the bytes must be UTF-8. A soft line break (an `=` at the
+/// end of a line, optionally followed by spaces or tabs) is removed, including within the
+/// bytes of a character.
+///
+/// Returns the decoded text. Like [`Replacinator::take_start`], the first section of `src`
+/// is no longer available afterwards.
+pub fn decode_quoted_printable<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+) -> Result<&'a mut str, Error> {
+    let mark = src.mark();
+    loop {
+        src.skip_while(|c| c != '=');
+        if src.read_char().is_none() {
+            break;
+        }
+        if !discard_soft_break(src) {
+            let c = decode_char(src, true)?;
+            src.write_char(c);
+        }
+    }
+    Ok(src
+        .take_marked(mark)
+        .expect("The mark was made from this Replacinator"))
+}
+
+/// Decode the encoded words (such as `=?UTF-8?Q?caf=C3=A9?=`) in the remainder of `src` in
+/// place, as in RFC 2047
+///
+/// Both the `Q` and `B` encodings are supported. Whitespace between two adjacent encoded words
+/// is removed. Encoded words in a charset other than UTF-8 or US-ASCII are left unchanged, as is
+/// text which isn't a well-formed encoded word.
+///
+/// Returns the decoded text. Like [`Replacinator::take_start`], the first section of `src`
+/// is no longer available afterwards.
+pub fn decode_encoded_words<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+) -> Result<&'a mut str, Error> {
+    let mark = src.mark();
+    let mut after_word = false;
+    loop {
+        let remainder = src.remainder();
+        let start = match remainder.find("=?") {
+            Some(start) => start,
+            None => {
+                src.skip_bytes(remainder.len());
+                break;
+            }
+        };
+        let word = match EncodedWord::parse(&remainder[start..]) {
+            Some(word) => word,
+            None => {
+                src.skip_bytes(start + 2);
+                after_word = false;
+                continue;
+            }
+        };
+        let between = &remainder[..start];
+        if after_word && between.bytes().all(|b| b.is_ascii_whitespace()) {
+            src.discard_bytes(start);
+        } else {
+            src.skip_bytes(start);
+        }
+        src.discard_bytes(word.prefix);
+        let end = src.read_offset() + word.text;
+        if word.base64 {
+            decode_base64_text(src, end)?;
+        } else {
+            decode_q_text(src, end)?;
+        }
+        src.discard_bytes("?=".len());
+        after_word = true;
+    }
+    Ok(src
+        .take_marked(mark)
+        .expect("The mark was made from this Replacinator"))
+}
+
+/// The lengths of the parts of an encoded word
+struct EncodedWord {
+    /// The length of the `=?charset?encoding?` before the encoded text
+    prefix: usize,
+    /// The length of the encoded text, which is followed by `?=`
+    text: usize,
+    /// Whether the `B` encoding is used, rather than `Q`
+    base64: bool,
+}
+
+impl EncodedWord {
+    /// Parse the encoded word at the start of `text`, if it is one which can be decoded
+    fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.strip_prefix("=?")?.splitn(4, '?');
+        let label = parts.next()?;
+        let encoding = parts.next()?;
+        let encoded = parts.next()?;
+        if !parts.next()?.starts_with('=') || encoded.contains(|c: char| c.is_whitespace()) {
+            return None;
+        }
+        // RFC 2231 allows the charset to be followed by `*` and a language
+        let charset = label.split('*').next()?;
+        if !charset.eq_ignore_ascii_case("UTF-8") && !charset.eq_ignore_ascii_case("US-ASCII") {
+            return None;
+        }
+        let base64 = match encoding {
+            "B" | "b" => true,
+            "Q" | "q" => false,
+            _ => return None,
+        };
+        Some(Self {
+            prefix: "=?".len() + label.len() + 1 + encoding.len() + 1,
+            text: encoded.len(),
+            base64,
+        })
+    }
+}
+
+/// Decode `Q` encoded text up to the offset `end`
+fn decode_q_text<O: Observer>(src: &mut Replacinator<'_, O>, end: usize) -> Result<(), Error> {
+    while src.read_offset() < end {
+        match src.read_char() {
+            Some('_') => src.write_char(' '),
+            Some('=') => {
+                let c = decode_char(src, false)?;
+                src.write_char(c);
+            }
+            Some(c) => src.write_char(c),
+            None => unreachable!("The encoded text is within the remainder"),
+        }
+    }
+    Ok(())
+}
+
+/// Decode `B` encoded text up to the offset `end`
+fn decode_base64_text<O: Observer>(src: &mut Replacinator<'_, O>, end: usize) -> Result<(), Error> {
+    // The bytes of a character which has only been partly decoded
+    let mut pending = [0; 4];
+    let mut pending_len = 0;
+    let mut group = 0u32;
+    let mut digits = 0;
+    while src.read_offset() < end {
+        let c = src
+            .peek()
+            .expect("The encoded text is within the remainder");
+        if c == '=' {
+            src.discard_bytes(end - src.read_offset());
+            break;
+        }
+        let value = Some(c)
+            .filter(char::is_ascii)
+            .and_then(|c| base64_value(c as u8))
+            .ok_or_else(|| error(src, ErrorKind::InvalidDigit(c)))?;
+        src.read_char();
+        group = group << 6 | u32::from(value);
+        digits += 1;
+        if digits == 4 {
+            for &byte in &group.to_be_bytes()[1..] {
+                push_byte(src, &mut pending, &mut pending_len, byte)?;
+            }
+            group = 0;
+            digits = 0;
+        }
+    }
+    if digits == 1 {
+        return Err(error(src, ErrorKind::UnexpectedEnd));
+    }
+    if digits > 1 {
+        let bytes = group << (6 * (4 - digits)) << 8;
+        for &byte in &bytes.to_be_bytes()[..digits - 1] {
+            push_byte(src, &mut pending, &mut pending_len, byte)?;
+        }
+    }
+    if pending_len != 0 {
+        return Err(error(src, ErrorKind::InvalidUtf8));
+    }
+    Ok(())
+}
+
+/// Add `byte` to the partly decoded character in `pending`, writing the character once it is
+/// complete
+fn push_byte<O: Observer>(
+    src: &mut Replacinator<'_, O>,
+    pending: &mut [u8; 4],
+    pending_len: &mut usize,
+    byte: u8,
+) -> Result<(), Error> {
+    pending[*pending_len] = byte;
+    *pending_len += 1;
+    let len = utf8_len(pending[0]).ok_or_else(|| error(src, ErrorKind::InvalidUtf8))?;
+    if *pending_len == len {
+        let c = core::str::from_utf8(&pending[..len])
+            .map_err(|_| error(src, ErrorKind::InvalidUtf8))?
+            .chars()
+            .next()
+            .expect("A decoded character is not empty");
+        src.write_char(c);
+        *pending_len = 0;
+    }
+    Ok(())
+}
+
+/// The length of the UTF-8 sequence which starts with `byte`, if it can start one
+fn utf8_len(byte: u8) -> Option<usize> {
+    match byte {
+        0x00..=0x7F => Some(1),
+        0xC2..=0xDF => Some(2),
+        0xE0..=0xEF => Some(3),
+        0xF0..=0xF4 => Some(4),
+        _ => None,
+    }
+}
+
+/// Discard the rest of a soft line break, if the `=` which has just been read starts one
+fn discard_soft_break<O: Observer>(src: &mut Replacinator<'_, O>) -> bool {
+    let remainder = src.remainder();
+    let rest = remainder.trim_start_matches([' ', '\t']);
+    let end = if rest.starts_with("\r\n") {
+        2
+    } else if rest.starts_with('\n') || rest.is_empty() {
+        usize::from(!rest.is_empty())
+    } else {
+        return false;
+    };
+    src.discard_bytes(remainder.len() - rest.len() + end);
+    true
+}
+
+/// Decode the character whose first byte follows the `=` which has just been read
+///
+/// If `soft_breaks` is set, soft line breaks between the bytes of the character are removed.
+fn decode_char<O: Observer>(
+    src: &mut Replacinator<'_, O>,
+    soft_breaks: bool,
+) -> Result<char, Error> {
+    let mut bytes = [0; 4];
+    bytes[0] = decode_byte(src)?;
+    let len = utf8_len(bytes[0]).ok_or_else(|| error(src, ErrorKind::InvalidUtf8))?;
+    for byte in &mut bytes[1..len] {
+        loop {
+            if src.peek() != Some('=') {
+                return Err(error(src, ErrorKind::InvalidUtf8));
+            }
+            src.read_char();
+            if !(soft_breaks && discard_soft_break(src)) {
+                break;
+            }
+        }
+        *byte = decode_byte(src)?;
+    }
+    match core::str::from_utf8(&bytes[..len]) {
+        Ok(s) => Ok(s.chars().next().expect("A decoded character is not empty")),
+        Err(_) => Err(error(src, ErrorKind::InvalidUtf8)),
+    }
+}
+
+fn decode_byte<O: Observer>(src: &mut Replacinator<'_, O>) -> Result<u8, Error> {
+    let mut byte = 0;
+    for _ in 0..2 {
+        let c = src
+            .peek()
+            .ok_or_else(|| error(src, ErrorKind::UnexpectedEnd))?;
+        let digit = c
+            .to_digit(16)
+            .ok_or_else(|| error(src, ErrorKind::InvalidDigit(c)))?;
+        src.read_char();
+        byte = byte * 16 + digit as u8;
+    }
+    Ok(byte)
+}
+
+#[cold]
+fn error<O: Observer>(src: &Replacinator<'_, O>, kind: ErrorKind) -> Error {
+    Error {
+        kind,
+        position: src.position(),
+    }
+}
@@ -0,0 +1,158 @@
+//! Tracking of nested pairs of delimiters, such as brackets
+
+use core::fmt;
+
+use crate::{Observer, Position, Replacinator};
+
+/// An error encountered whilst tracking nested delimiters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    position: Position,
+}
+
+impl Error {
+    /// The kind of error which occurred
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The position in the original string at which the error was detected
+    pub fn position(&self) -> Position {
+        self.position
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::Mismatched {
+                expected: Some(expected),
+                found,
+            } => write!(f, "expected {:?} but found {:?}", expected, found),
+            ErrorKind::Mismatched {
+                expected: None,
+                found,
+            } => write!(f, "unmatched {:?}", found),
+            ErrorKind::UnexpectedEnd => write!(f, "unexpected end with unclosed delimiters"),
+            ErrorKind::TooDeep => write!(f, "too many levels of nesting"),
+        }?;
+        write!(f, " at byte {}", self.position.byte())
+    }
+}
+
+/// The kinds of [`Error`] which can occur whilst tracking nested delimiters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A closing delimiter did not match the innermost open delimiter
+    Mismatched {
+        /// The closing delimiter which would have matched, if any delimiters were open
+        expected: Option<char>,
+        /// The closing delimiter which was found
+        found: char,
+    },
+    /// The input ended before the delimiters were closed
+    UnexpectedEnd,
+    /// There were more levels of nesting than fit in the stack given to [`Nesting::new`]
+    TooDeep,
+}
+
+/// A tracker of the delimiters which are open in the text read from a [`Replacinator`]
+///
+/// Text must be read through this tracker, using [`Nesting::skip_char`] or
+/// [`Nesting::skip_to_close`], for it to be tracked.
+#[derive(Debug)]
+pub struct Nesting<'p, 's> {
+    /// The pairs of opening and closing delimiters
+    pairs: &'p [(char, char)],
+    /// The indices in `pairs` of the open delimiters, outermost first
+    stack: &'s mut [usize],
+    depth: usize,
+}
+
+impl<'p, 's> Nesting<'p, 's> {
+    /// The pairs of delimiters used by most programming languages: `()`, `[]` and `{}`
+    pub const BRACKETS: &'static [(char, char)] = &[('(', ')'), ('[', ']'), ('{', '}')];
+
+    /// Create a tracker for `pairs` of delimiters, which can hold as many levels of nesting as
+    /// `stack` has elements
+    ///
+    /// As no allocation is available, `stack` is used to store the open delimiters.
+    pub fn new(pairs: &'p [(char, char)], stack: &'s mut [usize]) -> Self {
+        Self {
+            pairs,
+            stack,
+            depth: 0,
+        }
+    }
+
+    /// The number of open delimiters
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The innermost open delimiter and the delimiter which will close it
+    pub fn innermost(&self) -> Option<(char, char)> {
+        let top = self.depth.checked_sub(1)?;
+        Some(self.pairs[self.stack[top]])
+    }
+
+    /// Read and write back the next character of `src`, tracking it if it is a delimiter
+    ///
+    /// If an error is returned, the character has not been read.
+    pub fn skip_char<O: Observer>(
+        &mut self,
+        src: &mut Replacinator<'_, O>,
+    ) -> Result<Option<char>, Error> {
+        let c = match src.peek() {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+        let error = |kind| Error {
+            kind,
+            position: src.position(),
+        };
+        if let Some(open) = self.pairs.iter().position(|&(open, _)| open == c) {
+            let top = self
+                .stack
+                .get_mut(self.depth)
+                .ok_or_else(|| error(ErrorKind::TooDeep))?;
+            *top = open;
+            self.depth += 1;
+        } else if self.pairs.iter().any(|&(_, close)| close == c) {
+            let expected = self.innermost().map(|(_, close)| close);
+            if expected != Some(c) {
+                return Err(error(ErrorKind::Mismatched { expected, found: c }));
+            }
+            self.depth -= 1;
+        }
+        Ok(src.skip_char())
+    }
+
+    /// Read and write back characters of `src` until the innermost open delimiter is closed,
+    /// including its closing delimiter
+    ///
+    /// If no delimiters are open, the whole remainder is read.
+    pub fn skip_to_close<O: Observer>(
+        &mut self,
+        src: &mut Replacinator<'_, O>,
+    ) -> Result<(), Error> {
+        let target = match self.depth.checked_sub(1) {
+            Some(target) => target,
+            None => {
+                while self.skip_char(src)?.is_some() {}
+                return Ok(());
+            }
+        };
+        while self.depth > target {
+            if self.skip_char(src)?.is_none() {
+                return Err(Error {
+                    kind: ErrorKind::UnexpectedEnd,
+                    position: src.position(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
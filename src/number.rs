@@ -0,0 +1,114 @@
+//! Formatting of numbers directly into the second section, without `core::fmt`
+
+use crate::{raw::unchecked_from_utf8, Observer, Replacinator, WriteError};
+
+/// The longest text written by [`Replacinator::write_f32_fixed`]: a sign, the 39 digits of the
+/// integer part of `f32::MAX`, a point and the most decimal places
+const MAX_LEN: usize = 1 + 39 + 1 + MAX_DECIMALS as usize;
+
+/// The most decimal places which [`Replacinator::write_f32_fixed`] writes
+const MAX_DECIMALS: u8 = 9;
+
+/// The text of a number, built from the end of `buf`
+struct Digits {
+    buf: [u8; MAX_LEN],
+    start: usize,
+}
+
+impl Digits {
+    fn new() -> Self {
+        Self {
+            buf: [0; MAX_LEN],
+            start: MAX_LEN,
+        }
+    }
+
+    fn push(&mut self, b: u8) {
+        self.start -= 1;
+        self.buf[self.start] = b;
+    }
+
+    /// Push the decimal digits of `n`, padded with zeros to at least `width` digits
+    fn push_number(&mut self, mut n: u128, width: usize) {
+        let end = self.start;
+        while n != 0 || end - self.start < width.max(1) {
+            self.push(b'0' + (n % 10) as u8);
+            n /= 10;
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        // Safety: Only ASCII bytes are pushed
+        unsafe { unchecked_from_utf8(&self.buf[self.start..]) }
+    }
+}
+
+impl<'a, O: Observer> Replacinator<'a, O> {
+    /// Write `n` in decimal to the end of the first section, returning the number of bytes
+    /// written
+    ///
+    /// Nothing is written if the number doesn't fit before the read position. This requires the
+    /// `number_formatting` feature.
+    pub fn write_u64(&mut self, n: u64) -> Result<usize, WriteError> {
+        let mut digits = Digits::new();
+        digits.push_number(n.into(), 1);
+        self.write_number(digits.as_str())
+    }
+
+    /// Write `n` in decimal to the end of the first section, with a `-` if it is negative,
+    /// returning the number of bytes written
+    ///
+    /// Nothing is written if the number doesn't fit before the read position. This requires the
+    /// `number_formatting` feature.
+    pub fn write_i64(&mut self, n: i64) -> Result<usize, WriteError> {
+        let mut digits = Digits::new();
+        digits.push_number(n.unsigned_abs().into(), 1);
+        if n < 0 {
+            digits.push(b'-');
+        }
+        self.write_number(digits.as_str())
+    }
+
+    /// Write `x` in decimal with `decimals` digits after the point, rounding to the nearest,
+    /// and return the number of bytes written
+    ///
+    /// At most 9 decimal places are written; a larger `decimals` is treated as 9. With no
+    /// decimal places, no point is written. Infinities are written as `inf` and `-inf`, and NaN
+    /// as `NaN`. A `-` is written if `x` is negative, unless it rounds to zero.
+    ///
+    /// Nothing is written if the number doesn't fit before the read position. This requires the
+    /// `number_formatting` feature.
+    pub fn write_f32_fixed(&mut self, x: f32, decimals: u8) -> Result<usize, WriteError> {
+        if x.is_nan() {
+            return self.write_number("NaN");
+        }
+        if x.is_infinite() {
+            return self.write_number(if x < 0.0 { "-inf" } else { "inf" });
+        }
+        let decimals = decimals.min(MAX_DECIMALS);
+        let scale = (0..decimals).fold(1u64, |scale, _| scale * 10);
+        let abs = f64::from(x.abs());
+        // The integer part of any finite `f32` fits in a `u128`
+        let mut integer = abs as u128;
+        let mut fraction = ((abs - integer as f64) * scale as f64 + 0.5) as u64;
+        if fraction >= scale {
+            integer += 1;
+            fraction -= scale;
+        }
+        let mut digits = Digits::new();
+        if decimals > 0 {
+            digits.push_number(fraction.into(), decimals.into());
+            digits.push(b'.');
+        }
+        digits.push_number(integer, 1);
+        if x < 0.0 && (integer != 0 || fraction != 0) {
+            digits.push(b'-');
+        }
+        self.write_number(digits.as_str())
+    }
+
+    fn write_number(&mut self, text: &str) -> Result<usize, WriteError> {
+        self.try_write_str(text)?;
+        Ok(text.len())
+    }
+}
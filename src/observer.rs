@@ -0,0 +1,67 @@
+/// Hooks which are called as a [`Replacinator`](crate::Replacinator) is used
+///
+/// This allows tracing, coverage or replay tooling to be built without this crate depending on
+/// any particular logging backend. Every hook does nothing by default. A [`Replacinator`] created
+/// with [`Replacinator::new_in_observed`] calls the hooks of its observer. Otherwise, it uses
+/// [`NoObserver`], whose calls are optimised away.
+///
+/// The transforms in this crate's modules which take a [`Replacinator`] accept one with any
+/// observer, and make their edits through the same methods, so the observer sees them too.
+/// Functions which create their own [`Replacinator`], such as from a `&mut str`, use
+/// [`NoObserver`].
+///
+/// [`Replacinator`]: crate::Replacinator
+/// [`Replacinator::new_in_observed`]: crate::Replacinator::new_in_observed
+pub trait Observer {
+    /// Called with text which is about to be read from the start of the remainder
+    ///
+    /// If the text is written back, [`Observer::write`] is then called with the same text.
+    #[inline]
+    fn read(&mut self, text: &str) {
+        let _ = text;
+    }
+
+    /// Called with text which is about to be written to the end of the first section
+    #[inline]
+    fn write(&mut self, text: &str) {
+        let _ = text;
+    }
+
+    /// Called with the first section, as it is taken as a mutable view
+    #[inline]
+    fn take_start(&mut self, text: &str) {
+        let _ = text;
+    }
+
+    /// Called with the remainder, as it is taken as a mutable view
+    #[inline]
+    fn take_remainder(&mut self, text: &str) {
+        let _ = text;
+    }
+
+    /// Called when the second section is filled using `fill`, to move the write position to the
+    /// read position
+    #[inline]
+    fn synchronise(&mut self, fill: crate::FillStrategy) {
+        let _ = fill;
+    }
+
+    /// Called when the positions are successfully returned to a
+    /// [`Checkpoint`](crate::Checkpoint), with the read and write offsets which were restored
+    ///
+    /// This is also called when the end of the first section is removed without moving the read
+    /// position, such as by [`Replacinator::truncate_buffer`] or a transform dropping text it
+    /// has already written. `read_offset` is then the current read offset.
+    ///
+    /// [`Replacinator::truncate_buffer`]: crate::Replacinator::truncate_buffer
+    #[inline]
+    fn rewind(&mut self, read_offset: usize, write_offset: usize) {
+        let _ = (read_offset, write_offset);
+    }
+}
+
+/// The [`Observer`] used by default, which does nothing
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NoObserver;
+
+impl Observer for NoObserver {}
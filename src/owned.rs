@@ -0,0 +1,169 @@
+//! Applying transforms to an owned [`String`]
+//!
+//! A transform through a [`Replacinator`] leaves its result somewhere within the string, usually
+//! followed by padding. These helpers run a transform, then shrink the `String` to its result.
+//! They accept any buffer which can be borrowed as an [`AsMutStr`] and implements
+//! [`RetainRange`], so other owned string types can be used too. [`grow_transform`] also makes
+//! room beforehand, for a transform which lengthens the text, so needs a `String`.
+//!
+//! The functions returning a [`Cow`] check whether the transform would change their input first,
+//! and only copy it to transform in place if so.
+//!
+//! This module requires the `alloc` feature.
+
+use alloc::{borrow::Cow, string::String};
+use core::{convert::Infallible, iter, ops::Range};
+
+use crate::{
+    array::transform_range,
+    percent,
+    rewrite::{self, Outcome, Rewriter},
+    transforms, unescape, AsMutStr, Replacinator,
+};
+
+/// An owned string buffer which can be shrunk to a part of its contents
+///
+/// The helpers in this module use this to leave the buffer holding only the result of a
+/// transform. It is implemented for [`String`]. Other owned string types can implement it along
+/// with [`AsMutStr`] for a mutable borrow of themselves, in the crate which defines them.
+pub trait RetainRange {
+    /// Remove the text before `range.start` and from `range.end` onwards
+    ///
+    /// Both ends of `range` are on character boundaries of the buffer's contents.
+    fn retain_range(&mut self, range: Range<usize>);
+}
+
+impl RetainRange for String {
+    fn retain_range(&mut self, range: Range<usize>) {
+        self.truncate(range.end);
+        self.drain(..range.start);
+    }
+}
+
+/// Apply `transform` to `string`, then shrink `string` to the result of `transform`
+///
+/// # Panics
+/// If `transform` returns a string which isn't part of `string`
+pub fn transform_string<B, F>(string: &mut B, transform: F)
+where
+    B: RetainRange + ?Sized,
+    for<'b> &'b mut B: AsMutStr<'b>,
+    F: for<'a> FnOnce(&mut Replacinator<'a>) -> &'a mut str,
+{
+    match try_transform_string(string, |src| Ok::<_, Infallible>(transform(src))) {
+        Ok(()) => {}
+        Err(never) => match never {},
+    }
+}
+
+/// Apply the fallible `transform` to `string`, then shrink `string` to its result if it
+/// succeeded
+///
+/// If `transform` fails, `string` is left valid, but its contents are unspecified.
+///
+/// # Panics
+/// If `transform` returns a string which isn't part of `string`
+pub fn try_transform_string<B, F, E>(string: &mut B, transform: F) -> Result<(), E>
+where
+    B: RetainRange + ?Sized,
+    for<'b> &'b mut B: AsMutStr<'b>,
+    F: for<'a> FnOnce(&mut Replacinator<'a>) -> Result<&'a mut str, E>,
+{
+    let (start, end) = transform_range((&mut *string).into_mut_str(), transform)?;
+    string.retain_range(start..end);
+    Ok(())
+}
+
+/// Run `rewriter` over `string`, as [`Replacinator::drive`] does, making room first so that its
+/// output may be longer than its input
+///
+/// A first pass runs a clone of `rewriter` to find how much longer the output gets, and the text
+/// is moved that far towards the end of `string`, reallocating if needed. The second pass then
+/// rewrites it in place from the start. This allows transforms which grow the text, such as
+/// adding escape sequences, without a second buffer.
+///
+/// # Panics
+/// If `rewriter` writes more in the second pass than in the first. Its output must only depend
+/// on its state when cloned and the characters it is given.
+pub fn grow_transform<R: Rewriter + Clone>(string: &mut String, mut rewriter: R) {
+    let growth = rewrite::required_growth(&mut rewriter.clone(), string);
+    if growth > 0 {
+        string.extend(iter::repeat_n(' ', growth));
+        // Safety: Rotating moves the spaces, which are each a whole character, to the start.
+        // The rest of the string is moved unchanged, so stays valid UTF-8
+        unsafe { string.as_mut_vec() }.rotate_right(growth);
+    }
+    transform_string(string, |src| {
+        let mark = src.mark();
+        src.discard_bytes(growth);
+        match src.drive(&mut rewriter) {
+            Outcome::Finished | Outcome::Stopped => {}
+            Outcome::NoRoom(_) => panic!("The rewriter wrote more than when measured"),
+        }
+        src.skip_bytes(src.remainder().len());
+        src.take_marked(mark)
+            .expect("The mark was made from this Replacinator")
+    });
+}
+
+/// Decode the JSON escape sequences in `string`, using [`unescape::unescape_json`]
+///
+/// If decoding fails, `string` is left valid, but its contents are unspecified.
+pub fn unescape_json_string<B>(string: &mut B) -> Result<(), unescape::Error>
+where
+    B: RetainRange + ?Sized,
+    for<'b> &'b mut B: AsMutStr<'b>,
+{
+    try_transform_string(string, unescape::unescape_json)
+}
+
+/// Decode the `%XX` sequences in `string`, using [`percent::decode_in_place`]
+///
+/// If decoding fails, `string` is left valid, but its contents are unspecified.
+pub fn percent_decode_string<B>(string: &mut B) -> Result<(), percent::Error>
+where
+    B: RetainRange + ?Sized,
+    for<'b> &'b mut B: AsMutStr<'b>,
+{
+    try_transform_string(string, percent::decode_in_place)
+}
+
+/// Decode the JSON escape sequences in `text`, copying it only if it contains any
+///
+/// Like [`unescape::unescape_json`], decoding stops at an unescaped `"`.
+pub fn unescape_json_cow(text: &str) -> Result<Cow<'_, str>, unescape::Error> {
+    match text
+        .bytes()
+        .position(|b| b == b'\\' || b == b'"' || b < b' ')
+    {
+        None => Ok(Cow::Borrowed(text)),
+        Some(end) if text.as_bytes()[end] == b'"' => Ok(Cow::Borrowed(&text[..end])),
+        Some(_) => {
+            let mut string = String::from(text);
+            unescape_json_string(&mut string)?;
+            Ok(Cow::Owned(string))
+        }
+    }
+}
+
+/// Decode the `%XX` sequences in `text`, copying it only if it contains any
+pub fn percent_decode_cow(text: &str) -> Result<Cow<'_, str>, percent::Error> {
+    if !text.contains('%') {
+        return Ok(Cow::Borrowed(text));
+    }
+    let mut string = String::from(text);
+    percent_decode_string(&mut string)?;
+    Ok(Cow::Owned(string))
+}
+
+/// Convert each `\r\n` and lone `\r` in `text` to `\n`, copying it only if it contains any
+///
+/// See [`transforms::normalize_newlines`].
+pub fn normalize_newlines_cow(text: &str) -> Cow<'_, str> {
+    if !text.contains('\r') {
+        return Cow::Borrowed(text);
+    }
+    let mut string = String::from(text);
+    transform_string(&mut string, transforms::normalize_newlines);
+    Cow::Owned(string)
+}
@@ -0,0 +1,257 @@
+//! Splitting a string into independent chunks, to be transformed in parallel
+//!
+//! [`split_mut`] divides a string into chunks which each end on a character or line boundary.
+//! Each chunk is a separate `&mut str`, so can be given to a different thread (for example using
+//! `std::thread::scope`, or by collecting the chunks for `rayon`) and transformed with its own
+//! [`Replacinator`]. The result of each transform is somewhere within its chunk, and [`join`]
+//! moves these results together at the start of the string.
+//!
+//! With the `std` feature, [`transform_chunks`] does all of this using scoped threads. For more
+//! control, such as to give each chunk a different transform or to collect other results,
+//! `Replacinator::scope` spawns scoped threads which each transform a chunk with their own
+//! [`Replacinator`].
+//!
+//! A [`Replacinator`] is [`Send`] and [`Sync`] whenever its [`Observer`](crate::Observer) is, so
+//! it can also be moved to another thread directly. The [`Reader`](crate::Reader) and
+//! [`Writer`](crate::Writer) halves of one [`Replacinator`] can't be.
+
+use core::{mem::take, ops::Range};
+
+use crate::Replacinator;
+
+/// Where the string can be split between chunks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Boundary {
+    /// Between any two characters
+    Char,
+    /// Only after a `\n`, so that no line is split between chunks
+    ///
+    /// This is needed for transforms which look at more than one character at once, such as
+    /// [`transforms::normalize_newlines`](crate::transforms::normalize_newlines).
+    Line,
+}
+
+/// Split `text` into at most `count` chunks of roughly equal length, each ending on `boundary`
+///
+/// Fewer chunks are produced if `text` is too short, or if there are too few line boundaries. No
+/// chunk is empty, so an empty `text` produces no chunks. A `count` of zero is treated as one, so
+/// a non-empty `text` is a single chunk.
+pub fn split_mut(text: &mut str, count: usize, boundary: Boundary) -> Chunks<'_> {
+    Chunks {
+        rest: text,
+        count: count.max(1),
+        boundary,
+    }
+}
+
+/// An iterator over the chunks of a string
+///
+/// Created by [`split_mut`].
+#[derive(Debug)]
+pub struct Chunks<'t> {
+    rest: &'t mut str,
+    /// The most chunks which may still be produced
+    count: usize,
+    boundary: Boundary,
+}
+
+impl<'t> Iterator for Chunks<'t> {
+    type Item = &'t mut str;
+
+    fn next(&mut self) -> Option<&'t mut str> {
+        let len = self.rest.len();
+        if len == 0 || self.count == 0 {
+            return None;
+        }
+        let end = chunk_end(self.rest, len.div_ceil(self.count), self.boundary);
+        let (chunk, rest) = take(&mut self.rest).split_at_mut(end);
+        self.rest = rest;
+        self.count -= 1;
+        Some(chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.rest.len() {
+            0 => (0, Some(0)),
+            _ => (1, Some(self.count)),
+        }
+    }
+}
+
+impl<'t> core::iter::FusedIterator for Chunks<'t> {}
+
+/// The end of the first non-empty chunk of `text` which is at least `target` bytes long, or all
+/// of `text`
+fn chunk_end(text: &str, target: usize, boundary: Boundary) -> usize {
+    let len = text.len();
+    let target = target.max(1);
+    if target >= len {
+        return len;
+    }
+    match boundary {
+        Boundary::Char => (target..len)
+            .find(|&i| text.is_char_boundary(i))
+            .unwrap_or(len),
+        Boundary::Line => text.as_bytes()[target - 1..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(len, |i| target + i),
+    }
+}
+
+/// Move the parts of `text` in `results` to be consecutive at the start of `text`, returning them
+///
+/// `results` are byte ranges in `text`, such as the results of transforming each chunk from
+/// [`split_mut`]. The rest of `text` is filled with spaces.
+///
+/// # Panics
+/// If `results` are not in order, overlap, or are not on character boundaries of `text`
+pub fn join(text: &mut str, results: impl IntoIterator<Item = Range<usize>>) -> &mut str {
+    let mut results = results.into_iter();
+    Replacinator::new_in(text, |src| {
+        for result in &mut results {
+            let skipped = result
+                .start
+                .checked_sub(src.read_offset())
+                .expect("The results to join must be in order, and must not overlap");
+            src.discard_bytes(skipped);
+            src.skip_bytes(result.len());
+        }
+        src.discard_bytes(src.remainder().len());
+        src.take_start()
+    })
+}
+
+/// Split `text` into at most `count` chunks, apply `transform` to each on a separate thread, and
+/// join the results
+///
+/// See [`split_mut`] and [`join`]. The chunks are transformed in place, so no allocation is made
+/// for the text. If `transform` panics, `text` is still left valid, but its contents are
+/// unspecified.
+///
+/// This function requires the `std` feature.
+///
+/// # Panics
+/// If `transform` panics, or if it returns a string which isn't part of its chunk
+#[cfg(feature = "std")]
+pub fn transform_chunks<F>(
+    text: &mut str,
+    count: usize,
+    boundary: Boundary,
+    transform: F,
+) -> &mut str
+where
+    F: for<'a> Fn(&mut Replacinator<'a>) -> &'a mut str + Sync,
+{
+    use std::{thread, vec::Vec};
+
+    let contents = text.as_ptr() as usize;
+    let transform = &transform;
+    let results: Vec<Range<usize>> = thread::scope(|scope| {
+        let threads: Vec<_> = split_mut(text, count, boundary)
+            .map(|chunk| {
+                let offset = chunk.as_ptr() as usize - contents;
+                scope.spawn(move || {
+                    let (start, end) = crate::array::transform_range(chunk, |src| {
+                        Ok::<_, core::convert::Infallible>(transform(src))
+                    })
+                    .unwrap_or_else(|never| match never {});
+                    offset + start..offset + end
+                })
+            })
+            .collect();
+        threads
+            .into_iter()
+            .map(|thread| {
+                thread
+                    .join()
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e))
+            })
+            .collect()
+    });
+    join(text, results)
+}
+
+// A `Replacinator` only holds the borrowed bytes and its observer, so can be shared or sent
+// between threads like the `&mut str` it was created from
+const _: fn() = || {
+    fn send_and_sync<T: Send + Sync>() {}
+    send_and_sync::<Replacinator<'static>>();
+    send_and_sync::<crate::ByteReplacinator<'static>>();
+};
+
+#[cfg(feature = "std")]
+impl<'env> Replacinator<'env> {
+    /// Split `text` into chunks which are each transformed on their own scoped thread, within the
+    /// given function
+    ///
+    /// Each chunk is split from the start of the part of `text` which hasn't yet been given to a
+    /// thread, using [`Scope::spawn`]. Like [`std::thread::scope`], every thread is joined before
+    /// this returns, and each chunk is returned to valid UTF-8 before its thread ends, even if
+    /// the transform panics.
+    ///
+    /// This function requires the `std` feature.
+    ///
+    /// # Panics
+    /// If a thread panicked and wasn't joined using the handle returned by [`Scope::spawn`]
+    pub fn scope<R>(
+        text: &'env mut str,
+        f: impl for<'scope> FnOnce(&Scope<'scope, 'env>) -> R,
+    ) -> R {
+        std::thread::scope(move |scope| {
+            f(&Scope {
+                scope,
+                rest: core::cell::Cell::new(text),
+            })
+        })
+    }
+}
+
+/// A scope in which chunks of a string are transformed on separate threads
+///
+/// Created by [`Replacinator::scope`]. This requires the `std` feature.
+#[cfg(feature = "std")]
+pub struct Scope<'scope, 'env> {
+    scope: &'scope std::thread::Scope<'scope, 'env>,
+    /// The part of the string which hasn't been given to a thread
+    rest: core::cell::Cell<&'env mut str>,
+}
+
+#[cfg(feature = "std")]
+impl<'scope, 'env> Scope<'scope, 'env> {
+    /// Split off the next chunk of the string, which is at least `len` bytes long and ends on
+    /// `boundary`, and apply `transform` to it on a new thread
+    ///
+    /// The chunk is shorter than `len` only if it is the rest of the string, and is only empty if
+    /// the whole string has been given to threads. The thread's [`Replacinator`] is created with
+    /// [`Replacinator::new_in`].
+    pub fn spawn<F, T>(
+        &self,
+        len: usize,
+        boundary: Boundary,
+        transform: F,
+    ) -> std::thread::ScopedJoinHandle<'scope, T>
+    where
+        F: FnOnce(&mut Replacinator<'env>) -> T + Send + 'scope,
+        T: Send + 'scope,
+    {
+        let rest = self.rest.take();
+        let (chunk, rest) = rest.split_at_mut(chunk_end(rest, len, boundary));
+        self.rest.set(rest);
+        let mut transform = Some(transform);
+        self.scope.spawn(move || {
+            Replacinator::new_in(chunk, |src| {
+                let transform = transform.take().expect("`new_in` calls its function once");
+                transform(src)
+            })
+        })
+    }
+
+    /// The length in bytes of the part of the string which hasn't been given to a thread
+    pub fn remaining_len(&self) -> usize {
+        let rest = self.rest.take();
+        let len = rest.len();
+        self.rest.set(rest);
+        len
+    }
+}
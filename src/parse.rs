@@ -0,0 +1,238 @@
+//! Parser combinators which consume the remainder of a [`Replacinator`]
+//!
+//! A [`Parser`] reads from the remainder, and may write to the first section as it goes. Using
+//! [`token`], the text a parser wrote can then be extracted in place, so that combinator-based
+//! parsers can produce `&'a mut str` slices without allocating.
+//!
+//! Parsers which fail return `None`. The combinators in this module try to return `src` to where
+//! it was before a failed parser started using [`Replacinator::rewind`], so that an alternative
+//! can be tried. This is always possible if the failed parser only read and skipped text, but
+//! may not be if it wrote text which was not in the remainder, or took the first section.
+
+use crate::Replacinator;
+
+/// A parser over the remainder of a [`Replacinator`]
+///
+/// This is implemented for functions which take a `&mut Replacinator<'a>` and return an `Option`.
+pub trait Parser<'a> {
+    /// The value produced by a successful parse
+    type Output;
+
+    /// Parse from the remainder of `src`, returning `None` if the remainder doesn't match
+    fn parse(&mut self, src: &mut Replacinator<'a>) -> Option<Self::Output>;
+
+    /// Transform the output of this parser with `f`
+    fn map<F, O>(self, f: F) -> Map<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(Self::Output) -> O,
+    {
+        Map { parser: self, f }
+    }
+
+    /// Try this parser, then `other` if this parser fails
+    fn or<P>(self, other: P) -> Or<Self, P>
+    where
+        Self: Sized,
+        P: Parser<'a, Output = Self::Output>,
+    {
+        Or {
+            first: self,
+            second: other,
+        }
+    }
+
+    /// Run this parser followed by `next`, producing both outputs
+    ///
+    /// If `next` fails, `src` is returned to before this parser started.
+    fn then<P>(self, next: P) -> Then<Self, P>
+    where
+        Self: Sized,
+        P: Parser<'a>,
+    {
+        Then { first: self, next }
+    }
+
+    /// Run this parser if it matches, producing `None` (and succeeding) if it doesn't
+    fn optional(self) -> Optional<Self>
+    where
+        Self: Sized,
+    {
+        Optional { parser: self }
+    }
+
+    /// Run this parser as many times as it matches, combining the outputs with `f`
+    ///
+    /// This always succeeds, starting from a clone of `init`.
+    fn fold_many<B, F>(self, init: B, f: F) -> FoldMany<Self, B, F>
+    where
+        Self: Sized,
+        B: Clone,
+        F: FnMut(B, Self::Output) -> B,
+    {
+        FoldMany {
+            parser: self,
+            init,
+            f,
+        }
+    }
+}
+
+impl<'a, F, O> Parser<'a> for F
+where
+    F: FnMut(&mut Replacinator<'a>) -> Option<O>,
+{
+    type Output = O;
+
+    fn parse(&mut self, src: &mut Replacinator<'a>) -> Option<O> {
+        self(src)
+    }
+}
+
+/// Run `parser`, returning `src` to where it was if it fails
+///
+/// This is used by all of the combinators in this module.
+pub fn attempt<'a, P: Parser<'a>>(parser: &mut P, src: &mut Replacinator<'a>) -> Option<P::Output> {
+    let checkpoint = src.checkpoint();
+    let output = parser.parse(src);
+    if output.is_none() {
+        // If the parser wrote text which can't be undone, there is nothing more we can do
+        let _ = src.rewind(checkpoint);
+    }
+    output
+}
+
+/// A parser which skips `expected`
+pub fn tag<'a, 't>(expected: &'t str) -> impl Parser<'a, Output = ()> + 't {
+    move |src: &mut Replacinator<'a>| src.skip_str(expected).then_some(())
+}
+
+/// A parser which skips one or more characters matching `pred`, producing their length in bytes
+pub fn skip_while1<'a>(mut pred: impl FnMut(char) -> bool) -> impl Parser<'a, Output = usize> {
+    move |src: &mut Replacinator<'a>| Some(src.skip_while(&mut pred)).filter(|&len| len > 0)
+}
+
+/// A parser which discards one or more characters matching `pred`, producing their length in bytes
+pub fn discard_while1<'a>(mut pred: impl FnMut(char) -> bool) -> impl Parser<'a, Output = usize> {
+    move |src: &mut Replacinator<'a>| Some(src.discard_while(&mut pred)).filter(|&len| len > 0)
+}
+
+/// A parser which runs `parser`, producing the text it wrote as a mutable view
+///
+/// Like [`Replacinator::take_marked`], the first section is no longer available afterwards.
+pub fn token<'a, P: Parser<'a>>(mut parser: P) -> impl Parser<'a, Output = &'a mut str> {
+    move |src: &mut Replacinator<'a>| {
+        let mark = src.mark();
+        attempt(&mut parser, src)?;
+        src.take_marked(mark)
+    }
+}
+
+/// The parser created by [`Parser::map`]
+#[derive(Debug, Clone)]
+pub struct Map<P, F> {
+    parser: P,
+    f: F,
+}
+
+impl<'a, P, F, O> Parser<'a> for Map<P, F>
+where
+    P: Parser<'a>,
+    F: FnMut(P::Output) -> O,
+{
+    type Output = O;
+
+    fn parse(&mut self, src: &mut Replacinator<'a>) -> Option<O> {
+        attempt(&mut self.parser, src).map(&mut self.f)
+    }
+}
+
+/// The parser created by [`Parser::or`]
+#[derive(Debug, Clone)]
+pub struct Or<P, Q> {
+    first: P,
+    second: Q,
+}
+
+impl<'a, P, Q> Parser<'a> for Or<P, Q>
+where
+    P: Parser<'a>,
+    Q: Parser<'a, Output = P::Output>,
+{
+    type Output = P::Output;
+
+    fn parse(&mut self, src: &mut Replacinator<'a>) -> Option<P::Output> {
+        attempt(&mut self.first, src).or_else(|| attempt(&mut self.second, src))
+    }
+}
+
+/// The parser created by [`Parser::then`]
+#[derive(Debug, Clone)]
+pub struct Then<P, Q> {
+    first: P,
+    next: Q,
+}
+
+impl<'a, P, Q> Parser<'a> for Then<P, Q>
+where
+    P: Parser<'a>,
+    Q: Parser<'a>,
+{
+    type Output = (P::Output, Q::Output);
+
+    fn parse(&mut self, src: &mut Replacinator<'a>) -> Option<Self::Output> {
+        let checkpoint = src.checkpoint();
+        let first = attempt(&mut self.first, src)?;
+        match attempt(&mut self.next, src) {
+            Some(next) => Some((first, next)),
+            None => {
+                let _ = src.rewind(checkpoint);
+                None
+            }
+        }
+    }
+}
+
+/// The parser created by [`Parser::optional`]
+#[derive(Debug, Clone)]
+pub struct Optional<P> {
+    parser: P,
+}
+
+impl<'a, P: Parser<'a>> Parser<'a> for Optional<P> {
+    type Output = Option<P::Output>;
+
+    fn parse(&mut self, src: &mut Replacinator<'a>) -> Option<Self::Output> {
+        Some(attempt(&mut self.parser, src))
+    }
+}
+
+/// The parser created by [`Parser::fold_many`]
+#[derive(Debug, Clone)]
+pub struct FoldMany<P, B, F> {
+    parser: P,
+    init: B,
+    f: F,
+}
+
+impl<'a, P, B, F> Parser<'a> for FoldMany<P, B, F>
+where
+    P: Parser<'a>,
+    B: Clone,
+    F: FnMut(B, P::Output) -> B,
+{
+    type Output = B;
+
+    fn parse(&mut self, src: &mut Replacinator<'a>) -> Option<B> {
+        let mut acc = self.init.clone();
+        loop {
+            let before = src.consumed_len();
+            match attempt(&mut self.parser, src) {
+                // Stop if the parser matched without reading anything, which would repeat forever
+                Some(output) if src.consumed_len() != before => acc = (self.f)(acc, output),
+                Some(output) => return Some((self.f)(acc, output)),
+                None => return Some(acc),
+            }
+        }
+    }
+}
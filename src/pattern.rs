@@ -0,0 +1,228 @@
+use core::{fmt, ops::Range};
+
+use crate::Position;
+
+/// Something which can be searched for in the remainder of a [`Replacinator`](crate::Replacinator)
+///
+/// This stands in for the unstable `core::str::pattern::Pattern`, and is implemented for the same
+/// types: a `char`, a string, a set of `char`s as a slice or array, and a closure which matches a
+/// single `char`.
+pub trait Pattern {
+    /// The byte range of the first match of this pattern in `haystack`
+    fn find_in(&mut self, haystack: &str) -> Option<Range<usize>>;
+
+    /// The length in bytes of every match of this pattern, if they are all the same length
+    ///
+    /// This allows [`Replacinator::replace_literal`](crate::Replacinator::replace_literal) to
+    /// check its replacement without searching. By default, this is `None`.
+    fn match_len(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl Pattern for char {
+    fn find_in(&mut self, haystack: &str) -> Option<Range<usize>> {
+        let start = haystack.find(*self)?;
+        Some(start..start + self.len_utf8())
+    }
+
+    fn match_len(&self) -> Option<usize> {
+        Some(self.len_utf8())
+    }
+}
+
+impl Pattern for &str {
+    fn find_in(&mut self, haystack: &str) -> Option<Range<usize>> {
+        let start = haystack.find(*self)?;
+        Some(start..start + self.len())
+    }
+
+    fn match_len(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Pattern for &alloc::string::String {
+    fn find_in(&mut self, haystack: &str) -> Option<Range<usize>> {
+        self.as_str().find_in(haystack)
+    }
+
+    fn match_len(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+impl Pattern for &[char] {
+    fn find_in(&mut self, haystack: &str) -> Option<Range<usize>> {
+        let set = *self;
+        (|c| set.contains(&c)).find_in(haystack)
+    }
+}
+
+impl<const N: usize> Pattern for [char; N] {
+    fn find_in(&mut self, haystack: &str) -> Option<Range<usize>> {
+        (&self[..]).find_in(haystack)
+    }
+}
+
+impl<const N: usize> Pattern for &[char; N] {
+    fn find_in(&mut self, haystack: &str) -> Option<Range<usize>> {
+        (&self[..]).find_in(haystack)
+    }
+}
+
+impl<F: FnMut(char) -> bool> Pattern for F {
+    fn find_in(&mut self, haystack: &str) -> Option<Range<usize>> {
+        let (start, c) = haystack.char_indices().find(|&(_, c)| self(c))?;
+        Some(start..start + c.len_utf8())
+    }
+}
+
+/// The error returned by [`Replacinator::replace_literal`](crate::Replacinator::replace_literal)
+/// and [`transforms::replace_many_in_place`](crate::transforms::replace_many_in_place) when a
+/// match can't be replaced
+///
+/// This is found before anything is replaced, so the remainder is left unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplaceError {
+    kind: ReplaceErrorKind,
+    position: Position,
+}
+
+impl ReplaceError {
+    pub(crate) fn new(kind: ReplaceErrorKind, position: Position) -> Self {
+        Self { kind, position }
+    }
+
+    /// The kind of error which occurred
+    pub fn kind(&self) -> ReplaceErrorKind {
+        self.kind
+    }
+
+    /// The position in the original string of the match which can't be replaced
+    ///
+    /// If the pattern gives its [`Pattern::match_len`], or for
+    /// [`transforms::replace_many_in_place`](crate::transforms::replace_many_in_place), this is
+    /// the read position instead, as no match can be replaced.
+    pub fn position(&self) -> Position {
+        self.position
+    }
+}
+
+impl fmt::Display for ReplaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ReplaceErrorKind::EmptyMatch => write!(f, "cannot replace the empty string"),
+            ReplaceErrorKind::TooLong {
+                match_len,
+                replacement_len,
+            } => write!(
+                f,
+                "a replacement of {} bytes is longer than the match of {} bytes",
+                replacement_len, match_len
+            ),
+        }?;
+        write!(f, " at byte {}", self.position.byte())
+    }
+}
+
+/// The kinds of [`ReplaceError`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ReplaceErrorKind {
+    /// The pattern matched the empty string
+    EmptyMatch,
+    /// The replacement is longer than the match, so would overwrite text which hasn't been read
+    TooLong {
+        /// The length of the match in bytes
+        match_len: usize,
+        /// The length of the replacement in bytes
+        replacement_len: usize,
+    },
+}
+
+/// A string matched without regard to ASCII case, as by [`str::eq_ignore_ascii_case`]
+///
+/// Non-ASCII characters must match exactly, so a match is always the same length as the string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IgnoreAsciiCase<'p>(pub &'p str);
+
+impl Pattern for IgnoreAsciiCase<'_> {
+    fn find_in(&mut self, haystack: &str) -> Option<Range<usize>> {
+        let needle = self.0.as_bytes();
+        if needle.is_empty() {
+            return Some(0..0);
+        }
+        // ASCII case folding leaves the other bytes unchanged, so a match can only start where
+        // the needle's first character could, which is at a character boundary
+        let start = haystack
+            .as_bytes()
+            .windows(needle.len())
+            .position(|window| window.eq_ignore_ascii_case(needle))?;
+        Some(start..start + needle.len())
+    }
+
+    fn match_len(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+/// A string matched after Unicode simple case folding, so that `"ΣΊΣΥΦΟΣ"` matches `"σίσυφος"`
+///
+/// Each character is folded to a single character, so a match has as many characters as the
+/// string, but may have a different length in bytes. Folding uses the lowercase mappings in
+/// `core`, which agree with simple case folding except for a few characters which are handled
+/// separately. This requires the `case_folding` feature.
+#[cfg(feature = "case_folding")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaseFolded<'p>(pub &'p str);
+
+#[cfg(feature = "case_folding")]
+impl Pattern for CaseFolded<'_> {
+    fn find_in(&mut self, haystack: &str) -> Option<Range<usize>> {
+        let needle = self.0;
+        haystack
+            .char_indices()
+            .map(|(start, _)| start)
+            .chain(core::iter::once(haystack.len()))
+            .find_map(|start| {
+                let mut end = start;
+                for expected in needle.chars() {
+                    let c = haystack[end..].chars().next()?;
+                    if fold(c) != fold(expected) {
+                        return None;
+                    }
+                    end += c.len_utf8();
+                }
+                Some(start..end)
+            })
+    }
+}
+
+/// The simple case folding of `c`
+#[cfg(feature = "case_folding")]
+fn fold(c: char) -> char {
+    match c {
+        // These fold to a different character from their lowercase mapping
+        '\u{17F}' => 's',
+        '\u{3C2}' => '\u{3C3}',
+        '\u{3D0}' => '\u{3B2}',
+        '\u{3D1}' => '\u{3B8}',
+        '\u{3D5}' => '\u{3C6}',
+        '\u{3D6}' => '\u{3C0}',
+        '\u{3F0}' => '\u{3BA}',
+        '\u{3F1}' => '\u{3C1}',
+        '\u{3F5}' => '\u{3B5}',
+        '\u{1E9B}' => '\u{1E61}',
+        '\u{1FBE}' => '\u{3B9}',
+        _ => {
+            let mut lower = c.to_lowercase();
+            match (lower.next(), lower.next()) {
+                (Some(lower), None) => lower,
+                // Characters whose lowercase is several characters have no simple folding
+                _ => c,
+            }
+        }
+    }
+}
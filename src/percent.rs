@@ -0,0 +1,247 @@
+//! Decoding of percent-encoded text, as used in URLs
+
+use core::fmt;
+
+use crate::{Observer, Position, Replacinator};
+
+/// An error encountered whilst percent-decoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    position: Position,
+}
+
+impl Error {
+    /// The kind of error which occurred
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The position in the original string at which the error was detected
+    pub fn position(&self) -> Position {
+        self.position
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::UnexpectedEnd => write!(f, "unexpected end of percent-encoded byte"),
+            ErrorKind::InvalidDigit(c) => write!(f, "invalid hex digit {:?}", c),
+            ErrorKind::InvalidUtf8 => write!(f, "percent-encoded bytes are not valid UTF-8"),
+        }?;
+        write!(f, " at byte {}", self.position.byte())
+    }
+}
+
+/// The kinds of [`Error`] which can occur whilst percent-decoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The input ended before the two hex digits after a `%`
+    UnexpectedEnd,
+    /// A `%` was followed by a character which is not a hex digit
+    InvalidDigit(char),
+    /// The decoded bytes of a character are not valid UTF-8
+    ///
+    /// Each character must be encoded as a consecutive run of `%XX` sequences.
+    InvalidUtf8,
+}
+
+/// Decode the `%XX` sequences in the remainder of `src` in place
+///
+/// Returns the decoded text. Like [`Replacinator::take_start`], the first section of `src`
+/// is no longer available afterwards.
+pub fn decode_in_place<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+) -> Result<&'a mut str, Error> {
+    decode_with(src, false)
+}
+
+/// Decode the remainder of `src` in place as `application/x-www-form-urlencoded` text, where
+/// `+` is also decoded as a space
+///
+/// Returns the decoded text. Like [`Replacinator::take_start`], the first section of `src`
+/// is no longer available afterwards.
+pub fn decode_form_in_place<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+) -> Result<&'a mut str, Error> {
+    decode_with(src, true)
+}
+
+fn decode_with<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+    plus_as_space: bool,
+) -> Result<&'a mut str, Error> {
+    let mark = src.mark();
+    loop {
+        src.skip_while(|c| c != '%' && !(plus_as_space && c == '+'));
+        match src.read_char() {
+            None => break,
+            Some('+') => src.write_char(' '),
+            Some(_) => {
+                let c = decode_char(src)?;
+                src.write_char(c);
+            }
+        }
+    }
+    Ok(src
+        .take_marked(mark)
+        .expect("The mark was made from this Replacinator"))
+}
+
+/// Decode the character whose first byte follows the `%` which has just been read
+fn decode_char<O: Observer>(src: &mut Replacinator<'_, O>) -> Result<char, Error> {
+    let mut bytes = [0; 4];
+    bytes[0] = decode_byte(src)?;
+    let len = match bytes[0] {
+        0x00..=0x7F => 1,
+        0xC2..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF4 => 4,
+        _ => return Err(error(src, ErrorKind::InvalidUtf8)),
+    };
+    for byte in &mut bytes[1..len] {
+        if src.peek() != Some('%') {
+            return Err(error(src, ErrorKind::InvalidUtf8));
+        }
+        src.read_char();
+        *byte = decode_byte(src)?;
+    }
+    match core::str::from_utf8(&bytes[..len]) {
+        Ok(s) => Ok(s.chars().next().expect("A decoded character is not empty")),
+        Err(_) => Err(error(src, ErrorKind::InvalidUtf8)),
+    }
+}
+
+fn decode_byte<O: Observer>(src: &mut Replacinator<'_, O>) -> Result<u8, Error> {
+    let mut byte = 0;
+    for _ in 0..2 {
+        let c = src
+            .peek()
+            .ok_or_else(|| error(src, ErrorKind::UnexpectedEnd))?;
+        let digit = c
+            .to_digit(16)
+            .ok_or_else(|| error(src, ErrorKind::InvalidDigit(c)))?;
+        src.read_char();
+        byte = byte * 16 + digit as u8;
+    }
+    Ok(byte)
+}
+
+/// Find the malformed `%XX` sequences in the remainder of `src`, without changing it
+///
+/// Each [`Error`] is the one which [`decode_in_place`] would report if it started at the
+/// sequence, with its position in the original string. This allows the caller to choose between
+/// rejecting the text and decoding what it can, before anything is rewritten.
+pub fn malformed<'s, O: Observer>(src: &'s Replacinator<'_, O>) -> Malformed<'s> {
+    Malformed {
+        text: src.remainder(),
+        index: 0,
+        scanned: 0,
+        base: src.position(),
+    }
+}
+
+/// Find the malformed `%XX` sequences in `text`, with positions relative to the start of `text`
+///
+/// See [`malformed`].
+pub fn malformed_in(text: &str) -> Malformed<'_> {
+    Malformed {
+        text,
+        index: 0,
+        scanned: 0,
+        base: Position::default(),
+    }
+}
+
+/// An iterator over the malformed `%XX` sequences in some text
+///
+/// Created by [`malformed`] and [`malformed_in`]. After a malformed sequence, scanning continues
+/// from the position of its error.
+#[derive(Debug, Clone)]
+pub struct Malformed<'t> {
+    text: &'t str,
+    /// The index in `text` to continue scanning from
+    index: usize,
+    /// The index in `text` which `base` is the position of
+    scanned: usize,
+    /// The position in the original string of `text[..scanned]`
+    base: Position,
+}
+
+impl<'t> Malformed<'t> {
+    /// Check the character encoded by the sequence starting with the `%` at `start`, returning
+    /// the index after it
+    fn check_char(&self, start: usize) -> Result<usize, (ErrorKind, usize)> {
+        let bytes = self.text.as_bytes();
+        let mut decoded = [0; 4];
+        decoded[0] = self.check_byte(start + 1)?;
+        let len = match decoded[0] {
+            0x00..=0x7F => 1,
+            0xC2..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF4 => 4,
+            _ => return Err((ErrorKind::InvalidUtf8, start + 3)),
+        };
+        let mut end = start + 3;
+        for byte in &mut decoded[1..len] {
+            if bytes.get(end) != Some(&b'%') {
+                return Err((ErrorKind::InvalidUtf8, end));
+            }
+            *byte = self.check_byte(end + 1)?;
+            end += 3;
+        }
+        match core::str::from_utf8(&decoded[..len]) {
+            Ok(_) => Ok(end),
+            Err(_) => Err((ErrorKind::InvalidUtf8, end)),
+        }
+    }
+
+    /// Check the two hex digits at `start`, returning the byte they encode
+    fn check_byte(&self, start: usize) -> Result<u8, (ErrorKind, usize)> {
+        let mut byte = 0;
+        for i in start..start + 2 {
+            let c = self.text[i..]
+                .chars()
+                .next()
+                .ok_or((ErrorKind::UnexpectedEnd, i))?;
+            let digit = c.to_digit(16).ok_or((ErrorKind::InvalidDigit(c), i))?;
+            byte = byte * 16 + digit as u8;
+        }
+        Ok(byte)
+    }
+}
+
+impl<'t> Iterator for Malformed<'t> {
+    type Item = Error;
+
+    fn next(&mut self) -> Option<Error> {
+        loop {
+            let start = self.index + self.text[self.index..].find('%')?;
+            match self.check_char(start) {
+                Ok(end) => self.index = end,
+                Err((kind, position)) => {
+                    self.index = position.max(start + 1);
+                    // Errors are found in order, so the position can be tracked incrementally
+                    self.base = self.base.after(&self.text[self.scanned..position]);
+                    self.scanned = position;
+                    return Some(Error {
+                        kind,
+                        position: self.base,
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl<'t> core::iter::FusedIterator for Malformed<'t> {}
+
+#[cold]
+fn error<O: Observer>(src: &Replacinator<'_, O>, kind: ErrorKind) -> Error {
+    Error {
+        kind,
+        position: src.position(),
+    }
+}
@@ -0,0 +1,188 @@
+//! Running several transforms over a string, one after another
+//!
+//! Each transform in a [`Pipeline`] is given its own [`Replacinator`], over the text returned by
+//! the transform before it. This removes the need to reborrow the result of each pass by hand.
+//! Unlike [`transforms::pipeline`](crate::transforms::pipeline), the transforms are not fused, so
+//! the text is read once per transform, but any transform can be used, including fallible ones
+//! such as [`percent::decode_in_place`](crate::percent::decode_in_place).
+
+use core::{fmt, marker::PhantomData};
+
+use crate::Replacinator;
+
+/// A sequence of transforms, each applied to the result of the one before
+///
+/// The errors of fallible transforms are converted into `E`, which must be given when the
+/// pipeline is created if it can't be inferred, such as `Pipeline::<percent::Error>::new()`.
+/// `S` is the [`Stage`] made up of the transforms added so far.
+pub struct Pipeline<E, S = Identity> {
+    stages: S,
+    len: usize,
+    _error: PhantomData<fn() -> E>,
+}
+
+impl<E> Pipeline<E> {
+    /// Create a pipeline with no transforms, which returns the text it is given unchanged
+    pub fn new() -> Self {
+        Self {
+            stages: Identity,
+            len: 0,
+            _error: PhantomData,
+        }
+    }
+}
+
+impl<E> Default for Pipeline<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E, S: Stage<E>> Pipeline<E, S> {
+    /// Add `transform` to the end of this pipeline
+    pub fn then<F>(self, transform: F) -> Pipeline<E, Then<S, Transform<F>>>
+    where
+        F: for<'a> FnMut(&mut Replacinator<'a>) -> &'a mut str,
+    {
+        self.push(Transform(transform))
+    }
+
+    /// Add the fallible `transform` to the end of this pipeline
+    ///
+    /// If it fails, the pipeline stops, and its error is converted into `E`.
+    pub fn try_then<F, T>(self, transform: F) -> Pipeline<E, Then<S, TryTransform<F, T>>>
+    where
+        F: for<'a> FnMut(&mut Replacinator<'a>) -> Result<&'a mut str, T>,
+        E: From<T>,
+    {
+        let stage = TryTransform {
+            transform,
+            index: self.len,
+            _error: PhantomData,
+        };
+        self.push(stage)
+    }
+
+    fn push<T: Stage<E>>(self, stage: T) -> Pipeline<E, Then<S, T>> {
+        Pipeline {
+            stages: Then(self.stages, stage),
+            len: self.len + 1,
+            _error: PhantomData,
+        }
+    }
+
+    /// The number of transforms in this pipeline
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether this pipeline has no transforms
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Apply each transform in turn to `text`, returning the result of the last
+    ///
+    /// If a transform fails, the text is left valid, but its contents are unspecified.
+    pub fn run<'a>(&mut self, text: &'a mut str) -> Result<&'a mut str, Error<E>> {
+        self.stages.run(text)
+    }
+}
+
+/// One or more transforms in a [`Pipeline`]
+///
+/// This is implemented by the types which [`Pipeline::then`] and [`Pipeline::try_then`] build,
+/// so doesn't need to be implemented outside of this crate.
+pub trait Stage<E> {
+    /// Apply the transforms to `text`, returning their result
+    fn run<'a>(&mut self, text: &'a mut str) -> Result<&'a mut str, Error<E>>;
+}
+
+/// The [`Stage`] of an empty [`Pipeline`]
+#[derive(Debug, Clone, Copy)]
+pub struct Identity;
+
+impl<E> Stage<E> for Identity {
+    fn run<'a>(&mut self, text: &'a mut str) -> Result<&'a mut str, Error<E>> {
+        Ok(text)
+    }
+}
+
+/// Two [`Stage`]s, one after the other
+#[derive(Debug, Clone, Copy)]
+pub struct Then<A, B>(A, B);
+
+impl<E, A: Stage<E>, B: Stage<E>> Stage<E> for Then<A, B> {
+    fn run<'a>(&mut self, text: &'a mut str) -> Result<&'a mut str, Error<E>> {
+        let text = self.0.run(text)?;
+        self.1.run(text)
+    }
+}
+
+/// A [`Stage`] added by [`Pipeline::then`]
+#[derive(Debug, Clone, Copy)]
+pub struct Transform<F>(F);
+
+impl<E, F> Stage<E> for Transform<F>
+where
+    F: for<'a> FnMut(&mut Replacinator<'a>) -> &'a mut str,
+{
+    fn run<'a>(&mut self, text: &'a mut str) -> Result<&'a mut str, Error<E>> {
+        Ok(Replacinator::new_in(text, &mut self.0))
+    }
+}
+
+/// A [`Stage`] added by [`Pipeline::try_then`]
+pub struct TryTransform<F, T> {
+    transform: F,
+    index: usize,
+    _error: PhantomData<fn() -> T>,
+}
+
+impl<E, F, T> Stage<E> for TryTransform<F, T>
+where
+    F: for<'a> FnMut(&mut Replacinator<'a>) -> Result<&'a mut str, T>,
+    E: From<T>,
+{
+    fn run<'a>(&mut self, text: &'a mut str) -> Result<&'a mut str, Error<E>> {
+        let index = self.index;
+        Replacinator::new_in(text, &mut self.transform).map_err(|error| Error {
+            stage: index,
+            error: error.into(),
+        })
+    }
+}
+
+/// The error returned when a transform in a [`Pipeline`] fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Error<E> {
+    stage: usize,
+    error: E,
+}
+
+impl<E> Error<E> {
+    /// The index in the pipeline of the transform which failed, starting from zero
+    pub fn stage(&self) -> usize {
+        self.stage
+    }
+
+    /// The error returned by the transform
+    pub fn error(&self) -> &E {
+        &self.error
+    }
+
+    /// Take the error returned by the transform
+    pub fn into_inner(self) -> E {
+        self.error
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} in transform {} of the pipeline",
+            self.error, self.stage
+        )
+    }
+}
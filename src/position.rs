@@ -0,0 +1,152 @@
+use core::{ops::Range, str::Chars};
+
+/// A position in the string which a [`Replacinator`](crate::Replacinator) was created from
+///
+/// This is tracked as text is read, so it refers to the original string even after parts of it
+/// have been rewritten or taken. With the `line_tracking` feature, the line and column are also
+/// tracked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    byte: usize,
+    char: usize,
+    #[cfg(feature = "line_tracking")]
+    line: usize,
+    #[cfg(feature = "line_tracking")]
+    column: usize,
+}
+
+impl Position {
+    /// The byte offset of this position
+    pub fn byte(&self) -> usize {
+        self.byte
+    }
+
+    /// The number of characters before this position
+    pub fn char(&self) -> usize {
+        self.char
+    }
+
+    /// The number of line feeds before this position, which is the zero-based line number
+    #[cfg(feature = "line_tracking")]
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The number of characters between the start of the line and this position, which is the
+    /// zero-based column number
+    #[cfg(feature = "line_tracking")]
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// This position, moved past `text`
+    #[inline]
+    pub(crate) fn after(mut self, text: &str) -> Self {
+        self.advance(text.as_bytes());
+        self
+    }
+
+    /// Move this position past `bytes`
+    ///
+    /// Characters are counted as the bytes which are not UTF-8 continuation bytes.
+    #[inline]
+    pub(crate) fn advance(&mut self, bytes: &[u8]) {
+        self.byte += bytes.len();
+        for &b in bytes {
+            let starts_char = !crate::is_utf8_continuation(b);
+            self.char += usize::from(starts_char);
+            #[cfg(feature = "line_tracking")]
+            if b == b'\n' {
+                self.line += 1;
+                self.column = 0;
+            } else if starts_char {
+                self.column += 1;
+            }
+        }
+    }
+}
+
+/// The part of the original string between two [`Position`]s
+///
+/// Returned by [`Replacinator::find`](crate::Replacinator::find) and
+/// [`Replacinator::span_since`](crate::Replacinator::span_since).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    start: Position,
+    end: Position,
+}
+
+impl Span {
+    pub(crate) fn new(start: Position, end: Position) -> Self {
+        debug_assert!(start <= end);
+        Self { start, end }
+    }
+
+    /// The position of the start of this span
+    pub fn start(&self) -> Position {
+        self.start
+    }
+
+    /// The position just after the end of this span
+    pub fn end(&self) -> Position {
+        self.end
+    }
+
+    /// The byte offsets of this span in the original string
+    pub fn bytes(&self) -> Range<usize> {
+        self.start.byte..self.end.byte
+    }
+
+    /// The length of this span in bytes
+    pub fn len(&self) -> usize {
+        self.end.byte - self.start.byte
+    }
+
+    /// Whether this span is empty
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// An iterator over the characters of the remainder of a [`Replacinator`](crate::Replacinator),
+/// with their positions in the original string
+///
+/// Created by [`Replacinator::char_indices`](crate::Replacinator::char_indices). Unlike
+/// [`ReadChars`](crate::ReadChars), this doesn't advance the read position.
+#[derive(Debug, Clone)]
+pub struct CharIndices<'s> {
+    chars: Chars<'s>,
+    position: Position,
+}
+
+impl<'s> CharIndices<'s> {
+    pub(crate) fn new(text: &'s str, position: Position) -> Self {
+        Self {
+            chars: text.chars(),
+            position,
+        }
+    }
+
+    /// The position of the next character, or of the end of the string if there are none left
+    pub fn position(&self) -> Position {
+        self.position
+    }
+}
+
+impl<'s> Iterator for CharIndices<'s> {
+    type Item = (Position, char);
+
+    #[inline]
+    fn next(&mut self) -> Option<(Position, char)> {
+        let c = self.chars.next()?;
+        let position = self.position;
+        self.position = position.after(c.encode_utf8(&mut [0; 4]));
+        Some((position, c))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chars.size_hint()
+    }
+}
+
+impl<'s> core::iter::FusedIterator for CharIndices<'s> {}
@@ -0,0 +1,97 @@
+//! Conversions from bytes to string slices which are checked unless explicitly disabled
+//!
+//! These are the conversions which [`Replacinator`](crate::Replacinator) uses internally, exposed for
+//! crates building their own in-place structures which want the same policy. They are checked in
+//! debug builds, and in release builds unless the `disable_safety_checks` feature is enabled.
+//! The `paranoid` feature takes precedence over everything else, so that the checks can be
+//! guaranteed regardless of the features enabled by other dependents.
+//!
+//! A single [`Replacinator`](crate::Replacinator) can choose otherwise at runtime, using
+//! [`Replacinator::new_in_checked`](crate::Replacinator::new_in_checked) or
+//! [`Replacinator::new_in_unchecked_fastpath`](crate::Replacinator::new_in_unchecked_fastpath),
+//! unless `paranoid` is enabled.
+
+/// Whether the `paranoid` feature is enabled, so that every conversion is checked
+pub const PARANOID: bool = cfg!(feature = "paranoid");
+
+// Building with `REPLACINATOR_UNCHECKED` set turns the checks off in debug builds too, unless
+// `paranoid` is enabled. This is for testing this crate: running the test suite both with and
+// without it covers both the checked and the unchecked conversions.
+const UNCHECKED_FOR_TESTING: bool = option_env!("REPLACINATOR_UNCHECKED").is_some();
+
+/// Whether the conversions in this module check that their input is valid UTF-8
+pub const SAFETY_CHECKS: bool = PARANOID
+    || !UNCHECKED_FOR_TESTING
+        && (cfg!(debug_assertions) || !cfg!(feature = "disable_safety_checks"));
+
+/// Convert a byte slice into a string slice
+///
+/// This function uses a safe path if the safety checks are enabled (see [`SAFETY_CHECKS`]):
+///
+/// - When debug_assertions are enabled (default in a debug build)
+/// - When the `"paranoid"` feature for this crate is enabled
+/// - When the `"disable_safety_checks"` feature for this crate is not enabled
+///
+/// Note that this safe path is `O(len(v))`
+///
+/// ## Safety
+///
+/// Calling [`core::str::from_utf8_unchecked`] on the slice must be safe
+#[inline]
+pub unsafe fn unchecked_from_utf8(v: &[u8]) -> &str {
+    // Safety: As guaranteed by the caller
+    unsafe { from_utf8_checked_if(SAFETY_CHECKS, v) }
+}
+
+/// Convert a byte slice into a string slice, checking that it is valid UTF-8 if `checks` is set
+/// or [`PARANOID`] is enabled
+///
+/// ## Safety
+///
+/// Calling [`core::str::from_utf8_unchecked`] on the slice must be safe
+#[inline]
+pub(crate) unsafe fn from_utf8_checked_if(checks: bool, v: &[u8]) -> &str {
+    if checks || PARANOID {
+        core::str::from_utf8(v).expect(
+            "`replacinator::raw` was asked to create a string slice which would contain invalid UTF-8.
+This indicates a soundness hole in the caller; this assertion should be unreachable.
+If the caller is `replacinator` itself, please report this at the issue page: https://github.com/DJMcNab/replacinator/issues.",
+        )
+    } else {
+        // Safety: Calling this function is safe as guaranteed by the caller
+        unsafe { core::str::from_utf8_unchecked(v) }
+    }
+}
+
+/// Convert an exclusive byte slice into an exclusive string slice
+///
+/// The same caveats regarding the safe path used apply as in [`unchecked_from_utf8`].
+///
+/// ## Safety
+///
+/// Calling [`core::str::from_utf8_unchecked_mut`] on the input slice must be safe
+#[inline]
+pub unsafe fn unchecked_from_utf8_mut(v: &mut [u8]) -> &mut str {
+    // Safety: As guaranteed by the caller
+    unsafe { from_utf8_mut_checked_if(SAFETY_CHECKS, v) }
+}
+
+/// Convert an exclusive byte slice into an exclusive string slice, checking that it is valid
+/// UTF-8 if `checks` is set or [`PARANOID`] is enabled
+///
+/// ## Safety
+///
+/// Calling [`core::str::from_utf8_unchecked_mut`] on the input slice must be safe
+#[inline]
+pub(crate) unsafe fn from_utf8_mut_checked_if(checks: bool, v: &mut [u8]) -> &mut str {
+    if checks || PARANOID {
+        core::str::from_utf8_mut(v).expect(
+            "`replacinator::raw` was asked to create a string slice which would contain invalid UTF-8.
+This indicates a soundness hole in the caller; this assertion should be unreachable.
+If the caller is `replacinator` itself, please report this at the issue page: https://github.com/DJMcNab/replacinator/issues.",
+        )
+    } else {
+        // Safety: Calling this function is safe as guaranteed by the caller
+        unsafe { core::str::from_utf8_unchecked_mut(v) }
+    }
+}
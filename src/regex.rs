@@ -0,0 +1,137 @@
+//! In-place replacement of the matches of a regular expression, using [`regex_automata`]
+//!
+//! This requires the `regex` feature.
+
+use alloc::string::String;
+use core::{fmt, mem, ops::Range};
+
+use regex_automata::{meta::Regex, util::captures::Captures, Input};
+
+use crate::{transforms::TransformReport, Observer, Position, Replacinator};
+
+/// An error encountered whilst replacing the matches of a regular expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    position: Position,
+}
+
+impl Error {
+    /// The kind of error which occurred
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The position in the original string of the match which could not be replaced
+    pub fn position(&self) -> Position {
+        self.position
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::TooLong => write!(f, "replacement is longer than its match"),
+            ErrorKind::Format => write!(f, "replacement could not be formatted"),
+        }?;
+        write!(f, " at byte {}", self.position.byte())
+    }
+}
+
+/// The kinds of [`Error`] which can occur whilst replacing the matches of a regular expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The replacement for a match was longer than the match
+    TooLong,
+    /// The replacement function returned an error
+    Format,
+}
+
+/// Replace every match of `regex` in the remainder of `src` with the text `replace` writes for it
+///
+/// `replace` is given the captures of the match, the text their spans index into, and where to
+/// write the replacement. Each replacement must be no longer than its match, so that it can be
+/// written in place. Otherwise, an error is returned with the match left at the start of the
+/// remainder, and earlier matches already replaced.
+///
+/// The remainder is searched as if it were the whole text, so `^` and `\A` only match at its
+/// start. Empty matches directly after another match are skipped, as by the `regex` crate.
+///
+/// # Panics
+/// If `regex` matches part of a character, which can only happen if its UTF-8 mode is disabled
+pub fn replace_all_shrinking<O: Observer>(
+    src: &mut Replacinator<'_, O>,
+    regex: &Regex,
+    mut replace: impl FnMut(&Captures, &str, &mut dyn fmt::Write) -> fmt::Result,
+) -> Result<TransformReport, Error> {
+    let (read_offset, write_offset) = (src.read_offset(), src.write_offset());
+    let mut captures = regex.create_captures();
+    let (mut replacement, mut next_replacement) = (String::new(), String::new());
+    let mut count = 0;
+    // The match being replaced is left at the start of the remainder until the next match is
+    // found, so that the search for it sees the original text before it
+    let mut current = None;
+    loop {
+        let haystack = src.remainder();
+        let next = find(regex, haystack, current, &mut captures);
+        let result = next.clone().map(|found| {
+            next_replacement.clear();
+            let len = found.len();
+            match replace(&captures, haystack, &mut next_replacement) {
+                Err(fmt::Error) => Err(ErrorKind::Format),
+                Ok(()) if next_replacement.len() > len => Err(ErrorKind::TooLong),
+                Ok(()) => Ok(found),
+            }
+        });
+        let current_len = current.unwrap_or(0);
+        if current.is_some() {
+            src.discard_bytes(current_len);
+            src.write_str(&replacement);
+            count += 1;
+        }
+        let found = match result {
+            None => break,
+            Some(Ok(found)) => found,
+            Some(Err(kind)) => {
+                src.skip_bytes(next.map_or(0, |found| found.start) - current_len);
+                return Err(Error {
+                    kind,
+                    position: src.position(),
+                });
+            }
+        };
+        src.skip_bytes(found.start - current_len);
+        current = Some(found.len());
+        mem::swap(&mut replacement, &mut next_replacement);
+    }
+    src.skip_bytes(src.remainder().len());
+    Ok(TransformReport::since(
+        src,
+        read_offset,
+        write_offset,
+        count,
+    ))
+}
+
+/// Find the next match of `regex` in `haystack`, after the match of length `current` at its start
+fn find(
+    regex: &Regex,
+    haystack: &str,
+    current: Option<usize>,
+    captures: &mut Captures,
+) -> Option<Range<usize>> {
+    let mut start = current.unwrap_or(0);
+    loop {
+        regex.search_captures(&Input::new(haystack).range(start..), captures);
+        let found = captures.get_match()?.range();
+        assert!(
+            haystack.is_char_boundary(found.start) && haystack.is_char_boundary(found.end),
+            "A match must not split a character"
+        );
+        if !found.is_empty() || current != Some(found.end) {
+            return Some(found);
+        }
+        start = found.end + haystack[found.end..].chars().next()?.len_utf8();
+    }
+}
@@ -0,0 +1,307 @@
+//! Recording the operations applied to a [`Replacinator`], and replaying them against a fresh one
+//!
+//! A [`Recorder`] is an [`Observer`] which logs every operation as it happens. The log can be
+//! [`encode`]d, stored alongside the input which caused a problem, and later [`decode`]d and
+//! [`replay`]ed against a copy of that input. This allows a failure seen in production to be
+//! reproduced under tools such as Miri or AddressSanitizer, without the code which drove the
+//! original [`Replacinator`].
+//!
+//! Only the operations which are reported to an [`Observer`] are recorded. Changes made directly
+//! to a view, such as through [`Replacinator::remainder_mut`], are not, so a replay of code which
+//! makes them stops with [`ErrorKind::Diverged`] at the first read which differs.
+//!
+//! This module requires the `std` feature.
+
+use core::fmt;
+use std::{format, string::String, vec::Vec};
+
+use crate::{Checkpoint, FillStrategy, Observer, Replacinator};
+
+/// An error encountered whilst decoding or replaying a log of [`Operation`]s
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    position: usize,
+}
+
+impl Error {
+    /// The kind of error which occurred
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The byte offset at which the error was detected
+    ///
+    /// This is in the log for [`ErrorKind::Malformed`], and in the original string otherwise.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::Malformed => write!(f, "malformed operation log"),
+            ErrorKind::Diverged => write!(f, "replay diverged from the recording"),
+        }?;
+        write!(f, " at byte {}", self.position)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The kinds of [`Error`] which can occur whilst decoding or replaying a log of [`Operation`]s
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The log could not be decoded
+    Malformed,
+    /// An operation could not be applied as it was recorded, such as a read of text which is not
+    /// next in the remainder, or a write for which there is no room
+    Diverged,
+}
+
+#[cold]
+fn error(kind: ErrorKind, position: usize) -> Error {
+    Error { kind, position }
+}
+
+/// A single operation reported to an [`Observer`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation {
+    /// The text was read from the start of the remainder
+    Read(String),
+    /// The text was written to the end of the first section
+    Write(String),
+    /// The first section was taken, using [`Replacinator::take_start`] or
+    /// [`Replacinator::take_marked`]
+    TakeStart,
+    /// The remainder was taken, using [`Replacinator::take_remainder`]
+    TakeRemainder,
+    /// The second section was filled, using [`Replacinator::synchronise`] or
+    /// [`Replacinator::synchronise_with`]
+    Synchronise(FillStrategy),
+    /// The positions were returned to a [`Checkpoint`] with these offsets, using
+    /// [`Replacinator::rewind`], or the end of the first section was removed, leaving the write
+    /// offset at `write_offset`
+    Rewind {
+        read_offset: usize,
+        write_offset: usize,
+    },
+}
+
+/// An [`Observer`] which records every [`Operation`], for later use with [`replay`]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Recorder {
+    operations: Vec<Operation>,
+}
+
+impl Recorder {
+    /// Create a recorder which has recorded nothing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The operations recorded so far, in the order they happened
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    /// The operations recorded, in the order they happened
+    pub fn into_operations(self) -> Vec<Operation> {
+        self.operations
+    }
+}
+
+impl Observer for Recorder {
+    fn read(&mut self, text: &str) {
+        self.operations.push(Operation::Read(text.into()));
+    }
+
+    fn write(&mut self, text: &str) {
+        self.operations.push(Operation::Write(text.into()));
+    }
+
+    fn take_start(&mut self, _: &str) {
+        self.operations.push(Operation::TakeStart);
+    }
+
+    fn take_remainder(&mut self, _: &str) {
+        self.operations.push(Operation::TakeRemainder);
+    }
+
+    fn synchronise(&mut self, fill: FillStrategy) {
+        self.operations.push(Operation::Synchronise(fill));
+    }
+
+    fn rewind(&mut self, read_offset: usize, write_offset: usize) {
+        self.operations.push(Operation::Rewind {
+            read_offset,
+            write_offset,
+        });
+    }
+}
+
+/// Apply `operations` to `src`, in order, returning the slices which were taken
+///
+/// If `src` was created from the same string as the [`Replacinator`] they were recorded from,
+/// this repeats exactly the same sequence of changes to that string. If an operation can't be
+/// applied, this stops before it, leaving `src` consistent.
+pub fn replay<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+    operations: &[Operation],
+) -> Result<Vec<&'a mut str>, Error> {
+    let mut taken = Vec::new();
+    // The checkpoints which could be rewound to, since the last time the first section was taken
+    let mut checkpoints: Vec<(usize, usize, Checkpoint)> = Vec::new();
+    for operation in operations {
+        let offsets = (src.read_offset(), src.write_offset());
+        if checkpoints.last().map(|&(read, write, _)| (read, write)) != Some(offsets) {
+            checkpoints.push((offsets.0, offsets.1, src.checkpoint()));
+        }
+        match operation {
+            Operation::Read(text) => {
+                if !src.starts_with(text) {
+                    return Err(error(ErrorKind::Diverged, src.read_offset()));
+                }
+                src.discard_bytes(text.len());
+            }
+            Operation::Write(text) => {
+                if text.len() > src.read_offset() - src.write_offset() {
+                    return Err(error(ErrorKind::Diverged, src.write_offset()));
+                }
+                src.write_str(text);
+            }
+            Operation::TakeStart => {
+                taken.push(src.take_start());
+                checkpoints.clear();
+            }
+            Operation::TakeRemainder => taken.push(src.take_remainder()),
+            &Operation::Synchronise(fill) => src.synchronise_with(fill),
+            &Operation::Rewind {
+                read_offset,
+                write_offset,
+            } => {
+                let checkpoint = checkpoints
+                    .iter()
+                    .rev()
+                    .find(|&&(read, write, _)| (read, write) == (read_offset, write_offset));
+                match checkpoint {
+                    Some(&(_, _, checkpoint)) if src.rewind(checkpoint) => {}
+                    // The end of the first section was removed, rather than returning to a checkpoint
+                    _ if read_offset == src.read_offset() => {
+                        let len =
+                            (src.written_len() + write_offset).checked_sub(src.write_offset());
+                        match len {
+                            Some(len) if src.start().is_char_boundary(len) => {
+                                src.truncate_start(len)
+                            }
+                            _ => return Err(error(ErrorKind::Diverged, src.write_offset())),
+                        }
+                    }
+                    _ => return Err(error(ErrorKind::Diverged, src.read_offset())),
+                }
+            }
+        }
+    }
+    Ok(taken)
+}
+
+/// Encode `operations` as text, to be stored and later read using [`decode`]
+///
+/// Each operation is written on its own line, as its name followed by its arguments. Text is
+/// written as its length in bytes, a colon, then the text itself, so it needs no escaping.
+pub fn encode(operations: &[Operation]) -> String {
+    let mut log = String::new();
+    for operation in operations {
+        let line = match operation {
+            Operation::Read(text) => format!("read {}:{}", text.len(), text),
+            Operation::Write(text) => format!("write {}:{}", text.len(), text),
+            Operation::TakeStart => "take_start".into(),
+            Operation::TakeRemainder => "take_remainder".into(),
+            Operation::Synchronise(FillStrategy::Space) => "synchronise space".into(),
+            Operation::Synchronise(FillStrategy::Nul) => "synchronise nul".into(),
+            Operation::Synchronise(FillStrategy::Char(c)) => {
+                format!("synchronise char {}:{}", c.len_utf8(), c)
+            }
+            Operation::Synchronise(FillStrategy::Compact) => "synchronise compact".into(),
+            Operation::Rewind {
+                read_offset,
+                write_offset,
+            } => format!("rewind {} {}", read_offset, write_offset),
+        };
+        log.push_str(&line);
+        log.push('\n');
+    }
+    log
+}
+
+/// Decode a log written by [`encode`]
+pub fn decode(log: &str) -> Result<Vec<Operation>, Error> {
+    let mut operations = Vec::new();
+    let mut rest = log;
+    while !rest.is_empty() {
+        let position = log.len() - rest.len();
+        let malformed = || error(ErrorKind::Malformed, position);
+        let name_len = rest.find([' ', '\n']).ok_or_else(malformed)?;
+        let (name, arguments) = rest.split_at(name_len);
+        let (operation, after) = match name {
+            "read" | "write" => {
+                let (text, after) = decode_text(arguments).ok_or_else(malformed)?;
+                match name {
+                    "read" => (Operation::Read(text.into()), after),
+                    _ => (Operation::Write(text.into()), after),
+                }
+            }
+            "take_start" => (Operation::TakeStart, arguments),
+            "take_remainder" => (Operation::TakeRemainder, arguments),
+            "synchronise" => {
+                let arguments = arguments.strip_prefix(' ').ok_or_else(malformed)?;
+                let (fill, after) = match arguments.strip_prefix("char") {
+                    Some(arguments) => {
+                        let (text, after) = decode_text(arguments).ok_or_else(malformed)?;
+                        let mut chars = text.chars();
+                        match (chars.next(), chars.next()) {
+                            (Some(c), None) => (FillStrategy::Char(c), after),
+                            _ => return Err(malformed()),
+                        }
+                    }
+                    None => {
+                        let end = arguments.find('\n').ok_or_else(malformed)?;
+                        let fill = match &arguments[..end] {
+                            "space" => FillStrategy::Space,
+                            "nul" => FillStrategy::Nul,
+                            "compact" => FillStrategy::Compact,
+                            _ => return Err(malformed()),
+                        };
+                        (fill, &arguments[end..])
+                    }
+                };
+                (Operation::Synchronise(fill), after)
+            }
+            "rewind" => {
+                let arguments = arguments.strip_prefix(' ').ok_or_else(malformed)?;
+                let end = arguments.find('\n').ok_or_else(malformed)?;
+                let (read_offset, write_offset) =
+                    arguments[..end].split_once(' ').ok_or_else(malformed)?;
+                let operation = Operation::Rewind {
+                    read_offset: read_offset.parse().map_err(|_| malformed())?,
+                    write_offset: write_offset.parse().map_err(|_| malformed())?,
+                };
+                (operation, &arguments[end..])
+            }
+            _ => return Err(malformed()),
+        };
+        rest = after.strip_prefix('\n').ok_or_else(malformed)?;
+        operations.push(operation);
+    }
+    Ok(operations)
+}
+
+/// Decode text written by [`encode`] at the start of `arguments`, returning it and the rest of
+/// `arguments`
+fn decode_text(arguments: &str) -> Option<(&str, &str)> {
+    let (len, text) = arguments.strip_prefix(' ')?.split_once(':')?;
+    let len = len.parse().ok()?;
+    Some((text.get(..len)?, &text[len..]))
+}
@@ -0,0 +1,301 @@
+//! Rewriting a string slice from right to left
+
+use core::mem::take;
+
+use crate::{
+    raw::{unchecked_from_utf8, unchecked_from_utf8_mut},
+    AsMutStr, HeadroomError,
+};
+
+/// A partially updated string slice, which is read and written from the end backwards
+///
+/// This is the mirror image of a [`Replacinator`](crate::Replacinator), with three sections:
+/// - The first section is the characters which have not yet been read, as valid UTF-8
+/// - The second section of memory has arbitrary contents
+/// - The third section is a valid UTF-8 string, containing exactly the characters which have
+///   been 'written' to the string, in order. Each write is added to the front of this section.
+///
+/// As text is read from the end, this can make changes near the end of a string without scanning
+/// the rest of it. It also allows text to grow in place: if a forward pass computes how much the
+/// text will grow, and the string has that much padding at its end, this can be created with
+/// [`RevReplacinator::new_in_with_headroom`] to write the grown text from the end.
+///
+/// Like a [`Replacinator`](crate::Replacinator), the second section is returned to valid UTF-8 by
+/// filling it with spaces when this is dropped.
+pub struct RevReplacinator<'a> {
+    contents: &'a mut [u8],
+    /// The end of the first section
+    read_position: usize,
+    /// The start of the third section
+    write_position: usize,
+    /// The offset of `contents` in the original string
+    offset: usize,
+}
+
+impl<'a> RevReplacinator<'a> {
+    /// Create a new [`RevReplacinator`] for the given string slice, and operate on it within the
+    /// given function
+    ///
+    /// This function can be safe because it ensures that `value` is returned to a valid string
+    /// slice, by ensuring that [`Drop`] is called as required.
+    pub fn new_in<R>(
+        value: impl AsMutStr<'a>,
+        mut with: impl FnMut(&mut RevReplacinator<'a>) -> R,
+    ) -> R {
+        // Safety: Because we create a new scope, `it` is always dropped,
+        // so the data behind value returns to being utf8 by the end of the borrow.
+        let mut it = unsafe { Self::construct(value.into_mut_str()) };
+        with(&mut it)
+    }
+
+    /// Create a new [`RevReplacinator`] which treats the last `gap` bytes of `value` as scratch
+    /// space, and operate on it within the given function
+    ///
+    /// The last `gap` bytes are discarded without being read, so up to `gap` bytes more can be
+    /// written than have been read.
+    ///
+    /// # Errors
+    /// If `gap` is longer than `value`, or `value.len() - gap` is not on a character boundary of
+    /// `value`, before `with` is called. `value` is left unchanged.
+    pub fn new_in_with_headroom<R>(
+        value: impl AsMutStr<'a>,
+        gap: usize,
+        mut with: impl FnMut(&mut RevReplacinator<'a>) -> R,
+    ) -> Result<R, HeadroomError> {
+        let value = value.into_mut_str();
+        let end = value.len().checked_sub(gap);
+        if !end.is_some_and(|end| value.is_char_boundary(end)) {
+            return Err(HeadroomError { gap });
+        }
+        Ok(Self::new_in(value, |it| {
+            it.read_position -= gap;
+            with(it)
+        }))
+    }
+
+    /// Create a new [`RevReplacinator`] from the given string
+    ///
+    /// # Safety
+    /// Before 'a ends, the resulting [`RevReplacinator`] must be dropped
+    pub unsafe fn construct(from: &'a mut str) -> Self {
+        let len = from.len();
+        Self {
+            // SAFETY: By the time this borrow ends, the memory contents are back to being utf8,
+            // as the second section is filled when this is dropped
+            contents: unsafe { from.as_bytes_mut() },
+            read_position: len,
+            write_position: len,
+            offset: 0,
+        }
+    }
+
+    /// View the string contents of the first section, which has not yet been read
+    #[inline]
+    pub fn remainder(&self) -> &str {
+        unsafe { unchecked_from_utf8(&self.contents[..self.read_position]) }
+    }
+
+    /// View the string contents of the first section mutably
+    #[inline]
+    pub fn remainder_mut(&mut self) -> &mut str {
+        unsafe { unchecked_from_utf8_mut(&mut self.contents[..self.read_position]) }
+    }
+
+    /// View the string contents of the third section, which has been written
+    #[inline]
+    pub fn end(&self) -> &str {
+        unsafe { unchecked_from_utf8(&self.contents[self.write_position..]) }
+    }
+
+    /// View the string contents of the third section mutably
+    #[inline]
+    pub fn end_mut(&mut self) -> &mut str {
+        unsafe { unchecked_from_utf8_mut(&mut self.contents[self.write_position..]) }
+    }
+
+    /// Take the third section as a mutable view
+    ///
+    /// The first section is unaffected, and can still be read.
+    pub fn take_end(&mut self) -> &'a mut str {
+        self.fill_invalid_region();
+        let contents = take(&mut self.contents);
+        let (start, end) = contents.split_at_mut(self.read_position);
+        self.contents = start;
+        let end = &mut end[self.write_position - self.read_position..];
+        self.write_position = self.read_position;
+        self.check_invariants();
+        unsafe { unchecked_from_utf8_mut(end) }
+    }
+
+    /// Take the first section as a mutable view, leaving nothing left to read
+    ///
+    /// The third section is unaffected, and can still be accessed or taken.
+    pub fn take_remainder(&mut self) -> &'a mut str {
+        let contents = take(&mut self.contents);
+        let (start, end) = contents.split_at_mut(self.read_position);
+        self.contents = end;
+        self.offset += self.read_position;
+        self.write_position -= self.read_position;
+        self.read_position = 0;
+        self.check_invariants();
+        unsafe { unchecked_from_utf8_mut(start) }
+    }
+
+    /// The byte offset in the original string of the end of the remainder
+    pub fn read_offset(&self) -> usize {
+        self.offset + self.read_position
+    }
+
+    /// The byte offset in the original string at which the third section starts
+    pub fn write_offset(&self) -> usize {
+        self.offset + self.write_position
+    }
+
+    /// The last character of the remainder, without reading it
+    #[inline]
+    pub fn peek(&self) -> Option<char> {
+        self.remainder().chars().next_back()
+    }
+
+    /// Whether the remainder ends with `suffix`
+    #[inline]
+    pub fn ends_with(&self, suffix: &str) -> bool {
+        self.remainder().ends_with(suffix)
+    }
+
+    /// Read the last character of the remainder, without writing it
+    #[inline]
+    pub fn read_char(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.read_position -= c.len_utf8();
+        self.check_invariants();
+        Some(c)
+    }
+
+    /// Read the last character of the remainder without writing it, removing it from the output
+    ///
+    /// This is the same as [`RevReplacinator::read_char`], but makes the intent clear.
+    #[inline]
+    pub fn discard_char(&mut self) -> Option<char> {
+        self.read_char()
+    }
+
+    /// Read characters from the end without writing them while they match `pred`, returning the
+    /// number of bytes discarded
+    pub fn discard_while(&mut self, pred: impl FnMut(char) -> bool) -> usize {
+        let len = self.remainder().len() - self.remainder().trim_end_matches(pred).len();
+        self.read_position -= len;
+        self.check_invariants();
+        len
+    }
+
+    /// Read and write back the last character of the remainder
+    #[inline]
+    pub fn skip_char(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.skip_bytes(c.len_utf8());
+        Some(c)
+    }
+
+    /// Read and write back characters from the end while they match `pred`, returning the number
+    /// of bytes skipped
+    pub fn skip_while(&mut self, pred: impl FnMut(char) -> bool) -> usize {
+        let len = self.remainder().len() - self.remainder().trim_end_matches(pred).len();
+        self.skip_bytes(len);
+        len
+    }
+
+    /// Read and write back `expected`, if the remainder ends with it
+    ///
+    /// Returns whether `expected` was skipped.
+    pub fn skip_str(&mut self, expected: &str) -> bool {
+        let matches = self.ends_with(expected);
+        if matches {
+            self.skip_bytes(expected.len());
+        }
+        matches
+    }
+
+    /// Write `c` to the start of the third section
+    ///
+    /// # Panics
+    /// If there is no room between the read position and the write position
+    #[inline]
+    pub fn write_char(&mut self, c: char) {
+        self.write_bytes(c.encode_utf8(&mut [0; 4]).as_bytes());
+    }
+
+    /// Write `s` to the start of the third section
+    ///
+    /// # Panics
+    /// If `s` is longer than the space between the read position and the write position
+    #[inline]
+    pub fn write_str(&mut self, s: &str) {
+        self.write_bytes(s.as_bytes());
+    }
+
+    /// Fill the second section with spaces, and move the write position to the read position
+    pub fn synchronise(&mut self) {
+        self.fill_invalid_region();
+        self.write_position = self.read_position;
+        self.check_invariants();
+    }
+
+    /// Move the last `len` bytes of the remainder to the start of the third section
+    ///
+    /// `len` bytes from the end of the remainder must be on a character boundary
+    #[inline]
+    fn skip_bytes(&mut self, len: usize) {
+        let start = self.read_position - len;
+        assert!(self.remainder().is_char_boundary(start));
+        if self.read_position != self.write_position {
+            self.contents
+                .copy_within(start..self.read_position, self.write_position - len);
+        }
+        self.read_position = start;
+        self.write_position -= len;
+        self.check_invariants();
+    }
+
+    #[inline]
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        assert!(
+            bytes.len() <= self.write_position - self.read_position,
+            "Cannot write more bytes than have been read"
+        );
+        let start = self.write_position - bytes.len();
+        self.contents[start..self.write_position].copy_from_slice(bytes);
+        self.write_position = start;
+        self.check_invariants();
+    }
+
+    /// Return the second section to valid UTF-8, without moving the write position
+    ///
+    /// If the positions can't be trusted, the whole slice is filled instead.
+    fn fill_invalid_region(&mut self) {
+        if self.invariants_hold() {
+            self.contents[self.read_position..self.write_position].fill(b' ');
+        } else {
+            self.contents.fill(b' ');
+        }
+    }
+
+    #[inline]
+    fn invariants_hold(&self) -> bool {
+        self.read_position <= self.write_position && self.write_position <= self.contents.len()
+    }
+
+    #[inline]
+    fn check_invariants(&self) {
+        assert!(
+            self.invariants_hold(),
+            "An internal invariant of `replacinator` was violated"
+        );
+    }
+}
+
+impl<'a> Drop for RevReplacinator<'a> {
+    fn drop(&mut self) {
+        self.fill_invalid_region();
+    }
+}
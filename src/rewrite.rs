@@ -0,0 +1,197 @@
+//! Pluggable character-by-character rewriting, driven by [`Replacinator::drive`]
+//!
+//! A [`Rewriter`] sees each character of the remainder in turn, and writes its replacement to a
+//! [`Sink`]. The driver keeps track of the positions, and deals with output which doesn't fit, so
+//! that a custom lexer or filter only needs to decide what to write.
+
+use core::ops::ControlFlow;
+
+use crate::{Observer, Position, Replacinator};
+
+/// A transform which rewrites text one character at a time
+///
+/// This is implemented for closures taking the same arguments as [`Rewriter::rewrite`].
+pub trait Rewriter {
+    /// Write the replacement for `c` to `out`
+    ///
+    /// Returning [`ControlFlow::Break`] stops the driver after this character. If anything
+    /// written to `out` doesn't fit, the output for `c` is discarded and `c` is left in the
+    /// remainder, so this may be called again with the same character later.
+    fn rewrite(&mut self, c: char, out: &mut Sink<'_>) -> ControlFlow<()>;
+
+    /// Write anything held back once the whole remainder has been rewritten
+    ///
+    /// If this output doesn't fit, it is discarded.
+    #[inline]
+    fn finish(&mut self, out: &mut Sink<'_>) {
+        let _ = out;
+    }
+}
+
+impl<F> Rewriter for F
+where
+    F: FnMut(char, &mut Sink<'_>) -> ControlFlow<()>,
+{
+    #[inline]
+    fn rewrite(&mut self, c: char, out: &mut Sink<'_>) -> ControlFlow<()> {
+        self(c, out)
+    }
+}
+
+/// Where a [`Rewriter`] writes its output
+///
+/// Writing only succeeds if the text fits before the read position, which is after the
+/// character being rewritten.
+pub struct Sink<'s> {
+    target: &'s mut dyn Target,
+    overflowed: bool,
+}
+
+impl<'s> Sink<'s> {
+    /// The number of bytes which can still be written
+    pub fn available(&self) -> usize {
+        self.target.available()
+    }
+
+    /// Write `s`, or return `Err` without writing anything if it doesn't fit
+    pub fn write_str(&mut self, s: &str) -> Result<(), NoRoom> {
+        if s.len() > self.target.available() {
+            self.overflowed = true;
+            return Err(NoRoom);
+        }
+        self.target.write(s);
+        Ok(())
+    }
+
+    /// Write `c`, or return `Err` if it doesn't fit
+    pub fn write_char(&mut self, c: char) -> Result<(), NoRoom> {
+        self.write_str(c.encode_utf8(&mut [0; 4]))
+    }
+}
+
+/// The error returned when output written to a [`Sink`] doesn't fit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoRoom;
+
+/// Why [`Replacinator::drive`] stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The whole remainder was rewritten, and [`Rewriter::finish`] was called
+    Finished,
+    /// The rewriter returned [`ControlFlow::Break`]
+    Stopped,
+    /// The output for the character at this position didn't fit, so it was left in the remainder
+    NoRoom(Position),
+}
+
+/// The replacinator as seen through a [`Sink`], which hides its observer type
+trait Target {
+    fn available(&self) -> usize;
+    fn write(&mut self, s: &str);
+}
+
+impl<'a, O: Observer> Target for Replacinator<'a, O> {
+    fn available(&self) -> usize {
+        self.gap_len()
+    }
+
+    fn write(&mut self, s: &str) {
+        self.write_str(s);
+    }
+}
+
+/// A target which accepts everything, counting the bytes written
+#[cfg(feature = "alloc")]
+struct Measure {
+    written: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl Target for Measure {
+    fn available(&self) -> usize {
+        usize::MAX
+    }
+
+    fn write(&mut self, s: &str) {
+        self.written += s.len();
+    }
+}
+
+/// The number of bytes which must be freed before `text` for [`Replacinator::drive`] to run
+/// `rewriter` over it without running out of room
+///
+/// This is the most that the output for any prefix of `text` is longer than that prefix.
+#[cfg(feature = "alloc")]
+pub(crate) fn required_growth(rewriter: &mut impl Rewriter, text: &str) -> usize {
+    let mut measure = Measure { written: 0 };
+    let mut growth = 0;
+    let mut read = 0;
+    for c in text.chars() {
+        read += c.len_utf8();
+        let mut sink = Sink {
+            target: &mut measure,
+            overflowed: false,
+        };
+        let flow = rewriter.rewrite(c, &mut sink);
+        growth = growth.max(measure.written.saturating_sub(read));
+        if flow.is_break() {
+            return growth;
+        }
+    }
+    let mut sink = Sink {
+        target: &mut measure,
+        overflowed: false,
+    };
+    rewriter.finish(&mut sink);
+    growth.max(measure.written.saturating_sub(read))
+}
+
+impl<'a, O: Observer> Replacinator<'a, O> {
+    /// Run `rewriter` over the remainder, writing its output in place of each character
+    ///
+    /// The output for a character may be longer than the character, as long as it fits in the
+    /// space freed by earlier characters. If it doesn't, everything written for that character is
+    /// discarded, and the character is left at the start of the remainder, as with
+    /// [`Replacinator::map_chars`]; the returned [`Outcome`] gives its position.
+    ///
+    /// The rewritten text is at the end of the first section, so can be taken with
+    /// [`Replacinator::take_marked`] or [`Replacinator::take_start`].
+    pub fn drive(&mut self, rewriter: &mut impl Rewriter) -> Outcome {
+        loop {
+            let checkpoint = self.checkpoint();
+            let write_high_water = self.inner.write_high_water;
+            let c = match self.read_char() {
+                Some(c) => c,
+                None => break,
+            };
+            let mut sink = Sink {
+                target: self,
+                overflowed: false,
+            };
+            let flow = rewriter.rewrite(c, &mut sink);
+            if sink.overflowed {
+                // The output may have been written over `c`, so it is restored before rewinding
+                let start = checkpoint.read_position;
+                c.encode_utf8(&mut self.inner.contents[start..start + c.len_utf8()]);
+                self.inner.write_high_water = write_high_water;
+                let rewound = self.rewind(checkpoint);
+                debug_assert!(rewound, "Nothing after the checkpoint has been changed");
+                return Outcome::NoRoom(self.position());
+            }
+            if flow.is_break() {
+                return Outcome::Stopped;
+            }
+        }
+        let write_position = self.written_len();
+        let mut sink = Sink {
+            target: self,
+            overflowed: false,
+        };
+        rewriter.finish(&mut sink);
+        if sink.overflowed {
+            // Everything written by `finish` is in the second section, so can just be forgotten
+            self.truncate_start(write_position);
+        }
+        Outcome::Finished
+    }
+}
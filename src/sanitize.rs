@@ -0,0 +1,321 @@
+//! Passes which make untrusted text safe for a particular use
+
+use core::{fmt, ops::Range};
+
+use crate::{Observer, Position, Replacinator};
+
+/// An error encountered whilst masking ranges of text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    position: Position,
+}
+
+impl Error {
+    /// The kind of error which occurred
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The position in the original string at which the error was detected
+    pub fn position(&self) -> Position {
+        self.position
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::OutOfOrder { start, end } => {
+                write!(f, "range {}..{} is out of order", start, end)
+            }
+            ErrorKind::OutOfBounds { start, end } => {
+                write!(f, "range {}..{} is past the end", start, end)
+            }
+            ErrorKind::NotCharBoundary(offset) => {
+                write!(f, "offset {} is not on a character boundary", offset)
+            }
+        }?;
+        write!(f, " at byte {}", self.position.byte())
+    }
+}
+
+/// The kinds of [`Error`] which can occur whilst masking ranges of text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The range starts before the read position, or before the end of the previous range, or
+    /// ends before it starts
+    OutOfOrder {
+        /// The start of the range
+        start: usize,
+        /// The end of the range
+        end: usize,
+    },
+    /// The range ends after the end of the string
+    OutOfBounds {
+        /// The start of the range
+        start: usize,
+        /// The end of the range
+        end: usize,
+    },
+    /// The given offset, at the start or end of a range, is within a character
+    NotCharBoundary(usize),
+}
+
+/// The names of devices which cannot be used as file names on Windows, with or without an extension
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Make `name` safe to use as a file name, truncating it to at most `max_len` bytes
+///
+/// Path separators, control characters, and other characters which are not permitted in file
+/// names on Windows are replaced with `_`. So are trailing dots and spaces, names which consist
+/// only of dots, and names which would refer to a Windows device (such as `CON` or `lpt1.txt`).
+///
+/// Returns the sanitised name, which is at the start of `name`. The rest of `name` is
+/// filled with spaces.
+pub fn filename(name: &mut str, max_len: usize) -> &mut str {
+    let name = Replacinator::new_in(name, |src| {
+        src.map_chars(|c| {
+            let forbidden = matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|');
+            Some(if forbidden || c.is_control() { '_' } else { c })
+        });
+        src.take_start()
+    });
+    let mut len = max_len.min(name.len());
+    while !name.is_char_boundary(len) {
+        len -= 1;
+    }
+    let (name, truncated) = name.split_at_mut(len);
+    blank(truncated);
+
+    let trimmed = name.trim_end_matches(['.', ' ']).len();
+    replace_all(&mut name[trimmed..]);
+    let stem = name.find('.').unwrap_or(name.len());
+    if RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(&name[..stem]))
+    {
+        replace_all(&mut name[..stem]);
+    }
+    name
+}
+
+/// Replace every character of `s` with `_`
+fn replace_all(s: &mut str) {
+    Replacinator::new_in(s, |src| {
+        src.map_chars(|_| Some('_'));
+    });
+}
+
+/// Replace every byte of `s` with a space
+fn blank(s: &mut str) {
+    Replacinator::new_in(s, |src| {
+        src.discard_while(|_| true);
+    });
+}
+
+/// Overwrite the characters in each of `ranges` of the remainder of `src` with `mask`
+///
+/// `ranges` are byte offsets in the original string, such as [`Span::bytes`](crate::Span::bytes),
+/// and must be in order and not overlap. Each character in a range is replaced with `mask` if it
+/// is the same length, and otherwise with that many `*`s. This keeps the length of the text, so
+/// that positions after a masked range are unchanged, and so that the text which was masked is
+/// never copied elsewhere in the string.
+///
+/// Returns the whole remainder, with the ranges masked. Like [`Replacinator::take_start`], the first
+/// section of `src` is no longer available afterwards. If an error is returned, the ranges
+/// before the invalid one have been masked.
+pub fn mask_ranges<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+    ranges: impl IntoIterator<Item = Range<usize>>,
+    mask: char,
+) -> Result<&'a mut str, Error> {
+    let mark = src.mark();
+    for range in ranges {
+        let start = range
+            .start
+            .checked_sub(src.read_offset())
+            .filter(|_| range.start <= range.end)
+            .ok_or_else(|| {
+                let (start, end) = (range.start, range.end);
+                error(src, ErrorKind::OutOfOrder { start, end })
+            })?;
+        let len = range.end - range.start;
+        if start + len > src.remainder().len() {
+            let (start, end) = (range.start, range.end);
+            return Err(error(src, ErrorKind::OutOfBounds { start, end }));
+        }
+        if !src.remainder().is_char_boundary(start) {
+            return Err(error(src, ErrorKind::NotCharBoundary(range.start)));
+        }
+        src.skip_bytes(start);
+        if !src.remainder().is_char_boundary(len) {
+            return Err(error(src, ErrorKind::NotCharBoundary(range.end)));
+        }
+        let end = src.read_offset() + len;
+        while src.read_offset() < end {
+            let c = src
+                .read_char()
+                .expect("The range was checked to be within the remainder");
+            if c.len_utf8() == mask.len_utf8() {
+                src.write_char(mask);
+            } else {
+                src.write_str(&"****"[..c.len_utf8()]);
+            }
+        }
+    }
+    src.skip_bytes(src.remainder().len());
+    Ok(src
+        .take_marked(mark)
+        .expect("The mark was made from this Replacinator"))
+}
+
+#[cold]
+fn error<O: Observer>(src: &Replacinator<'_, O>, kind: ErrorKind) -> Error {
+    Error {
+        kind,
+        position: src.position(),
+    }
+}
+
+/// The control characters which are usually wanted in text: line feed, carriage return and tab
+pub const LINE_CONTROLS: &[char] = &['\n', '\r', '\t'];
+
+/// Remove a leading byte order mark and the control characters from the remainder of `src`
+///
+/// Control characters (C0, C1 and delete) other than those in `allowed` are removed, or
+/// replaced with `replacement` if it is given. As with [`Replacinator::map_chars`], if a
+/// replacement is longer than the character it replaces and doesn't fit, sanitising stops
+/// before that character, which remains in the remainder. Replacing with U+FFFD only fits
+/// where earlier characters have been removed.
+///
+/// Returns the sanitised text. Like [`Replacinator::take_start`], the first section of `src`
+/// is no longer available afterwards.
+pub fn text<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+    allowed: &[char],
+    replacement: Option<char>,
+) -> &'a mut str {
+    let mark = src.mark();
+    if src.peek() == Some('\u{FEFF}') {
+        src.discard_char();
+    }
+    src.map_chars(|c| {
+        if c.is_control() && !allowed.contains(&c) {
+            replacement
+        } else {
+            Some(c)
+        }
+    });
+    src.take_marked(mark)
+        .expect("The mark was made from this Replacinator")
+}
+
+/// Remove NUL characters and U+FFFD REPLACEMENT CHARACTER from the remainder of `src`
+///
+/// This makes text suitable for C APIs, which treat NUL as the end of the string, and for
+/// validators which reject text that was lossily decoded. These characters are replaced with
+/// `marker` if it is given. As with [`text`], if the marker is longer than a NUL and doesn't
+/// fit, scrubbing stops before that character, which remains in the remainder.
+///
+/// Returns the scrubbed text. Like [`Replacinator::take_start`], the first section of `src`
+/// is no longer available afterwards.
+pub fn c_string<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+    marker: Option<char>,
+) -> &'a mut str {
+    let mark = src.mark();
+    src.map_chars(|c| match c {
+        '\0' | '\u{FFFD}' => marker,
+        c => Some(c),
+    });
+    src.take_marked(mark)
+        .expect("The mark was made from this Replacinator")
+}
+
+/// Remove the ANSI escape sequences from the remainder of `src`
+///
+/// This removes control sequences (such as colours and cursor movement), operating system
+/// commands (such as window titles and hyperlinks), and the other escape sequences of ECMA-48,
+/// introduced by either `ESC` or their 8-bit C1 control. A malformed sequence is removed up to
+/// the character which made it malformed, which is kept.
+///
+/// A sequence which is incomplete at the end of the remainder is left unread, so that when used
+/// with a [`ReplacinatorStream`](crate::ReplacinatorStream), it is completed by the next chunk.
+///
+/// Returns the stripped text. Like [`Replacinator::take_start`], the first section of `src`
+/// is no longer available afterwards.
+pub fn strip_ansi_escapes<'a, O: Observer>(src: &mut Replacinator<'a, O>) -> &'a mut str {
+    let mark = src.mark();
+    loop {
+        src.skip_while(|c| !starts_escape(c));
+        if src.peek().is_none() {
+            break;
+        }
+        match escape_len(src.remainder()) {
+            Some(len) => src.discard_bytes(len),
+            None => break,
+        }
+    }
+    src.take_marked(mark)
+        .expect("The mark was made from this Replacinator")
+}
+
+/// Whether `c` starts an escape sequence: `ESC`, or a C1 control which introduces a control
+/// sequence or control string
+fn starts_escape(c: char) -> bool {
+    matches!(
+        c,
+        '\u{1B}' | '\u{9B}' | '\u{90}' | '\u{98}' | '\u{9D}' | '\u{9E}' | '\u{9F}'
+    )
+}
+
+/// The length of the escape sequence at the start of `text`, or `None` if it is incomplete
+///
+/// `text` must start with a character for which [`starts_escape`] is true.
+fn escape_len(text: &str) -> Option<usize> {
+    #[derive(Clone, Copy)]
+    enum State {
+        Start,
+        /// After `ESC`
+        Escape,
+        /// Within the intermediate bytes of an escape sequence other than a control sequence
+        Intermediate,
+        /// Within the parameter and intermediate bytes of a control sequence
+        Control,
+        /// Within a control string, such as an operating system command
+        String,
+        /// After an `ESC` within a control string, which may start its terminator
+        StringEscape,
+    }
+
+    let mut state = State::Start;
+    for (i, c) in text.char_indices() {
+        let end = i + c.len_utf8();
+        state = match (state, c) {
+            (State::Start, '\u{1B}') => State::Escape,
+            (State::Start, '\u{9B}') => State::Control,
+            (State::Start, _) => State::String,
+            (State::Escape, '[') => State::Control,
+            (State::Escape, ']' | 'P' | 'X' | '^' | '_') => State::String,
+            (State::Escape | State::Intermediate, ' '..='/') => State::Intermediate,
+            (State::Escape | State::Intermediate, '0'..='~') => return Some(end),
+            (State::Control, ' '..='?') => State::Control,
+            (State::Control, '@'..='~') => return Some(end),
+            // Control strings may also be terminated by `BEL`, as xterm allows
+            (State::String, '\u{7}' | '\u{9C}') => return Some(end),
+            (State::String, '\u{1B}') => State::StringEscape,
+            (State::String, _) => State::String,
+            (State::StringEscape, '\\') => return Some(end),
+            // The `ESC` didn't start a terminator, so starts the next sequence instead
+            (State::StringEscape, _) => return Some(i - 1),
+            // The sequence is malformed, so ends before this character
+            _ => return Some(i),
+        };
+    }
+    None
+}
@@ -0,0 +1,72 @@
+//! Fast paths for scanning the remainder of a [`Replacinator`](crate::Replacinator)
+//!
+//! Text is mostly ASCII, so these avoid decoding characters where they can. Searching for a
+//! character or a literal string is left to [`str::find`], which already uses `memchr` and the
+//! Two-Way algorithm, and filling the second section uses [`slice::fill`], which is a `memset`.
+
+use core::convert::TryInto;
+
+const HIGH_BITS: u64 = u64::from_le_bytes([0x80; 8]);
+const LOW_BITS: u64 = u64::from_le_bytes([0x7F; 8]);
+
+/// The length in bytes of the longest prefix of `s` whose characters all match `pred`
+///
+/// ASCII bytes are passed to `pred` without decoding them as UTF-8.
+#[inline]
+pub(crate) fn prefix_len(s: &str, mut pred: impl FnMut(char) -> bool) -> usize {
+    let bytes = s.as_bytes();
+    let mut len = 0;
+    while let Some(&b) = bytes.get(len) {
+        let c = if b.is_ascii() {
+            char::from(b)
+        } else {
+            s[len..]
+                .chars()
+                .next()
+                .expect("`len` is always on a character boundary before the end")
+        };
+        if !pred(c) {
+            break;
+        }
+        len += c.len_utf8();
+    }
+    len
+}
+
+/// The length in bytes of the longest prefix of `bytes` which is ASCII whitespace, as defined by
+/// [`u8::is_ascii_whitespace`]
+///
+/// This checks eight bytes at a time.
+pub(crate) fn ascii_whitespace_len(bytes: &[u8]) -> usize {
+    let mut chunks = bytes.chunks_exact(8);
+    let mut len = 0;
+    for chunk in &mut chunks {
+        let word = u64::from_le_bytes(chunk.try_into().expect("Chunks are eight bytes long"));
+        let whitespace = [b' ', b'\t', b'\n', b'\r', b'\x0C']
+            .iter()
+            .fold(0, |found, &c| found | zero_bytes(word ^ splat(c)));
+        let other = !whitespace & HIGH_BITS;
+        if other != 0 {
+            // The bytes are little-endian, so the lowest set bit is in the first other byte
+            return len + (other.trailing_zeros() / 8) as usize;
+        }
+        len += 8;
+    }
+    len + chunks
+        .remainder()
+        .iter()
+        .take_while(|b| b.is_ascii_whitespace())
+        .count()
+}
+
+/// A word with the high bit of each byte set exactly where that byte of `word` is zero
+#[inline]
+fn zero_bytes(word: u64) -> u64 {
+    !(((word & LOW_BITS) + LOW_BITS) | word | LOW_BITS)
+}
+
+/// A word with every byte equal to `b`
+#[inline]
+fn splat(b: u8) -> u64 {
+    u64::from_le_bytes([b; 8])
+}
@@ -0,0 +1,262 @@
+//! Collecting the sections taken from a [`Replacinator`], without an allocator
+//!
+//! A transform which splits its input, such as into the strings of a JSON array, hands back one
+//! `&'a mut str` for each part. [`Segments`] stores up to a fixed number of these inline, so the
+//! pattern works in `no_std` code.
+
+use core::{fmt, iter::FusedIterator, slice};
+
+use crate::{Observer, Replacinator};
+
+/// Up to `N` disjoint parts of a string, in the order they were pushed
+pub struct Segments<'a, const N: usize> {
+    segments: [Option<&'a mut str>; N],
+    len: usize,
+}
+
+impl<'a, const N: usize> Segments<'a, N> {
+    /// Create an empty collection
+    pub fn new() -> Self {
+        Self {
+            segments: core::array::from_fn(|_| None),
+            len: 0,
+        }
+    }
+
+    /// Add `segment` to the end, or give it back if there are already `N` segments
+    pub fn push(&mut self, segment: &'a mut str) -> Result<(), &'a mut str> {
+        match self.segments.get_mut(self.len) {
+            Some(slot) => {
+                *slot = Some(segment);
+                self.len += 1;
+                Ok(())
+            }
+            None => Err(segment),
+        }
+    }
+
+    /// Take the first section of `src` and add it to the end, or leave it in place if there are
+    /// already `N` segments
+    ///
+    /// Returns whether the section was taken.
+    pub fn push_start<O: Observer>(&mut self, src: &mut Replacinator<'a, O>) -> bool {
+        if self.is_full() {
+            return false;
+        }
+        self.push(src.take_start()).is_ok()
+    }
+
+    /// Remove the last segment
+    pub fn pop(&mut self) -> Option<&'a mut str> {
+        self.len = self.len.checked_sub(1)?;
+        self.segments[self.len].take()
+    }
+
+    /// The segment at `index`
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.as_slice()
+            .get(index)
+            .map(|segment| segment.as_deref())?
+    }
+
+    /// The segment at `index`, mutably
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut str> {
+        self.segments[..self.len]
+            .get_mut(index)
+            .map(|segment| segment.as_deref_mut())?
+    }
+
+    /// The number of segments
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there are no segments
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The most segments which can be stored
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Whether no more segments can be pushed
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// The total length in bytes of the segments
+    pub fn total_len(&self) -> usize {
+        self.iter().map(str::len).sum()
+    }
+
+    /// Iterate over the segments
+    pub fn iter(&self) -> Iter<'_, 'a> {
+        Iter {
+            inner: self.as_slice().iter(),
+        }
+    }
+
+    /// Iterate over the segments, mutably
+    pub fn iter_mut(&mut self) -> IterMut<'_, 'a> {
+        IterMut {
+            inner: self.segments[..self.len].iter_mut(),
+        }
+    }
+
+    /// Display the segments one after another, with `separator` between each pair
+    ///
+    /// Nothing is copied, so this can be written anywhere which accepts [`fmt::Display`].
+    pub fn joined<'s>(&'s self, separator: &'s str) -> Joined<'s, 'a, N> {
+        Joined {
+            segments: self,
+            separator,
+        }
+    }
+
+    fn as_slice(&self) -> &[Option<&'a mut str>] {
+        &self.segments[..self.len]
+    }
+}
+
+impl<'a, const N: usize> Default for Segments<'a, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, const N: usize> fmt::Debug for Segments<'a, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'s, 'a, const N: usize> IntoIterator for &'s Segments<'a, N> {
+    type Item = &'s str;
+    type IntoIter = Iter<'s, 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'s, 'a, const N: usize> IntoIterator for &'s mut Segments<'a, N> {
+    type Item = &'s mut str;
+    type IntoIter = IterMut<'s, 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<'a, const N: usize> IntoIterator for Segments<'a, N> {
+    type Item = &'a mut str;
+    type IntoIter = IntoIter<'a, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            inner: IntoIterator::into_iter(self.segments),
+        }
+    }
+}
+
+/// An iterator over the segments of a [`Segments`]
+///
+/// Created by [`Segments::iter`].
+#[derive(Debug, Clone)]
+pub struct Iter<'s, 'a> {
+    inner: slice::Iter<'s, Option<&'a mut str>>,
+}
+
+impl<'s, 'a> Iterator for Iter<'s, 'a> {
+    type Item = &'s str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()?.as_deref()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'s, 'a> DoubleEndedIterator for Iter<'s, 'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()?.as_deref()
+    }
+}
+
+impl<'s, 'a> ExactSizeIterator for Iter<'s, 'a> {}
+
+impl<'s, 'a> FusedIterator for Iter<'s, 'a> {}
+
+/// A mutable iterator over the segments of a [`Segments`]
+///
+/// Created by [`Segments::iter_mut`].
+#[derive(Debug)]
+pub struct IterMut<'s, 'a> {
+    inner: slice::IterMut<'s, Option<&'a mut str>>,
+}
+
+impl<'s, 'a> Iterator for IterMut<'s, 'a> {
+    type Item = &'s mut str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()?.as_deref_mut()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'s, 'a> DoubleEndedIterator for IterMut<'s, 'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()?.as_deref_mut()
+    }
+}
+
+impl<'s, 'a> ExactSizeIterator for IterMut<'s, 'a> {}
+
+impl<'s, 'a> FusedIterator for IterMut<'s, 'a> {}
+
+/// An iterator over the segments taken from a [`Segments`]
+///
+/// Created by [`Segments::into_iter`](IntoIterator::into_iter).
+#[derive(Debug)]
+pub struct IntoIter<'a, const N: usize> {
+    inner: core::array::IntoIter<Option<&'a mut str>, N>,
+}
+
+impl<'a, const N: usize> Iterator for IntoIter<'a, N> {
+    type Item = &'a mut str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The segments are all before the empty slots
+        self.inner.next()?
+    }
+}
+
+impl<'a, const N: usize> FusedIterator for IntoIter<'a, N> {}
+
+/// The segments of a [`Segments`], displayed with a separator between them
+///
+/// Created by [`Segments::joined`].
+#[derive(Debug, Clone, Copy)]
+pub struct Joined<'s, 'a, const N: usize> {
+    segments: &'s Segments<'a, N>,
+    separator: &'s str,
+}
+
+impl<'s, 'a, const N: usize> fmt::Display for Joined<'s, 'a, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, segment) in self.segments.iter().enumerate() {
+            if index != 0 {
+                f.write_str(self.separator)?;
+            }
+            f.write_str(segment)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,417 @@
+//! Deserializing JSON documents with [`serde`], unescaping strings in place
+//!
+//! Strings and object keys are unescaped within the document, and handed to serde as slices
+//! of it. This means that types which borrow `&'de str` can be deserialized from strings which
+//! contain escapes, which otherwise would need an owned `String`. The document is left with its
+//! strings unescaped, so is no longer valid JSON afterwards.
+//!
+//! This module requires the `serde` feature.
+
+use core::fmt;
+
+use ::serde::de::{self, value::BorrowedStrDeserializer, DeserializeSeed, Visitor};
+
+use crate::{
+    json::{self, Walker},
+    NoObserver, Observer, Position, Replacinator,
+};
+
+/// An error encountered whilst deserializing a JSON document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    /// `None` if the error was raised by the type being deserialized, and has not yet been
+    /// given the position of the value it was raised for
+    position: Option<Position>,
+}
+
+impl Error {
+    /// The kind of error which occurred
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The position in the original document at which the error was detected
+    pub fn position(&self) -> Position {
+        self.position.unwrap_or_default()
+    }
+
+    fn raised(kind: ErrorKind) -> Self {
+        Self {
+            kind,
+            position: None,
+        }
+    }
+}
+
+impl From<json::Error> for Error {
+    fn from(error: json::Error) -> Self {
+        Self {
+            kind: ErrorKind::Json(error.kind()),
+            position: Some(error.position()),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::Json(kind) => json::describe(kind, f),
+            ErrorKind::Custom => write!(f, "invalid value"),
+            ErrorKind::InvalidType => write!(f, "invalid type"),
+            ErrorKind::InvalidLength(len) => write!(f, "invalid length {}", len),
+            ErrorKind::UnknownVariant => write!(f, "unknown variant"),
+            ErrorKind::UnknownField => write!(f, "unknown field"),
+            ErrorKind::MissingField(field) => write!(f, "missing field `{}`", field),
+            ErrorKind::DuplicateField(field) => write!(f, "duplicate field `{}`", field),
+        }?;
+        write!(f, " at byte {}", self.position().byte())
+    }
+}
+
+impl de::StdError for Error {}
+
+/// As this module doesn't allocate, the messages given by the type being deserialized are not
+/// kept. Instead, the kind of error is recorded, where serde reports it.
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(_: T) -> Self {
+        Self::raised(ErrorKind::Custom)
+    }
+
+    fn invalid_type(_: de::Unexpected<'_>, _: &dyn de::Expected) -> Self {
+        Self::raised(ErrorKind::InvalidType)
+    }
+
+    fn invalid_length(len: usize, _: &dyn de::Expected) -> Self {
+        Self::raised(ErrorKind::InvalidLength(len))
+    }
+
+    fn unknown_variant(_: &str, _: &'static [&'static str]) -> Self {
+        Self::raised(ErrorKind::UnknownVariant)
+    }
+
+    fn unknown_field(_: &str, _: &'static [&'static str]) -> Self {
+        Self::raised(ErrorKind::UnknownField)
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Self::raised(ErrorKind::MissingField(field))
+    }
+
+    fn duplicate_field(field: &'static str) -> Self {
+        Self::raised(ErrorKind::DuplicateField(field))
+    }
+}
+
+/// The kinds of [`Error`] which can occur whilst deserializing a JSON document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The document is not valid JSON
+    Json(json::ErrorKind),
+    /// The type being deserialized rejected a value, for a reason not covered by another kind
+    Custom,
+    /// A value was not of the type expected
+    InvalidType,
+    /// An array or object had the given number of elements, which was not the number expected
+    InvalidLength(usize),
+    /// An enum variant was not recognised
+    UnknownVariant,
+    /// An object had a field which was not recognised
+    UnknownField,
+    /// An object was missing the given field
+    MissingField(&'static str),
+    /// An object had the given field more than once
+    DuplicateField(&'static str),
+}
+
+/// Deserialize an instance of `T` from the JSON document `document`, unescaping its strings in
+/// place
+///
+/// Only whitespace may follow the value.
+pub fn from_str<'de, T: de::Deserialize<'de>>(document: &'de mut str) -> Result<T, Error> {
+    Replacinator::new_in(document, |src| {
+        let mut deserializer = StrDeserializer::new(src);
+        let value = T::deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        Ok(value)
+    })
+}
+
+/// A [`serde`] deserializer for a JSON value at the start of the remainder of a
+/// [`Replacinator`]
+///
+/// Strings are unescaped in place, and are passed to the visitor as borrowed slices of the
+/// original string. [`from_str`] covers the common case of deserializing a whole document.
+pub struct StrDeserializer<'r, 'de, O = NoObserver> {
+    walker: Walker<'r, 'de, O>,
+}
+
+impl<'r, 'de, O: Observer> StrDeserializer<'r, 'de, O> {
+    /// Create a deserializer which reads from the remainder of `src`
+    pub fn new(src: &'r mut Replacinator<'de, O>) -> Self {
+        let mut walker = Walker::new(src);
+        walker.skip_whitespace();
+        Self { walker }
+    }
+
+    /// Check that only whitespace follows the value which was deserialized
+    pub fn end(&mut self) -> Result<(), Error> {
+        self.walker.skip_whitespace();
+        Ok(self.walker.end()?)
+    }
+
+    /// Give an error raised by the type being deserialized the current position
+    fn locate<T>(&self, result: Result<T, Error>) -> Result<T, Error> {
+        result.map_err(|error| Error {
+            position: error.position.or_else(|| Some(self.walker.position())),
+            ..error
+        })
+    }
+
+    fn number<V: Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value, Error> {
+        self.walker.take_start();
+        self.walker.skip_number()?;
+        let text: &str = self.walker.take_start();
+        if !text.contains(['.', 'e', 'E']) {
+            if let Ok(n) = text.parse() {
+                return visitor.visit_u64(n);
+            }
+            if let Ok(n) = text.parse() {
+                return visitor.visit_i64(n);
+            }
+        }
+        visitor.visit_f64(text.parse().expect("A JSON number is a valid float"))
+    }
+
+    /// Check that only whitespace remains before the `close` of a container
+    fn close(&mut self, close: char) -> Result<(), Error> {
+        self.walker.skip_whitespace();
+        Ok(self.walker.expect(close)?)
+    }
+}
+
+impl<'de, 'r, 'a, O: Observer> de::Deserializer<'de> for &'a mut StrDeserializer<'r, 'de, O> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let result = match self.walker.peek()? {
+            'n' => {
+                self.walker.skip_literal("null")?;
+                visitor.visit_unit()
+            }
+            't' => {
+                self.walker.skip_literal("true")?;
+                visitor.visit_bool(true)
+            }
+            'f' => {
+                self.walker.skip_literal("false")?;
+                visitor.visit_bool(false)
+            }
+            '-' | '0'..='9' => self.number(visitor),
+            '"' => visitor.visit_borrowed_str(self.walker.unescape_string()?),
+            '[' => {
+                self.walker.expect('[')?;
+                visitor
+                    .visit_seq(Elements::new(&mut *self))
+                    .and_then(|value| {
+                        self.close(']')?;
+                        Ok(value)
+                    })
+            }
+            '{' => {
+                self.walker.expect('{')?;
+                visitor
+                    .visit_map(Elements::new(&mut *self))
+                    .and_then(|value| {
+                        self.close('}')?;
+                        Ok(value)
+                    })
+            }
+            c => return Err(self.walker.error(json::ErrorKind::UnexpectedChar(c)).into()),
+        };
+        self.locate(result)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let result = if self.walker.peek()? == 'n' {
+            self.walker.skip_literal("null")?;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(&mut *self)
+        };
+        self.locate(result)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let result = visitor.visit_newtype_struct(&mut *self);
+        self.locate(result)
+    }
+
+    /// Unit variants are strings, and other variants are objects with a single member, whose key
+    /// is the name of the variant
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let result = match self.walker.peek()? {
+            '"' => {
+                let variant: &str = self.walker.unescape_string()?;
+                visitor.visit_enum(BorrowedStrDeserializer::new(variant))
+            }
+            '{' => {
+                self.walker.expect('{')?;
+                self.walker.skip_whitespace();
+                visitor
+                    .visit_enum(Variant { de: &mut *self })
+                    .and_then(|value| {
+                        self.close('}')?;
+                        Ok(value)
+                    })
+            }
+            c => return Err(self.walker.error(json::ErrorKind::UnexpectedChar(c)).into()),
+        };
+        self.locate(result)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.walker.skip_value()?;
+        visitor.visit_unit()
+    }
+
+    ::serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf unit
+        unit_struct seq tuple tuple_struct map struct identifier
+    }
+}
+
+/// The elements of an array, or the members of an object, after its opening bracket
+///
+/// The closing bracket is left for the deserializer, so that it can check that the visitor
+/// didn't stop early.
+struct Elements<'a, 'r, 'de, O> {
+    de: &'a mut StrDeserializer<'r, 'de, O>,
+    first: bool,
+}
+
+impl<'a, 'r, 'de, O: Observer> Elements<'a, 'r, 'de, O> {
+    fn new(de: &'a mut StrDeserializer<'r, 'de, O>) -> Self {
+        Self { de, first: true }
+    }
+
+    /// Move to the start of the next element, returning whether there is one before `close`
+    fn next(&mut self, close: char) -> Result<bool, Error> {
+        let walker = &mut self.de.walker;
+        walker.skip_whitespace();
+        if walker.peek()? == close {
+            return Ok(false);
+        }
+        if !self.first {
+            walker.expect(',')?;
+            walker.skip_whitespace();
+        }
+        self.first = false;
+        Ok(true)
+    }
+}
+
+impl<'a, 'r, 'de, O: Observer> de::SeqAccess<'de> for Elements<'a, 'r, 'de, O> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        if !self.next(']')? {
+            return Ok(None);
+        }
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+}
+
+impl<'a, 'r, 'de, O: Observer> de::MapAccess<'de> for Elements<'a, 'r, 'de, O> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        if !self.next('}')? {
+            return Ok(None);
+        }
+        let c = self.de.walker.peek()?;
+        if c != '"' {
+            return Err(self
+                .de
+                .walker
+                .error(json::ErrorKind::UnexpectedChar(c))
+                .into());
+        }
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let walker = &mut self.de.walker;
+        walker.skip_whitespace();
+        walker.expect(':')?;
+        walker.skip_whitespace();
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+/// The single member of an object which represents an enum variant
+struct Variant<'a, 'r, 'de, O> {
+    de: &'a mut StrDeserializer<'r, 'de, O>,
+}
+
+impl<'a, 'r, 'de, O: Observer> de::EnumAccess<'de> for Variant<'a, 'r, 'de, O> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), Error> {
+        let c = self.de.walker.peek()?;
+        if c != '"' {
+            return Err(self
+                .de
+                .walker
+                .error(json::ErrorKind::UnexpectedChar(c))
+                .into());
+        }
+        let variant = seed.deserialize(&mut *self.de)?;
+        let walker = &mut self.de.walker;
+        walker.skip_whitespace();
+        walker.expect(':')?;
+        walker.skip_whitespace();
+        Ok((variant, self))
+    }
+}
+
+impl<'a, 'r, 'de, O: Observer> de::VariantAccess<'de> for Variant<'a, 'r, 'de, O> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        de::Deserialize::deserialize(self.de)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_seq(self.de, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_map(self.de, visitor)
+    }
+}
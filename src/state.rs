@@ -0,0 +1,103 @@
+use core::fmt;
+
+use crate::{FillStrategy, Observer, Position, Replacinator};
+
+/// The positions of a [`Replacinator`], saved so that it can be resumed later
+///
+/// Created by [`Replacinator::save_state`], and used by [`ReplacinatorGuard::resume`]. This allows
+/// the borrow of the string to end between two parts of a transform, such as across an `await`,
+/// without starting again from the beginning.
+///
+/// [`ReplacinatorGuard::resume`]: crate::ReplacinatorGuard::resume
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct State {
+    /// The offset in the string of the start of the first section
+    pub(crate) base: usize,
+    /// The offset in the string of the write position
+    pub(crate) write: usize,
+    /// The offset in the string of the end of the remainder
+    pub(crate) end: usize,
+    /// The position of the read position in the original string
+    pub(crate) position: Position,
+    pub(crate) compacted: usize,
+    pub(crate) padding: usize,
+    pub(crate) fill: FillStrategy,
+    /// The read position when the first section was last taken
+    pub(crate) taken: Position,
+    /// The offsets in the string of the bytes which have been changed
+    pub(crate) dirty: Option<(usize, usize)>,
+}
+
+impl State {
+    /// The position in the original string of the next character to be read
+    ///
+    /// This is [`Replacinator::position`] when the state was saved.
+    pub fn read(&self) -> Position {
+        self.position
+    }
+
+    /// The byte offset in the string at which the next character will be written
+    ///
+    /// This is [`Replacinator::write_offset`] when the state was saved.
+    pub fn write(&self) -> usize {
+        self.write
+    }
+
+    /// The offset in the string of the read position
+    pub(crate) fn read_index(&self) -> usize {
+        self.position.byte() - self.compacted
+    }
+}
+
+/// The error returned by [`ReplacinatorGuard::resume`] when a [`State`] doesn't fit the string
+///
+/// [`ReplacinatorGuard::resume`]: crate::ReplacinatorGuard::resume
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StateError {
+    /// The string is shorter than the one the state was saved from
+    TooShort,
+    /// A saved position is not on a character boundary of the string
+    NotCharBoundary(usize),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            StateError::TooShort => write!(f, "the string is too short for the saved state"),
+            StateError::NotCharBoundary(offset) => write!(
+                f,
+                "the saved offset {} is not on a character boundary",
+                offset
+            ),
+        }
+    }
+}
+
+impl<'a, O: Observer> Replacinator<'a, O> {
+    /// Save the positions of this, so that it can be resumed after the string is released
+    ///
+    /// The state refers to the string which this was created from, which must be given unchanged
+    /// to [`ReplacinatorGuard::resume`]. If [`Replacinator::fill`] is [`FillStrategy::Compact`],
+    /// this compacts first, as the remainder would otherwise move once the string is released.
+    ///
+    /// [`ReplacinatorGuard::resume`]: crate::ReplacinatorGuard::resume
+    pub fn save_state(&mut self) -> State {
+        if self.fill == FillStrategy::Compact {
+            self.compact();
+        }
+        let write = self.write_offset();
+        let base = write - self.inner.write_position;
+        State {
+            base,
+            write,
+            end: base + self.inner.contents.len(),
+            position: self.inner.position,
+            compacted: self.compacted,
+            padding: self.padding,
+            fill: self.fill,
+            taken: self.taken,
+            dirty: self.dirty_range().map(|dirty| (dirty.start, dirty.end)),
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! Applying in-place transforms to text which arrives in chunks
+
+use core::fmt;
+
+use crate::{raw::unchecked_from_utf8_mut, Replacinator};
+
+/// An error encountered whilst processing a chunk of a stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    position: usize,
+}
+
+impl Error {
+    /// The kind of error which occurred
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The position in the buffer of the first byte which is not valid UTF-8
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::InvalidUtf8 => write!(f, "invalid UTF-8"),
+            ErrorKind::IncompleteChar => write!(f, "stream ended partway through a character"),
+        }?;
+        write!(f, " at byte {}", self.position)
+    }
+}
+
+/// The kinds of [`Error`] which can occur whilst processing a chunk of a stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The buffer contains bytes which are not valid UTF-8
+    InvalidUtf8,
+    /// The final chunk ends partway through a character
+    IncompleteChar,
+}
+
+/// A buffer which text is read into in chunks, to be transformed in place with a [`Replacinator`]
+///
+/// Each chunk is read into [`ReplacinatorStream::spare`], then transformed using
+/// [`ReplacinatorStream::process`]. A character which is split between chunks, and any part of
+/// the text which the transform leaves unread (such as an incomplete escape sequence), is moved
+/// to the start of the buffer and processed again with the next chunk. This means that
+/// transforms written against [`Replacinator`] work unchanged, so long as they stop before
+/// anything which might continue into the next chunk.
+#[derive(Debug)]
+pub struct ReplacinatorStream<'b> {
+    buf: &'b mut [u8],
+    /// The start of the bytes which have not been processed
+    pending: usize,
+    /// The end of the bytes which have been read into the buffer
+    filled: usize,
+}
+
+impl<'b> ReplacinatorStream<'b> {
+    /// Create a stream which reads its chunks into `buf`
+    ///
+    /// `buf` limits the amount of text which can be carried over between chunks.
+    pub fn new(buf: &'b mut [u8]) -> Self {
+        Self {
+            buf,
+            pending: 0,
+            filled: 0,
+        }
+    }
+
+    /// The free space at the end of the buffer, which the next chunk should be read into
+    ///
+    /// Call [`ReplacinatorStream::fill`] with the number of bytes read. This is empty if the
+    /// text carried over from previous chunks fills the buffer.
+    pub fn spare(&mut self) -> &mut [u8] {
+        if self.pending > 0 {
+            self.buf.copy_within(self.pending..self.filled, 0);
+            self.filled -= self.pending;
+            self.pending = 0;
+        }
+        &mut self.buf[self.filled..]
+    }
+
+    /// Record that `len` bytes have been read into [`ReplacinatorStream::spare`]
+    ///
+    /// # Panics
+    /// If `len` is longer than [`ReplacinatorStream::spare`]
+    pub fn fill(&mut self, len: usize) {
+        assert!(len <= self.spare().len());
+        self.filled += len;
+    }
+
+    /// Transform the text which has been read, using `with`
+    ///
+    /// Returns the result of `with` and the text it wrote. The remainder left unread by `with`
+    /// is carried over to the next chunk. If `last` is false, so is a character which is split
+    /// at the end of the chunk.
+    pub fn process<R>(
+        &mut self,
+        last: bool,
+        with: impl FnOnce(&mut Replacinator<'_>) -> R,
+    ) -> Result<(R, &mut str), Error> {
+        let chunk = &mut self.buf[self.pending..self.filled];
+        let valid = match core::str::from_utf8(chunk) {
+            Ok(text) => text.len(),
+            Err(e) if e.error_len().is_none() && !last => e.valid_up_to(),
+            Err(e) => {
+                let kind = match e.error_len() {
+                    Some(_) => ErrorKind::InvalidUtf8,
+                    None => ErrorKind::IncompleteChar,
+                };
+                return Err(Error {
+                    kind,
+                    position: self.pending + e.valid_up_to(),
+                });
+            }
+        };
+        let text = core::str::from_utf8_mut(&mut chunk[..valid])
+            .expect("The chunk was checked to be valid UTF-8 up to this point");
+        let contents = text.as_ptr() as usize;
+        let offset = |s: &str| s.as_ptr() as usize - contents;
+        let mut with = Some(with);
+        let (result, written, unread) = Replacinator::new_in(text, |src| {
+            let with = with.take().expect("`new_in` only calls the function once");
+            let result = with(src);
+            // Split off the remainder first, so that taking the start doesn't move it. If `with`
+            // took the start itself, it may have been moved already
+            let unread = src.take_remainder();
+            let unread = offset(unread)..offset(unread) + unread.len();
+            let written = src.take_start();
+            let written = offset(written)..offset(written) + written.len();
+            (result, written, unread)
+        });
+        // Move the unread text to directly before any character which is split at the end of the
+        // chunk, so that they are carried over together
+        let carried_start = valid - unread.len();
+        self.buf.copy_within(
+            self.pending + unread.start..self.pending + unread.end,
+            self.pending + carried_start,
+        );
+        let output = self.pending + written.start..self.pending + written.end;
+        self.pending += carried_start;
+        // Safety: The text written by the Replacinator is valid UTF-8
+        let output = unsafe { unchecked_from_utf8_mut(&mut self.buf[output]) };
+        Ok((result, output))
+    }
+}
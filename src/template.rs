@@ -0,0 +1,312 @@
+//! Substitution of placeholders in templates
+
+use core::fmt;
+
+use crate::{Observer, Position, Replacinator, Span};
+
+/// An error encountered whilst rendering a template
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    position: Position,
+}
+
+impl Error {
+    /// The kind of error which occurred
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The position in the original template at which the error was detected
+    pub fn position(&self) -> Position {
+        self.position
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::Unclosed => write!(f, "unclosed placeholder"),
+            ErrorKind::UnknownKey => write!(f, "unknown placeholder"),
+            ErrorKind::TooLong => write!(f, "replacement does not fit in place of placeholder"),
+            ErrorKind::ValueTooLong { extra, .. } => {
+                write!(f, "value needs {} more bytes than are available", extra)
+            }
+        }?;
+        write!(f, " at byte {}", self.position.byte())
+    }
+}
+
+/// The kinds of [`Error`] which can occur whilst rendering a template
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A `{` was not followed by a matching `}`
+    Unclosed,
+    /// The lookup function returned `None` for a placeholder
+    UnknownKey,
+    /// The value of a placeholder is longer than the space available for it
+    TooLong,
+    /// The value of a variable reference is longer than the space available for it
+    ValueTooLong {
+        /// The reference which could not be substituted
+        reference: Span,
+        /// The number of bytes more than were available which the value needed
+        extra: usize,
+    },
+}
+
+/// Replace each `{key}` placeholder in `template` with the value returned by `lookup`
+///
+/// `{{` and `}}` are replaced with `{` and `}` respectively. A value can be used if it is no
+/// longer than its placeholder, including any space freed by earlier substitutions.
+///
+/// The rendered template is `&template[..len]`, where `len` is the returned value; the rest of
+/// the template is filled with spaces. If an error is returned, the template may have been
+/// partially rendered, but is still valid UTF-8.
+pub fn render_in_place<'v>(
+    template: &mut str,
+    mut lookup: impl FnMut(&str) -> Option<&'v str>,
+) -> Result<usize, Error> {
+    Replacinator::new_in(template, |src| loop {
+        src.skip_while(|c| c != '{' && c != '}');
+        let error = |src: &Replacinator<'_>, kind| Error {
+            kind,
+            position: src.position(),
+        };
+        if src.starts_with("{{") || src.starts_with("}}") {
+            src.discard_char();
+            src.skip_char();
+            continue;
+        }
+        match src.peek() {
+            Some('{') => {}
+            // A lone `}` is kept unchanged
+            Some('}') => {
+                src.skip_char();
+                continue;
+            }
+            _ => return Ok(src.written_len()),
+        }
+        let end = match src.remainder().find('}') {
+            Some(end) => end,
+            None => return Err(error(src, ErrorKind::Unclosed)),
+        };
+        let value = match lookup(&src.remainder()[1..end]) {
+            Some(value) => value,
+            None => return Err(error(src, ErrorKind::UnknownKey)),
+        };
+        let available = src.gap_len() + end + 1;
+        if value.len() > available {
+            return Err(error(src, ErrorKind::TooLong));
+        }
+        src.discard_bytes(end + 1);
+        src.write_str(value);
+    })
+}
+
+/// Substitute each fixed-width slot such as `{name______}` in the remainder of `src` with the
+/// value returned by `lookup`, padded with spaces to the width of the slot
+///
+/// The key of a slot is the text between its braces, without any trailing `_`s, which pad the
+/// slot to the width its value may need. Every `{` starts a slot. As each value is padded to
+/// the width of its slot in bytes, including the braces, the rest of the text doesn't move. An
+/// error is returned if a value is longer than its slot.
+///
+/// Returns the rendered text. Like [`Replacinator::take_start`], the first section of `src`
+/// is no longer available afterwards.
+pub fn render<'a, 'v, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+    mut lookup: impl FnMut(&str) -> Option<&'v str>,
+) -> Result<&'a mut str, Error> {
+    let error = |src: &Replacinator<'_, O>, kind| Error {
+        kind,
+        position: src.position(),
+    };
+    let mark = src.mark();
+    loop {
+        src.skip_while(|c| c != '{');
+        let remainder = src.remainder();
+        if remainder.is_empty() {
+            break;
+        }
+        let width = remainder
+            .find('}')
+            .ok_or_else(|| error(src, ErrorKind::Unclosed))?
+            + 1;
+        let key = remainder[1..width - 1].trim_end_matches('_');
+        let value = lookup(key).ok_or_else(|| error(src, ErrorKind::UnknownKey))?;
+        if value.len() > width {
+            return Err(error(src, ErrorKind::TooLong));
+        }
+        src.discard_bytes(width);
+        src.write_str(value);
+        for _ in value.len()..width {
+            src.write_char(' ');
+        }
+    }
+    Ok(src
+        .take_marked(mark)
+        .expect("The mark was made from this Replacinator"))
+}
+
+/// What to do with a variable reference which cannot be substituted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unresolved {
+    /// Leave the reference unchanged
+    Keep,
+    /// Remove the reference
+    Strip,
+}
+
+/// The outcome of [`expand_vars`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Expansion {
+    len: usize,
+    unresolved: usize,
+}
+
+impl Expansion {
+    /// The length in bytes of the expanded text
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the expanded text is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of references which could not be substituted
+    pub fn unresolved(&self) -> usize {
+        self.unresolved
+    }
+}
+
+/// Substitute each `$VAR` or `${VAR}` reference in `text` with the value returned by `lookup`
+///
+/// A variable name in a `$VAR` reference consists of ASCII letters, digits and underscores,
+/// and cannot start with a digit. A `$` which is not followed by a variable name is unchanged.
+///
+/// A reference is unresolved if `lookup` returns `None`, or if the value is longer than the
+/// reference, including any space freed by earlier substitutions. Unresolved references are
+/// handled as described by `unresolved`, and counted in the returned [`Expansion`].
+///
+/// The expanded text is `&text[..len]`, where `len` is [`Expansion::len`]; the rest of the text
+/// is filled with spaces. An error is only returned for a `${` without a matching `}`.
+pub fn expand_vars<'v>(
+    text: &mut str,
+    mut lookup: impl FnMut(&str) -> Option<&'v str>,
+    unresolved: Unresolved,
+) -> Result<Expansion, Error> {
+    Replacinator::new_in(text, |src| {
+        let mut expansion = Expansion {
+            len: 0,
+            unresolved: 0,
+        };
+        loop {
+            src.skip_while(|c| c != '$');
+            if src.peek().is_none() {
+                break;
+            }
+            let (name, len) = match var_reference(src.remainder()) {
+                Ok(Some(reference)) => reference,
+                Ok(None) => {
+                    src.skip_char();
+                    continue;
+                }
+                Err(kind) => {
+                    return Err(Error {
+                        kind,
+                        position: src.position(),
+                    })
+                }
+            };
+            let available = src.gap_len() + len;
+            match lookup(name) {
+                Some(value) if value.len() <= available => {
+                    src.discard_bytes(len);
+                    src.write_str(value);
+                }
+                _ => {
+                    expansion.unresolved += 1;
+                    match unresolved {
+                        Unresolved::Keep => src.skip_bytes(len),
+                        Unresolved::Strip => src.discard_bytes(len),
+                    }
+                }
+            }
+        }
+        expansion.len = src.written_len();
+        Ok(expansion)
+    })
+}
+
+/// Substitute each `$VAR` or `${VAR}` reference in the remainder of `src` with the value
+/// returned by `lookup`, failing if any reference cannot be substituted
+///
+/// References are found as in [`expand_vars`]. Unlike [`expand_vars`], an error is returned if
+/// `lookup` returns `None` for a reference, or if its value doesn't fit. In the latter case,
+/// [`ErrorKind::ValueTooLong`] gives the span of the reference and how many more bytes the value
+/// needed, so that the caller can provide that much headroom (such as with
+/// [`Replacinator::new_in_with_headroom`]) and try again.
+///
+/// Returns the expanded text. Like [`Replacinator::take_start`], the first section of `src`
+/// is no longer available afterwards.
+pub fn expand_vars_in_place<'a, 'v, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+    mut lookup: impl FnMut(&str) -> Option<&'v str>,
+) -> Result<&'a mut str, Error> {
+    let error = |src: &Replacinator<'_, O>, kind| Error {
+        kind,
+        position: src.position(),
+    };
+    let mark = src.mark();
+    loop {
+        src.skip_while(|c| c != '$');
+        if src.peek().is_none() {
+            break;
+        }
+        let remainder = src.remainder();
+        let (name, len) = match var_reference(remainder) {
+            Ok(Some(reference)) => reference,
+            Ok(None) => {
+                src.skip_char();
+                continue;
+            }
+            Err(kind) => return Err(error(src, kind)),
+        };
+        let value = lookup(name).ok_or_else(|| error(src, ErrorKind::UnknownKey))?;
+        let available = src.gap_len() + len;
+        if value.len() > available {
+            let start = src.position();
+            let reference = Span::new(start, start.after(&remainder[..len]));
+            let extra = value.len() - available;
+            return Err(error(src, ErrorKind::ValueTooLong { reference, extra }));
+        }
+        src.discard_bytes(len);
+        src.write_str(value);
+    }
+    Ok(src
+        .take_marked(mark)
+        .expect("The mark was made from this Replacinator"))
+}
+
+/// Find the name and length of the variable reference at the start of `remainder`, which starts
+/// with `$`
+///
+/// Returns `None` if the `$` doesn't start a reference.
+fn var_reference(remainder: &str) -> Result<Option<(&str, usize)>, ErrorKind> {
+    if remainder.starts_with("${") {
+        let end = remainder.find('}').ok_or(ErrorKind::Unclosed)?;
+        return Ok(Some((&remainder[2..end], end + 1)));
+    }
+    let name = &remainder[1..];
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        return Ok(None);
+    }
+    let end = name
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(name.len());
+    Ok(Some((&name[..end], end + 1)))
+}
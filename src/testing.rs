@@ -0,0 +1,57 @@
+//! Utilities for testing transforms built on a [`Replacinator`]
+//!
+//! This module requires the `std` feature.
+
+use std::string::String;
+
+use crate::Replacinator;
+
+/// Check that `transform` agrees with the copying `reference` implementation on `input`
+///
+/// `transform` is applied to a copy of `input` through [`Replacinator::new_in`]. Its result is
+/// compared to the result of `reference`, and the copy is checked to still be valid UTF-8 of
+/// the same length, containing the result.
+///
+/// # Panics
+/// If the results differ, or an invariant doesn't hold. The message includes `input`.
+#[track_caller]
+pub fn verify_transform<F>(input: &str, transform: F, reference: impl FnOnce(&str) -> String)
+where
+    F: for<'a> FnOnce(&mut Replacinator<'a>) -> &'a mut str,
+{
+    let expected = reference(input);
+    let mut value = String::from(input);
+    let mut transform = Some(transform);
+    let contents = value.as_ptr() as usize;
+    let actual = Replacinator::new_in(&mut value, |src| {
+        let transform = transform.take().expect("`new_in` calls its function once");
+        transform(src)
+    });
+    let offset = (actual.as_ptr() as usize).checked_sub(contents);
+    let actual = String::from(&*actual);
+    assert_eq!(
+        actual, expected,
+        "The transform disagreed with the reference for {:?}",
+        input
+    );
+    assert_eq!(
+        value.len(),
+        input.len(),
+        "The length of the string changed for {:?}",
+        input
+    );
+    assert!(
+        core::str::from_utf8(value.as_bytes()).is_ok(),
+        "The string was left invalid for {:?}",
+        input
+    );
+    let end = offset.and_then(|offset| offset.checked_add(actual.len()));
+    assert_eq!(
+        offset
+            .zip(end)
+            .and_then(|(offset, end)| value.get(offset..end)),
+        Some(&*actual),
+        "The result is not in the string for {:?}",
+        input
+    );
+}
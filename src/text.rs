@@ -0,0 +1,185 @@
+//! General purpose transformations of text
+
+use crate::{transforms, Replacinator};
+
+/// Convert `text` into a slug suitable for use in a URL, such as `hello-world` from `Hello, World!`
+///
+/// Letters are lowercased, and common accented Latin letters are transliterated to ASCII.
+/// Runs of whitespace and punctuation become a single `-`, and any other characters are removed.
+///
+/// Returns the slug, which is at the start of `text`. The rest of `text` is filled with spaces.
+pub fn slugify_in_place(text: &mut str) -> &mut str {
+    Replacinator::new_in(text, transforms::slugify_in_place)
+}
+
+/// Normalise `text` for use as a search key, such as `rust borrow checker` from `The Rust Borrow-Checker!`
+///
+/// Letters are lowercased where this doesn't make them longer. Words are runs of alphanumeric
+/// characters, and are separated by a single space in the output. Everything else is removed,
+/// as are any words for which `stop_word` returns `true`.
+///
+/// Returns the normalised text, which is at the start of `text`. The rest of `text` is
+/// filled with spaces.
+pub fn normalize_query(text: &mut str, mut stop_word: impl FnMut(&str) -> bool) -> &mut str {
+    Replacinator::new_in(text, |src| {
+        loop {
+            src.discard_while(|c| !c.is_alphanumeric());
+            if src.peek().is_none() {
+                break;
+            }
+            let word_start = src.written_len();
+            if word_start > 0 {
+                src.write_char(' ');
+            }
+            while let Some(c) = src.peek().filter(|c| c.is_alphanumeric()) {
+                src.read_char();
+                let mut lower = c.to_lowercase();
+                match (lower.next(), lower.next()) {
+                    (Some(lower), None) if lower.len_utf8() <= c.len_utf8() => {
+                        src.write_char(lower)
+                    }
+                    _ => src.write_char(c),
+                }
+            }
+            let word = src.start()[word_start..].trim_start_matches(' ');
+            if stop_word(word) {
+                src.truncate_start(word_start);
+            }
+        }
+        src.take_start()
+    })
+}
+
+/// Whether `text` appears to have been encoded as UTF-8 twice
+///
+/// This is the case where the UTF-8 bytes were decoded as Windows-1252 or Latin-1, then encoded
+/// as UTF-8 again. It is detected if every non-ASCII character of `text` is part of a sequence
+/// which [`repair_mojibake`](crate::transforms::repair_mojibake) would repair, and there is at
+/// least one such sequence. Text which was correctly encoded almost never has this form.
+pub fn is_double_encoded(text: &str) -> bool {
+    let mut found = false;
+    let mut rest = text;
+    while let Some(start) = rest.find(|c: char| !c.is_ascii()) {
+        match crate::transforms::mojibake(&rest[start..]) {
+            Some((_, len, _)) => rest = &rest[start + len..],
+            None => return false,
+        }
+        found = true;
+    }
+    found
+}
+
+/// Repair `text` if it was encoded as UTF-8 more than once, as detected by [`is_double_encoded`]
+///
+/// Each layer of encoding is removed in turn, until the text no longer appears to be double
+/// encoded. Each repair shrinks the text, so this is done in place.
+///
+/// Returns the repaired text, which is at the start of `text`. The rest of `text` is filled with
+/// spaces.
+pub fn repair_double_encoding(mut text: &mut str) -> &mut str {
+    while is_double_encoded(text) {
+        text = Replacinator::new_in(text, crate::transforms::repair_mojibake);
+    }
+    text
+}
+
+/// Whether [`lines`] includes the line terminators in the text of each line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Terminators {
+    /// Each line's text ends with its terminator
+    Keep,
+    /// Each line's text excludes its terminator
+    Strip,
+}
+
+/// The terminator at the end of a [`Line`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Terminator {
+    /// `\n`
+    LineFeed,
+    /// `\r\n`
+    CarriageReturnLineFeed,
+    /// The line is the last in the text, and has no terminator
+    None,
+}
+
+impl Terminator {
+    /// The text of this terminator
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Terminator::LineFeed => "\n",
+            Terminator::CarriageReturnLineFeed => "\r\n",
+            Terminator::None => "",
+        }
+    }
+}
+
+/// A line of text found by [`lines`]
+#[derive(Debug)]
+pub struct Line<'a> {
+    text: &'a mut str,
+    terminator: Terminator,
+}
+
+impl<'a> Line<'a> {
+    /// View the text of this line
+    pub fn text(&self) -> &str {
+        self.text
+    }
+
+    /// The terminator which ended this line
+    pub fn terminator(&self) -> Terminator {
+        self.terminator
+    }
+
+    /// Take the text of this line as a mutable view
+    pub fn into_text(self) -> &'a mut str {
+        self.text
+    }
+}
+
+/// Split `text` into lines ending with `\n` or `\r\n`, recording which terminator each line had
+///
+/// As with [`str::lines`], a `\r` which is not followed by `\n` is part of the line, and the
+/// final line may be unterminated but is not yielded if it is empty.
+pub fn lines(text: &mut str, terminators: Terminators) -> Lines<'_> {
+    Lines {
+        rest: text,
+        terminators,
+    }
+}
+
+/// An iterator over the lines of some text
+///
+/// Created by [`lines`].
+#[derive(Debug)]
+pub struct Lines<'a> {
+    rest: &'a mut str,
+    terminators: Terminators,
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = Line<'a>;
+
+    fn next(&mut self) -> Option<Line<'a>> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        let (len, terminator) = match self.rest.find('\n') {
+            Some(end) if self.rest[..end].ends_with('\r') => {
+                (end + 1, Terminator::CarriageReturnLineFeed)
+            }
+            Some(end) => (end + 1, Terminator::LineFeed),
+            None => (self.rest.len(), Terminator::None),
+        };
+        let (line, rest) = core::mem::take(&mut self.rest).split_at_mut(len);
+        self.rest = rest;
+        let text = match self.terminators {
+            Terminators::Keep => line,
+            Terminators::Strip => &mut line[..len - terminator.as_str().len()],
+        };
+        Some(Line { text, terminator })
+    }
+}
+
+impl<'a> core::iter::FusedIterator for Lines<'a> {}
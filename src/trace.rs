@@ -0,0 +1,244 @@
+//! Tracing of the cursor operations of a [`Replacinator`], for debugging transforms
+//!
+//! A [`Tracer`] is an [`Observer`] which reports each operation as an [`Event`], along with the
+//! byte offsets in the original string at which it happened. Create a traced [`Replacinator`]
+//! with [`Replacinator::with_tracer`]. With the `std` feature, [`eprint`] can be used as the
+//! callback to print each event to standard error. Similarly, with the `log` feature, [`log()`]
+//! forwards each event to the `log` crate, and with the `defmt` feature, [`defmt()`] forwards it
+//! to `defmt`.
+//!
+//! This requires the `trace` feature.
+
+use core::fmt;
+
+use crate::{AsMutStr, FillStrategy, Observer, Replacinator};
+
+/// An operation on a traced [`Replacinator`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event<'t> {
+    /// `text` was read from the remainder, starting at byte `offset` of the original string
+    Read {
+        /// The byte offset of the start of `text`
+        offset: usize,
+        /// The text which was read
+        text: &'t str,
+    },
+    /// `text` was written to the first section, starting at byte `offset` of the original string
+    Write {
+        /// The byte offset at which `text` was written
+        offset: usize,
+        /// The text which was written
+        text: &'t str,
+    },
+    /// The second section was filled using `fill`, leaving the given offsets
+    Synchronise {
+        /// How the second section was filled
+        fill: FillStrategy,
+        /// The read offset afterwards
+        read_offset: usize,
+        /// The write offset afterwards
+        write_offset: usize,
+    },
+    /// The positions were returned to a [`Checkpoint`](crate::Checkpoint) with these offsets, or
+    /// the end of the first section was removed
+    Rewind {
+        /// The read offset which was restored
+        read_offset: usize,
+        /// The write offset which was restored
+        write_offset: usize,
+    },
+    /// The first section was taken
+    TakeStart(&'t str),
+    /// The remainder was taken
+    TakeRemainder(&'t str),
+}
+
+impl fmt::Display for Event<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Event::Read { offset, text } => write!(f, "read {:?} at byte {}", text, offset),
+            Event::Write { offset, text } => write!(f, "write {:?} at byte {}", text, offset),
+            Event::Synchronise {
+                fill,
+                read_offset,
+                write_offset,
+            } => write!(
+                f,
+                "synchronise with {:?}, reading at byte {} and writing at byte {}",
+                fill, read_offset, write_offset
+            ),
+            Event::Rewind {
+                read_offset,
+                write_offset,
+            } => write!(
+                f,
+                "rewind to reading at byte {} and writing at byte {}",
+                read_offset, write_offset
+            ),
+            Event::TakeStart(text) => write!(f, "take start {:?}", text),
+            Event::TakeRemainder(text) => write!(f, "take remainder {:?}", text),
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl ::defmt::Format for Event<'_> {
+    fn format(&self, f: ::defmt::Formatter<'_>) {
+        match *self {
+            Event::Read { offset, text } => {
+                ::defmt::write!(f, "read {=str:?} at byte {=usize}", text, offset)
+            }
+            Event::Write { offset, text } => {
+                ::defmt::write!(f, "write {=str:?} at byte {=usize}", text, offset)
+            }
+            Event::Synchronise {
+                fill,
+                read_offset,
+                write_offset,
+            } => ::defmt::write!(
+                f,
+                "synchronise with {}, reading at byte {=usize} and writing at byte {=usize}",
+                ::defmt::Debug2Format(&fill),
+                read_offset,
+                write_offset
+            ),
+            Event::Rewind {
+                read_offset,
+                write_offset,
+            } => ::defmt::write!(
+                f,
+                "rewind to reading at byte {=usize} and writing at byte {=usize}",
+                read_offset,
+                write_offset
+            ),
+            Event::TakeStart(text) => ::defmt::write!(f, "take start {=str:?}", text),
+            Event::TakeRemainder(text) => ::defmt::write!(f, "take remainder {=str:?}", text),
+        }
+    }
+}
+
+/// An [`Observer`] which calls a function with each [`Event`]
+///
+/// The offsets in each event are the same as [`Replacinator::read_offset`] and
+/// [`Replacinator::write_offset`] of the traced [`Replacinator`].
+#[derive(Debug, Clone)]
+pub struct Tracer<F> {
+    trace: F,
+    read_offset: usize,
+    write_offset: usize,
+    /// The number of bytes the remainder has been moved towards the start by compaction
+    compacted: usize,
+}
+
+impl<F: FnMut(&Event<'_>)> Tracer<F> {
+    /// Create a tracer which calls `trace` with each event
+    pub fn new(trace: F) -> Self {
+        Self {
+            trace,
+            read_offset: 0,
+            write_offset: 0,
+            compacted: 0,
+        }
+    }
+
+    /// Take back the function called with each event
+    pub fn into_inner(self) -> F {
+        self.trace
+    }
+
+    /// Move the write offset to the read offset, as when the second section is filled
+    fn fill(&mut self, fill: FillStrategy) {
+        if fill == FillStrategy::Compact {
+            self.compacted = self.read_offset - self.write_offset;
+        } else {
+            self.write_offset = self.read_offset - self.compacted;
+        }
+    }
+}
+
+impl<F: FnMut(&Event<'_>)> Observer for Tracer<F> {
+    fn read(&mut self, text: &str) {
+        (self.trace)(&Event::Read {
+            offset: self.read_offset,
+            text,
+        });
+        self.read_offset += text.len();
+    }
+
+    fn write(&mut self, text: &str) {
+        (self.trace)(&Event::Write {
+            offset: self.write_offset,
+            text,
+        });
+        self.write_offset += text.len();
+    }
+
+    fn take_start(&mut self, text: &str) {
+        self.fill(if cfg!(feature = "compact_on_take") {
+            FillStrategy::Compact
+        } else {
+            FillStrategy::DEFAULT
+        });
+        (self.trace)(&Event::TakeStart(text));
+    }
+
+    fn take_remainder(&mut self, text: &str) {
+        (self.trace)(&Event::TakeRemainder(text));
+    }
+
+    fn synchronise(&mut self, fill: FillStrategy) {
+        self.fill(fill);
+        (self.trace)(&Event::Synchronise {
+            fill,
+            read_offset: self.read_offset,
+            write_offset: self.write_offset,
+        });
+    }
+
+    fn rewind(&mut self, read_offset: usize, write_offset: usize) {
+        self.read_offset = read_offset;
+        self.write_offset = write_offset;
+        (self.trace)(&Event::Rewind {
+            read_offset,
+            write_offset,
+        });
+    }
+}
+
+impl<'a, F: FnMut(&Event<'_>)> Replacinator<'a, Tracer<F>> {
+    /// Create a new [`Replacinator`] which calls `trace` with each operation on it, and operate
+    /// on it within the given function
+    ///
+    /// This is [`Replacinator::new_in_observed`] with a [`Tracer`].
+    pub fn with_tracer<R>(
+        value: impl AsMutStr<'a>,
+        trace: F,
+        with: impl FnMut(&mut Replacinator<'a, Tracer<F>>) -> R,
+    ) -> R {
+        Replacinator::new_in_observed(value, Tracer::new(trace), with)
+    }
+}
+
+/// Print `event` to standard error, for use as the callback of a [`Tracer`]
+///
+/// This requires the `std` feature.
+#[cfg(feature = "std")]
+pub fn eprint(event: &Event<'_>) {
+    std::eprintln!("replacinator: {}", event);
+}
+
+/// Log `event` at the trace level with the `log` crate, for use as the callback of a [`Tracer`]
+///
+/// The target is `replacinator`. This requires the `log` feature.
+#[cfg(feature = "log")]
+pub fn log(event: &Event<'_>) {
+    ::log::trace!(target: "replacinator", "{}", event);
+}
+
+/// Log `event` at the trace level with `defmt`, for use as the callback of a [`Tracer`]
+///
+/// This requires the `defmt` feature, which also implements `defmt::Format` for [`Event`].
+#[cfg(feature = "defmt")]
+pub fn defmt(event: &Event<'_>) {
+    ::defmt::trace!("replacinator: {}", event);
+}
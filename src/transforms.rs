@@ -0,0 +1,1187 @@
+//! General purpose transforms of the remainder of a [`Replacinator`]
+
+use crate::{latin, Observer, Pattern, ReplaceError, ReplaceErrorKind, Replacinator};
+
+/// A summary of the text processed by a transform
+///
+/// The lengths allow a caller to find how much of the string is meaningful afterwards, such as
+/// to truncate a `String` which the transform was applied to from the start.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TransformReport {
+    read: usize,
+    written: usize,
+    replacements: usize,
+}
+
+impl TransformReport {
+    /// Summarise what `src` has read and written since its offsets were `read_offset` and
+    /// `write_offset`
+    pub(crate) fn since<O: Observer>(
+        src: &Replacinator<'_, O>,
+        read_offset: usize,
+        write_offset: usize,
+        replacements: usize,
+    ) -> Self {
+        Self {
+            read: src.read_offset() - read_offset,
+            written: src.write_offset() - write_offset,
+            replacements,
+        }
+    }
+
+    /// The number of bytes read from the remainder
+    pub fn read_len(&self) -> usize {
+        self.read
+    }
+
+    /// The number of bytes written to the first section
+    pub fn written_len(&self) -> usize {
+        self.written
+    }
+
+    /// The number of replacements made
+    pub fn replacements(&self) -> usize {
+        self.replacements
+    }
+}
+
+/// Which characters are treated as whitespace
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Whitespace {
+    /// Only ASCII whitespace, as defined by [`char::is_ascii_whitespace`]
+    Ascii,
+    /// All Unicode whitespace, as defined by [`char::is_whitespace`]
+    Unicode,
+}
+
+impl Whitespace {
+    /// Whether `c` is whitespace under this definition
+    pub fn matches(self, c: char) -> bool {
+        match self {
+            Whitespace::Ascii => c.is_ascii_whitespace(),
+            Whitespace::Unicode => c.is_whitespace(),
+        }
+    }
+}
+
+/// Replace each run of whitespace in the remainder of `src` with a single space, and remove
+/// whitespace from both ends
+///
+/// Returns the collapsed text. Like [`Replacinator::take_start`], the first section of `src`
+/// is no longer available afterwards.
+pub fn collapse_whitespace<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+    whitespace: Whitespace,
+) -> &'a mut str {
+    let mark = src.mark();
+    let mut first = true;
+    loop {
+        match whitespace {
+            Whitespace::Ascii => src.discard_ascii_whitespace(),
+            Whitespace::Unicode => src.discard_while(char::is_whitespace),
+        };
+        if src.peek().is_none() {
+            break;
+        }
+        if !first {
+            src.write_char(' ');
+        }
+        first = false;
+        src.skip_while(|c| !whitespace.matches(c));
+    }
+    src.take_marked(mark)
+        .expect("The mark was made from this Replacinator")
+}
+
+/// Collapse each run of repeated matches of `pattern` in the remainder of `src` into a single
+/// match, as `tr -s` does
+///
+/// For example, squeezing `[' ', '-']` turns `"a  b--c -d"` into `"a b-c -d"`. A run is of the
+/// same text repeated, so the different characters in `" -"` are both kept.
+///
+/// Returns the squeezed text. Like [`Replacinator::take_start`], the first section of `src` is
+/// no longer available afterwards.
+pub fn squeeze<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+    mut pattern: impl Pattern,
+) -> &'a mut str {
+    let mark = src.mark();
+    while let Some(found) = pattern.find_in(src.remainder()) {
+        if found.is_empty() {
+            match src.remainder()[found.end..].chars().next() {
+                Some(c) => src.skip_bytes(found.end + c.len_utf8()),
+                None => break,
+            }
+            continue;
+        }
+        src.skip_bytes(found.end);
+        loop {
+            let start = src.start();
+            let matched = &start[start.len() - found.len()..];
+            if !src.remainder().starts_with(matched) {
+                break;
+            }
+            src.discard_bytes(found.len());
+        }
+    }
+    src.skip_bytes(src.remainder().len());
+    src.take_marked(mark)
+        .expect("The mark was made from this Replacinator")
+}
+
+/// Which ends of the text [`trim_in_place`] removes whitespace from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trim {
+    /// Only the start
+    Start,
+    /// Only the end
+    End,
+    /// Both the start and the end
+    Both,
+}
+
+/// Remove whitespace from the start, end or both ends of the remainder of `src`
+///
+/// The trimmed text is moved to the write position, so for a [`Replacinator`] which has written
+/// nothing, it starts at the start of the string.
+///
+/// Returns the trimmed text. Like [`Replacinator::take_start`], the first section of `src` is no
+/// longer available afterwards.
+pub fn trim_in_place<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+    ends: Trim,
+    whitespace: Whitespace,
+) -> &'a mut str {
+    let mark = src.mark();
+    if ends != Trim::End {
+        src.discard_while(|c| whitespace.matches(c));
+    }
+    let len = match ends {
+        Trim::Start => src.remainder().len(),
+        Trim::End | Trim::Both => src
+            .remainder()
+            .trim_end_matches(|c| whitespace.matches(c))
+            .len(),
+    };
+    src.skip_bytes(len);
+    src.discard_bytes(src.remainder().len());
+    src.take_marked(mark)
+        .expect("The mark was made from this Replacinator")
+}
+
+/// Where [`pad_to`] places text within the padding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    /// At the start, with the padding after it
+    Left,
+    /// At the end, with the padding before it
+    Right,
+    /// In the middle, with any odd character of padding after it
+    Center,
+}
+
+/// Pad the remainder of `src` with `fill` until it is `width` characters long
+///
+/// Padding is written into the space before the remainder, so there is only room for as much of
+/// it as has been freed, such as by discarding whitespace from the start, or by an earlier
+/// transform which shrank the text. Text which is already at least `width` characters long is
+/// left unchanged.
+///
+/// Returns the padded text, and the number of `fill` characters which didn't fit. Like
+/// [`Replacinator::take_start`], the first section of `src` is no longer available afterwards.
+pub fn pad_to<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+    width: usize,
+    align: Align,
+    fill: char,
+) -> (&'a mut str, usize) {
+    let mark = src.mark();
+    let wanted = width.saturating_sub(src.remainder().chars().count());
+    let room = src.gap_len() / fill.len_utf8();
+    let padding = wanted.min(room);
+    let before = match align {
+        Align::Left => 0,
+        Align::Right => padding,
+        Align::Center => padding / 2,
+    };
+    (0..before).for_each(|_| src.write_char(fill));
+    src.skip_bytes(src.remainder().len());
+    (before..padding).for_each(|_| src.write_char(fill));
+    let text = src
+        .take_marked(mark)
+        .expect("The mark was made from this Replacinator");
+    (text, wanted - padding)
+}
+
+/// Convert each `\r\n` and lone `\r` in the remainder of `src` to `\n`
+///
+/// Text between carriage returns is moved in bulk, so text which contains none is not copied.
+///
+/// Returns the converted text. Like [`Replacinator::take_start`], the first section of `src`
+/// is no longer available afterwards.
+pub fn normalize_newlines<'a, O: Observer>(src: &mut Replacinator<'a, O>) -> &'a mut str {
+    let mark = src.mark();
+    loop {
+        let len = src.remainder().find('\r');
+        src.skip_bytes(len.unwrap_or(src.remainder().len()));
+        if len.is_none() {
+            break;
+        }
+        src.discard_char();
+        if src.peek() != Some('\n') {
+            src.write_char('\n');
+        }
+    }
+    src.take_marked(mark)
+        .expect("The mark was made from this Replacinator")
+}
+
+/// Tidy the spacing around punctuation in the remainder of `src`, as a copy-editor would
+///
+/// Within each line, runs of spaces and tabs are collapsed to a single space, and removed before
+/// `,`, `.`, `;`, `:`, `!` and `?` and at the end of the line. Indentation is left unchanged.
+/// A space is added between `,`, `;`, `:`, `!` or `?` and a letter which directly follows it.
+/// `.` is excluded from this, as it often appears within abbreviations and domain names.
+///
+/// Adding a space grows the text, so is only possible once enough whitespace has been removed
+/// before it. Returns the tidied text, and the number of spaces which could not be added. Like
+/// [`Replacinator::take_start`], the first section of `src` is no longer available afterwards.
+pub fn fix_punctuation_spacing<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+) -> (&'a mut str, usize) {
+    let is_space = |c| c == ' ' || c == '\t';
+    let is_punctuation = |c| matches!(c, ',' | '.' | ';' | ':' | '!' | '?');
+    let mark = src.mark();
+    let mut missed = 0;
+    let mut line_start = true;
+    loop {
+        if line_start {
+            src.skip_while(is_space);
+        } else if src.discard_while(is_space) > 0 {
+            match src.peek() {
+                None | Some('\n' | '\r') => {}
+                Some(c) if is_punctuation(c) => {}
+                Some(_) => src.write_char(' '),
+            }
+        }
+        let c = match src.skip_char() {
+            Some(c) => c,
+            None => break,
+        };
+        line_start = c == '\n' || c == '\r';
+        if is_punctuation(c) && c != '.' && src.peek().is_some_and(char::is_alphabetic) {
+            if src.gap_len() > 0 {
+                src.write_char(' ');
+            } else {
+                missed += 1;
+            }
+        }
+    }
+    let text = src
+        .take_marked(mark)
+        .expect("The mark was made from this Replacinator");
+    (text, missed)
+}
+
+/// Replace typographic punctuation in the remainder of `src` with ASCII equivalents
+///
+/// Curly and low quotes become `'` and `"`, prime marks become `'` and `"`, en dashes and
+/// figure dashes become `-`, em dashes and horizontal bars become `--`, and ellipses become
+/// `...`. Each replacement is no longer than the character it replaces, and text between
+/// replacements is moved in bulk.
+///
+/// Returns the converted text. Like [`Replacinator::take_start`], the first section of `src`
+/// is no longer available afterwards.
+pub fn downgrade_punctuation<'a, O: Observer>(src: &mut Replacinator<'a, O>) -> &'a mut str {
+    replace_chars(src, ascii_punctuation)
+}
+
+/// The ASCII equivalent of `c`, if it is typographic punctuation
+fn ascii_punctuation(c: char) -> Option<&'static str> {
+    Some(match c {
+        '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2032}' => "'",
+        '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{2033}' => "\"",
+        '\u{2012}' | '\u{2013}' => "-",
+        '\u{2014}' | '\u{2015}' => "--",
+        '\u{2026}' => "...",
+        _ => return None,
+    })
+}
+
+/// Expand the Latin ligatures in the Alphabetic Presentation Forms block in the remainder of
+/// `src` to the letters they are made of
+///
+/// For example, `ﬁ` becomes `fi`, and `ﬄ` becomes `ffl`. These often appear in text extracted
+/// from PDFs, where they prevent searching for the words which contain them. Each ligature is
+/// three bytes long, so is never shorter than its expansion.
+///
+/// Returns the expanded text. Like [`Replacinator::take_start`], the first section of `src`
+/// is no longer available afterwards.
+pub fn expand_ligatures<'a, O: Observer>(src: &mut Replacinator<'a, O>) -> &'a mut str {
+    replace_chars(src, ligature_letters)
+}
+
+/// The letters which make up `c`, if it is a Latin ligature
+fn ligature_letters(c: char) -> Option<&'static str> {
+    Some(match c {
+        '\u{FB00}' => "ff",
+        '\u{FB01}' => "fi",
+        '\u{FB02}' => "fl",
+        '\u{FB03}' => "ffi",
+        '\u{FB04}' => "ffl",
+        '\u{FB05}' => "\u{17F}t",
+        '\u{FB06}' => "st",
+        _ => return None,
+    })
+}
+
+/// Remove soft hyphens and other invisible formatting characters from the remainder of `src`
+///
+/// These are U+00AD SOFT HYPHEN, U+034F COMBINING GRAPHEME JOINER, U+180E MONGOLIAN VOWEL
+/// SEPARATOR, U+200B ZERO WIDTH SPACE, U+2060 WORD JOINER, the invisible mathematical operators
+/// U+2061 to U+2064, and U+FEFF ZERO WIDTH NO-BREAK SPACE. They are often left in text copied from
+/// typeset documents, where they break searching and comparison. The zero width joiner and
+/// non-joiner are kept, as they change how emoji and some scripts are displayed.
+///
+/// Returns the cleaned text. Like [`Replacinator::take_start`], the first section of `src`
+/// is no longer available afterwards.
+pub fn strip_invisible<'a, O: Observer>(src: &mut Replacinator<'a, O>) -> &'a mut str {
+    replace_chars(src, invisible)
+}
+
+/// The empty string, if `c` is an invisible formatting character removed by [`strip_invisible`]
+fn invisible(c: char) -> Option<&'static str> {
+    match c {
+        '\u{AD}' | '\u{34F}' | '\u{180E}' | '\u{200B}' | '\u{2060}'..='\u{2064}' | '\u{FEFF}' => {
+            Some("")
+        }
+        _ => None,
+    }
+}
+
+/// Combine each ASCII letter in the remainder of `src` which is followed by a combining mark into
+/// the precomposed letter, where one exists in the Latin-1 Supplement or Latin Extended-A blocks
+///
+/// For example, `e` followed by U+0301 COMBINING ACUTE ACCENT becomes `é`. Each composed letter
+/// is shorter than the letter and mark it replaces.
+///
+/// This is only the part of NFC normalisation which matters most for Latin text, not the whole
+/// of it. These are left unchanged, although NFC would change them:
+///
+/// - Letters whose precomposed form is outside these blocks, such as those of Latin Extended
+///   Additional (`ẹ`), Greek, Cyrillic and Hangul
+/// - A mark which follows a letter which is already precomposed, or another mark
+/// - Marks which are not in canonical order, as they are not reordered
+/// - Singletons such as U+212B ANGSTROM SIGN, which NFC replaces with `Å`
+///
+/// This requires the `latin_composition` feature. With the `unicode-normalization` feature,
+/// [`normalize_nfc_in_place`] normalises all of these.
+///
+/// Returns the composed text. Like [`Replacinator::take_start`], the first section of `src`
+/// is no longer available afterwards.
+#[cfg(feature = "latin_composition")]
+pub fn compose_latin<'a, O: Observer>(src: &mut Replacinator<'a, O>) -> &'a mut str {
+    let mark = src.mark();
+    while let Some(index) = src.remainder().find(latin::is_combining_mark) {
+        let remainder = src.remainder();
+        let combining = remainder[index..].chars().next();
+        let base = remainder[..index].chars().next_back();
+        match base
+            .zip(combining)
+            .and_then(|(base, c)| latin::compose(base, c))
+        {
+            Some(composed) => {
+                // The base letter is ASCII, so is one byte long
+                src.skip_bytes(index - 1);
+                src.discard_n(2);
+                src.write_char(composed);
+            }
+            None => src.skip_bytes(index + combining.map_or(0, char::len_utf8)),
+        }
+    }
+    src.skip_bytes(src.remainder().len());
+    src.take_marked(mark)
+        .expect("The mark was made from this Replacinator")
+}
+
+/// The error returned by [`normalize_nfc_in_place`] when normalising some of the text to NFC
+/// would have made it longer
+///
+/// This requires the `unicode-normalization` feature.
+#[cfg(feature = "unicode-normalization")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NfcError {
+    positions: alloc::vec::Vec<crate::Position>,
+}
+
+#[cfg(feature = "unicode-normalization")]
+impl NfcError {
+    /// The positions in the original string of the start of each run of text which was left
+    /// unchanged, in order
+    ///
+    /// Each run starts with a character which can't combine with the text before it, and ends
+    /// directly before the next such character.
+    pub fn positions(&self) -> &[crate::Position] {
+        &self.positions
+    }
+}
+
+#[cfg(feature = "unicode-normalization")]
+impl core::fmt::Display for NfcError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "normalising to NFC would lengthen {} runs of text, the first at byte {}",
+            self.positions.len(),
+            self.positions[0].byte()
+        )
+    }
+}
+
+/// Normalise the remainder of `src` to Unicode Normalization Form C, except where that would
+/// make it longer
+///
+/// Composition usually makes text shorter or leaves it the same length, such as `e` followed by
+/// U+0301 COMBINING ACUTE ACCENT becoming `é`. However, some characters are excluded from
+/// composition, so NFC replaces them with their longer decomposition, such as U+0958 DEVANAGARI
+/// LETTER QA. The text is normalised in runs, each of which is separately normalised, and a run
+/// whose normalised form is longer is left unchanged. The remainder is normalised on its own, so
+/// characters which have already been read are not composed with it.
+///
+/// Returns the normalised text if every run was normalised. Otherwise, the error lists where
+/// each run which was left unchanged starts, so that the caller can normalise those runs into a
+/// new allocation. The rest of the text has still been normalised, and is left in the first
+/// section of `src`. Like [`Replacinator::take_start`], the first section of `src` is no longer
+/// available after a success.
+///
+/// This requires the `unicode-normalization` feature.
+#[cfg(feature = "unicode-normalization")]
+pub fn normalize_nfc_in_place<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+) -> Result<&'a mut str, NfcError> {
+    use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+    let mark = src.mark();
+    let mut normalized = alloc::string::String::new();
+    let mut positions = alloc::vec::Vec::new();
+    loop {
+        let remainder = src.remainder();
+        // Only the last ASCII character before other text can compose with what follows it
+        let ascii = remainder.bytes().take_while(u8::is_ascii).count();
+        if ascii == remainder.len() {
+            src.skip_bytes(ascii);
+            break;
+        }
+        src.skip_bytes(ascii.saturating_sub(1));
+        let remainder = src.remainder();
+        let end = remainder
+            .char_indices()
+            .skip(1)
+            .find(|&(_, c)| starts_nfc_run(c))
+            .map_or(remainder.len(), |(index, _)| index);
+        let run = &remainder[..end];
+        if is_nfc_quick(run.chars()) == IsNormalized::Yes {
+            src.skip_bytes(end);
+            continue;
+        }
+        normalized.clear();
+        normalized.extend(run.nfc());
+        if normalized.len() > end {
+            positions.push(src.position());
+            src.skip_bytes(end);
+        } else if normalized == run {
+            src.skip_bytes(end);
+        } else {
+            src.discard_bytes(end);
+            src.write_str(&normalized);
+        }
+    }
+    if positions.is_empty() {
+        Ok(src
+            .take_marked(mark)
+            .expect("The mark was made from this Replacinator"))
+    } else {
+        Err(NfcError { positions })
+    }
+}
+
+/// Whether the NFC normalisation of text is unchanged by splitting it before `c`
+///
+/// This is the case if `c` is a starter which can't compose with the character before it.
+#[cfg(feature = "unicode-normalization")]
+fn starts_nfc_run(c: char) -> bool {
+    use unicode_normalization::{char::canonical_combining_class, is_nfc_quick, IsNormalized};
+
+    canonical_combining_class(c) == 0 && is_nfc_quick(core::iter::once(c)) == IsNormalized::Yes
+}
+
+/// Replace each accented Latin letter in the remainder of `src` with its ASCII base letter, and
+/// remove combining marks
+///
+/// For example, `é` becomes `e`, `ñ` becomes `n`, `ß` becomes `ss` and `æ` becomes `ae`. The
+/// letters replaced are those of the Latin-1 Supplement and Latin Extended-A blocks which have
+/// an ASCII spelling no longer than themselves, so the text never grows. Other non-ASCII
+/// characters are left unchanged, and `unmapped` is called with each of them and its position in
+/// the original string. This requires the `deunicode` feature.
+///
+/// Returns the replaced text. Like [`Replacinator::take_start`], the first section of `src` is
+/// no longer available afterwards.
+#[cfg(feature = "deunicode")]
+pub fn deunicode_in_place<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+    mut unmapped: impl FnMut(char, crate::Position),
+) -> &'a mut str {
+    let mark = src.mark();
+    loop {
+        src.skip_while(|c| c.is_ascii());
+        let c = match src.peek() {
+            Some(c) => c,
+            None => break,
+        };
+        if latin::is_combining_mark(c) {
+            src.discard_char();
+        } else if let Some(base) = latin::ascii_base(c) {
+            src.discard_char();
+            src.write_str(base);
+        } else {
+            unmapped(c, src.position());
+            src.skip_char();
+        }
+    }
+    src.take_marked(mark)
+        .expect("The mark was made from this Replacinator")
+}
+
+/// Turn the remainder of `src` into a slug, for use in a URL or as an identifier
+///
+/// ASCII letters are lowercased, and accented Latin letters are replaced with their lowercase
+/// ASCII base letters. Runs of whitespace and ASCII
+/// punctuation become a single `-`, with none left at the start or end, and any other
+/// characters are removed. For example, `"  Crème Brûlée: A Recipe!"` becomes
+/// `"creme-brulee-a-recipe"`. Each step shortens the text or leaves it the same length.
+///
+/// Returns the slug. Like [`Replacinator::take_start`], the first section of `src` is no longer
+/// available afterwards. [`text::slugify_in_place`](crate::text::slugify_in_place) applies this
+/// to a whole string.
+pub fn slugify_in_place<'a, O: Observer>(src: &mut Replacinator<'a, O>) -> &'a mut str {
+    let mark = src.mark();
+    let mut separated = false;
+    let mut started = false;
+    while let Some(c) = src.peek() {
+        if !separated && (c.is_ascii_lowercase() || c.is_ascii_digit()) {
+            src.skip_char();
+            started = true;
+            continue;
+        }
+        let mut buf = [0; 4];
+        let letters = if c.is_ascii_alphanumeric() {
+            Some(&*c.encode_utf8(&mut buf))
+        } else {
+            latin::ascii_base(c)
+        };
+        src.discard_char();
+        match letters {
+            Some(letters) => {
+                if separated && started {
+                    src.write_char('-');
+                }
+                for letter in letters.chars() {
+                    src.write_char(letter.to_ascii_lowercase());
+                }
+                separated = false;
+                started = true;
+            }
+            None => separated |= c.is_ascii() || c.is_whitespace(),
+        }
+    }
+    src.take_marked(mark)
+        .expect("The mark was made from this Replacinator")
+}
+
+/// Shorten the remainder of `src` to at most `max_bytes`, ending it with `…` if it was shortened
+///
+/// The text is cut at the last character boundary which leaves room for the `…`, or with the
+/// `unicode-segmentation` feature, the last extended grapheme cluster boundary. If `max_bytes` is
+/// too small for the `…`, the text is cut without it. Applying [`downgrade_punctuation`] to the
+/// result replaces the `…` with `...`, which is the same length.
+///
+/// Returns the shortened text, and the number of bytes which were cut from the remainder. The
+/// cut text is filled with spaces. Like [`Replacinator::take_start`], the first section of `src`
+/// is no longer available afterwards.
+pub fn truncate_with_ellipsis<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+    max_bytes: usize,
+) -> (&'a mut str, usize) {
+    let mark = src.mark();
+    let len = src.remainder().len();
+    let (kept, ellipsis) = if len <= max_bytes {
+        (len, "")
+    } else {
+        let ellipsis = if max_bytes >= '…'.len_utf8() {
+            "…"
+        } else {
+            ""
+        };
+        (
+            boundary_before(src.remainder(), max_bytes - ellipsis.len()),
+            ellipsis,
+        )
+    };
+    src.skip_bytes(kept);
+    src.discard_bytes(len - kept);
+    src.write_str(ellipsis);
+    let text = src
+        .take_marked(mark)
+        .expect("The mark was made from this Replacinator");
+    (text, len - kept)
+}
+
+/// The last boundary in `text` at or before `index` at which it can be cut
+fn boundary_before(text: &str, index: usize) -> usize {
+    #[cfg(feature = "unicode-segmentation")]
+    {
+        use unicode_segmentation::UnicodeSegmentation;
+        text.grapheme_indices(true)
+            .map(|(start, grapheme)| start + grapheme.len())
+            .take_while(|&end| end <= index)
+            .last()
+            .unwrap_or(0)
+    }
+    #[cfg(not(feature = "unicode-segmentation"))]
+    {
+        (0..=index)
+            .rev()
+            .find(|&i| text.is_char_boundary(i))
+            .unwrap_or(0)
+    }
+}
+
+/// Repair text in the remainder of `src` which was encoded as UTF-8, wrongly decoded as
+/// Windows-1252, then encoded as UTF-8 again
+///
+/// This is a common artifact in scraped content, where for example `’` appears as `â€™`. Each run
+/// of characters which Windows-1252 encodes as a valid UTF-8 sequence is replaced with the
+/// character that sequence encodes. The bytes which Windows-1252 leaves undefined are taken to
+/// have been decoded as the C1 control characters with the same values, as many decoders do.
+///
+/// Each byte of the original character became a character of at least two bytes, so the
+/// repaired text is always shorter. Text which happens to look like mojibake, such as `Ã©`, is
+/// also converted.
+///
+/// Returns the repaired text. Like [`Replacinator::take_start`], the first section of `src`
+/// is no longer available afterwards.
+pub fn repair_mojibake<'a, O: Observer>(src: &mut Replacinator<'a, O>) -> &'a mut str {
+    let mark = src.mark();
+    loop {
+        src.skip_while(|c| !matches!(windows_1252(c), Some(0xC2..=0xF4)));
+        if src.peek().is_none() {
+            break;
+        }
+        match mojibake(src.remainder()) {
+            Some((chars, _, c)) => {
+                src.discard_n(chars);
+                src.write_char(c);
+            }
+            None => {
+                src.skip_char();
+            }
+        }
+    }
+    src.take_marked(mark)
+        .expect("The mark was made from this Replacinator")
+}
+
+/// The mojibake sequence at the start of `text`, as repaired by [`repair_mojibake`]
+///
+/// Returns the number of characters in the sequence, its length in bytes, and the character it
+/// repairs to.
+pub(crate) fn mojibake(text: &str) -> Option<(usize, usize, char)> {
+    let mut chars = text.chars();
+    let first = chars.next()?;
+    let lead = windows_1252(first)?;
+    let width = match lead {
+        0xC2..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF4 => 4,
+        _ => return None,
+    };
+    let mut bytes = [lead, 0, 0, 0];
+    let mut len = first.len_utf8();
+    for (i, c) in chars.take(width - 1).enumerate() {
+        match windows_1252(c) {
+            Some(b @ 0x80..=0xBF) => bytes[i + 1] = b,
+            _ => return None,
+        }
+        len += c.len_utf8();
+    }
+    let repaired = core::str::from_utf8(&bytes[..width]).ok()?.chars().next()?;
+    Some((width, len, repaired))
+}
+
+/// The byte which encodes `c` in Windows-1252, if it is not ASCII
+///
+/// The undefined bytes are treated as the C1 control characters with the same values.
+fn windows_1252(c: char) -> Option<u8> {
+    Some(match c {
+        '\u{80}'..='\u{FF}' => c as u8,
+        '\u{20AC}' => 0x80,
+        '\u{201A}' => 0x82,
+        '\u{192}' => 0x83,
+        '\u{201E}' => 0x84,
+        '\u{2026}' => 0x85,
+        '\u{2020}' => 0x86,
+        '\u{2021}' => 0x87,
+        '\u{2C6}' => 0x88,
+        '\u{2030}' => 0x89,
+        '\u{160}' => 0x8A,
+        '\u{2039}' => 0x8B,
+        '\u{152}' => 0x8C,
+        '\u{17D}' => 0x8E,
+        '\u{2018}' => 0x91,
+        '\u{2019}' => 0x92,
+        '\u{201C}' => 0x93,
+        '\u{201D}' => 0x94,
+        '\u{2022}' => 0x95,
+        '\u{2013}' => 0x96,
+        '\u{2014}' => 0x97,
+        '\u{2DC}' => 0x98,
+        '\u{2122}' => 0x99,
+        '\u{161}' => 0x9A,
+        '\u{203A}' => 0x9B,
+        '\u{153}' => 0x9C,
+        '\u{17E}' => 0x9E,
+        '\u{178}' => 0x9F,
+        _ => return None,
+    })
+}
+
+/// Replace each non-ASCII character of the remainder of `src` for which `f` returns a
+/// replacement, which must be no longer than the character
+fn replace_chars<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+    f: fn(char) -> Option<&'static str>,
+) -> &'a mut str {
+    let mark = src.mark();
+    loop {
+        src.skip_while(|c| c.is_ascii() || f(c).is_none());
+        let replacement = match src.discard_char() {
+            Some(c) => f(c).expect("Only characters with replacements are left unskipped"),
+            None => break,
+        };
+        src.write_str(replacement);
+    }
+    src.take_marked(mark)
+        .expect("The mark was made from this Replacinator")
+}
+
+/// A transform which can be fused with others by [`pipeline`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Pass {
+    /// As [`normalize_newlines`]
+    NormalizeNewlines,
+    /// As [`collapse_whitespace`]
+    CollapseWhitespace(Whitespace),
+    /// As [`downgrade_punctuation`]
+    DowngradePunctuation,
+    /// As [`expand_ligatures`]
+    ExpandLigatures,
+    /// As [`strip_invisible`]
+    StripInvisible,
+}
+
+/// The state of a [`Pass`] between characters
+#[derive(Debug, Default, Clone, Copy)]
+struct PassState {
+    /// Whether a character other than whitespace has been output by [`Pass::CollapseWhitespace`]
+    started: bool,
+    /// Whether [`Pass::CollapseWhitespace`] has seen whitespace it hasn't yet output, or
+    /// [`Pass::NormalizeNewlines`] has just seen a `\r`
+    pending: bool,
+}
+
+impl Pass {
+    /// Whether `c` is output unchanged by this pass in `state`, without changing `state`
+    fn is_inert(self, state: PassState, c: char) -> bool {
+        match self {
+            Pass::NormalizeNewlines => !state.pending && c != '\r',
+            Pass::CollapseWhitespace(whitespace) => {
+                state.started && !state.pending && !whitespace.matches(c)
+            }
+            Pass::DowngradePunctuation => c.is_ascii() || ascii_punctuation(c).is_none(),
+            Pass::ExpandLigatures => c.is_ascii() || ligature_letters(c).is_none(),
+            Pass::StripInvisible => c.is_ascii() || invisible(c).is_none(),
+        }
+    }
+
+    /// Pass the output of this pass for `c` to `emit`
+    fn apply(self, state: &mut PassState, c: char, emit: &mut dyn FnMut(char)) {
+        let f = match self {
+            Pass::NormalizeNewlines => {
+                let after_cr = core::mem::replace(&mut state.pending, c == '\r');
+                match c {
+                    '\r' => emit('\n'),
+                    '\n' if after_cr => {}
+                    c => emit(c),
+                }
+                return;
+            }
+            Pass::CollapseWhitespace(whitespace) => {
+                if whitespace.matches(c) {
+                    state.pending = state.started;
+                } else {
+                    if core::mem::take(&mut state.pending) {
+                        emit(' ');
+                    }
+                    state.started = true;
+                    emit(c);
+                }
+                return;
+            }
+            Pass::DowngradePunctuation => ascii_punctuation,
+            Pass::ExpandLigatures => ligature_letters,
+            Pass::StripInvisible => invisible,
+        };
+        match f(c) {
+            Some(replacement) => replacement.chars().for_each(emit),
+            None => emit(c),
+        }
+    }
+}
+
+/// The most passes which can be given to [`pipeline`]
+pub const MAX_PASSES: usize = 16;
+
+/// The error returned by [`pipeline`] when given more than [`MAX_PASSES`] passes
+///
+/// This is found before anything is read, so the remainder is left unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TooManyPasses {
+    passes: usize,
+}
+
+impl TooManyPasses {
+    /// The number of passes which were given
+    pub fn passes(&self) -> usize {
+        self.passes
+    }
+}
+
+impl core::fmt::Display for TooManyPasses {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "a pipeline can have at most {} passes, but {} were given",
+            MAX_PASSES, self.passes
+        )
+    }
+}
+
+/// Apply each of `passes` in order to the remainder of `src`, reading it only once
+///
+/// The result is the same as applying the transform for each pass in turn, but each character
+/// is read once and fed through every pass before the next is read. Runs of characters which no
+/// pass would change are moved in bulk. Each pass is no longer than its input, so neither is
+/// the result.
+///
+/// Returns the transformed text. Like [`Replacinator::take_start`], the first section of `src`
+/// is no longer available afterwards.
+///
+/// # Errors
+/// If there are more than [`MAX_PASSES`] passes, before anything is read
+pub fn pipeline<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+    passes: &[Pass],
+) -> Result<&'a mut str, TooManyPasses> {
+    if passes.len() > MAX_PASSES {
+        return Err(TooManyPasses {
+            passes: passes.len(),
+        });
+    }
+    let mut states = [PassState::default(); MAX_PASSES];
+    let states = &mut states[..passes.len()];
+    let mark = src.mark();
+    loop {
+        src.skip_while(|c| {
+            passes
+                .iter()
+                .zip(states.iter())
+                .all(|(pass, &state)| pass.is_inert(state, c))
+        });
+        match src.read_char() {
+            Some(c) => feed(src, passes, states, c),
+            None => break,
+        }
+    }
+    Ok(src
+        .take_marked(mark)
+        .expect("The mark was made from this Replacinator"))
+}
+
+/// Feed `c` through `passes`, writing what comes out of the last one
+fn feed<O: Observer>(
+    src: &mut Replacinator<'_, O>,
+    passes: &[Pass],
+    states: &mut [PassState],
+    c: char,
+) {
+    match (passes.split_first(), states.split_first_mut()) {
+        (Some((pass, passes)), Some((state, states))) => {
+            pass.apply(state, c, &mut |c| feed(src, passes, states, c))
+        }
+        _ => src.write_char(c),
+    }
+}
+
+/// Replace every occurrence of each `(pattern, replacement)` pair in the remainder of `src`,
+/// reporting the number of occurrences replaced
+///
+/// The remainder is searched from left to right. Where several patterns match at the same
+/// position, the longest is replaced, and earlier pairs are preferred between patterns of the
+/// same length. Like [`Replacinator::replace_literal`], the whole remainder is read and written
+/// back.
+///
+/// With the `aho-corasick` feature, the patterns are built into an Aho-Corasick automaton, so the
+/// remainder is searched in a single pass. Otherwise, every pattern is compared at each position
+/// where one could start, so this takes time proportional to the length of the remainder times
+/// the number of patterns. Only the bytes which can start a pattern are compared, so text which
+/// can't match is moved in bulk.
+///
+/// # Errors
+/// If any pattern is empty, or any replacement is longer than its pattern. This is checked before
+/// anything is replaced, so the remainder is unchanged.
+pub fn replace_many_in_place<O: Observer>(
+    src: &mut Replacinator<'_, O>,
+    pairs: &[(&str, &str)],
+) -> Result<TransformReport, ReplaceError> {
+    for (pattern, replacement) in pairs {
+        let kind = if pattern.is_empty() {
+            ReplaceErrorKind::EmptyMatch
+        } else if replacement.len() > pattern.len() {
+            ReplaceErrorKind::TooLong {
+                match_len: pattern.len(),
+                replacement_len: replacement.len(),
+            }
+        } else {
+            continue;
+        };
+        return Err(ReplaceError::new(kind, src.position()));
+    }
+    let search = ManySearch::new(pairs);
+    let (read_offset, write_offset) = (src.read_offset(), src.write_offset());
+    let mut count = 0;
+    while let Some((start, (pattern, replacement))) = search.find_in(src.remainder()) {
+        // Patterns start with a character's first byte, so a match is at a character boundary
+        src.skip_bytes(start);
+        src.discard_bytes(pattern.len());
+        src.write_str(replacement);
+        count += 1;
+    }
+    src.skip_bytes(src.remainder().len());
+    Ok(TransformReport::since(
+        src,
+        read_offset,
+        write_offset,
+        count,
+    ))
+}
+
+/// The search for the leftmost longest pattern used by [`replace_many_in_place`]
+struct ManySearch<'p> {
+    pairs: &'p [(&'p str, &'p str)],
+    /// The bytes which can start a pattern
+    starts: [bool; 256],
+    /// The automaton matching every pattern, unless it was too large to build
+    #[cfg(feature = "aho-corasick")]
+    automaton: Option<aho_corasick::AhoCorasick>,
+}
+
+impl<'p> ManySearch<'p> {
+    fn new(pairs: &'p [(&'p str, &'p str)]) -> Self {
+        let mut starts = [false; 256];
+        for (pattern, _) in pairs {
+            starts[usize::from(pattern.as_bytes()[0])] = true;
+        }
+        Self {
+            pairs,
+            starts,
+            #[cfg(feature = "aho-corasick")]
+            automaton: aho_corasick::AhoCorasick::builder()
+                .match_kind(aho_corasick::MatchKind::LeftmostLongest)
+                .build(pairs.iter().map(|(pattern, _)| pattern))
+                .ok(),
+        }
+    }
+
+    /// Find the first match in `haystack`, giving its start and the pair which matched
+    fn find_in(&self, haystack: &str) -> Option<(usize, &'p (&'p str, &'p str))> {
+        #[cfg(feature = "aho-corasick")]
+        if let Some(automaton) = &self.automaton {
+            let found = automaton.find(haystack)?;
+            return Some((found.start(), &self.pairs[found.pattern().as_usize()]));
+        }
+        haystack
+            .as_bytes()
+            .iter()
+            .enumerate()
+            .filter(|&(_, &b)| self.starts[usize::from(b)])
+            .find_map(|(i, _)| {
+                let rest = &haystack[i..];
+                let longest = self
+                    .pairs
+                    .iter()
+                    .filter(|(pattern, _)| rest.starts_with(pattern))
+                    .reduce(|longest, pair| {
+                        if pair.0.len() > longest.0.len() {
+                            pair
+                        } else {
+                            longest
+                        }
+                    })?;
+                Some((i, longest))
+            })
+    }
+}
+
+/// The syntax of comments and string literals, used by [`strip_comments`]
+///
+/// None of the comment markers may be empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommentSyntax<'s> {
+    /// The markers which start a comment running to the end of the line, such as `//`
+    pub line: &'s [&'s str],
+    /// The pairs of markers which start and end a block comment, such as `/*` and `*/`
+    pub block: &'s [(&'s str, &'s str)],
+    /// The string literals, within which comment markers are not recognised
+    pub strings: &'s [StringSyntax],
+}
+
+impl CommentSyntax<'static> {
+    /// `//` and `/* */` comments, with `"` and `'` strings which use `\` to escape
+    pub const C: Self = Self {
+        line: &["//"],
+        block: &[("/*", "*/")],
+        strings: &[
+            StringSyntax::BACKSLASH_DOUBLE,
+            StringSyntax::BACKSLASH_SINGLE,
+        ],
+    };
+
+    /// JSON with `//` and `/* */` comments
+    pub const JSONC: Self = Self {
+        line: &["//"],
+        block: &[("/*", "*/")],
+        strings: &[StringSyntax::BACKSLASH_DOUBLE],
+    };
+
+    /// `#` comments, with `"` strings which use `\` to escape and `'` strings which don't,
+    /// as used by TOML and shell scripts
+    pub const HASH: Self = Self {
+        line: &["#"],
+        block: &[],
+        strings: &[
+            StringSyntax::BACKSLASH_DOUBLE,
+            StringSyntax {
+                quote: '\'',
+                escape: None,
+            },
+        ],
+    };
+}
+
+impl CommentSyntax<'_> {
+    /// Check that none of the line markers or block openers are empty, as an empty marker
+    /// would match everywhere without reading anything
+    pub(crate) fn check(&self) -> Result<(), EmptyMarker> {
+        let line = self.line.iter();
+        let block = self.block.iter().map(|(open, _)| open);
+        if line.chain(block).any(|marker| marker.is_empty()) {
+            return Err(EmptyMarker);
+        }
+        Ok(())
+    }
+}
+
+/// The error returned by [`strip_comments`] and
+/// [`Replacinator::skip_ignorable`](crate::Replacinator::skip_ignorable) when a line comment
+/// marker or block comment opener of the [`CommentSyntax`] is empty
+///
+/// This is found before anything is read, so the remainder is left unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmptyMarker;
+
+impl core::fmt::Display for EmptyMarker {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "a comment marker is empty")
+    }
+}
+
+/// The syntax of a string literal, used within [`CommentSyntax`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StringSyntax {
+    /// The character which starts and ends the string
+    pub quote: char,
+    /// The character which causes the following character to be part of the string
+    pub escape: Option<char>,
+}
+
+impl StringSyntax {
+    const BACKSLASH_DOUBLE: Self = Self {
+        quote: '"',
+        escape: Some('\\'),
+    };
+    const BACKSLASH_SINGLE: Self = Self {
+        quote: '\'',
+        escape: Some('\\'),
+    };
+}
+
+/// Remove the comments from the remainder of `src`
+///
+/// Line comments are removed up to, but not including, the end of the line. Block comments
+/// are replaced with a single space, so that they still separate the text on either side. An
+/// unterminated block comment or string continues to the end of the remainder.
+///
+/// Returns the text without comments. Like [`Replacinator::take_start`], the first section of
+/// `src` is no longer available afterwards.
+///
+/// # Errors
+/// If a line comment marker or block comment opener of `syntax` is empty
+pub fn strip_comments<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+    syntax: CommentSyntax<'_>,
+) -> Result<&'a mut str, EmptyMarker> {
+    syntax.check()?;
+    let mark = src.mark();
+    while let Some(c) = src.peek() {
+        if let Some(string) = syntax.strings.iter().find(|string| string.quote == c) {
+            src.skip_char();
+            while let Some(c) = src.skip_char() {
+                if c == string.quote {
+                    break;
+                }
+                if Some(c) == string.escape {
+                    src.skip_char();
+                }
+            }
+        } else if let Some(marker) = syntax.line.iter().find(|&&marker| src.starts_with(marker)) {
+            src.discard_bytes(marker.len());
+            src.discard_while(|c| c != '\n');
+        } else if let Some((open, close)) =
+            syntax.block.iter().find(|(open, _)| src.starts_with(open))
+        {
+            src.discard_bytes(open.len());
+            let len = src
+                .remainder()
+                .find(close)
+                .map_or(src.remainder().len(), |end| end + close.len());
+            src.discard_bytes(len);
+            src.write_char(' ');
+        } else {
+            src.skip_char();
+        }
+    }
+    Ok(src
+        .take_marked(mark)
+        .expect("The mark was made from this Replacinator"))
+}
@@ -0,0 +1,451 @@
+//! Decoding of escape sequences
+
+use core::fmt;
+
+use crate::{Observer, Position, Replacinator};
+
+/// An error encountered whilst decoding an escape sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    position: Position,
+}
+
+impl Error {
+    /// The kind of error which occurred
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The position in the original string at which the error was detected
+    pub fn position(&self) -> Position {
+        self.position
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::UnexpectedEnd => write!(f, "unexpected end of escape sequence"),
+            ErrorKind::InvalidDigit(c) => write!(f, "invalid hex digit {:?}", c),
+            ErrorKind::InvalidCodepoint(code) => write!(f, "invalid code point {:#x}", code),
+            ErrorKind::LoneSurrogate(code) => write!(f, "unpaired surrogate {:#x}", code),
+            ErrorKind::InvalidEscape(c) => write!(f, "invalid escape sequence \\{}", c),
+            ErrorKind::ControlCharacter(c) => write!(f, "unescaped control character {:?}", c),
+            ErrorKind::ExpectedQuote(c) => write!(f, "expected opening {:?}", c),
+            ErrorKind::Unterminated => write!(f, "unterminated quoted string"),
+        }?;
+        write!(f, " at byte {}", self.position.byte())
+    }
+}
+
+/// The kinds of [`Error`] which can occur whilst decoding an escape sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The input ended partway through the escape sequence
+    UnexpectedEnd,
+    /// A character which is not a hex digit was found
+    InvalidDigit(char),
+    /// The escape sequence does not encode a Unicode scalar value
+    InvalidCodepoint(u32),
+    /// A UTF-16 surrogate was not part of a valid surrogate pair
+    LoneSurrogate(u32),
+    /// A `\\` was followed by a character which does not start an escape sequence
+    InvalidEscape(char),
+    /// A control character was found which must be escaped
+    ControlCharacter(char),
+    /// A quoted string was expected, but the given quote character was not found
+    ExpectedQuote(char),
+    /// A quoted string was not followed by its closing quote
+    Unterminated,
+}
+
+/// Unescape the contents of a JSON string in place
+///
+/// Reads from `src` until the end of the remainder, or an unescaped `"` (which is not read).
+/// Returns the unescaped text. Like [`Replacinator::take_start`], the first section of `src`
+/// is no longer available afterwards.
+pub fn unescape_json<'a, O: Observer>(src: &mut Replacinator<'a, O>) -> Result<&'a mut str, Error> {
+    unescape_with(
+        src,
+        |c| c < ' ',
+        |src, c| {
+            Ok(match c {
+                '"' => '"',
+                '\\' => '\\',
+                '/' => '/',
+                'b' => '\x08',
+                'f' => '\x0c',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'u' => UnicodeEscape::JSON.read(src)?,
+                c => return Err(error(src, ErrorKind::InvalidEscape(c))),
+            })
+        },
+    )
+}
+
+/// Unescape the contents of a TOML basic string in place
+///
+/// This supports the simple escapes such as `\\n`, `\\e` for the escape character, and the
+/// escapes `\\uXXXX` and `\\UXXXXXXXX` of a Unicode scalar value. Control characters other than
+/// tab must be escaped.
+///
+/// Reads from `src` until the end of the remainder, or an unescaped `"` (which is not read).
+/// Returns the unescaped text. Like [`Replacinator::take_start`], the first section of `src`
+/// is no longer available afterwards.
+pub fn unescape_toml<'a, O: Observer>(src: &mut Replacinator<'a, O>) -> Result<&'a mut str, Error> {
+    unescape_with(
+        src,
+        |c| c < ' ' && c != '\t' || c == '\x7f',
+        |src, c| {
+            Ok(match c {
+                '"' => '"',
+                '\\' => '\\',
+                'b' => '\x08',
+                'e' => '\x1b',
+                'f' => '\x0c',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'u' => UnicodeEscape::new(4).read(src)?,
+                'U' => UnicodeEscape::new(8).read(src)?,
+                c => return Err(error(src, ErrorKind::InvalidEscape(c))),
+            })
+        },
+    )
+}
+
+/// Unescape the contents of a C string or character literal in place
+///
+/// This supports the simple escapes such as `\\n`, octal escapes of up to three digits,
+/// `\\xNN` with exactly two hex digits, and the universal character names `\\uNNNN` and
+/// `\\UNNNNNNNN`. Octal and hex escapes are interpreted as code points, rather than bytes.
+///
+/// Reads from `src` until the end of the remainder, or an unescaped `"` (which is not read).
+/// Returns the unescaped text. Like [`Replacinator::take_start`], the first section of `src`
+/// is no longer available afterwards.
+pub fn unescape_c<'a, O: Observer>(src: &mut Replacinator<'a, O>) -> Result<&'a mut str, Error> {
+    unescape_with(
+        src,
+        |_| false,
+        |src, c| {
+            Ok(match c {
+                '\'' | '"' | '?' | '\\' => c,
+                'a' => '\x07',
+                'b' => '\x08',
+                'f' => '\x0c',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'v' => '\x0b',
+                '0'..='7' => {
+                    let mut code = c.to_digit(8).unwrap();
+                    for _ in 0..2 {
+                        match src.peek().and_then(|c| c.to_digit(8)) {
+                            Some(digit) => {
+                                src.read_char();
+                                code = code * 8 + digit;
+                            }
+                            None => break,
+                        }
+                    }
+                    core::char::from_u32(code).expect("Octal escapes are at most 0o777")
+                }
+                'x' => UnicodeEscape::new(2).read(src)?,
+                'u' => UnicodeEscape::new(4).read(src)?,
+                'U' => UnicodeEscape::new(8).read(src)?,
+                c => return Err(error(src, ErrorKind::InvalidEscape(c))),
+            })
+        },
+    )
+}
+
+/// Remove the `\\` from every backslash-escaped character in place, so that `\\c` becomes `c`
+///
+/// Reads from `src` until the end of the remainder, or an unescaped `"` (which is not read).
+/// Returns the unescaped text. Like [`Replacinator::take_start`], the first section of `src`
+/// is no longer available afterwards.
+pub fn unescape_backslashes<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+) -> Result<&'a mut str, Error> {
+    unescape_with(src, |_| false, |_, c| Ok(c))
+}
+
+/// Unescape the quoted string at the start of the remainder of `src`, using `unescape` for its body
+///
+/// The opening `quote` is read, then `unescape` is called, which must stop before the closing
+/// `quote`. Both quotes are removed. For example, `|src| unescape_json(src)` with `"` reads
+/// a JSON string, and `|src| unescape_doubled(src, '\'')` reads a SQL string literal.
+///
+/// Returns the unescaped body. Like [`Replacinator::take_start`], the first section of `src`
+/// is no longer available afterwards.
+pub fn consume_quoted<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+    quote: char,
+    unescape: impl FnOnce(&mut Replacinator<'a, O>) -> Result<&'a mut str, Error>,
+) -> Result<&'a mut str, Error> {
+    if src.peek() != Some(quote) {
+        return Err(error(src, ErrorKind::ExpectedQuote(quote)));
+    }
+    src.discard_char();
+    let body = unescape(src)?;
+    if src.peek() != Some(quote) {
+        return Err(error(src, ErrorKind::Unterminated));
+    }
+    src.discard_char();
+    Ok(body)
+}
+
+/// Unescape text in which `quote` is escaped by doubling it, as in CSV fields and SQL strings
+///
+/// Reads from `src` until the end of the remainder, or a `quote` which is not doubled (which is
+/// not read). Returns the unescaped text. Like [`Replacinator::take_start`], the first section
+/// of `src` is no longer available afterwards.
+pub fn unescape_doubled<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+    quote: char,
+) -> Result<&'a mut str, Error> {
+    let mark = src.mark();
+    loop {
+        src.skip_while(|c| c != quote);
+        if src.peek_nth(1) != Some(quote) {
+            break;
+        }
+        src.skip_char();
+        src.discard_char();
+    }
+    Ok(src
+        .take_marked(mark)
+        .expect("The mark was made from this Replacinator"))
+}
+
+/// A table of escape sequences which are introduced by the same escape character, and each
+/// decode to a single character
+///
+/// This allows an unescaper for a bespoke format to be declared, rather than written by hand.
+/// Tables are usually created using [`unescape_rules!`](crate::unescape_rules), and can be
+/// `const`, in which case they are checked at compile time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EscapeTable<'t> {
+    escape: char,
+    rules: &'t [(&'t str, char)],
+    terminator: Option<char>,
+}
+
+impl<'t> EscapeTable<'t> {
+    /// Create a table in which `escape` followed by the first string of a rule decodes to its
+    /// character
+    ///
+    /// Where several sequences match, the longest is used.
+    ///
+    /// # Panics
+    /// If a sequence is empty, or a character is longer than `escape` and its sequence together.
+    /// For a `const` table, this is reported at compile time.
+    pub const fn new(escape: char, rules: &'t [(&'t str, char)]) -> Self {
+        let mut i = 0;
+        while i < rules.len() {
+            let (sequence, replacement) = rules[i];
+            assert!(!sequence.is_empty(), "An escape sequence cannot be empty");
+            assert!(
+                replacement.len_utf8() <= escape.len_utf8() + sequence.len(),
+                "An escape sequence cannot decode to a longer character"
+            );
+            i += 1;
+        }
+        Self {
+            escape,
+            rules,
+            terminator: None,
+        }
+    }
+
+    /// Stop unescaping at an unescaped `terminator`, such as the closing quote of a string
+    pub const fn terminated_by(self, terminator: char) -> Self {
+        Self {
+            terminator: Some(terminator),
+            ..self
+        }
+    }
+
+    /// Unescape the remainder of `src` in place using this table
+    ///
+    /// Reads from `src` until the end of the remainder, or the terminator (which is not read).
+    /// Returns the unescaped text. Like [`Replacinator::take_start`], the first section of `src`
+    /// is no longer available afterwards.
+    pub fn unescape<'a, O: Observer>(
+        &self,
+        src: &mut Replacinator<'a, O>,
+    ) -> Result<&'a mut str, Error> {
+        let mark = src.mark();
+        loop {
+            src.skip_while(|c| c != self.escape && Some(c) != self.terminator);
+            if src.peek().is_none() || src.peek() == self.terminator {
+                break;
+            }
+            src.discard_char();
+            let remainder = src.remainder();
+            let rule = self
+                .rules
+                .iter()
+                .filter(|(sequence, _)| remainder.starts_with(sequence))
+                .reduce(|longest, rule| {
+                    if rule.0.len() > longest.0.len() {
+                        rule
+                    } else {
+                        longest
+                    }
+                });
+            match rule {
+                Some(&(sequence, replacement)) => {
+                    src.discard_bytes(sequence.len());
+                    src.write_char(replacement);
+                }
+                None => {
+                    let kind = match src.peek() {
+                        Some(c) => ErrorKind::InvalidEscape(c),
+                        None => ErrorKind::UnexpectedEnd,
+                    };
+                    return Err(error(src, kind));
+                }
+            }
+        }
+        Ok(src
+            .take_marked(mark)
+            .expect("The mark was made from this Replacinator"))
+    }
+}
+
+/// Declare an [`EscapeTable`](crate::unescape::EscapeTable) from its escape character and rules
+///
+/// For example, `unescape_rules!('^' => { "n" => '\n', "^" => '^', "lt" => '<' })` decodes
+/// `^lt` as `<`. Assigning the result to a `const` checks the rules at compile time.
+#[macro_export]
+macro_rules! unescape_rules {
+    ($escape:expr => { $($sequence:expr => $replacement:expr),* $(,)? }) => {
+        $crate::unescape::EscapeTable::new($escape, &[$(($sequence, $replacement)),*])
+    };
+}
+
+/// Unescape text using `decode` for the character after each `\\`, returning an error for any
+/// character which matches `forbidden`
+fn unescape_with<'a, O: Observer>(
+    src: &mut Replacinator<'a, O>,
+    mut forbidden: impl FnMut(char) -> bool,
+    mut decode: impl FnMut(&mut Replacinator<'a, O>, char) -> Result<char, Error>,
+) -> Result<&'a mut str, Error> {
+    let mark = src.mark();
+    loop {
+        src.skip_while(|c| c != '\\' && c != '"' && !forbidden(c));
+        match src.peek() {
+            None | Some('"') => break,
+            Some('\\') => {
+                src.discard_char();
+                let c = src
+                    .read_char()
+                    .ok_or_else(|| error(src, ErrorKind::UnexpectedEnd))?;
+                let c = decode(src, c)?;
+                src.write_char(c);
+            }
+            Some(c) => return Err(error(src, ErrorKind::ControlCharacter(c))),
+        }
+    }
+    Ok(src
+        .take_marked(mark)
+        .expect("The mark was made from this Replacinator"))
+}
+
+/// The format of an escape sequence made up of a fixed number of hex digits, such as `\u00e9`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnicodeEscape<'p> {
+    digits: usize,
+    pair_prefix: Option<&'p str>,
+}
+
+impl UnicodeEscape<'static> {
+    /// The `\uXXXX` escapes used by JSON (and JavaScript), where characters outside of the
+    /// Basic Multilingual Plane are written as a surrogate pair of escapes
+    pub const JSON: Self = Self {
+        digits: 4,
+        pair_prefix: Some("\\u"),
+    };
+
+    /// An escape of `digits` hex digits, which encodes a single Unicode scalar value
+    pub fn new(digits: usize) -> Self {
+        Self {
+            digits,
+            pair_prefix: None,
+        }
+    }
+}
+
+impl<'p> UnicodeEscape<'p> {
+    /// Allow a high surrogate to be followed by `prefix` and a low surrogate, which are combined
+    ///
+    /// `prefix` is the text which introduces the second escape, such as `\u`.
+    pub fn with_surrogate_pairs<'q>(self, prefix: &'q str) -> UnicodeEscape<'q> {
+        UnicodeEscape {
+            digits: self.digits,
+            pair_prefix: Some(prefix),
+        }
+    }
+
+    /// Read the hex digits of an escape sequence from `src`, after the text which introduces it
+    ///
+    /// If the escape is a high surrogate and surrogate pairs are enabled, the second escape of
+    /// the pair is also read. Nothing is written to `src`. If an error is returned, the characters
+    /// up to the point of the error have been read.
+    pub fn read<O: Observer>(&self, src: &mut Replacinator<'_, O>) -> Result<char, Error> {
+        let code = self.read_hex(src)?;
+        let prefix = match self.pair_prefix {
+            Some(prefix) if (0xD800..0xDC00).contains(&code) => prefix,
+            _ => {
+                return match core::char::from_u32(code) {
+                    Some(c) => Ok(c),
+                    None if (0xD800..0xE000).contains(&code) => {
+                        Err(error(src, ErrorKind::LoneSurrogate(code)))
+                    }
+                    None => Err(error(src, ErrorKind::InvalidCodepoint(code))),
+                }
+            }
+        };
+        if !src.starts_with(prefix) {
+            return Err(error(src, ErrorKind::LoneSurrogate(code)));
+        }
+        src.discard_bytes(prefix.len());
+        let low = self.read_hex(src)?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return Err(error(src, ErrorKind::LoneSurrogate(code)));
+        }
+        let c = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+        Ok(core::char::from_u32(c).expect("Surrogate pairs always form a valid character"))
+    }
+
+    fn read_hex<O: Observer>(&self, src: &mut Replacinator<'_, O>) -> Result<u32, Error> {
+        let mut code: u32 = 0;
+        for _ in 0..self.digits {
+            let c = src
+                .peek()
+                .ok_or_else(|| error(src, ErrorKind::UnexpectedEnd))?;
+            let digit = c
+                .to_digit(16)
+                .ok_or_else(|| error(src, ErrorKind::InvalidDigit(c)))?;
+            src.read_char();
+            code = code
+                .checked_mul(16)
+                .map(|code| code + digit)
+                .ok_or_else(|| error(src, ErrorKind::InvalidCodepoint(u32::MAX)))?;
+        }
+        Ok(code)
+    }
+}
+
+#[cold]
+fn error<O: Observer>(src: &Replacinator<'_, O>, kind: ErrorKind) -> Error {
+    Error {
+        kind,
+        position: src.position(),
+    }
+}
@@ -0,0 +1,114 @@
+use core::fmt;
+
+use crate::{is_utf8_continuation, Observer, Replacinator};
+
+/// A broken invariant of a [`Replacinator`], found by [`Replacinator::validate`]
+///
+/// None of these should be possible through the safe API, so finding one indicates a bug in this
+/// crate, or in unsafe code which modified the string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvariantError {
+    /// The [`Replacinator`] was poisoned by an earlier broken invariant
+    Poisoned,
+    /// The write position is after the read position, or the read position is after the end
+    Order,
+    /// The write position, at this offset in the string, is within a character
+    WriteNotCharBoundary(usize),
+    /// The read position, at this offset in the string, is within a character
+    ReadNotCharBoundary(usize),
+    /// The first section isn't valid UTF-8, from this offset in the string
+    InvalidStart(usize),
+    /// The remainder isn't valid UTF-8, from this offset in the string
+    InvalidRemainder(usize),
+}
+
+impl fmt::Display for InvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            InvariantError::Poisoned => write!(f, "the replacinator is poisoned"),
+            InvariantError::Order => write!(f, "the positions are out of order"),
+            InvariantError::WriteNotCharBoundary(offset) => write!(
+                f,
+                "the write position is not on a character boundary at byte {}",
+                offset
+            ),
+            InvariantError::ReadNotCharBoundary(offset) => write!(
+                f,
+                "the read position is not on a character boundary at byte {}",
+                offset
+            ),
+            InvariantError::InvalidStart(offset) => {
+                write!(f, "the first section is invalid UTF-8 at byte {}", offset)
+            }
+            InvariantError::InvalidRemainder(offset) => {
+                write!(f, "the remainder is invalid UTF-8 at byte {}", offset)
+            }
+        }
+    }
+}
+
+impl<'a, O: Observer> Replacinator<'a, O> {
+    /// Check every internal invariant, including that both sections which can be viewed are
+    /// valid UTF-8
+    ///
+    /// This takes time proportional to the length of the string, so is intended for tests and
+    /// fuzzers. The cheaper checks made after each operation don't include the UTF-8 validation.
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        let inner = &self.inner;
+        if inner.poisoned {
+            return Err(InvariantError::Poisoned);
+        }
+        if !inner.invariants_hold() {
+            return Err(InvariantError::Order);
+        }
+        self.check_boundaries()?;
+        let base = self.write_offset() - inner.write_position;
+        if let Err(e) = core::str::from_utf8(&inner.contents[..inner.write_position]) {
+            return Err(InvariantError::InvalidStart(base + e.valid_up_to()));
+        }
+        if let Err(e) = core::str::from_utf8(&inner.contents[inner.read_position..]) {
+            let read = base + inner.read_position;
+            return Err(InvariantError::InvalidRemainder(read + e.valid_up_to()));
+        }
+        Ok(())
+    }
+
+    /// Check that the write and read positions are on character boundaries, looking at no more
+    /// than the character on either side of each
+    pub(crate) fn check_boundaries(&self) -> Result<(), InvariantError> {
+        let inner = &self.inner;
+        let base = self.write_offset() - inner.write_position;
+        if !matches!(inner.contents.get(..inner.write_position), Some(start) if ends_on_char_boundary(start))
+        {
+            return Err(InvariantError::WriteNotCharBoundary(
+                base + inner.write_position,
+            ));
+        }
+        if matches!(inner.contents.get(inner.read_position), Some(&b) if is_utf8_continuation(b)) {
+            return Err(InvariantError::ReadNotCharBoundary(
+                base + inner.read_position,
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Whether the last character of `bytes` is complete, assuming that `bytes` starts at a character
+/// boundary
+#[inline]
+fn ends_on_char_boundary(bytes: &[u8]) -> bool {
+    let tail = &bytes[bytes.len().saturating_sub(4)..];
+    match tail.iter().rposition(|&b| !is_utf8_continuation(b)) {
+        Some(lead) => {
+            let width = match tail[lead] {
+                0x00..=0x7F => 1,
+                0xC0..=0xDF => 2,
+                0xE0..=0xEF => 3,
+                _ => 4,
+            };
+            lead + width == tail.len()
+        }
+        None => tail.is_empty(),
+    }
+}
@@ -0,0 +1,72 @@
+//! Tests of rewriting a string stored in a fixed-capacity buffer
+
+use replacinator::{
+    transforms::{self, Whitespace},
+    unescape, ArrayReplacinator,
+};
+
+#[test]
+fn text_is_copied_in_if_it_fits() {
+    let array = ArrayReplacinator::<8>::copy_from("ab é").unwrap();
+    assert_eq!(
+        (array.as_str(), array.len(), array.capacity()),
+        ("ab é", 5, 8)
+    );
+    assert!(!array.is_empty());
+    assert_eq!(format!("{:?}", array), "ArrayReplacinator(\"ab é\")");
+
+    assert!(ArrayReplacinator::<4>::copy_from("ab é").is_none());
+    assert_eq!(
+        ArrayReplacinator::<4>::copy_from("abcd").unwrap().as_str(),
+        "abcd"
+    );
+    let empty = ArrayReplacinator::<0>::default();
+    assert!(empty.is_empty());
+    assert_eq!(empty.as_str(), "");
+}
+
+#[test]
+fn transforms_shrink_the_string_to_their_result() {
+    let mut array = ArrayReplacinator::<32>::copy_from("  a \t b  ").unwrap();
+    array.transform(|src| transforms::collapse_whitespace(src, Whitespace::Ascii));
+    assert_eq!(array.as_str(), "a b");
+
+    let mut array = ArrayReplacinator::<32>::copy_from(r"xé\n").unwrap();
+    array.try_transform(unescape::unescape_json).unwrap();
+    assert_eq!((array.as_str(), array.len()), ("xé\n", 4));
+
+    array.as_mut_str().make_ascii_uppercase();
+    assert_eq!(array.as_str(), "Xé\n");
+}
+
+#[test]
+fn failed_transforms_leave_a_valid_string() {
+    let mut array = ArrayReplacinator::<16>::copy_from(r"ab\q").unwrap();
+    let error = array.try_transform(unescape::unescape_json).unwrap_err();
+    assert_eq!(error.kind(), unescape::ErrorKind::InvalidEscape('q'));
+    assert!(array.len() <= 4);
+    array.transform(|src| src.take_remainder());
+}
+
+#[test]
+fn the_string_keeps_its_length_when_rewritten_with() {
+    let mut array = ArrayReplacinator::<8>::copy_from("a-b").unwrap();
+    let written = array.with(|src| {
+        src.skip_char();
+        src.discard_char();
+        src.take_start().len()
+    });
+    assert_eq!(written, 1);
+    if cfg!(feature = "compact_on_take") {
+        assert_eq!(array.as_str(), "ab ");
+    } else {
+        assert_eq!(array.as_str(), "a b");
+    }
+}
+
+#[test]
+#[should_panic(expected = "The transform must return a part of the string")]
+fn transforms_returning_other_text_panic() {
+    let mut array = ArrayReplacinator::<4>::copy_from("x").unwrap();
+    array.transform(|_| Box::leak(String::from("x").into_boxed_str()));
+}
@@ -0,0 +1,64 @@
+//! Tests of limiting the amount of text written
+
+use replacinator::{
+    budget::{Budget, ErrorKind},
+    Replacinator,
+};
+
+#[test]
+fn writes_within_the_budget_succeed() {
+    let mut text = String::from("héllo world");
+    Replacinator::new_in(&mut text, |src| {
+        let mut budget = Budget::new(src, 5);
+        assert_eq!(budget.skip_char(), Ok(Some('h')));
+        budget.replacinator().discard_char();
+        assert_eq!(budget.write_char('e'), Ok(()));
+        assert_eq!(budget.skip_str("llo"), Ok(true));
+        assert_eq!((budget.written(), budget.remaining()), (5, 0));
+        assert_eq!(budget.skip_str("x"), Ok(false));
+        assert_eq!(budget.write_str(""), Ok(()));
+        assert_eq!(budget.into_inner().start(), "hello");
+    });
+}
+
+#[test]
+fn writes_beyond_the_budget_fail_without_reading() {
+    let mut text = String::from("ab中d");
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_char();
+        let mut budget = Budget::new(src, 3);
+        budget.skip_char().unwrap();
+        let error = budget.skip_char().unwrap_err();
+        assert_eq!(
+            (error.kind(), error.position().byte()),
+            (ErrorKind::Exhausted, 2)
+        );
+        assert_eq!(error.to_string(), "output budget exhausted at byte 2");
+        assert_eq!(
+            budget.skip_str("中").unwrap_err().kind(),
+            ErrorKind::Exhausted
+        );
+        budget.replacinator().discard_char();
+        assert!(budget.write_str("xyz").is_err());
+        assert_eq!(budget.write_str("xy"), Ok(()));
+        assert_eq!(budget.replacinator().remainder(), "d");
+        assert_eq!(budget.replacinator().start(), "abxy");
+    });
+}
+
+#[test]
+fn text_written_around_the_budget_counts_against_it() {
+    let mut text = String::from("abcd");
+    Replacinator::new_in(&mut text, |src| {
+        let mut budget = Budget::new(src, 2);
+        budget.replacinator().skip_str("abc");
+        assert_eq!((budget.written(), budget.remaining()), (3, 0));
+        assert_eq!(budget.skip_char().unwrap_err().kind(), ErrorKind::Exhausted);
+        assert_eq!(budget.skip_char().unwrap_err().position().byte(), 3);
+    });
+    let mut text = String::new();
+    Replacinator::new_in(&mut text, |src| {
+        let mut budget = Budget::new(src, 0);
+        assert_eq!(budget.skip_char(), Ok(None));
+    });
+}
@@ -0,0 +1,99 @@
+//! Tests of `ByteReplacinator`
+
+use replacinator::ByteReplacinator;
+
+#[test]
+fn reads_and_writes_arbitrary_bytes() {
+    let mut buffer = *b"a%FFb\xFE";
+    let mut src = ByteReplacinator::new(&mut buffer);
+    assert_eq!(src.skip_byte(), Some(b'a'));
+    assert_eq!(src.read_byte(), Some(b'%'));
+    src.discard_bytes(2);
+    src.write_byte(0xFF);
+    src.skip_bytes(2);
+    assert_eq!(src.peek_byte(), None);
+    assert_eq!(src.read_byte(), None);
+    assert_eq!(src.start_bytes(), b"a\xFFb\xFE");
+    src.synchronise_with(0);
+    assert_eq!(buffer, *b"a\xFFb\xFE\0\0");
+}
+
+#[test]
+fn take_start_splits_off_the_written_bytes() {
+    let mut buffer = *b"key=value";
+    let mut src = ByteReplacinator::new(&mut buffer);
+    src.skip_bytes(3);
+    src.discard_bytes(1);
+    let key = src.take_start();
+    assert_eq!(src.remainder_bytes(), b"value");
+    assert_eq!(src.start_bytes(), b"");
+    key.make_ascii_uppercase();
+    src.remainder_bytes_mut()[0] = b'V';
+    assert_eq!(buffer, *b"KEY=Value");
+}
+
+#[test]
+fn skip_bytes_moves_bytes_across_a_gap() {
+    let mut buffer = *b"--abc";
+    let mut src = ByteReplacinator::new(&mut buffer);
+    src.discard_bytes(2);
+    src.skip_bytes(3);
+    src.synchronise_with(b'.');
+    assert_eq!(buffer, *b"abc..");
+}
+
+#[test]
+#[should_panic(expected = "Not enough room to write 2 bytes")]
+fn write_bytes_panics_without_room() {
+    let mut buffer = *b"ab";
+    let mut src = ByteReplacinator::new(&mut buffer);
+    src.read_byte();
+    src.write_bytes(b"xy");
+}
+
+#[test]
+#[should_panic]
+fn skip_bytes_panics_past_the_end() {
+    let mut buffer = *b"ab";
+    let mut src = ByteReplacinator::new(&mut buffer);
+    src.skip_bytes(3);
+}
+
+#[test]
+fn xor_with_a_repeating_key_is_its_own_inverse() {
+    let mut buffer = *b"ab\x00cde";
+    let mut src = ByteReplacinator::new(&mut buffer);
+    src.skip_byte();
+    src.xor_remaining(b"\x01\x02");
+    assert_eq!(src.remainder_bytes(), b"c\x02bfd");
+    assert_eq!(src.position().byte(), 1);
+    src.xor_remaining(b"\x01\x02");
+    assert_eq!(src.remainder_bytes(), b"b\x00cde");
+    src.xor_remaining(b"\x00");
+    src.skip_bytes(5);
+    assert_eq!(buffer, *b"ab\x00cde");
+}
+
+#[test]
+fn wrapping_add_is_undone_by_its_negation() {
+    let mut buffer = *b"az\xFF";
+    let mut src = ByteReplacinator::new(&mut buffer);
+    src.wrapping_add_remaining(1);
+    assert_eq!(src.remainder_bytes(), b"b{\x00");
+    src.wrapping_add_remaining(0);
+    src.wrapping_add_remaining(1u8.wrapping_neg());
+    assert_eq!(src.remainder_bytes(), b"az\xFF");
+    let mut empty = [];
+    ByteReplacinator::new(&mut empty).wrapping_add_remaining(3);
+}
+
+#[test]
+fn xor_with_an_empty_key_changes_nothing() {
+    let mut buffer = *b"a";
+    let mut src = ByteReplacinator::new(&mut buffer);
+    src.xor_remaining(&[]);
+    assert_eq!(
+        (src.remainder_bytes(), src.position().byte()),
+        (&b"a"[..], 0)
+    );
+}
@@ -0,0 +1,99 @@
+//! Tests of decoding binary-to-text encodings in place
+
+use replacinator::{
+    codec::{self, ErrorKind},
+    ByteReplacinator,
+};
+
+/// Decode the start of `text` using `decode`, returning the decoded bytes and what remains
+fn decoded(
+    text: &[u8],
+    decode: for<'a> fn(&mut ByteReplacinator<'a>) -> Result<&'a mut [u8], codec::Error>,
+) -> Result<(Vec<u8>, Vec<u8>), codec::Error> {
+    let mut buffer = text.to_vec();
+    let mut src = ByteReplacinator::new(&mut buffer);
+    let decoded = decode(&mut src)?.to_vec();
+    Ok((decoded, src.remainder_bytes().to_vec()))
+}
+
+#[test]
+fn hex_is_decoded_until_a_non_digit() {
+    assert_eq!(
+        decoded(b"00fFa5C3A9 rest", codec::decode_hex_in_place).unwrap(),
+        (b"\x00\xff\xa5\xc3\xa9".to_vec(), b" rest".to_vec())
+    );
+    assert_eq!(
+        decoded(b"", codec::decode_hex_in_place).unwrap(),
+        (vec![], vec![])
+    );
+    assert_eq!(
+        decoded(b"zz", codec::decode_hex_in_place).unwrap(),
+        (vec![], b"zz".to_vec())
+    );
+}
+
+#[test]
+fn hex_of_an_odd_length_is_incomplete() {
+    let error = decoded(b"abc", codec::decode_hex_in_place).unwrap_err();
+    assert_eq!(
+        (error.kind(), error.position().byte()),
+        (ErrorKind::Incomplete, 3)
+    );
+    let error = decoded(b"a-", codec::decode_hex_in_place).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "encoded text ended partway through a byte at byte 1"
+    );
+}
+
+#[test]
+fn base64_is_decoded_with_or_without_padding() {
+    for (text, bytes) in [
+        (&b"aGVsbG8="[..], &b"hello"[..]),
+        (b"aGVsbG8", b"hello"),
+        (b"aGk=", b"hi"),
+        (b"aA==", b"h"),
+        (b"aA", b"h"),
+        (b"+/+/", b"\xfb\xff\xbf"),
+        (b"", b""),
+    ] {
+        let (decoded, remainder) = decoded(text, codec::decode_base64_in_place).unwrap();
+        assert_eq!((&*decoded, &*remainder), (bytes, &b""[..]));
+    }
+}
+
+#[test]
+fn base64_stops_at_the_end_of_the_encoded_text() {
+    assert_eq!(
+        decoded(b"aGk= rest", codec::decode_base64_in_place).unwrap(),
+        (b"hi".to_vec(), b" rest".to_vec())
+    );
+    assert_eq!(
+        decoded(b"aGVsbG8\"", codec::decode_base64_in_place).unwrap(),
+        (b"hello".to_vec(), b"\"".to_vec())
+    );
+    // A short group ends the encoded text
+    assert_eq!(
+        decoded(b"aGk-aGk", codec::decode_base64_in_place).unwrap(),
+        (b"hi".to_vec(), b"-aGk".to_vec())
+    );
+    assert_eq!(
+        decoded(b"aGk=aGk=", codec::decode_base64_in_place).unwrap(),
+        (b"hi".to_vec(), b"aGk=".to_vec())
+    );
+}
+
+#[test]
+fn base64_rejects_bad_padding_and_lone_digits() {
+    for (text, kind, position) in [
+        (&b"a"[..], ErrorKind::Incomplete, 1),
+        (b"aGVsb", ErrorKind::Incomplete, 5),
+        (b"aA=", ErrorKind::InvalidPadding, 3),
+        (b"=", ErrorKind::InvalidPadding, 0),
+    ] {
+        let error = decoded(text, codec::decode_base64_in_place).unwrap_err();
+        assert_eq!((error.kind(), error.position().byte()), (kind, position));
+    }
+    let error = decoded(b"aA=x", codec::decode_base64_in_place).unwrap_err();
+    assert_eq!(error.to_string(), "invalid base64 padding at byte 3");
+}
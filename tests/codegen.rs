@@ -0,0 +1,67 @@
+//! Tests that the per-character cursor methods are inlined into their callers
+//!
+//! Builds `examples/codegen.rs` in release mode, with the safety checks disabled, and checks
+//! that the LLVM IR of its functions contains no calls to the cursor methods. Only the cold
+//! paths (panics, poisoning and overflowing the gap) may remain out of line.
+
+use std::{env, fs, path::PathBuf, process::Command};
+
+const FUNCTIONS: &[&str] = &[
+    "codegen_remove_spaces",
+    "codegen_replace_dashes",
+    "codegen_remove_nuls",
+];
+
+const CURSOR_METHODS: &[&str] = &[
+    "::peek",
+    "::read_char",
+    "::skip_char",
+    "::discard_char",
+    "::write_char",
+    "::write_str",
+    "::read_byte",
+    "::write_byte",
+    "::write_bytes",
+    "::skip_bytes",
+    "::mark_dirty",
+    "ends_on_char_boundary",
+];
+
+/// Build the example, returning its LLVM IR
+fn example_ir() -> String {
+    let target = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/codegen");
+    let ir = target.join("codegen.ll");
+    let status = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".into()))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["rustc", "--quiet", "--release", "--example", "codegen"])
+        .args(["--features", "disable_safety_checks", "--target-dir"])
+        .arg(&target)
+        .arg("--")
+        .arg(format!("--emit=llvm-ir={}", ir.display()))
+        .status()
+        .expect("Could not run cargo");
+    assert!(status.success(), "Building the codegen example failed");
+    fs::read_to_string(ir).expect("The IR was emitted")
+}
+
+/// The body of the function `name` in `ir`
+fn body<'a>(ir: &'a str, name: &str) -> &'a str {
+    let start = ir
+        .find(&format!("@{}(", name))
+        .unwrap_or_else(|| panic!("{} is not defined", name));
+    let len = ir[start..].find("\n}\n").expect("The function ends");
+    &ir[start..start + len]
+}
+
+#[test]
+fn cursor_methods_are_inlined() {
+    let ir = example_ir();
+    for name in FUNCTIONS {
+        let calls: Vec<&str> = body(&ir, name)
+            .lines()
+            .filter(|line| line.trim_start().starts_with("; call"))
+            .filter(|line| CURSOR_METHODS.iter().any(|method| line.contains(method)))
+            .collect();
+        assert!(calls.is_empty(), "{} was not inlined: {:#?}", name, calls);
+    }
+}
@@ -0,0 +1,183 @@
+//! Tests of the in-place CSV functions
+
+use replacinator::csv;
+
+/// Convert `doc` to TSV, returning the converted text
+fn tsv(doc: &str) -> Result<String, csv::Error> {
+    let mut doc = String::from(doc);
+    let len = csv::to_tsv(&mut doc)?;
+    assert!(doc[len..].bytes().all(|b| b == b' '));
+    doc.truncate(len);
+    Ok(doc)
+}
+
+#[test]
+fn to_tsv_replaces_separators_and_unquotes_fields() {
+    assert_eq!(
+        tsv("name,quote\r\nann,\"say \"\"hi\"\", then go\"\n,\n").unwrap(),
+        "name\tquote\r\nann\tsay \"hi\", then go\n\t\n"
+    );
+}
+
+#[test]
+fn to_tsv_of_empty_document_is_empty() {
+    assert_eq!(tsv("").unwrap(), "");
+    assert_eq!(tsv("\"\"").unwrap(), "");
+}
+
+#[test]
+fn to_tsv_rejects_unrepresentable_fields() {
+    let error = tsv("a,\"b\nc\"").unwrap_err();
+    assert_eq!(error.kind(), csv::ErrorKind::Unrepresentable('\n'));
+    assert_eq!(error.position().byte(), 4);
+    let error = tsv("a\tb").unwrap_err();
+    assert_eq!(error.kind(), csv::ErrorKind::Unrepresentable('\t'));
+    assert_eq!(error.position().byte(), 1);
+}
+
+#[test]
+fn to_tsv_reports_malformed_documents() {
+    let error = tsv("a,\"b").unwrap_err();
+    assert_eq!(error.kind(), csv::ErrorKind::UnterminatedQuote);
+    let error = tsv("\"a\"b").unwrap_err();
+    assert_eq!(error.kind(), csv::ErrorKind::UnexpectedChar('b'));
+    assert_eq!(error.position().byte(), 3);
+    let error = tsv("a\rb").unwrap_err();
+    assert_eq!(error.kind(), csv::ErrorKind::UnexpectedChar('\r'));
+}
+
+/// Project `doc` onto `columns`, returning the projected text
+fn projected(doc: &str, columns: &[usize]) -> Result<String, csv::Error> {
+    let mut doc = String::from(doc);
+    let len = csv::project(&mut doc, columns)?;
+    assert!(doc[len..].bytes().all(|b| b == b' '));
+    doc.truncate(len);
+    Ok(doc)
+}
+
+#[test]
+fn project_keeps_columns_in_their_original_order() {
+    let doc = "id,name,\"a,b\"\r\n1,ann,\"x\"\"y\"\n2,bob,z\n";
+    assert_eq!(
+        projected(doc, &[2, 0]).unwrap(),
+        "id,\"a,b\"\r\n1,\"x\"\"y\"\n2,z\n"
+    );
+    assert_eq!(projected(doc, &[1]).unwrap(), "name\r\nann\nbob\n");
+}
+
+#[test]
+fn project_onto_no_columns_leaves_empty_records() {
+    assert_eq!(projected("a,b\nc,d", &[]).unwrap(), "\n");
+    assert_eq!(projected("a,b", &[7]).unwrap(), "");
+}
+
+#[test]
+fn project_reports_malformed_documents() {
+    let error = projected("a,\"b", &[0]).unwrap_err();
+    assert_eq!(error.kind(), csv::ErrorKind::UnterminatedQuote);
+    let error = projected("a,\"b\"c", &[0]).unwrap_err();
+    assert_eq!(error.kind(), csv::ErrorKind::UnexpectedChar('c'));
+    assert_eq!(error.position().byte(), 5);
+}
+
+#[test]
+fn header_index_finds_quoted_and_unquoted_names() {
+    let doc = "id,\"full \"\"name\"\"\",age\r\n1,ann,3";
+    assert_eq!(csv::header_index(doc, "id"), Some(0));
+    assert_eq!(csv::header_index(doc, "full \"name\""), Some(1));
+    assert_eq!(csv::header_index(doc, "age"), Some(2));
+    assert_eq!(csv::header_index(doc, "ann"), None);
+    assert_eq!(csv::header_index(doc, "ag"), None);
+    assert_eq!(csv::header_index("", ""), Some(0));
+    assert_eq!(csv::header_index("\"unterminated", "unterminated"), None);
+}
+
+/// Unquote the fields of `doc`, returning each field's text and whether it ended its record
+fn fields(doc: &str, dialect: csv::Dialect) -> Vec<Result<(String, bool), csv::ErrorKind>> {
+    let mut doc = String::from(doc);
+    replacinator::Replacinator::new_in(&mut doc, |src| {
+        csv::fields(src, dialect)
+            .map(|field| {
+                field
+                    .map(|field| (field.text().to_owned(), field.ends_record()))
+                    .map_err(|e| e.kind())
+            })
+            .collect()
+    })
+}
+
+#[test]
+fn fields_are_unquoted_and_split_into_records() {
+    let found = fields(
+        "a,\"b,\"\"c\"\"\"\r\n\"multi\nline\",é\n",
+        csv::Dialect::CSV,
+    );
+    assert_eq!(
+        found,
+        [
+            Ok(("a".into(), false)),
+            Ok(("b,\"c\"".into(), true)),
+            Ok(("multi\nline".into(), false)),
+            Ok(("é".into(), true)),
+        ]
+    );
+    assert_eq!(
+        fields(",\n\"\"", csv::Dialect::CSV),
+        [
+            Ok(("".into(), false)),
+            Ok(("".into(), true)),
+            Ok(("".into(), true)),
+        ]
+    );
+    assert!(fields("", csv::Dialect::CSV).is_empty());
+}
+
+#[test]
+fn fields_use_the_dialect() {
+    assert_eq!(
+        fields("1,5;\"x;y\"", csv::Dialect::SEMICOLON),
+        [Ok(("1,5".into(), false)), Ok(("x;y".into(), true))]
+    );
+    let tabs = csv::Dialect {
+        delimiter: '\t',
+        quote: '\'',
+    };
+    assert_eq!(
+        fields("'it''s'\t\"q\"", tabs),
+        [Ok(("it's".into(), false)), Ok(("\"q\"".into(), true))]
+    );
+}
+
+#[test]
+fn fields_stop_after_an_error() {
+    assert_eq!(
+        fields("a,\"b\"c,d", csv::Dialect::CSV),
+        [
+            Ok(("a".into(), false)),
+            Err(csv::ErrorKind::UnexpectedChar('c'))
+        ]
+    );
+    assert_eq!(
+        fields("\"open\nx", csv::Dialect::CSV),
+        [Err(csv::ErrorKind::UnterminatedQuote)]
+    );
+    assert_eq!(
+        fields("a\rb", csv::Dialect::CSV),
+        [Err(csv::ErrorKind::UnexpectedChar('\r'))]
+    );
+}
+
+#[test]
+fn fields_can_be_changed_in_place() {
+    let mut doc = String::from("\"a\",b\n");
+    replacinator::Replacinator::new_in(&mut doc, |src| {
+        for field in csv::fields(src, csv::Dialect::CSV) {
+            field.unwrap().into_text().make_ascii_uppercase();
+        }
+    });
+    if cfg!(feature = "compact_on_take") {
+        assert_eq!(doc, "AB    ");
+    } else {
+        assert_eq!(doc, "A   B ");
+    }
+}
@@ -0,0 +1,86 @@
+//! Tests of using the read and write positions of a `Replacinator` through separate handles
+
+use replacinator::{Reader, Replacinator, WriteError, Writer};
+
+/// Decode `\xNN` escapes, reading with `reader` and writing with `writer`
+fn decode(reader: &mut Reader<'_, '_>, writer: &mut Writer<'_, '_>) {
+    while let Some(c) = reader.read_char() {
+        if c != '\\' || !reader.starts_with("x") {
+            writer.write_char(c);
+            continue;
+        }
+        reader.read_char();
+        let high = reader.read_char().and_then(|c| c.to_digit(16)).unwrap();
+        let low = reader.read_char().and_then(|c| c.to_digit(16)).unwrap();
+        writer.write_char(char::from((high * 16 + low) as u8));
+    }
+}
+
+#[test]
+fn handles_can_be_used_by_separate_components() {
+    let mut text = String::from(r"a\x41é\x7a");
+    Replacinator::new_in(&mut text, |src| {
+        let (mut reader, mut writer) = src.split_cursors();
+        decode(&mut reader, &mut writer);
+        assert_eq!(reader.remaining(), 0);
+        assert_eq!(src.start(), "aAéz");
+    });
+}
+
+#[test]
+fn the_reader_reports_the_remainder_without_reading() {
+    let mut text = String::from("éab");
+    Replacinator::new_in(&mut text, |src| {
+        let (mut reader, _) = src.split_cursors();
+        assert_eq!(reader.peek(), Some('é'));
+        assert!(reader.starts_with("éa"));
+        assert_eq!((reader.remaining(), reader.position().byte()), (4, 0));
+        assert_eq!(reader.read_char(), Some('é'));
+        assert_eq!((reader.remaining(), reader.position().byte()), (2, 2));
+        assert!(!reader.starts_with("é"));
+        reader.read_char();
+        reader.read_char();
+        assert_eq!((reader.peek(), reader.read_char()), (None, None));
+    });
+}
+
+#[test]
+fn the_writer_can_only_use_space_freed_by_the_reader() {
+    let mut text = String::from("éa");
+    Replacinator::new_in(&mut text, |src| {
+        let (mut reader, mut writer) = src.split_cursors();
+        assert_eq!(writer.available(), 0);
+        assert_eq!(writer.try_write_str("x"), Err(WriteError::NoRoom));
+        reader.read_char();
+        assert_eq!(writer.available(), 2);
+        assert_eq!(writer.try_write_str("xyz"), Err(WriteError::NoRoom));
+        assert_eq!(writer.try_write_str("xy"), Ok(()));
+        assert_eq!(writer.available(), 0);
+        reader.read_char();
+        writer.write_str("z");
+        assert_eq!(src.start(), "xyz");
+    });
+}
+
+#[test]
+fn the_writer_reports_no_space_once_aborted() {
+    let mut text = String::from("ab");
+    Replacinator::new_in(&mut text, |src| {
+        src.read_char();
+        src.abort();
+        let (_, mut writer) = src.split_cursors();
+        assert_eq!(writer.available(), 0);
+        assert_eq!(writer.try_write_str("x"), Err(WriteError::Aborted));
+    });
+}
+
+#[test]
+#[should_panic(expected = "Not enough room to write 2 bytes")]
+fn writing_past_the_read_position_panics() {
+    let mut text = String::from("ab");
+    Replacinator::new_in(&mut text, |src| {
+        let (mut reader, mut writer) = src.split_cursors();
+        reader.read_char();
+        writer.write_str("xy");
+    });
+}
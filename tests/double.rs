@@ -0,0 +1,83 @@
+//! Tests of rewriting from both ends at once
+
+use replacinator::DoubleReplacinator;
+
+#[test]
+fn matches_can_be_moved_to_the_end() {
+    let mut text = String::from("a1b2é3");
+    DoubleReplacinator::new_in(&mut text, |src| {
+        while let Some(c) = src.read_front() {
+            if c.is_ascii_digit() {
+                if src.back_room() == 0 {
+                    src.shift_gap_to_back();
+                }
+                src.write_back_char(c);
+            } else {
+                src.write_front_char(c);
+            }
+        }
+        assert_eq!(
+            (src.start(), src.remainder(), src.end()),
+            ("abé", "", "321")
+        );
+    });
+    assert_eq!(text, "abé321");
+}
+
+#[test]
+fn reading_from_both_ends_reverses_in_place() {
+    let mut text = String::from("ab中d");
+    DoubleReplacinator::new_in(&mut text, |src| {
+        while let (Some(front), Some(back)) = (src.peek_front(), src.peek_back()) {
+            if front.len_utf8() != back.len_utf8() || src.remainder().len() <= back.len_utf8() {
+                break;
+            }
+            src.read_front();
+            src.read_back();
+            src.write_front_char(back);
+            src.write_back_char(front);
+        }
+        assert_eq!((src.start(), src.remainder(), src.end()), ("d", "b中", "a"));
+        assert_eq!(src.skip_back(), Some('中'));
+        assert_eq!(src.skip_front(), Some('b'));
+        assert_eq!(src.skip_front(), None);
+        assert_eq!(src.skip_back(), None);
+        src.start_mut().make_ascii_uppercase();
+        src.end_mut().make_ascii_uppercase();
+    });
+    assert_eq!(text, "DB中A");
+}
+
+#[test]
+fn shifting_the_gap_gives_all_the_room_to_one_side() {
+    let mut text = String::from("xyzw");
+    DoubleReplacinator::new_in(&mut text, |src| {
+        src.read_front();
+        src.read_back();
+        assert_eq!((src.front_room(), src.back_room()), (1, 1));
+        src.shift_gap_to_front();
+        assert_eq!(
+            (src.front_room(), src.back_room(), src.remainder()),
+            (2, 0, "yz")
+        );
+        src.write_front_str("ab");
+        src.remainder_mut().make_ascii_uppercase();
+        src.read_front();
+        src.shift_gap_to_back();
+        assert_eq!(
+            (src.front_room(), src.back_room(), src.remainder()),
+            (0, 1, "Z")
+        );
+    });
+    assert_eq!(text, "abZ ");
+}
+
+#[test]
+#[should_panic(expected = "Cannot write more bytes than there is room for at the front")]
+fn writing_beyond_the_room_panics() {
+    let mut text = String::from("ab");
+    DoubleReplacinator::new_in(&mut text, |src| {
+        src.read_back();
+        src.write_front_char('c');
+    });
+}
@@ -0,0 +1,98 @@
+//! Tests of recording the edits made through a `Replacinator`
+#![cfg(feature = "alloc")]
+
+use replacinator::{
+    edits::{Edit, EditLog},
+    html, transforms, unescape, Replacinator,
+};
+
+fn edit(original: std::ops::Range<usize>, output: std::ops::Range<usize>) -> Edit {
+    Edit { original, output }
+}
+
+#[test]
+fn text_written_back_unchanged_is_not_an_edit() {
+    let mut text = String::from("aé b");
+    let (_, log) = Replacinator::new_in_logged(&mut text, |src| {
+        src.skip_char();
+        let c = src.read_char().unwrap();
+        src.write_char(c);
+        src.skip_while(|_| true);
+    });
+    assert_eq!(log.edits(), EditLog::new().edits());
+    assert_eq!((log.to_original(3), log.to_output(3)), (3, 3));
+}
+
+#[test]
+fn removals_and_replacements_are_recorded() {
+    let mut text = String::from("x&amp;y--z");
+    let (_, log) = Replacinator::new_in_logged(&mut text, |src| {
+        src.skip_char();
+        src.discard_n(5);
+        src.write_char('&');
+        src.skip_char();
+        src.discard_n(2);
+        src.skip_char();
+    });
+    assert_eq!(log.edits(), &[edit(1..6, 1..2), edit(7..9, 3..3)]);
+    // Offsets after an edit move with it, and those inside it map to the start of its
+    // replacement
+    assert_eq!(log.to_original(2), 6);
+    assert_eq!(log.to_original(3), 9);
+    assert_eq!(log.to_output(3), 1);
+    assert_eq!(log.to_output(6), 2);
+    assert_eq!(log.to_output(8), 3);
+    assert_eq!(log.to_output(9), 3);
+}
+
+#[test]
+fn adjacent_edits_are_merged() {
+    let mut text = String::from("abcd");
+    let (_, log) = Replacinator::new_in_logged(&mut text, |src| {
+        src.discard_n(1);
+        src.discard_n(1);
+        src.write_str("X");
+        src.skip_char();
+    });
+    assert_eq!(log.edits(), &[edit(0..2, 0..1)]);
+}
+
+#[test]
+fn transforms_can_be_logged() {
+    let mut text = String::from("a /* é */ b");
+    let (output, log) = Replacinator::new_in_logged(&mut text, |src| {
+        let output = transforms::strip_comments(src, transforms::CommentSyntax::C).unwrap();
+        let b = output.find('b').unwrap();
+        (output.to_string(), b)
+    });
+    assert_eq!(output.0, "a   b");
+    assert_eq!(log.to_original(output.1), 11);
+    assert_eq!(log.to_output(11), output.1);
+
+    let mut text = String::from("&lt;é&gt;");
+    let (output, log) =
+        Replacinator::new_in_logged(&mut text, |src| html::decode_entities(src).to_string());
+    assert_eq!(output, "<é>");
+    assert_eq!(log.to_original(1), 4);
+    assert_eq!(log.to_original(3), 6);
+
+    let mut text = String::from(r"a\né");
+    let (output, log) = Replacinator::new_in_logged(&mut text, |src| {
+        unescape::unescape_json(src).map(|output| output.to_string())
+    });
+    assert_eq!(output.unwrap(), "a\né");
+    assert_eq!(log.to_original(2), 3);
+}
+
+#[test]
+fn rewinding_forgets_the_edits_since_the_checkpoint() {
+    let mut text = String::from("ab-cd");
+    let (_, log) = Replacinator::new_in_logged(&mut text, |src| {
+        src.skip_str("ab");
+        let checkpoint = src.checkpoint();
+        src.discard_n(1);
+        assert!(src.rewind(checkpoint));
+        src.skip_while(|_| true);
+    });
+    assert_eq!(log.edits(), &[]);
+}
@@ -0,0 +1,69 @@
+//! Tests of the fixed-width record functions
+
+use replacinator::fixed_width::{self, Unit};
+use replacinator::Replacinator;
+
+#[test]
+fn fields_are_split_and_trimmed() {
+    let mut record = String::from("ann   42 London  rest");
+    let mut fields = fixed_width::fields(&mut record, &[6, 3, 8], Unit::Bytes);
+    assert_eq!(fields.next().as_deref(), Some("ann"));
+    assert_eq!(fields.next().as_deref(), Some("42"));
+    assert_eq!(fields.next().as_deref(), Some("London"));
+    assert_eq!(fields.next(), None);
+    assert_eq!(fields.into_remainder(), "rest");
+}
+
+#[test]
+fn fields_past_the_end_are_empty() {
+    let mut record = String::from("ab");
+    let fields: Vec<&str> = fixed_width::fields(&mut record, &[1, 4, 2], Unit::Chars)
+        .map(|f| &*f)
+        .collect();
+    assert_eq!(fields, ["a", "b", ""]);
+    let mut record = String::from("    ");
+    let fields: Vec<&str> = fixed_width::fields(&mut record, &[2], Unit::Bytes)
+        .map(|f| &*f)
+        .collect();
+    assert_eq!(fields, [""]);
+}
+
+#[test]
+fn fields_measure_chars_or_bytes() {
+    let mut record = String::from("éa€b");
+    let fields: Vec<&str> = fixed_width::fields(&mut record, &[2, 2], Unit::Chars)
+        .map(|f| &*f)
+        .collect();
+    assert_eq!(fields, ["éa", "€b"]);
+    let mut record = String::from("éa€b");
+    let fields: Vec<&str> = fixed_width::fields(&mut record, &[3, 2, 1], Unit::Bytes)
+        .map(|f| &*f)
+        .collect();
+    // The second field would end inside '€', so no more fields are yielded
+    assert_eq!(fields, ["éa"]);
+}
+
+#[test]
+fn reader_continues_after_the_last_field() {
+    let mut text = String::from("00ab**x**\nnext");
+    Replacinator::new_in(&mut text, |src| {
+        let fields: Vec<&mut str> = fixed_width::reader(src, &[2, 2], Unit::Bytes).collect();
+        assert_eq!(fields, ["00", "ab"]);
+        let fields: Vec<&mut str> = fixed_width::reader(src, &[5], Unit::Chars)
+            .trim_pad('*')
+            .collect();
+        assert_eq!(fields, ["x"]);
+        assert_eq!(src.remainder(), "\nnext");
+    });
+}
+
+#[test]
+fn reader_stops_inside_a_character() {
+    let mut text = String::from("é");
+    Replacinator::new_in(&mut text, |src| {
+        let mut fields = fixed_width::reader(src, &[1], Unit::Bytes);
+        assert_eq!(fields.next(), None);
+        assert_eq!(fields.next(), None);
+        assert_eq!(src.remainder(), "é");
+    });
+}
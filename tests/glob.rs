@@ -0,0 +1,33 @@
+//! Tests of glob pattern analysis
+
+use replacinator::glob;
+
+#[test]
+fn metacharacters_are_found_with_brackets_as_one_span() {
+    let spans: Vec<_> = glob::metacharacters("a*b?[!]x]{c}").collect();
+    assert_eq!(spans, [1..2, 3..4, 4..9, 9..10, 11..12]);
+}
+
+#[test]
+fn metacharacters_skip_escapes_and_report_unmatched_brackets() {
+    let spans: Vec<_> = glob::metacharacters(r"\*\[a[b").collect();
+    assert_eq!(spans, vec![5..6]);
+    assert!(glob::is_literal(r"plain\*é"));
+    assert!(glob::is_literal(""));
+    assert!(!glob::is_literal("]"));
+}
+
+#[test]
+fn strip_removes_metacharacters_and_escapes() {
+    let mut pattern = String::from(r"src/**/[ab]*.r\?s{x}");
+    assert_eq!(glob::strip(&mut pattern), "src//.r?sx");
+    let mut pattern = String::from("é[");
+    assert_eq!(glob::strip(&mut pattern), "é");
+    assert_eq!(pattern, "é ");
+}
+
+#[test]
+fn strip_drops_a_trailing_backslash() {
+    let mut pattern = String::from(r"a\");
+    assert_eq!(glob::strip(&mut pattern), "a");
+}
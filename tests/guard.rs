@@ -0,0 +1,73 @@
+//! Tests of `ReplacinatorGuard`
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use replacinator::ReplacinatorGuard;
+
+#[test]
+fn guard_can_be_used_across_calls() {
+    let mut text = String::from("a-b-c");
+    let mut guard = ReplacinatorGuard::new(&mut text);
+    for _ in 0..2 {
+        guard.with(|src| {
+            src.skip_char();
+            src.discard_char();
+        });
+    }
+    assert_eq!(guard.get().start(), "ab");
+    assert_eq!(guard.get().remainder(), "c");
+    guard.with(|src| src.skip_char());
+    assert_eq!(guard.finish(), "abc  ");
+}
+
+#[test]
+fn guard_is_valid_utf8_between_calls() {
+    let mut text = String::from("éé€");
+    let mut guard = ReplacinatorGuard::new(&mut text);
+    guard.with(|src| {
+        src.discard_char();
+        src.skip_char();
+    });
+    // Leaking the guard doesn't leave the string invalid
+    std::mem::forget(guard);
+    assert_eq!(text, "é  €");
+}
+
+#[test]
+fn guard_restores_the_string_after_a_panic() {
+    let mut text = String::from("abc");
+    let mut guard = ReplacinatorGuard::new(&mut text);
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        guard.with(|src| {
+            src.read_char();
+            panic!("transform failed");
+        })
+    }));
+    assert!(result.is_err());
+    assert_eq!(guard.get().remainder(), "bc");
+    assert_eq!(guard.try_with(|src| src.skip_char()), Ok(Some('b')));
+    assert_eq!(guard.finish(), "b c");
+}
+
+#[test]
+fn guard_of_empty_string() {
+    let mut text = String::new();
+    let mut guard = ReplacinatorGuard::new(&mut text);
+    assert_eq!(guard.with(|src| src.read_char()), None);
+    assert_eq!(guard.finish(), "");
+}
+
+#[test]
+fn finishing_compacted_closes_the_gap() {
+    let mut text = String::from("a--b-é");
+    let mut guard = ReplacinatorGuard::new(&mut text);
+    guard.with(|src| {
+        src.skip_char();
+        src.discard_n(2);
+        src.skip_char();
+        src.discard_char();
+    });
+    let (result, padding) = guard.finish_compacted();
+    assert_eq!((&*result, padding), ("abé", 3));
+    assert_eq!(text, "abé   ");
+}
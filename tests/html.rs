@@ -0,0 +1,47 @@
+//! Tests of HTML character reference decoding
+
+use replacinator::html;
+use replacinator::Replacinator;
+
+fn decoded(text: &str) -> String {
+    let mut text = String::from(text);
+    Replacinator::new_in(&mut text, |src| html::decode_entities(src).to_owned())
+}
+
+#[test]
+fn decode_entities_decodes_numeric_and_xml_references() {
+    assert_eq!(
+        decoded("&lt;a href=&quot;x&quot;&gt; &amp;&apos; &#233;&#xE9;&#X1F600;"),
+        "<a href=\"x\"> &' éé😀"
+    );
+}
+
+#[test]
+fn decode_entities_leaves_unknown_references() {
+    assert_eq!(
+        decoded("a & b &amp &unknown; &#; &#x; &#12a;"),
+        "a & b &amp &unknown; &#; &#x; &#12a;"
+    );
+    assert_eq!(decoded("&&amp;"), "&&");
+    assert_eq!(decoded(""), "");
+}
+
+#[test]
+fn decode_entities_replaces_invalid_code_points() {
+    assert_eq!(
+        decoded("&#0;&#xD800;&#x110000;&#99999999999;"),
+        "\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}"
+    );
+}
+
+#[cfg(feature = "html_entities")]
+#[test]
+fn decode_entities_decodes_html_named_entities() {
+    assert_eq!(decoded("caf&eacute; &nbsp;&copy;"), "café \u{a0}©");
+}
+
+#[cfg(not(feature = "html_entities"))]
+#[test]
+fn decode_entities_leaves_html_named_entities() {
+    assert_eq!(decoded("caf&eacute;"), "caf&eacute;");
+}
@@ -0,0 +1,99 @@
+//! Tests of the `std::io` adapters
+#![cfg(feature = "std")]
+
+use std::io::{ErrorKind, Read, Write};
+
+use replacinator::{
+    io::{Reader, Writer},
+    Replacinator,
+};
+
+#[test]
+fn writer_writes_to_the_first_section() {
+    let mut text = String::from("abcdef");
+    Replacinator::new_in(&mut text, |src| {
+        src.discard_n(4);
+        let mut writer = Writer::new(src);
+        write!(writer, "{}{}", 1, 2).unwrap();
+        writer.flush().unwrap();
+        let src = writer.into_inner();
+        assert_eq!(src.start(), "12");
+        assert_eq!(src.remainder(), "ef");
+    });
+    assert_eq!(text, "12  ef");
+}
+
+#[test]
+fn writer_adds_split_characters_once_complete() {
+    let mut text = String::from("abcd");
+    Replacinator::new_in(&mut text, |src| {
+        src.discard_n(4);
+        let mut writer = Writer::new(src);
+        let euro = "€".as_bytes();
+        assert_eq!(writer.write(&euro[..2]).unwrap(), 2);
+        assert_eq!(writer.write(&euro[2..]).unwrap(), 1);
+        assert_eq!(writer.write(&euro[..1]).unwrap(), 1);
+        // The incomplete character is discarded
+        assert_eq!(writer.into_inner().start(), "€");
+    });
+    assert_eq!(text, "€ ");
+}
+
+#[test]
+fn writer_needs_room_before_the_read_position() {
+    let mut text = String::from("abc");
+    Replacinator::new_in(&mut text, |src| {
+        src.discard_char();
+        let mut writer = Writer::new(src);
+        assert_eq!(writer.write(b"xy").unwrap(), 1);
+        assert_eq!(writer.write(b"y").unwrap(), 0);
+        assert_eq!(
+            writer.write_all(b"y").unwrap_err().kind(),
+            ErrorKind::WriteZero
+        );
+        assert_eq!(writer.into_inner().start(), "x");
+    });
+    assert_eq!(text, "xbc");
+}
+
+#[test]
+fn writer_rejects_invalid_utf8() {
+    let mut text = String::from("abcd");
+    Replacinator::new_in(&mut text, |src| {
+        src.discard_n(4);
+        let mut writer = Writer::new(src);
+        let error = writer.write(b"a\xFFb").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(writer.into_inner().start(), "a");
+    });
+    assert_eq!(text, "a   ");
+}
+
+#[test]
+fn reader_reads_the_remainder() {
+    let mut text = String::from("héllo");
+    Replacinator::new_in(&mut text, |src| {
+        let mut read = String::new();
+        Reader::new(src).read_to_string(&mut read).unwrap();
+        assert_eq!(read, "héllo");
+        assert_eq!(src.remainder(), "");
+    });
+}
+
+#[test]
+fn reader_keeps_a_partially_read_character() {
+    let mut text = String::from("a€");
+    Replacinator::new_in(&mut text, |src| {
+        let mut reader = Reader::new(src);
+        let mut buf = [0; 2];
+        assert_eq!(reader.read(&mut buf).unwrap(), 2);
+        assert_eq!(&buf, b"a\xE2");
+        let src = reader.into_inner();
+        assert_eq!(src.remainder(), "€");
+        let mut reader = Reader::new(src);
+        let mut buf = [0; 8];
+        assert_eq!(reader.read(&mut buf).unwrap(), 3);
+        assert_eq!(reader.read(&mut buf).unwrap(), 0);
+        assert_eq!(reader.into_inner().remainder(), "");
+    });
+}
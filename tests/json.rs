@@ -0,0 +1,264 @@
+//! Tests of the in-place JSON functions
+
+use replacinator::{json, Replacinator};
+
+#[cfg(feature = "serde")]
+#[test]
+fn redact_masks_matching_values() {
+    let mut doc = String::from(r#"{"user":"ann","password":"hunter2","age":42}"#);
+    let masked = json::redact(&mut doc, &["password", "age"], 'x').unwrap();
+    assert_eq!(masked, 2);
+    assert_eq!(doc, r#"{"user":"ann","password":"xxxxxxx","age":0} "#);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn redact_matches_wildcards_and_nested_values() {
+    let mut doc = String::from(r#"{"a":{"token":"x"},"b":[{"token":"yz"}],"c":{"d":[1,"e"]}}"#);
+    let masked = json::redact(&mut doc, &["**.token", "c"], '*').unwrap();
+    assert_eq!(masked, 4);
+    assert_eq!(
+        doc,
+        r#"{"a":{"token":"*"},"b":[{"token":"**"}],"c":{"d":[0,"*"]}}"#
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn redact_with_a_wide_mask_never_grows_the_document() {
+    let mut doc = String::from(r#"{"password":"abc","pin":"é€"}"#);
+    let masked = json::redact(&mut doc, &["password", "pin"], '█').unwrap();
+    assert_eq!(masked, 2);
+    assert_eq!(doc, r#"{"password":"***","pin":"**█"}"#);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn redact_masks_escapes_as_one_character() {
+    let mut doc = String::from(r#"{"k":"a\u00e9"}"#);
+    json::redact(&mut doc, &["k"], '█').unwrap();
+    assert_eq!(doc, r#"{"k":"*█"}   "#);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn redact_rejects_masks_which_need_escaping() {
+    let mut doc = String::from(r#"{"k":"v"}"#);
+    let error = json::redact(&mut doc, &["k"], '"').unwrap_err();
+    assert_eq!(error.kind(), json::ErrorKind::InvalidMask);
+    assert_eq!(doc, r#"{"k":"v"}"#);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn redact_reports_invalid_documents() {
+    let mut doc = String::from(r#"{"k":"v""#);
+    let error = json::redact(&mut doc, &["k"], '*').unwrap_err();
+    assert_eq!(error.kind(), json::ErrorKind::UnexpectedEnd);
+    assert!(std::str::from_utf8(doc.as_bytes()).is_ok());
+}
+
+#[test]
+fn pointer_finds_nested_values() {
+    let mut doc = String::from(r#"{"a":{"b":[10,{"c":true}]},"d":null}"#);
+    assert_eq!(json::pointer(&mut doc, "/a/b/1/c").as_deref(), Some("true"));
+    let mut doc = String::from(r#"{"a":{"b":[10,{"c":true}]},"d":null}"#);
+    assert_eq!(json::pointer(&mut doc, "/a/b/0").as_deref(), Some("10"));
+    let mut doc = String::from(r#"{"a":{"b":[10]}}"#);
+    assert_eq!(
+        json::pointer(&mut doc, "/a").as_deref(),
+        Some(r#"{"b":[10]}"#)
+    );
+}
+
+#[test]
+fn pointer_of_empty_string_is_the_whole_document() {
+    let mut doc = String::from(" [1, 2] ");
+    assert_eq!(json::pointer(&mut doc, "").as_deref(), Some("[1, 2]"));
+}
+
+#[test]
+fn pointer_unescapes_strings_and_tokens() {
+    let mut doc = String::from(r#"{"a/b":{"m~n":"x\ty"}}"#);
+    assert_eq!(
+        json::pointer(&mut doc, "/a~1b/m~0n").as_deref(),
+        Some("x\ty")
+    );
+}
+
+#[test]
+fn pointer_returns_none_for_missing_or_invalid_paths() {
+    for pointer in ["/missing", "/a/5", "/a/01", "a", "/a/x", "/a~2"] {
+        let mut doc = String::from(r#"{"a":[1,2]}"#);
+        assert_eq!(json::pointer(&mut doc, pointer), None, "{}", pointer);
+        assert!(std::str::from_utf8(doc.as_bytes()).is_ok());
+    }
+    let mut doc = String::from(r#"{"a":"#);
+    assert_eq!(json::pointer(&mut doc, "/a"), None);
+}
+
+#[test]
+fn records_split_lines_and_skip_blank_ones() {
+    let mut buffer = String::from("{\"a\":1}\r\n\n  \n[2]\n\"last\"");
+    let records: Vec<&str> = json::records(&mut buffer).map(|r| &*r).collect();
+    assert_eq!(records, ["{\"a\":1}", "[2]", "\"last\""]);
+}
+
+#[test]
+fn records_of_an_empty_buffer_are_empty() {
+    let mut buffer = String::new();
+    assert_eq!(json::records(&mut buffer).next(), None);
+}
+
+#[test]
+fn streaming_records_keep_the_partial_line() {
+    let mut buffer = String::from("1\n2\n{\"inc");
+    let mut records = json::streaming_records(&mut buffer);
+    assert_eq!(records.next().as_deref(), Some("1"));
+    assert_eq!(records.next().as_deref(), Some("2"));
+    assert_eq!(records.next(), None);
+    assert_eq!(records.into_partial(), "{\"inc");
+}
+
+#[test]
+fn records_can_be_edited_in_place() {
+    let mut buffer = String::from("abc\ndef\n");
+    for record in json::records(&mut buffer) {
+        record.make_ascii_uppercase();
+    }
+    assert_eq!(buffer, "ABC\nDEF\n");
+}
+
+/// Canonicalize `doc`, returning the canonical text and the outcome
+fn canonical(doc: &str) -> Result<(String, json::Canonicalization), json::Error> {
+    let mut doc = String::from(doc);
+    let result = json::canonicalize(&mut doc)?;
+    assert!(doc[result.len()..].trim().is_empty());
+    doc.truncate(result.len());
+    Ok((doc, result))
+}
+
+#[test]
+fn canonicalize_removes_whitespace_and_escapes() {
+    let (doc, result) = canonical(" { \"a\" : [ 1 , \"\\u0041\\/\" ] , \"b\" : null } ").unwrap();
+    assert_eq!(doc, r#"{"a":[1,"A/"],"b":null}"#);
+    assert!(result.is_canonical());
+    assert!(!result.is_empty());
+}
+
+#[test]
+fn canonicalize_keeps_required_escapes() {
+    let (doc, _) = canonical(r#""\u0001\n\"\\é""#).unwrap();
+    assert_eq!(doc, r#""\u0001\n\"\\é""#);
+}
+
+#[test]
+fn canonicalize_normalises_numbers_without_growing_them() {
+    let (doc, result) = canonical("[1.50, -0.0, 2E+01, 1.0e-05]").unwrap();
+    assert_eq!(doc, "[1.5,0,2e1,1e-5]");
+    assert!(!result.numbers_canonical());
+    let (doc, result) = canonical("[10, 0.25]").unwrap();
+    assert_eq!(doc, "[10,0.25]");
+    assert!(result.numbers_canonical());
+}
+
+#[test]
+fn canonicalize_reports_unsorted_keys() {
+    let (doc, result) = canonical(r#"{"b":1,"a":2}"#).unwrap();
+    assert_eq!(doc, r#"{"b":1,"a":2}"#);
+    assert!(!result.keys_sorted());
+    assert!(!result.is_canonical());
+}
+
+#[test]
+fn canonicalize_reports_errors() {
+    let error = canonical("[1,]").unwrap_err();
+    assert_eq!(error.kind(), json::ErrorKind::UnexpectedChar(']'));
+    assert_eq!(error.position().byte(), 3);
+    let error = canonical(r#""\q""#).unwrap_err();
+    assert_eq!(error.kind(), json::ErrorKind::InvalidEscape);
+    let error = canonical("").unwrap_err();
+    assert_eq!(error.kind(), json::ErrorKind::UnexpectedEnd);
+}
+
+#[test]
+fn project_keeps_only_the_named_members() {
+    let mut doc = String::from(r#"{ "a" : 1, "b c" : [2, {"a": 3}], "d": "e" }"#);
+    assert_eq!(json::project(&mut doc, &["b c", "d"]).unwrap(), 1);
+    assert_eq!(doc.trim_end(), r#"{"b c" : [2, {"a": 3}],"d": "e"}"#);
+    let mut doc = String::from(r#"{"a":1,"b":2}"#);
+    assert_eq!(json::project(&mut doc, &[]).unwrap(), 2);
+    assert_eq!(doc.trim_end(), "{}");
+    let mut doc = String::from("{ }");
+    assert_eq!(json::project(&mut doc, &["a"]).unwrap(), 0);
+    assert_eq!(doc.trim_end(), "{}");
+}
+
+#[test]
+fn project_rejects_anything_but_an_object() {
+    let mut doc = String::from("[1]");
+    let error = json::project(&mut doc, &["a"]).unwrap_err();
+    assert_eq!(error.kind(), json::ErrorKind::UnexpectedChar('['));
+    let mut doc = String::from(r#"{"a":1} 2"#);
+    let error = json::project(&mut doc, &["a"]).unwrap_err();
+    assert_eq!(error.kind(), json::ErrorKind::UnexpectedChar('2'));
+    let mut doc = String::from(r#"{"a":1,"b":"#);
+    let error = json::project(&mut doc, &["a"]).unwrap_err();
+    assert_eq!(error.kind(), json::ErrorKind::UnexpectedEnd);
+    assert!(std::str::from_utf8(doc.as_bytes()).is_ok());
+}
+
+/// Parse `text` as an array of strings, returning the strings and the text after the array
+fn string_array(text: &str) -> Result<(Vec<String>, String), json::Error> {
+    let mut text = String::from(text);
+    Replacinator::new_in(&mut text, |src| {
+        let strings = json::parse_string_array(src)?
+            .map(|string| string.to_string())
+            .collect();
+        Ok((strings, src.remainder().to_string()))
+    })
+}
+
+#[test]
+fn parse_string_array_unescapes_each_string() {
+    let (strings, rest) = string_array(r#" [ "a\"b" , "é😀", "" ] rest"#).unwrap();
+    assert_eq!(
+        (strings, rest.as_str()),
+        (
+            vec!["a\"b".to_string(), "é😀".to_string(), String::new()],
+            " rest"
+        )
+    );
+    let (strings, rest) = string_array("[]").unwrap();
+    assert_eq!((strings.len(), rest.as_str()), (0, ""));
+}
+
+#[test]
+fn parse_string_array_only_rejects_the_controls_json_requires_escaping() {
+    // DEL and the C1 controls don't need to be escaped in JSON
+    let (strings, _) = string_array("[\"a\u{7f}b\", \"\u{85}\u{9f}\"]").unwrap();
+    assert_eq!(strings, ["a\u{7f}b", "\u{85}\u{9f}"]);
+    for text in ["[\"a\tb\"]", "[\"a\nb\"]", "[\"a\u{1f}\"]"] {
+        let error = string_array(text).unwrap_err();
+        let c = text[3..].chars().next().unwrap();
+        assert_eq!(
+            (error.kind(), error.position().byte()),
+            (json::ErrorKind::UnexpectedChar(c), 3)
+        );
+    }
+}
+
+#[test]
+fn parse_string_array_reads_nothing_if_the_array_is_invalid() {
+    let mut text = String::from(r#"["a", "b"#);
+    Replacinator::new_in(&mut text, |src| {
+        assert!(json::parse_string_array(src).is_err());
+        assert_eq!(src.remainder(), r#"["a", "b"#);
+    });
+}
+
+#[test]
+fn pointer_skips_strings_containing_del() {
+    let mut doc = String::from("{\"a\": \"x\u{7f}\u{85}\", \"b\": \"\u{7f}\"}");
+    assert_eq!(json::pointer(&mut doc, "/b").as_deref(), Some("\u{7f}"));
+}
@@ -0,0 +1,104 @@
+//! Tests of indentation tracking
+
+use replacinator::{
+    layout::{Change, ErrorKind, Indentation},
+    Replacinator,
+};
+
+/// The change reported for each line of `text`
+fn changes(text: &str, stack: &mut [usize]) -> Vec<Result<Change, ErrorKind>> {
+    let mut text = String::from(text);
+    let mut indentation = Indentation::new(stack);
+    Replacinator::new_in(&mut text, |src| {
+        let mut changes = Vec::new();
+        while !src.remainder().is_empty() {
+            changes.push(indentation.line(src).map_err(|e| e.kind()));
+            src.skip_while(|c| c != '\n');
+            src.skip_char();
+        }
+        changes
+    })
+}
+
+#[test]
+fn indents_and_dedents_are_reported() {
+    use Change::*;
+    let text = "a\n  b\n\t\t  c\n  d\n \te\nf\n";
+    let found = changes(text, &mut [0; 4]);
+    let expected = [Same, Indent, Indent, Dedent(1), Same, Dedent(1)];
+    assert_eq!(found, expected.map(Ok));
+}
+
+#[test]
+fn several_levels_can_close_at_once() {
+    let mut stack = [0; 4];
+    let mut indentation = Indentation::new(&mut stack);
+    let mut text = String::from(" a\n   b\n    c\nd");
+    Replacinator::new_in(&mut text, |src| {
+        for _ in 0..3 {
+            assert_eq!(indentation.line(src).unwrap(), Change::Indent);
+            src.skip_while(|c| c != '\n');
+            src.skip_char();
+        }
+        assert_eq!((indentation.depth(), indentation.width()), (3, 4));
+        assert_eq!(indentation.line(src).unwrap(), Change::Dedent(3));
+        assert_eq!((indentation.depth(), indentation.width()), (0, 0));
+    });
+}
+
+#[test]
+fn blank_lines_do_not_change_the_level() {
+    let found = changes("  a\n   \n\n\r\n  b", &mut [0; 2]);
+    let expected = [
+        Change::Indent,
+        Change::Blank,
+        Change::Blank,
+        Change::Blank,
+        Change::Same,
+    ];
+    assert_eq!(found, expected.map(Ok));
+}
+
+#[test]
+fn finish_closes_every_level() {
+    let mut stack = [0; 2];
+    let mut indentation = Indentation::new(&mut stack);
+    let mut text = String::from(" a");
+    Replacinator::new_in(&mut text, |src| indentation.line(src).unwrap());
+    assert_eq!(indentation.finish(), 1);
+    assert_eq!(indentation.finish(), 0);
+}
+
+#[test]
+fn inconsistent_dedents_are_errors() {
+    let found = changes("a\n    b\n  c\n", &mut [0; 2]);
+    assert_eq!(found[2], Err(ErrorKind::InconsistentDedent));
+    let mut text = String::from("    b\n  c");
+    let mut stack = [0; 2];
+    let mut indentation = Indentation::new(&mut stack);
+    Replacinator::new_in(&mut text, |src| {
+        indentation.line(src).unwrap();
+        src.skip_while(|c| c != '\n');
+        src.skip_char();
+        let error = indentation.line(src).unwrap_err();
+        assert_eq!(error.position().byte(), 6);
+        assert_eq!(
+            error.to_string(),
+            "dedent does not match any outer indentation level at byte 6"
+        );
+    });
+}
+
+#[test]
+fn levels_beyond_the_stack_are_errors() {
+    let found = changes("a\n b\n  c\n", &mut [0; 1]);
+    assert_eq!(
+        found,
+        [
+            Ok(Change::Same),
+            Ok(Change::Indent),
+            Err(ErrorKind::TooDeep)
+        ]
+    );
+    assert_eq!(changes(" a", &mut []), [Err(ErrorKind::TooDeep)]);
+}
@@ -0,0 +1,93 @@
+//! Tests of the token scanner
+
+use replacinator::lex::{self, CharClass, Classes, TokenKind};
+
+fn tokens(text: &str, classes: Classes<'_>) -> Vec<(TokenKind, String)> {
+    let mut text = String::from(text);
+    lex::scan(&mut text, classes)
+        .map(|token| (token.kind(), token.text().to_string()))
+        .collect()
+}
+
+#[test]
+fn default_classes_split_a_simple_expression() {
+    use TokenKind::*;
+    let found = tokens("let x_1 = 0x1F+1.5e3;\té", Classes::DEFAULT);
+    let expected = [
+        (Ident, "let"),
+        (Whitespace, " "),
+        (Ident, "x_1"),
+        (Whitespace, " "),
+        (Punct, "="),
+        (Whitespace, " "),
+        (Number, "0x1F"),
+        (Punct, "+"),
+        (Number, "1.5e3"),
+        (Punct, ";"),
+        (Whitespace, "\t"),
+        (Ident, "é"),
+    ];
+    let expected: Vec<_> = expected.iter().map(|&(k, t)| (k, t.to_string())).collect();
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn punctuation_is_one_character_per_token() {
+    let found = tokens("=>€", Classes::DEFAULT);
+    let texts: Vec<_> = found.iter().map(|(_, text)| text.as_str()).collect();
+    assert_eq!(texts, ["=", ">", "€"]);
+    assert!(found.iter().all(|&(kind, _)| kind == TokenKind::Punct));
+}
+
+#[test]
+fn empty_text_has_no_tokens() {
+    let mut text = String::new();
+    let mut tokens = lex::scan(&mut text, Classes::DEFAULT);
+    assert!(tokens.next().is_none());
+    assert!(tokens.next().is_none());
+    assert_eq!(tokens.into_remainder(), "");
+}
+
+#[test]
+fn custom_classes_change_the_token_boundaries() {
+    let classes = Classes {
+        whitespace: CharClass::AnyOf(","),
+        number_start: CharClass::HexDigit,
+        number_continue: CharClass::HexDigit,
+        ident_start: CharClass::AnyOf("$"),
+        ident_continue: CharClass::Alphabetic,
+    };
+    let found = tokens("ff,,$name 1", classes);
+    let expected = [
+        (TokenKind::Number, "ff"),
+        (TokenKind::Whitespace, ",,"),
+        (TokenKind::Ident, "$name"),
+        (TokenKind::Punct, " "),
+        (TokenKind::Number, "1"),
+    ];
+    let expected: Vec<_> = expected.iter().map(|&(k, t)| (k, t.to_string())).collect();
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn char_classes_match_their_characters() {
+    assert!(CharClass::Digit.matches('7') && !CharClass::Digit.matches('a'));
+    assert!(CharClass::HexDigit.matches('F') && !CharClass::HexDigit.matches('g'));
+    assert!(CharClass::Alphabetic.matches('é') && !CharClass::Alphabetic.matches('1'));
+    assert!(CharClass::Alphanumeric.matches('1') && !CharClass::Alphanumeric.matches('_'));
+    assert!(CharClass::Whitespace.matches('\n') && !CharClass::Whitespace.matches('x'));
+    assert!(CharClass::AnyOf("ab").matches('b') && !CharClass::AnyOf("").matches('a'));
+    let union = CharClass::Union(&[CharClass::Digit, CharClass::AnyOf("-")]);
+    assert!(union.matches('-') && union.matches('0') && !union.matches('+'));
+    assert!(!CharClass::Union(&[]).matches('a'));
+}
+
+#[test]
+fn token_text_can_be_modified_in_place() {
+    let mut text = String::from("abc def");
+    let mut tokens = lex::scan(&mut text, Classes::DEFAULT);
+    tokens.next().unwrap().into_text().make_ascii_uppercase();
+    tokens.next();
+    assert_eq!(tokens.into_remainder(), "def");
+    assert_eq!(text, "ABC def");
+}
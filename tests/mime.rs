@@ -0,0 +1,103 @@
+//! Tests of decoding the encodings used in email
+
+use replacinator::{
+    mime::{self, ErrorKind},
+    Replacinator,
+};
+
+fn decoded(
+    text: &str,
+    mut decode: impl for<'a> FnMut(&mut Replacinator<'a>) -> Result<&'a mut str, mime::Error>,
+) -> Result<String, mime::Error> {
+    let mut text = String::from(text);
+    Replacinator::new_in(&mut text, |src| decode(src).map(|s| s.to_owned()))
+}
+
+fn quoted_printable(text: &str) -> Result<String, mime::Error> {
+    decoded(text, mime::decode_quoted_printable)
+}
+
+fn encoded_words(text: &str) -> Result<String, mime::Error> {
+    decoded(text, mime::decode_encoded_words)
+}
+
+#[test]
+fn quoted_printable_decodes_bytes_and_removes_soft_breaks() {
+    assert_eq!(quoted_printable("caf=C3=A9 =3D 1").unwrap(), "café = 1");
+    assert_eq!(
+        quoted_printable("long =\r\nline= \t\nend=").unwrap(),
+        "long lineend"
+    );
+    // A soft break can split the bytes of a character
+    assert_eq!(quoted_printable("=C3=\n=a9").unwrap(), "é");
+    assert_eq!(quoted_printable("plain\ntext").unwrap(), "plain\ntext");
+    assert_eq!(quoted_printable("").unwrap(), "");
+}
+
+#[test]
+fn quoted_printable_reports_malformed_bytes() {
+    for (text, kind, position) in [
+        ("ab=4", ErrorKind::UnexpectedEnd, 4),
+        ("=G0", ErrorKind::InvalidDigit('G'), 1),
+        ("=C3 ", ErrorKind::InvalidUtf8, 3),
+        ("=FF", ErrorKind::InvalidUtf8, 3),
+        ("=ED=A0=80", ErrorKind::InvalidUtf8, 9),
+    ] {
+        let error = quoted_printable(text).unwrap_err();
+        assert_eq!(
+            (error.kind(), error.position().byte()),
+            (kind, position),
+            "{}",
+            text
+        );
+    }
+    assert_eq!(
+        quoted_printable("=x1").unwrap_err().to_string(),
+        "invalid digit 'x' at byte 1"
+    );
+}
+
+#[test]
+fn encoded_words_are_decoded_in_both_encodings() {
+    assert_eq!(
+        encoded_words("Re: =?UTF-8?Q?caf=C3=A9_au_lait?= now").unwrap(),
+        "Re: café au lait now"
+    );
+    assert_eq!(encoded_words("=?utf-8?B?Y2Fmw6k=?=").unwrap(), "café");
+    assert_eq!(encoded_words("=?US-ASCII*en?q?a_b?=").unwrap(), "a b");
+    assert_eq!(encoded_words("=?UTF-8?B?w6k?=").unwrap(), "é");
+}
+
+#[test]
+fn whitespace_between_adjacent_encoded_words_is_removed() {
+    assert_eq!(
+        encoded_words("=?UTF-8?Q?a?= \r\n =?UTF-8?Q?b?= c =?UTF-8?Q?d?=").unwrap(),
+        "ab c d"
+    );
+}
+
+#[test]
+fn other_text_is_left_unchanged() {
+    for text in [
+        "=?ISO-8859-1?Q?caf=E9?=",
+        "=?UTF-8?X?abc?=",
+        "=?UTF-8?Q?has space?=",
+        "=?UTF-8?Q?unterminated",
+        "a =? b",
+        "",
+    ] {
+        assert_eq!(encoded_words(text).unwrap(), text);
+    }
+}
+
+#[test]
+fn encoded_words_report_malformed_text() {
+    let error = encoded_words("=?UTF-8?B?w6*?=").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidDigit('*'));
+    let error = encoded_words("=?UTF-8?B?w?=").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::UnexpectedEnd);
+    let error = encoded_words("=?UTF-8?B?ww==?=").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidUtf8);
+    let error = encoded_words("=?UTF-8?Q?=C3?=").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidUtf8);
+}
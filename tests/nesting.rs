@@ -0,0 +1,119 @@
+//! Tests of tracking nested delimiters
+
+use replacinator::{
+    nesting::{ErrorKind, Nesting},
+    Replacinator,
+};
+
+#[test]
+fn depth_follows_the_delimiters_read() {
+    let mut stack = [0; 4];
+    let mut nesting = Nesting::new(Nesting::BRACKETS, &mut stack);
+    let mut text = String::from("f([x], {é})");
+    Replacinator::new_in(&mut text, |src| {
+        let mut depths = Vec::new();
+        while let Some(c) = nesting.skip_char(src).unwrap() {
+            depths.push((c, nesting.depth()));
+        }
+        let expected = [
+            ('f', 0),
+            ('(', 1),
+            ('[', 2),
+            ('x', 2),
+            (']', 1),
+            (',', 1),
+            (' ', 1),
+            ('{', 2),
+            ('é', 2),
+            ('}', 1),
+            (')', 0),
+        ];
+        assert_eq!(depths, expected);
+        assert_eq!(src.start(), "f([x], {é})");
+    });
+}
+
+#[test]
+fn innermost_reports_the_open_pair() {
+    let mut stack = [0; 2];
+    let mut nesting = Nesting::new(&[('<', '>'), ('«', '»')], &mut stack);
+    assert_eq!(nesting.innermost(), None);
+    let mut text = String::from("<«");
+    Replacinator::new_in(&mut text, |src| {
+        nesting.skip_char(src).unwrap();
+        assert_eq!(nesting.innermost(), Some(('<', '>')));
+        nesting.skip_char(src).unwrap();
+        assert_eq!(nesting.innermost(), Some(('«', '»')));
+    });
+}
+
+#[test]
+fn skip_to_close_reads_past_the_innermost_close() {
+    let mut stack = [0; 4];
+    let mut nesting = Nesting::new(Nesting::BRACKETS, &mut stack);
+    let mut text = String::from("{a: [1, (2)], b} rest");
+    Replacinator::new_in(&mut text, |src| {
+        nesting.skip_char(src).unwrap();
+        nesting.skip_to_close(src).unwrap();
+        assert_eq!(src.remainder(), " rest");
+        assert_eq!(nesting.depth(), 0);
+        nesting.skip_to_close(src).unwrap();
+        assert_eq!(src.remainder(), "");
+    });
+}
+
+#[test]
+fn mismatched_closes_are_errors() {
+    let mut stack = [0; 4];
+    let mut nesting = Nesting::new(Nesting::BRACKETS, &mut stack);
+    let mut text = String::from("(]");
+    Replacinator::new_in(&mut text, |src| {
+        nesting.skip_char(src).unwrap();
+        let error = nesting.skip_char(src).unwrap_err();
+        assert_eq!(
+            error.kind(),
+            ErrorKind::Mismatched {
+                expected: Some(')'),
+                found: ']'
+            }
+        );
+        assert_eq!(error.to_string(), "expected ')' but found ']' at byte 1");
+        // The character was not read
+        assert_eq!(src.remainder(), "]");
+        assert_eq!(nesting.depth(), 1);
+    });
+    let mut stack = [0; 4];
+    let mut nesting = Nesting::new(Nesting::BRACKETS, &mut stack);
+    let mut text = String::from(")");
+    Replacinator::new_in(&mut text, |src| {
+        let error = nesting.skip_char(src).unwrap_err();
+        assert_eq!(error.to_string(), "unmatched ')' at byte 0");
+    });
+}
+
+#[test]
+fn unclosed_and_too_deep_nesting_are_errors() {
+    let mut stack = [0; 2];
+    let mut nesting = Nesting::new(Nesting::BRACKETS, &mut stack);
+    let mut text = String::from("((x");
+    Replacinator::new_in(&mut text, |src| {
+        nesting.skip_char(src).unwrap();
+        let error = nesting.skip_to_close(src).unwrap_err();
+        assert_eq!(
+            (error.kind(), error.position().byte()),
+            (ErrorKind::UnexpectedEnd, 3)
+        );
+    });
+    let mut stack = [0; 1];
+    let mut nesting = Nesting::new(Nesting::BRACKETS, &mut stack);
+    let mut text = String::from("[[");
+    Replacinator::new_in(&mut text, |src| {
+        nesting.skip_char(src).unwrap();
+        let error = nesting.skip_char(src).unwrap_err();
+        assert_eq!(
+            (error.kind(), error.position().byte()),
+            (ErrorKind::TooDeep, 1)
+        );
+        assert_eq!(src.remainder(), "[");
+    });
+}
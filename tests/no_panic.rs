@@ -0,0 +1,25 @@
+//! Tests that the `try_` methods can be used without any possibility of panicking
+//!
+//! The `no-panic` crate calls an undefined function from its panic handler, so it only links if
+//! the optimiser removed every panicking path. Only Linux is checked, as the linker flag which
+//! rejects undefined symbols is specific to it.
+#![cfg(target_os = "linux")]
+
+use std::{path::Path, process::Command};
+
+#[test]
+fn try_methods_link_without_a_panic_handler() {
+    // The linker flags are in `no-panic/.cargo/config.toml`, which is only found from inside it
+    let output = Command::new(env!("CARGO"))
+        .args(["build", "--release"])
+        .current_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("no-panic"))
+        .env_remove("RUSTFLAGS")
+        .env_remove("CARGO_ENCODED_RUSTFLAGS")
+        .output()
+        .expect("Could not run cargo");
+    assert!(
+        output.status.success(),
+        "A panicking path is reachable from the `try_` methods:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,185 @@
+//! Tests of observing the operations on a [`Replacinator`]
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use replacinator::{
+    transforms::{self, CommentSyntax},
+    FillStrategy, Observer, OverflowPolicy, Replacinator,
+};
+
+/// An observer which concatenates the text read and written, and records the other hooks
+#[derive(Default)]
+struct Log {
+    read: String,
+    written: String,
+    taken: Vec<String>,
+    synchronised: Vec<FillStrategy>,
+    rewinds: Vec<(usize, usize)>,
+}
+
+impl Observer for Log {
+    fn read(&mut self, text: &str) {
+        self.read.push_str(text);
+    }
+
+    fn write(&mut self, text: &str) {
+        self.written.push_str(text);
+    }
+
+    fn take_start(&mut self, text: &str) {
+        self.taken.push(text.to_owned());
+    }
+
+    fn take_remainder(&mut self, text: &str) {
+        self.taken.push(text.to_owned());
+    }
+
+    fn synchronise(&mut self, fill: FillStrategy) {
+        self.synchronised.push(fill);
+    }
+
+    fn rewind(&mut self, read_offset: usize, write_offset: usize) {
+        self.rewinds.push((read_offset, write_offset));
+    }
+}
+
+#[test]
+fn reads_and_writes_are_observed() {
+    let mut text = String::from("aé-b");
+    Replacinator::new_in_observed(&mut text, Log::default(), |src| {
+        src.skip_char();
+        src.read_char();
+        src.write_char('e');
+        src.discard_char();
+        assert!(src.skip_str("b"));
+        let log = src.observer();
+        assert_eq!((log.read.as_str(), log.written.as_str()), ("aé-b", "aeb"));
+    });
+    assert_eq!(text, "aeb  ");
+}
+
+#[test]
+fn taking_synchronising_and_rewinding_are_observed() {
+    let mut text = String::from("ab,cd");
+    Replacinator::new_in_observed(&mut text, Log::default(), |src| {
+        src.skip_str("ab");
+        src.discard_char();
+        src.synchronise_with(FillStrategy::Char('_'));
+        let checkpoint = src.checkpoint();
+        src.skip_char();
+        assert!(src.rewind(checkpoint));
+        src.take_start();
+        src.take_remainder();
+        let log = src.observer();
+        assert_eq!(log.taken, ["ab_", "cd"]);
+        assert_eq!(log.synchronised, [FillStrategy::Char('_')]);
+        assert_eq!(log.rewinds, [(3, 3)]);
+    });
+}
+
+#[test]
+fn the_observer_can_be_changed_between_operations() {
+    let mut text = String::from("abc");
+    let log = Replacinator::new_in_observed(&mut text, Log::default(), |src| {
+        src.skip_char();
+        src.observer_mut().read.clear();
+        src.discard_char();
+        core::mem::take(src.observer_mut())
+    });
+    assert_eq!((log.read.as_str(), log.written.as_str()), ("b", "a"));
+}
+
+#[test]
+fn transforms_notify_the_observer_of_their_edits() {
+    let mut text = String::from("a /* é */ b // c\nd");
+    Replacinator::new_in_observed(&mut text, Log::default(), |src| {
+        let stripped = transforms::strip_comments(src, CommentSyntax::C).unwrap();
+        assert_eq!(src.observer().written, *stripped);
+        assert_eq!(src.observer().read, "a /* é */ b // c\nd");
+    });
+}
+
+#[test]
+fn removing_written_text_is_reported_as_a_rewind() {
+    let mut text = String::from("aé cd");
+    Replacinator::new_in_observed(&mut text, Log::default(), |src| {
+        src.skip_str("aé ");
+        assert_eq!(src.truncate_buffer(1), 1);
+        let log = src.observer();
+        assert_eq!((log.read.as_str(), log.written.as_str()), ("aé cd", "aé "));
+        assert_eq!(log.rewinds, [(4, 1)]);
+    });
+}
+
+#[test]
+fn writes_which_overflow_are_not_observed() {
+    let mut text = String::from("abc");
+    Replacinator::new_in_observed(&mut text, Log::default(), |src| {
+        src.discard_char();
+        assert!(catch_unwind(AssertUnwindSafe(|| src.write_str("xy"))).is_err());
+        assert_eq!(src.observer().written, "");
+        src.set_overflow_policy(OverflowPolicy::Saturate);
+        src.write_str("zé");
+        assert_eq!((src.start(), src.observer().written.as_str()), ("z", "z"));
+    });
+    assert_eq!(text, "zbc");
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn bytes_written_through_io_are_observed_as_whole_characters() {
+    use std::io::Write;
+
+    use replacinator::io::Writer;
+
+    let mut text = String::from("abcd");
+    Replacinator::new_in_observed(&mut text, Log::default(), |src| {
+        src.discard_while(|c| c != 'd');
+        let mut writer = Writer::new(src);
+        assert_eq!(writer.write(&"é".as_bytes()[..1]).unwrap(), 1);
+        writer.write_all(&"é".as_bytes()[1..]).unwrap();
+        assert_eq!(src.observer().written, "é");
+    });
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn removed_text_is_an_edit() {
+    use replacinator::edits::Edit;
+
+    let mut text = String::from("abcdef");
+    let (_, log) = Replacinator::new_in_logged(&mut text, |src| {
+        src.discard_char();
+        src.discard_char();
+        src.write_str("XY");
+        src.skip_str("cd");
+        src.truncate_buffer(1)
+    });
+    assert_eq!(
+        log.edits(),
+        [Edit {
+            original: 0..6,
+            output: 0..1
+        }]
+    );
+    assert_eq!(log.to_original(1), 6);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn removed_text_is_replayed() {
+    use replacinator::replay::{self, Recorder};
+
+    let mut text = String::from("a1b22c");
+    let operations = Replacinator::new_in_observed(&mut text, Recorder::new(), |src| {
+        src.skip_str("a1b2");
+        src.truncate_buffer(3);
+        src.observer().operations().to_vec()
+    });
+    let encoded = replay::encode(&operations);
+    let mut copy = String::from("a1b22c");
+    Replacinator::new_in(&mut copy, |src| {
+        replay::replay(src, &replay::decode(&encoded).unwrap()).unwrap();
+    });
+    assert_eq!(copy, text);
+}
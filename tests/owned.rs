@@ -0,0 +1,236 @@
+//! Tests of applying transforms to an owned `String`
+#![cfg(feature = "alloc")]
+
+use std::{
+    cell::{Cell, RefCell},
+    ops::{ControlFlow, Range},
+    rc::Rc,
+};
+
+use replacinator::{
+    owned::{self, RetainRange},
+    percent,
+    rewrite::Sink,
+    transforms::{self, CommentSyntax},
+    unescape, AsMutStr, Replacinator,
+};
+
+fn escape_quotes(c: char, out: &mut Sink<'_>) -> ControlFlow<()> {
+    let _ = match c {
+        '"' => out.write_str("\\\""),
+        c => out.write_char(c),
+    };
+    ControlFlow::Continue(())
+}
+
+#[test]
+fn strings_are_shrunk_to_the_result() {
+    let mut string = String::from("a /* é */ b");
+    owned::transform_string(&mut string, |src| {
+        transforms::strip_comments(src, CommentSyntax::C).unwrap()
+    });
+    assert_eq!(string, "a   b");
+
+    let mut string = String::from("  padded  ");
+    owned::transform_string(&mut string, |src| {
+        src.skip_while(|c| c == ' ');
+        let trimmed = src.take_remainder();
+        let len = trimmed.trim_end().len();
+        &mut trimmed[..len]
+    });
+    assert_eq!(string, "padded");
+
+    let mut string = String::new();
+    owned::transform_string(&mut string, |src| src.take_remainder());
+    assert_eq!(string, "");
+}
+
+#[test]
+fn failed_transforms_return_their_error() {
+    let mut string = String::from(r"aé\n");
+    owned::unescape_json_string(&mut string).unwrap();
+    assert_eq!(string, "aé\n");
+
+    let mut string = String::from(r"a\q");
+    let error = owned::unescape_json_string(&mut string).unwrap_err();
+    assert_eq!(
+        (error.kind(), error.position().byte()),
+        (unescape::ErrorKind::InvalidEscape('q'), 3)
+    );
+
+    let mut string = String::from("a%C3%A9%20b");
+    owned::percent_decode_string(&mut string).unwrap();
+    assert_eq!(string, "aé b");
+
+    let mut string = String::from("a%2");
+    let error = owned::percent_decode_string(&mut string).unwrap_err();
+    assert_eq!(error.kind(), percent::ErrorKind::UnexpectedEnd);
+
+    let result = owned::try_transform_string(&mut string, |_| Err("failed"));
+    assert_eq!(result, Err("failed"));
+}
+
+#[test]
+fn growing_makes_room_for_a_longer_result() {
+    let mut string = String::from("say \"é\"");
+    owned::grow_transform(&mut string, escape_quotes);
+    assert_eq!(string, r#"say \"é\""#);
+
+    let mut string = String::from("no quotes");
+    let capacity = string.capacity();
+    owned::grow_transform(&mut string, escape_quotes);
+    assert_eq!(
+        (string.as_str(), string.capacity()),
+        ("no quotes", capacity)
+    );
+
+    let mut string = String::from("\"\"");
+    owned::grow_transform(&mut string, escape_quotes);
+    assert_eq!(string, r#"\"\""#);
+}
+
+#[test]
+#[should_panic(expected = "The rewriter wrote more than when measured")]
+fn growing_panics_if_the_rewriter_writes_more_the_second_time() {
+    let passes = Rc::new(Cell::new(0));
+    let mut string = String::from("ab");
+    let rewriter = {
+        let passes = passes.clone();
+        move |c: char, out: &mut Sink<'_>| {
+            if c == 'a' {
+                passes.set(passes.get() + 1);
+            }
+            let _ = out.write_char(c);
+            if passes.get() > 1 {
+                let _ = out.write_char(c);
+            }
+            ControlFlow::Continue(())
+        }
+    };
+    owned::grow_transform(&mut string, rewriter);
+}
+
+#[test]
+fn unchanged_text_is_borrowed() {
+    use std::borrow::Cow;
+
+    let text = "plain é";
+    assert!(matches!(
+        owned::unescape_json_cow(text),
+        Ok(Cow::Borrowed("plain é"))
+    ));
+    assert!(matches!(
+        owned::percent_decode_cow(text),
+        Ok(Cow::Borrowed("plain é"))
+    ));
+    assert!(matches!(
+        owned::normalize_newlines_cow(text),
+        Cow::Borrowed("plain é")
+    ));
+    assert!(matches!(
+        owned::unescape_json_cow("ab\" rest"),
+        Ok(Cow::Borrowed("ab"))
+    ));
+    assert!(matches!(
+        owned::unescape_json_cow(""),
+        Ok(Cow::Borrowed(""))
+    ));
+}
+
+#[test]
+fn changed_text_is_owned() {
+    use std::borrow::Cow;
+
+    let decoded = owned::unescape_json_cow(r#"a\"é" rest"#).unwrap();
+    assert!(matches!(decoded, Cow::Owned(_)));
+    assert_eq!(decoded, "a\"é");
+    let decoded = owned::percent_decode_cow("a%2Fb").unwrap();
+    assert!(matches!(decoded, Cow::Owned(_)));
+    assert_eq!(decoded, "a/b");
+    let normalized = owned::normalize_newlines_cow("a\r\nb\rc");
+    assert!(matches!(normalized, Cow::Owned(_)));
+    assert_eq!(normalized, "a\nb\nc");
+}
+
+#[test]
+fn invalid_text_is_an_error_when_copied() {
+    let error = owned::unescape_json_cow("a\nb").unwrap_err();
+    assert_eq!(error.kind(), unescape::ErrorKind::ControlCharacter('\n'));
+    let error = owned::unescape_json_cow(r"\ud800").unwrap_err();
+    assert_eq!(error.kind(), unescape::ErrorKind::LoneSurrogate(0xd800));
+    let error = owned::percent_decode_cow("%zz").unwrap_err();
+    assert_eq!(error.kind(), percent::ErrorKind::InvalidDigit('z'));
+    let error = owned::percent_decode_cow("%C3").unwrap_err();
+    assert_eq!(error.kind(), percent::ErrorKind::InvalidUtf8);
+}
+
+/// A string allocated in an arena, which is borrowed through its own method rather than
+/// [`AsMut`], and which records how many times it has been shrunk
+struct ArenaString {
+    text: String,
+    shrinks: usize,
+}
+
+impl ArenaString {
+    fn as_mut_str(&mut self) -> &mut str {
+        &mut self.text
+    }
+}
+
+impl<'a> AsMutStr<'a> for &'a mut ArenaString {
+    fn into_mut_str(self) -> &'a mut str {
+        self.as_mut_str()
+    }
+}
+
+impl RetainRange for ArenaString {
+    fn retain_range(&mut self, range: Range<usize>) {
+        self.text.retain_range(range);
+        self.shrinks += 1;
+    }
+}
+
+#[test]
+fn other_buffers_can_be_transformed() {
+    let mut buffer = ArenaString {
+        text: String::from(r"a\té  "),
+        shrinks: 0,
+    };
+    owned::unescape_json_string(&mut buffer).unwrap();
+    assert_eq!((buffer.text.as_str(), buffer.shrinks), ("a\té  ", 1));
+
+    let mut buffer = ArenaString {
+        text: String::from("  x%2Fy"),
+        shrinks: 0,
+    };
+    owned::transform_string(&mut buffer, |src| {
+        src.skip_while(|c| c == ' ');
+        src.take_remainder()
+    });
+    owned::percent_decode_string(&mut buffer).unwrap();
+    assert_eq!((buffer.text.as_str(), buffer.shrinks), ("x/y", 2));
+
+    // A failed transform doesn't shrink the buffer
+    let result = owned::try_transform_string(&mut buffer, |_| Err(()));
+    assert_eq!((result, buffer.shrinks), (Err(()), 2));
+}
+
+/// A borrow of a buffer whose type is from another crate and doesn't implement [`AsMut<str>`],
+/// so [`AsMutStr`] can only be implemented for a wrapper around it
+struct Borrowed<'a>(&'a mut RefCell<String>);
+
+impl<'a> AsMutStr<'a> for Borrowed<'a> {
+    fn into_mut_str(self) -> &'a mut str {
+        self.0.get_mut()
+    }
+}
+
+#[test]
+fn foreign_buffers_can_be_wrapped() {
+    let mut buffer = RefCell::new(String::from("a  é"));
+    let len = Replacinator::new_in(Borrowed(&mut buffer), |src| {
+        src.map_chars(|c| Some(c.to_ascii_uppercase()));
+        src.written_len()
+    });
+    assert_eq!((buffer.into_inner(), len), (String::from("A  é"), 5));
+}
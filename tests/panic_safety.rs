@@ -0,0 +1,253 @@
+//! Tests for the guarantee that the borrowed string is valid UTF-8 however a `Replacinator` is left
+//!
+//! The string must be valid after a panic partway through writing multi-byte characters, or
+//! after an async transform is dropped at an `.await`, and must be left unchanged after
+//! `Replacinator::abort`.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use replacinator::{OverflowPolicy, Replacinator, WriteError};
+
+#[test]
+fn valid_after_panic_in_new_in() {
+    let mut value = String::from("añb€c😀d");
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        Replacinator::new_in(&mut value, |src| {
+            src.discard_char();
+            src.discard_char();
+            src.discard_char();
+            // Leave two bytes of the gap unwritten, then fail
+            src.write_char('é');
+            panic!("Failed partway through");
+        })
+    }));
+    assert!(result.is_err());
+    assert!(std::str::from_utf8(value.as_bytes()).is_ok());
+    assert_eq!(value, "é  €c😀d");
+}
+
+#[test]
+fn valid_after_panic_from_overfull_write() {
+    let mut value = String::from("ab😀");
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        Replacinator::new_in(&mut value, |src| {
+            src.discard_char();
+            src.write_char('😀');
+        })
+    }));
+    assert!(result.is_err());
+    assert_eq!(value, " b😀");
+}
+
+#[test]
+fn abort_keeps_the_text() {
+    let mut value = String::from("one  two three");
+    let start = Replacinator::new_in(&mut value, |src| {
+        src.skip_while(|c| c != ' ');
+        src.discard_while(|c| c == ' ');
+        src.write_char(' ');
+        src.abort();
+        assert!(src.is_aborted());
+        assert_eq!(src.remainder(), "");
+        assert_eq!(src.read_char(), None);
+        src.start().to_string()
+    });
+    assert_eq!(start, "one  ");
+    assert_eq!(value, "one  two three");
+}
+
+#[test]
+fn writing_after_abort_panics() {
+    let mut value = String::from("abc");
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        Replacinator::new_in(&mut value, |src| {
+            src.discard_char();
+            src.abort();
+            src.write_char('x');
+        })
+    }));
+    assert!(result.is_err());
+    assert_eq!(value, " bc");
+}
+
+#[test]
+fn try_write_reports_instead_of_panicking() {
+    let mut value = String::from("ab😀");
+    Replacinator::new_in(&mut value, |src| {
+        src.discard_char();
+        assert_eq!(src.try_write_str("xy"), Err(WriteError::NoRoom));
+        assert_eq!(src.try_write_char('é'), Err(WriteError::NoRoom));
+        assert_eq!(src.try_write_char('x'), Ok(()));
+        src.abort();
+        assert_eq!(src.try_write_char('y'), Err(WriteError::Aborted));
+        assert_eq!(src.try_synchronise(), Ok(()));
+    });
+    assert_eq!(value, "xb😀");
+}
+
+#[test]
+fn saturating_write_keeps_what_fits() {
+    let mut value = String::from("abc€");
+    Replacinator::new_in(&mut value, |src| {
+        src.set_overflow_policy(OverflowPolicy::Saturate);
+        src.discard_char();
+        src.discard_char();
+        src.write_str("é€");
+        assert!(src.overflowed());
+        src.clear_overflowed();
+        src.set_overflow_policy(OverflowPolicy::Error);
+        src.discard_char();
+        src.write_str("xy");
+        assert!(src.overflowed());
+    });
+    assert_eq!(value, "é €");
+}
+
+/// Setting `REPLACINATOR_UNCHECKED` while building runs these tests against the unchecked path
+#[test]
+#[cfg(debug_assertions)]
+fn safety_checks_follow_the_testing_switch() {
+    let unchecked = option_env!("REPLACINATOR_UNCHECKED").is_some();
+    assert_eq!(
+        replacinator::raw::SAFETY_CHECKS,
+        replacinator::raw::PARANOID || !unchecked
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn new_in_catching_returns_the_panic() {
+    let mut value = String::from("ab\u{e9}cd");
+    let result = Replacinator::new_in_catching(&mut value, |src| {
+        src.discard_char();
+        src.skip_char();
+        src.discard_char();
+        panic!("Failed");
+    });
+    let payload = result.unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"Failed"));
+    assert_eq!(value, "b   cd");
+
+    let mut value = String::from("abc");
+    let result = Replacinator::new_in_catching(&mut value, |src| src.skip_char());
+    assert_eq!(result.ok(), Some(Some('a')));
+}
+
+/// Poll `future` once, without being woken
+#[cfg(feature = "async")]
+fn poll_once<F: std::future::Future>(future: std::pin::Pin<&mut F>) -> std::task::Poll<F::Output> {
+    use std::task::{Context, RawWaker, RawWakerVTable, Waker};
+
+    fn raw() -> RawWaker {
+        RawWaker::new(std::ptr::null(), &VTABLE)
+    }
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(|_| raw(), |_| {}, |_| {}, |_| {});
+    // Safety: The vtable functions do nothing, so are valid for any data pointer
+    let waker = unsafe { Waker::from_raw(raw()) };
+    future.poll(&mut Context::from_waker(&waker))
+}
+
+/// A future which is pending the first time it is polled
+#[cfg(feature = "async")]
+struct YieldOnce(bool);
+
+#[cfg(feature = "async")]
+impl std::future::Future for YieldOnce {
+    type Output = ();
+
+    fn poll(
+        mut self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<()> {
+        if self.0 {
+            return std::task::Poll::Ready(());
+        }
+        self.0 = true;
+        std::task::Poll::Pending
+    }
+}
+
+#[test]
+#[cfg(feature = "async")]
+fn valid_after_async_transform_is_cancelled() {
+    let mut value = String::from("añb€c😀d");
+    {
+        let future = Replacinator::new_in_async(&mut value, |mut guard| async move {
+            guard.with(|src| {
+                src.discard_char();
+                src.discard_char();
+                src.write_char('é');
+            });
+            YieldOnce(false).await;
+            guard.with(|src| src.skip_char());
+            guard.finish().len()
+        });
+        let mut future = Box::pin(future);
+        assert!(poll_once(future.as_mut()).is_pending());
+    }
+    assert!(std::str::from_utf8(value.as_bytes()).is_ok());
+    assert_eq!(value, "é b€c😀d");
+}
+
+#[test]
+#[cfg(feature = "async")]
+fn unchanged_after_async_transform_is_dropped_before_polling() {
+    let mut value = String::from("añb€c😀d");
+    let future = Replacinator::new_in_async(&mut value, |mut guard| async move {
+        guard.with(|src| src.discard_char());
+    });
+    drop(future);
+    assert_eq!(value, "añb€c😀d");
+}
+
+#[test]
+#[cfg(feature = "async")]
+fn async_transform_continues_after_await() {
+    let mut value = String::from("añb€c😀d");
+    let mut future = Box::pin(Replacinator::new_in_async(
+        &mut value,
+        |mut guard| async move {
+            guard.with(|src| {
+                src.discard_char();
+                src.skip_char();
+            });
+            YieldOnce(false).await;
+            guard.with(|src| {
+                src.discard_char();
+                src.skip_char();
+                src.take_start().to_string()
+            })
+        },
+    ));
+    assert!(poll_once(future.as_mut()).is_pending());
+    let taken = match poll_once(future.as_mut()) {
+        std::task::Poll::Ready(taken) => taken,
+        std::task::Poll::Pending => panic!("The transform should have finished"),
+    };
+    drop(future);
+    assert_eq!(taken, "ñ€");
+    assert!(std::str::from_utf8(value.as_bytes()).is_ok());
+}
+
+#[test]
+fn panics_outside_the_invariant_checks_do_not_poison() {
+    let mut value = String::from("ab😀");
+    let mut guard = replacinator::ReplacinatorGuard::new(&mut value);
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        guard.with(|src| {
+            src.discard_char();
+            // There is no room for this, so it panics before anything is written
+            src.write_char('😀');
+        })
+    }));
+    assert!(result.is_err());
+    assert!(!guard.get().is_poisoned());
+    assert_eq!(guard.get().validate(), Ok(()));
+    assert_eq!(guard.try_with(|src| src.try_read_char()), Ok(Ok(Some('b'))));
+    assert_eq!(guard.try_with(|src| src.try_synchronise()), Ok(Ok(())));
+    assert_eq!(guard.finish(), "  😀");
+    assert_eq!(
+        replacinator::Poisoned.to_string(),
+        "the replacinator was poisoned by a violated internal invariant"
+    );
+}
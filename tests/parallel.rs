@@ -0,0 +1,104 @@
+//! Tests of splitting a string into chunks to transform in parallel
+
+use replacinator::parallel::{self, Boundary};
+
+fn chunks(text: &str, count: usize, boundary: Boundary) -> Vec<String> {
+    let mut text = String::from(text);
+    parallel::split_mut(&mut text, count, boundary)
+        .map(|chunk| chunk.to_string())
+        .collect()
+}
+
+#[test]
+fn chunks_end_on_the_boundary() {
+    assert_eq!(chunks("abcdef", 3, Boundary::Char), ["ab", "cd", "ef"]);
+    assert_eq!(chunks("éééé", 3, Boundary::Char), ["éé", "é", "é"]);
+    assert_eq!(
+        chunks("a\nbb\nccc\nd", 3, Boundary::Line),
+        ["a\nbb\n", "ccc\n", "d"]
+    );
+    assert_eq!(chunks("no lines", 4, Boundary::Line), ["no lines"]);
+    assert_eq!(chunks("ab", 8, Boundary::Char), ["a", "b"]);
+    assert!(chunks("", 2, Boundary::Char).is_empty());
+}
+
+#[test]
+fn splitting_into_no_chunks_produces_one() {
+    assert_eq!(chunks("a\nb", 0, Boundary::Line), ["a\nb"]);
+    assert!(chunks("", 0, Boundary::Char).is_empty());
+}
+
+#[test]
+fn joined_results_are_moved_to_the_start() {
+    let mut text = String::from("ab-cdé-f");
+    assert_eq!(parallel::join(&mut text, [1..2, 3..7, 8..9]), "bcdéf");
+    assert_eq!(text, "bcdéf   ");
+    let mut text = String::from("abc");
+    assert_eq!(parallel::join(&mut text, []), "");
+    assert_eq!(text, "   ");
+}
+
+#[test]
+#[should_panic(expected = "The results to join must be in order, and must not overlap")]
+fn joining_overlapping_results_panics() {
+    parallel::join(&mut String::from("abcd"), [0..2, 1..3]);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn chunks_are_transformed_on_separate_threads() {
+    use replacinator::transforms::{self, Whitespace};
+
+    let mut text = String::from("  a  b\n c\r\n\n  d  e  \n");
+    let joined = parallel::transform_chunks(&mut text, 3, Boundary::Line, |src| {
+        transforms::collapse_whitespace(src, Whitespace::Ascii)
+    });
+    // Each chunk is collapsed separately, so no space is added between them
+    assert_eq!(joined, "a bc d e");
+    let mut text = String::new();
+    assert_eq!(
+        parallel::transform_chunks(&mut text, 2, Boundary::Char, |src| src.take_remainder()),
+        ""
+    );
+    let mut text = String::from(" a ");
+    assert_eq!(
+        parallel::transform_chunks(&mut text, 0, Boundary::Char, |src| {
+            transforms::collapse_whitespace(src, Whitespace::Ascii)
+        }),
+        "a"
+    );
+}
+
+#[test]
+#[cfg(feature = "std")]
+#[should_panic(expected = "failed in a chunk")]
+fn panics_in_a_chunk_are_propagated() {
+    let mut text = String::from("ab");
+    parallel::transform_chunks(&mut text, 2, Boundary::Char, |src| {
+        if src.remainder() == "b" {
+            panic!("failed in a chunk");
+        }
+        src.take_remainder()
+    });
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn scoped_threads_each_transform_a_chunk() {
+    use replacinator::Replacinator;
+
+    let mut text = String::from("first\nsecond\nthird");
+    let lens = Replacinator::scope(&mut text, |scope| {
+        let first = scope.spawn(1, Boundary::Line, |src| {
+            src.map_chars(|c| Some(c.to_ascii_uppercase()));
+            src.written_len()
+        });
+        assert_eq!(scope.remaining_len(), 12);
+        let rest = scope.spawn(100, Boundary::Char, |src| src.remainder().len());
+        assert_eq!(scope.remaining_len(), 0);
+        let empty = scope.spawn(1, Boundary::Char, |src| src.remainder().len());
+        [first, rest, empty].map(|thread| thread.join().unwrap())
+    });
+    assert_eq!(lens, [6, 12, 0]);
+    assert_eq!(text, "FIRST\nsecond\nthird");
+}
@@ -0,0 +1,109 @@
+//! Tests of the parser combinators
+
+use replacinator::{
+    parse::{self, discard_while1, skip_while1, tag, token, Parser},
+    Replacinator,
+};
+
+#[test]
+fn tag_and_skip_while1_need_a_match() {
+    let mut text = String::from("let x1");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(tag("lex").parse(src), None);
+        assert_eq!(tag("let").parse(src), Some(()));
+        assert_eq!(skip_while1(char::is_alphabetic).parse(src), None);
+        assert_eq!(discard_while1(char::is_whitespace).parse(src), Some(1));
+        assert_eq!(skip_while1(char::is_alphanumeric).parse(src), Some(2));
+        assert_eq!(src.remainder(), "");
+        assert_eq!(tag("").parse(src), Some(()));
+    });
+    assert_eq!(text, "letx1 ");
+}
+
+#[test]
+fn token_extracts_the_text_written() {
+    let mut text = String::from("a b-c");
+    Replacinator::new_in(&mut text, |src| {
+        let word = |c: char| c.is_alphabetic() || c == ' ';
+        let mut spaceless = token(
+            skip_while1(char::is_alphabetic)
+                .then(discard_while1(char::is_whitespace))
+                .then(skip_while1(word)),
+        );
+        assert_eq!(spaceless.parse(src).as_deref(), Some("ab"));
+        assert_eq!(token(tag("x")).parse(src), None);
+        assert_eq!(src.remainder(), "-c");
+    });
+}
+
+#[test]
+fn or_tries_the_alternative_from_the_same_place() {
+    let mut text = String::from("abd");
+    Replacinator::new_in(&mut text, |src| {
+        let mut parser = tag("ab")
+            .then(tag("c"))
+            .map(|_| 1)
+            .or(tag("abd").map(|()| 2));
+        assert_eq!(parser.parse(src), Some(2));
+        assert_eq!(src.remainder(), "");
+    });
+}
+
+#[test]
+fn then_rewinds_when_the_second_parser_fails() {
+    let mut text = String::from("ab");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(tag("a").then(tag("c")).parse(src), None);
+        assert_eq!(src.remainder(), "ab");
+        assert_eq!(tag("a").then(tag("b")).parse(src), Some(((), ())));
+    });
+}
+
+#[test]
+fn optional_always_succeeds() {
+    let mut text = String::from("ab");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(tag("b").optional().parse(src), Some(None));
+        assert_eq!(tag("a").optional().parse(src), Some(Some(())));
+        assert_eq!(src.remainder(), "b");
+    });
+}
+
+#[test]
+fn fold_many_combines_every_match() {
+    let mut text = String::from("1,22,333;");
+    Replacinator::new_in(&mut text, |src| {
+        let number = skip_while1(|c: char| c.is_ascii_digit());
+        let mut lengths = number
+            .then(tag(",").optional())
+            .map(|(len, _)| len)
+            .fold_many(0, |acc, len| acc * 10 + len);
+        assert_eq!(lengths.parse(src), Some(123));
+        assert_eq!(lengths.parse(src), Some(0));
+        assert_eq!(src.remainder(), ";");
+    });
+}
+
+#[test]
+fn fold_many_stops_on_an_empty_match() {
+    let mut text = String::from("aa");
+    Replacinator::new_in(&mut text, |src| {
+        let mut count = tag("").fold_many(0, |acc, ()| acc + 1);
+        assert_eq!(count.parse(src), Some(1));
+        assert_eq!(src.remainder(), "aa");
+    });
+}
+
+#[test]
+fn attempt_rewinds_a_failed_closure() {
+    let mut text = String::from("abc");
+    Replacinator::new_in(&mut text, |src| {
+        let mut skip_then_fail = |src: &mut Replacinator<'_>| {
+            src.skip_char();
+            src.discard_char();
+            None::<()>
+        };
+        assert_eq!(parse::attempt(&mut skip_then_fail, src), None);
+        assert_eq!(src.remainder(), "abc");
+    });
+}
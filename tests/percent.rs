@@ -0,0 +1,89 @@
+//! Tests of percent-decoding
+
+use replacinator::percent::{self, ErrorKind};
+use replacinator::Replacinator;
+
+fn decoded(text: &str, form: bool) -> Result<String, percent::Error> {
+    let mut text = String::from(text);
+    Replacinator::new_in(&mut text, |src| {
+        let decoded = if form {
+            percent::decode_form_in_place(src)
+        } else {
+            percent::decode_in_place(src)
+        };
+        decoded.map(|s| s.to_owned())
+    })
+}
+
+#[test]
+fn decode_in_place_decodes_multi_byte_characters() {
+    assert_eq!(
+        decoded("a%20b%2fc%C3%A9%e2%82%AC", false).unwrap(),
+        "a b/cé€"
+    );
+    assert_eq!(decoded("%F0%9F%98%80+", false).unwrap(), "😀+");
+    assert_eq!(decoded("", false).unwrap(), "");
+}
+
+#[test]
+fn decode_form_in_place_also_decodes_plus() {
+    assert_eq!(decoded("a+b%2B", true).unwrap(), "a b+");
+}
+
+#[test]
+fn decode_in_place_reports_malformed_sequences() {
+    let error = decoded("ab%2", false).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::UnexpectedEnd);
+    assert_eq!(error.position().byte(), 4);
+    let error = decoded("%g0", false).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidDigit('g'));
+    assert_eq!(error.position().byte(), 1);
+}
+
+#[test]
+fn decode_in_place_rejects_invalid_utf8() {
+    for text in ["%C3", "%C3A9", "%80", "%C0%80", "%ED%A0%80", "%F5%80%80%80"] {
+        let error = decoded(text, false).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidUtf8, "{}", text);
+    }
+}
+
+#[test]
+fn malformed_finds_every_bad_sequence() {
+    let errors: Vec<_> = percent::malformed_in("%2fé%g0 %C3 ok%41%2")
+        .map(|error| (error.kind(), error.position().byte()))
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            (ErrorKind::InvalidDigit('g'), 6),
+            (ErrorKind::InvalidUtf8, 12),
+            (ErrorKind::UnexpectedEnd, 20),
+        ]
+    );
+    assert_eq!(percent::malformed_in("a%20b%C3%A9").count(), 0);
+    assert_eq!(percent::malformed_in("").count(), 0);
+}
+
+#[test]
+fn malformed_agrees_with_decoding() {
+    for text in ["ab%2", "%g0", "%C3", "%C3A9", "%80", "%ED%A0%80", "é%é"] {
+        let scanned = percent::malformed_in(text).next().unwrap();
+        let error = decoded(text, false).unwrap_err();
+        assert_eq!(scanned, error, "{}", text);
+    }
+}
+
+#[test]
+fn malformed_reports_positions_in_the_original_string() {
+    let mut text = String::from("é-é%zz");
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_str("é-");
+        let error = percent::malformed(src).next().unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidDigit('z'));
+        assert_eq!((error.position().byte(), error.position().char()), (6, 4));
+        // Nothing was changed
+        assert_eq!(src.remainder(), "é%zz");
+    });
+    assert_eq!(text, "é-é%zz");
+}
@@ -0,0 +1,153 @@
+//! Tests of tracking positions in the original string
+
+use replacinator::{Position, Replacinator};
+
+#[test]
+fn position_counts_bytes_and_characters_read() {
+    let mut text = String::from("aé中😀b");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.position(), Position::default());
+        src.skip_char();
+        src.discard_char();
+        src.skip_while(|c| c != 'b');
+        let position = src.position();
+        assert_eq!((position.byte(), position.char()), (10, 4));
+        src.skip_char();
+        assert_eq!(src.position().byte(), 11);
+        assert_eq!(src.read_char(), None);
+        assert_eq!(src.position().byte(), 11);
+    });
+}
+
+#[test]
+fn position_survives_taking_and_rewinding() {
+    let mut text = String::from("ab cd");
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_while(|c| c != ' ');
+        src.take_start();
+        src.discard_char();
+        let checkpoint = src.checkpoint();
+        src.skip_char();
+        assert_eq!(src.position().byte(), 4);
+        assert!(src.rewind(checkpoint));
+        assert_eq!(src.position().byte(), 3);
+    });
+}
+
+#[test]
+fn position_continues_into_split_halves() {
+    let mut text = String::from("é,x");
+    Replacinator::new_in(&mut text, |src| {
+        let (left, mut right) = src.split_at_char(',').unwrap();
+        assert_eq!(left.get().position().byte(), 0);
+        right.with(|right| {
+            assert_eq!(right.position().byte(), 3);
+            right.skip_char();
+            assert_eq!(right.position().char(), 3);
+        });
+    });
+}
+
+#[test]
+fn spans_cover_the_text_read() {
+    let mut text = String::from("say \"hé\" now");
+    Replacinator::new_in(&mut text, |src| {
+        let quote = src.find('"').unwrap();
+        assert_eq!(quote.bytes(), 4..5);
+        assert_eq!((quote.start().char(), quote.len()), (4, 1));
+        assert_eq!(src.position().byte(), 0);
+        assert!(src.advance_read_to(quote.start().byte()));
+        let mark = src.mark();
+        assert_eq!(mark.position().byte(), 4);
+        src.discard_char();
+        src.skip_while(|c| c != '"');
+        src.discard_char();
+        let (span, token) = src.take_marked_spanned(mark).unwrap();
+        assert_eq!((span.bytes(), &*token), (4..9, "hé"));
+        let (span, start) = src.take_start_spanned();
+        assert!(span.is_empty());
+        assert_eq!((span.start().byte(), &*start), (9, ""));
+    });
+}
+
+#[test]
+fn char_indices_report_positions_without_reading() {
+    let mut text = String::from("aé\nb");
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_char();
+        let mut indices = src.char_indices();
+        let found: Vec<_> = indices.by_ref().map(|(p, c)| (p.byte(), c)).collect();
+        assert_eq!(found, [(1, 'é'), (3, '\n'), (4, 'b')]);
+        assert_eq!(indices.position().byte(), 5);
+        assert_eq!(src.remainder(), "é\nb");
+    });
+}
+
+#[test]
+#[cfg(feature = "line_tracking")]
+fn lines_and_columns_are_tracked() {
+    let mut text = String::from("ab\né\n\nxyz");
+    Replacinator::new_in(&mut text, |src| {
+        let positions: Vec<_> = src
+            .char_indices()
+            .map(|(p, c)| (c, p.line(), p.column()))
+            .collect();
+        assert_eq!(positions[3], ('é', 1, 0));
+        assert_eq!(positions[4], ('\n', 1, 1));
+        assert_eq!(positions[6], ('x', 3, 0));
+        src.skip_while(|c| c != 'z');
+        assert_eq!((src.position().line(), src.position().column()), (3, 2));
+    });
+}
+
+#[test]
+fn span_since_covers_the_text_read_after_the_mark() {
+    let mut text = String::from("ab,é,d");
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_str("ab");
+        let checkpoint = src.checkpoint();
+        let mark = src.mark();
+        assert!(src.span_since(mark).is_empty());
+        src.skip_char();
+        src.skip_char();
+        let span = src.span_since(mark);
+        assert_eq!(
+            (span.bytes(), span.start().char(), span.end().char()),
+            (2..5, 2, 4)
+        );
+        assert!(src.rewind(checkpoint));
+        src.skip_char();
+        // Only the text read again after rewinding is covered
+        assert_eq!(src.span_since(mark).bytes(), 2..3);
+    });
+}
+
+#[test]
+fn find_reports_where_the_match_is_or_none() {
+    let mut text = String::from("é-é->");
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_char();
+        let arrow = src.find("->").unwrap();
+        assert_eq!((arrow.bytes(), arrow.start().char()), (5..7, 3));
+        assert_eq!(src.find('x'), None);
+        let empty = src.find("").unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.start(), src.position());
+    });
+}
+
+#[test]
+fn errors_report_a_position_in_the_original_string() {
+    use replacinator::{percent, unescape};
+
+    let mut text = String::from("é\\q%");
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_char();
+        let error = unescape::unescape_json(src).unwrap_err();
+        let position: Position = error.position();
+        assert_eq!((position.byte(), position.char()), (4, 3));
+        let error = percent::decode_in_place(src).unwrap_err();
+        assert_eq!(error.position().byte(), 5);
+        assert!(error.position() > position);
+    });
+}
@@ -0,0 +1,805 @@
+//! Property tests for the safety argument of `Replacinator`
+//!
+//! Random sequences of operations are applied to random strings. After every operation, the
+//! sections must be consistent, and once the `Replacinator` is dropped (including by a panic
+//! partway through), the string must be valid UTF-8.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use proptest::prelude::*;
+use replacinator::{
+    parallel::{self, Boundary},
+    AsMutStr, FillStrategy, Replacinator, ReplacinatorGuard,
+};
+
+#[derive(Debug, Clone)]
+enum Op {
+    SkipChar,
+    ReadChar,
+    WriteChar(char),
+    WriteStr(String),
+    SkipWhileAlphabetic,
+    DiscardWhileWhitespace,
+    SkipStr(String),
+    MapChars(char),
+    Synchronise,
+    Checkpoint,
+    Rewind,
+    TakeStart,
+    Panic,
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        4 => Just(Op::SkipChar),
+        4 => Just(Op::ReadChar),
+        2 => any::<char>().prop_map(Op::WriteChar),
+        2 => ".{0,3}".prop_map(Op::WriteStr),
+        1 => Just(Op::SkipWhileAlphabetic),
+        1 => Just(Op::DiscardWhileWhitespace),
+        1 => ".{0,2}".prop_map(Op::SkipStr),
+        1 => any::<char>().prop_map(Op::MapChars),
+        1 => Just(Op::Synchronise),
+        1 => Just(Op::Checkpoint),
+        1 => Just(Op::Rewind),
+        1 => Just(Op::TakeStart),
+        1 => Just(Op::Panic),
+    ]
+}
+
+/// A string buffer which only lends out its contents through [`AsMutStr`], as one allocated in
+/// an arena might
+struct ArenaString<'b>(&'b mut str);
+
+impl<'a, 'b> AsMutStr<'a> for &'a mut ArenaString<'b> {
+    fn into_mut_str(self) -> &'a mut str {
+        self.0
+    }
+}
+
+/// Apply `ops` to `src`, checking that its sections are consistent after each one
+///
+/// `len` is the length of the string `src` was created from.
+fn apply(src: &mut Replacinator<'_>, ops: &[Op], len: usize) {
+    let mut checkpoint = None;
+    for op in ops {
+        match op {
+            Op::SkipChar => {
+                src.skip_char();
+            }
+            Op::ReadChar => {
+                src.read_char();
+            }
+            Op::WriteChar(c) => src.write_char(*c),
+            Op::WriteStr(s) => src.write_str(s),
+            Op::SkipWhileAlphabetic => {
+                src.skip_while(char::is_alphabetic);
+            }
+            Op::DiscardWhileWhitespace => {
+                src.discard_while(char::is_whitespace);
+            }
+            Op::SkipStr(s) => {
+                src.skip_str(s);
+            }
+            Op::MapChars(c) => {
+                let c = *c;
+                src.map_chars(|_| Some(c));
+            }
+            Op::Synchronise => src.synchronise(),
+            Op::Checkpoint => checkpoint = Some(src.checkpoint()),
+            Op::Rewind => {
+                if let Some(checkpoint) = checkpoint {
+                    let _ = src.rewind(checkpoint);
+                }
+            }
+            Op::TakeStart => {
+                src.take_start();
+            }
+            Op::Panic => panic!("A transform panicked"),
+        }
+        assert!(src.write_offset() <= src.read_offset());
+        assert_eq!(src.read_offset() + src.remainder().len(), len);
+        assert!(src.start().len() <= src.write_offset());
+        assert_eq!(src.validate(), Ok(()));
+        assert_eq!(src.remaining_len(), src.remainder().len());
+        assert_eq!(src.written_len() + src.gap_len(), src.consumed_len());
+        assert!(src.capacity_for_write() <= src.gap_len());
+    }
+}
+
+proptest! {
+    #[test]
+    fn valid_after_any_operations(text in ".{0,24}", ops in prop::collection::vec(op(), 0..24)) {
+        let mut value = text.clone();
+        let len = value.len();
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            Replacinator::new_in(&mut value, |src| apply(src, &ops, len))
+        }));
+        let _ = result;
+        prop_assert!(std::str::from_utf8(value.as_bytes()).is_ok());
+        prop_assert_eq!(value.len(), text.len());
+    }
+
+    #[test]
+    fn skipping_everything_is_identity(text in ".{0,64}") {
+        let mut value = text.clone();
+        Replacinator::new_in(&mut value, |src| while src.skip_char().is_some() {});
+        prop_assert_eq!(value, text);
+    }
+
+    #[test]
+    fn take_start_is_what_was_written(text in ".{0,64}") {
+        let mut value = text.clone();
+        let written = Replacinator::new_in(&mut value, |src| {
+            src.discard_while(char::is_whitespace);
+            src.skip_while(|c| !c.is_whitespace());
+            src.take_start().to_string()
+        });
+        let expected = text.trim_start().split(char::is_whitespace).next().unwrap_or("");
+        prop_assert_eq!(written, expected);
+    }
+
+    #[test]
+    fn positions_count_what_was_read(text in ".{0,64}") {
+        let mut value = text.clone();
+        Replacinator::new_in(&mut value, |src| loop {
+            let position = src.position();
+            assert_eq!(position.char(), text[..position.byte()].chars().count());
+            if let Some((next, c)) = src.char_indices().next() {
+                assert_eq!((next, Some(c)), (position, src.peek()));
+            }
+            if src.read_char().is_none() {
+                break;
+            }
+            src.skip_while(char::is_alphabetic);
+            src.discard_while(char::is_whitespace);
+        });
+    }
+
+    #[test]
+    fn chunks_join_to_the_original(text in ".{0,64}", count in 0..8usize, lines: bool) {
+        let boundary = if lines { Boundary::Line } else { Boundary::Char };
+        let mut value = text.clone();
+        let contents = value.as_ptr() as usize;
+        let ranges: Vec<_> = parallel::split_mut(&mut value, count, boundary)
+            .map(|chunk| {
+                let start = chunk.as_ptr() as usize - contents;
+                start..start + chunk.len()
+            })
+            .collect();
+        prop_assert!(ranges.len() <= count.max(1));
+        let joined = parallel::join(&mut value, ranges).to_string();
+        prop_assert_eq!(joined, text);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn transforming_chunks_matches_transforming_everything(text in "[a\\r\\n ]{0,64}", count in 1..8usize) {
+        let mut expected = text.clone();
+        let expected = Replacinator::new_in(&mut expected, replacinator::transforms::normalize_newlines).to_string();
+        let mut value = text.clone();
+        let actual = parallel::transform_chunks(&mut value, count, Boundary::Line, replacinator::transforms::normalize_newlines);
+        prop_assert_eq!(&*actual, &*expected);
+    }
+
+    #[test]
+    fn resuming_matches_continuing(text in "[a-c \u{e9}]{0,64}", split in 0..64usize, compact: bool) {
+        fn strip_spaces(src: &mut Replacinator<'_>, count: usize) {
+            for _ in 0..count {
+                if src.peek() == Some(' ') {
+                    src.discard_char();
+                } else if src.skip_char().is_none() {
+                    break;
+                }
+            }
+        }
+        let fill = if compact { FillStrategy::Compact } else { FillStrategy::Space };
+        let expected: String = text.chars().filter(|&c| c != ' ').collect();
+        let mut value = text.clone();
+        let state = Replacinator::new_in(&mut value, |src| {
+            src.set_fill(fill);
+            strip_spaces(src, split);
+            src.save_state()
+        });
+        let mut guard = ReplacinatorGuard::resume(&mut value, state).unwrap();
+        let actual = guard.with(|src| {
+            strip_spaces(src, usize::MAX);
+            src.start().to_string()
+        });
+        prop_assert_eq!(actual, expected);
+    }
+
+    #[test]
+    #[cfg(feature = "number_formatting")]
+    fn integers_are_written_as_by_display(n: i64) {
+        let mut value = " ".repeat(40);
+        let written = Replacinator::new_in(&mut value, |src| {
+            src.discard_while(|_| true);
+            let len = src.write_i64(n).unwrap() + src.write_u64(n as u64).unwrap();
+            prop_assert_eq!(len, src.written_len());
+            Ok(src.start().to_string())
+        })?;
+        prop_assert_eq!(written, format!("{}{}", n, n as u64));
+    }
+
+    #[test]
+    #[cfg(feature = "deunicode")]
+    fn deunicode_leaves_only_what_it_reports(text in "[a-z \u{c0}-\u{17f}\u{300}-\u{36f}\u{20ac}]{0,32}") {
+        let mut value = text.clone();
+        let mut unmapped = String::new();
+        let actual = Replacinator::new_in(&mut value, |src| {
+            replacinator::transforms::deunicode_in_place(src, |c, _| unmapped.push(c)).to_string()
+        });
+        prop_assert_eq!(actual.chars().filter(|c| !c.is_ascii()).collect::<String>(), unmapped);
+        prop_assert!(actual.len() <= text.len());
+    }
+
+    #[test]
+    fn slugs_are_lowercase_words_joined_by_dashes(text in "[a-zA-Z0-9 _\\-\u{c0}-\u{17f}\u{301}\u{20ac}]{0,32}") {
+        let mut value = text.clone();
+        let slug = Replacinator::new_in(&mut value, |src| {
+            replacinator::transforms::slugify_in_place(src).to_string()
+        });
+        prop_assert!(slug.len() <= text.len());
+        let is_word = |word: &str| {
+            !word.is_empty() && word.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+        };
+        prop_assert!(slug.is_empty() || slug.split('-').all(is_word), "{:?}", slug);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn growing_matches_escaping_a_copy(text in "[a\"\\\\\n\u{e9}]{0,32}") {
+        use replacinator::rewrite::Sink;
+        use std::ops::ControlFlow;
+
+        let escape = |c: char, out: &mut Sink<'_>| {
+            let _ = match c {
+                '"' => out.write_str("\\\""),
+                '\\' => out.write_str("\\\\"),
+                '\n' => out.write_str("\\n"),
+                c => out.write_char(c),
+            };
+            ControlFlow::Continue(())
+        };
+        let mut value = text.clone();
+        replacinator::owned::grow_transform(&mut value, escape);
+        let expected: String = text
+            .chars()
+            .map(|c| match c {
+                '"' => "\\\"".to_string(),
+                '\\' => "\\\\".to_string(),
+                '\n' => "\\n".to_string(),
+                c => c.to_string(),
+            })
+            .collect();
+        prop_assert_eq!(value, expected);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn edit_log_maps_kept_characters_back(text in "[ab \u{e9}]{0,32}") {
+        let mut value = text.clone();
+        let (kept, log) = Replacinator::new_in_logged(&mut value, |src| {
+            let mut kept = Vec::new();
+            while let Some(c) = src.peek() {
+                if c == ' ' {
+                    src.discard_char();
+                } else if c == '\u{e9}' {
+                    src.discard_char();
+                    src.write_char('e');
+                } else {
+                    kept.push((src.read_offset(), src.write_offset()));
+                    src.skip_char();
+                }
+            }
+            kept
+        });
+        for (original, output) in kept {
+            prop_assert_eq!(log.to_original(output), original);
+            prop_assert_eq!(log.to_output(original), output);
+        }
+    }
+
+    #[test]
+    fn taken_tokens_know_where_they_came_from(text in "[ab \u{e9}]{0,32}") {
+        let mut value = text.clone();
+        let tokens = Replacinator::new_in(&mut value, |src| {
+            let mut tokens = Vec::new();
+            loop {
+                src.discard_while(|c| c == ' ');
+                let mark = src.mark();
+                if src.skip_while(|c| c != ' ') == 0 {
+                    break;
+                }
+                let (span, token) = src.take_marked_spanned(mark).unwrap();
+                let offset = src.offset_of(token);
+                tokens.push((span, token.to_string(), offset));
+            }
+            tokens
+        });
+        for (span, token, offset) in tokens {
+            prop_assert_eq!(&text[span.bytes()], &*token);
+            let offset = offset.unwrap();
+            prop_assert_eq!(&value[offset..offset + token.len()], &*token);
+        }
+    }
+
+    #[test]
+    fn pipeline_matches_running_each_transform(text in "[a %2\r\n]{0,32}") {
+        use replacinator::{percent, pipeline::Pipeline, transforms};
+
+        let mut expected = text.clone();
+        let normalized = Replacinator::new_in(&mut expected, transforms::normalize_newlines);
+        let expected = Replacinator::new_in(normalized, percent::decode_in_place)
+            .map(|decoded| decoded.to_string())
+            .map_err(|error| error.position());
+        let mut value = text.clone();
+        let actual = Pipeline::<percent::Error>::new()
+            .then(transforms::normalize_newlines)
+            .try_then(percent::decode_in_place)
+            .run(&mut value)
+            .map(|decoded| decoded.to_string())
+            .map_err(|error| {
+                assert_eq!(error.stage(), 1);
+                error.into_inner().position()
+            });
+        prop_assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn trimming_matches_str(text in "[ a\u{e9}\n]{0,16}", end in 0..3usize) {
+        use replacinator::transforms::{self, Trim, Whitespace};
+
+        let (ends, expected) = match end {
+            0 => (Trim::Start, text.trim_start()),
+            1 => (Trim::End, text.trim_end()),
+            _ => (Trim::Both, text.trim()),
+        };
+        let mut value = text.clone();
+        let actual = Replacinator::new_in(&mut value, |src| {
+            transforms::trim_in_place(src, ends, Whitespace::Unicode).to_string()
+        });
+        prop_assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn padding_fills_the_space_freed(text in " {0,8}[a\u{e9}]{0,8}", width in 0..20usize, align in 0..3usize) {
+        use replacinator::transforms::{self, Align};
+
+        let align = [Align::Left, Align::Right, Align::Center][align];
+        let trimmed = text.trim_start();
+        let len = trimmed.chars().count();
+        let mut value = text.clone();
+        let (padded, missed) = Replacinator::new_in(&mut value, |src| {
+            src.discard_while(|c| c == ' ');
+            let (padded, missed) = transforms::pad_to(src, width, align, '.');
+            (padded.to_string(), missed)
+        });
+        let room = text.len() - trimmed.len();
+        prop_assert_eq!(missed, width.saturating_sub(len).saturating_sub(room));
+        prop_assert_eq!(padded.chars().count() + missed, width.max(len));
+        prop_assert_eq!(padded.trim_matches('.'), trimmed);
+        if align == Align::Left {
+            prop_assert!(padded.starts_with(trimmed));
+        }
+        if align == Align::Right {
+            prop_assert!(padded.ends_with(trimmed));
+        }
+    }
+
+    #[test]
+    fn squeezing_removes_repeated_characters(text in "[ a\u{e9}-]{0,32}") {
+        let mut expected = String::new();
+        for c in text.chars() {
+            if !(matches!(c, ' ' | '\u{e9}') && expected.ends_with(c)) {
+                expected.push(c);
+            }
+        }
+        let mut value = text.clone();
+        let actual = Replacinator::new_in(&mut value, |src| {
+            replacinator::transforms::squeeze(src, [' ', '\u{e9}']).to_string()
+        });
+        prop_assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn ignoring_ascii_case_matches_lowercasing(text in "[aAbB\u{e9}\u{c9}]{0,32}") {
+        let mut value = text.clone();
+        let (actual, count) = Replacinator::new_in(&mut value, |src| {
+            let report = src.replace_literal_ignore_ascii_case("ab", "x").unwrap();
+            (src.start().to_string(), report.replacements())
+        });
+        let lower = text.to_ascii_lowercase();
+        prop_assert_eq!(count, lower.matches("ab").count());
+        prop_assert_eq!(actual.to_ascii_lowercase(), lower.replace("ab", "x"));
+    }
+
+    #[test]
+    fn replacing_many_matches_the_leftmost_longest_pattern(text in "[abc\u{e9}]{0,32}") {
+        let pairs = [("ab", "x"), ("abc", "y"), ("\u{e9}", "e"), ("b", ""), ("ab", "z")];
+        let mut expected = String::new();
+        let mut rest = text.as_str();
+        while let Some(c) = rest.chars().next() {
+            let longest = pairs
+                .iter()
+                .filter(|(pattern, _)| rest.starts_with(pattern))
+                .fold(None, |longest: Option<&(&str, &str)>, pair| match longest {
+                    Some(longest) if longest.0.len() >= pair.0.len() => Some(longest),
+                    _ => Some(pair),
+                });
+            match longest {
+                Some((pattern, replacement)) => {
+                    expected.push_str(replacement);
+                    rest = &rest[pattern.len()..];
+                }
+                None => {
+                    expected.push(c);
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        }
+        let mut value = text.clone();
+        let actual = Replacinator::new_in(&mut value, |src| {
+            replacinator::transforms::replace_many_in_place(src, &pairs).unwrap();
+            src.start().to_string()
+        });
+        prop_assert_eq!(actual, expected);
+    }
+
+    #[test]
+    #[cfg(feature = "case_folding")]
+    fn case_folding_matches_both_sigmas(text in "[\u{3a3}\u{3c3}\u{3c2}a]{0,32}") {
+        let mut value = text.clone();
+        let actual = Replacinator::new_in(&mut value, |src| {
+            src.replace_literal_case_folded("\u{3c3}", "s").unwrap();
+            src.start().to_string()
+        });
+        prop_assert_eq!(actual, text.replace(['\u{3a3}', '\u{3c3}', '\u{3c2}'], "s"));
+    }
+
+    #[test]
+    fn checking_does_not_change_the_result(text in "[ a\u{e9}\n]{0,32}") {
+        use replacinator::transforms::{self, Whitespace};
+
+        let collapse = |src: &mut Replacinator<'_>| {
+            transforms::collapse_whitespace(src, Whitespace::Unicode).to_string()
+        };
+        let mut value = text.clone();
+        let expected = Replacinator::new_in(&mut value, collapse);
+        let mut value = text.clone();
+        let checked = Replacinator::new_in_checked(&mut value, |src| {
+            assert!(src.checks_utf8());
+            collapse(src)
+        });
+        let mut value = text.clone();
+        let unchecked = Replacinator::new_in_unchecked_fastpath(&mut value, collapse);
+        prop_assert_eq!(&checked, &expected);
+        prop_assert_eq!(&unchecked, &expected);
+    }
+
+    #[test]
+    fn byte_counts_match_str_boundaries(text in "[a\u{e9}\u{20ac}]{0,16}", n in 0..48usize) {
+        use replacinator::ByteError;
+
+        let mut value = text.clone();
+        let (result, rest) = Replacinator::new_in(&mut value, |src| {
+            (src.advance_read_bytes(n), src.remainder().to_string())
+        });
+        match result {
+            Ok(()) => prop_assert_eq!(&rest, &text[n..]),
+            Err(ByteError::PastEnd) => prop_assert!(n > text.len()),
+            Err(ByteError::NotCharBoundary(end)) => {
+                prop_assert!(!text.is_char_boundary(n));
+                prop_assert!(end > n && text.is_char_boundary(end));
+                prop_assert!((n..end).all(|i| !text.is_char_boundary(i)));
+                prop_assert_eq!(&rest, &text);
+            }
+            Err(e) => prop_assert!(false, "unexpected error {}", e),
+        }
+        prop_assert_eq!(value, text);
+    }
+
+    #[test]
+    fn writing_bytes_only_writes_utf8(text in "[a\u{e9}]{4,16}", bytes in prop::collection::vec(any::<u8>(), 0..4)) {
+        use replacinator::ByteError;
+
+        let mut value = text.clone();
+        let (result, start) = Replacinator::new_in(&mut value, |src| {
+            src.discard_n(4);
+            (src.write_bytes(&bytes), src.start().to_string())
+        });
+        match std::str::from_utf8(&bytes) {
+            Ok(expected) => {
+                prop_assert_eq!(result, Ok(()));
+                prop_assert_eq!(start, expected);
+            }
+            Err(e) => {
+                prop_assert_eq!(result, Err(ByteError::InvalidUtf8(e.valid_up_to())));
+                prop_assert_eq!(start, "");
+            }
+        }
+    }
+
+    #[test]
+    fn segments_hold_what_was_taken(text in "[a-c,]{0,24}") {
+        use replacinator::segments::Segments;
+
+        let mut value = text.clone();
+        let (joined, total, len, rest) = Replacinator::new_in(&mut value, |src| {
+            let mut segments = Segments::<'_, 4>::new();
+            while !segments.is_full() {
+                if src.skip_until(',').is_none() {
+                    break;
+                }
+                src.discard_char();
+                segments.push_start(src);
+            }
+            let joined = segments.joined(",").to_string();
+            (joined, segments.total_len(), segments.len(), src.remainder().to_string())
+        });
+        let parts: Vec<_> = text.split(',').collect();
+        let taken = parts.len().saturating_sub(1).min(4);
+        prop_assert_eq!(len, taken);
+        prop_assert_eq!(&joined, &parts[..taken].join(","));
+        prop_assert_eq!(total, parts[..taken].iter().map(|part| part.len()).sum::<usize>());
+        let consumed: usize = parts[..taken].iter().map(|part| part.len() + 1).sum();
+        prop_assert_eq!(&rest, &text[consumed..]);
+    }
+
+    #[test]
+    fn string_arrays_round_trip(
+        strings in prop::collection::vec(prop::collection::vec((any::<char>(), 0..3u8), 0..6), 0..5),
+        space in "[ \t\n\r]{0,2}",
+    ) {
+        use replacinator::json;
+
+        let mut document = format!("{}[", space);
+        for (index, string) in strings.iter().enumerate() {
+            if index != 0 {
+                document.push_str(&space);
+                document.push(',');
+            }
+            document.push_str(&space);
+            document.push('"');
+            for &(c, style) in string {
+                match (c, style) {
+                    ('"' | '\\', _) => document.extend(['\\', c]),
+                    ('\n', 0) => document.push_str("\\n"),
+                    (c, 0) if !c.is_control() => document.push(c),
+                    ('/', 1) => document.push_str("\\/"),
+                    (c, _) => {
+                        for unit in c.encode_utf16(&mut [0; 2]) {
+                            document.push_str(&format!("\\u{:04X}", unit));
+                        }
+                    }
+                }
+            }
+            document.push('"');
+        }
+        document.push_str(&space);
+        document.push_str("]tail");
+        let (parsed, rest) = Replacinator::new_in(&mut document, |src| {
+            let parsed: Vec<String> = json::parse_string_array(src)
+                .unwrap()
+                .map(|s| s.to_string())
+                .collect();
+            (parsed, src.remainder().to_string())
+        });
+        let expected: Vec<String> = strings
+            .iter()
+            .map(|string| string.iter().map(|&(c, _)| c).collect())
+            .collect();
+        prop_assert_eq!(parsed, expected);
+        prop_assert_eq!(rest, "tail");
+    }
+
+    #[test]
+    fn invalid_string_arrays_are_left_unread(text in r#"[ \[\]",\\u0-9a-fDnx\u{1}]{0,24}"#) {
+        use replacinator::json;
+
+        let mut value = text.clone();
+        let checked = Replacinator::new_in(&mut value, |src| {
+            match json::parse_string_array(src) {
+                Ok(strings) => {
+                    strings.for_each(drop);
+                    Ok(())
+                }
+                Err(e) => {
+                    assert_eq!(src.read_offset(), 0);
+                    Err(e.position().byte())
+                }
+            }
+        });
+        match checked {
+            Ok(()) => {}
+            Err(byte) => {
+                prop_assert!(byte <= text.len());
+                prop_assert_eq!(value, text);
+            }
+        }
+    }
+
+    #[test]
+    fn config_lines_split_into_key_and_value(
+        key in "[a-z_][a-z0-9 _.-]{0,8}",
+        value in "\\PC{0,12}",
+        style in 0..3u8,
+        comment in prop::option::of("[#;][ -~]{0,8}"),
+    ) {
+        use replacinator::config;
+
+        let key = key.trim_end();
+        let written = match style {
+            0 => {
+                let mut written = String::from("\"");
+                for c in value.chars() {
+                    match c {
+                        '"' | '\\' => written.extend(['\\', c]),
+                        '\u{7f}' => written.push_str("\\u007F"),
+                        c if c.is_control() => written.push_str(&format!("\\U{:08x}", c as u32)),
+                        c => written.push(c),
+                    }
+                }
+                written.push('"');
+                written
+            }
+            1 if !value.contains('\'') => format!("'{}'", value),
+            _ => value.replace(|c: char| "#;\"'".contains(c) || c.is_whitespace(), "_"),
+        };
+        let mut line = format!("  {} =\t{} {}\r\n", key, written, comment.unwrap_or_default());
+        let expected = if style < 2 && written.starts_with(['"', '\'']) {
+            value.clone()
+        } else {
+            written.clone()
+        };
+        prop_assert!(!config::is_comment(&line));
+        let (parsed_key, parsed_value) = config::key_value(&mut line).unwrap();
+        prop_assert_eq!(&*parsed_key, key);
+        prop_assert_eq!(&*parsed_value, expected);
+    }
+
+    #[test]
+    fn form_decoding_matches_the_url_standard(text in "(%[0-9A-Fa-f]{2}|%[C-F][0-9A-F]%[89AB][0-9A-F]|[a=&+%0-9\u{e9}]){0,16}") {
+        use replacinator::http;
+
+        fn reference(part: &str) -> String {
+            let bytes = part.as_bytes();
+            let mut decoded = Vec::new();
+            let mut i = 0;
+            while i < bytes.len() {
+                let hex = bytes
+                    .get(i + 1..i + 3)
+                    .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                    .map(|hex| u8::from_str_radix(std::str::from_utf8(hex).unwrap(), 16).unwrap());
+                match (bytes[i], hex) {
+                    (b'%', Some(byte)) => {
+                        decoded.push(byte);
+                        i += 3;
+                        continue;
+                    }
+                    (b'+', _) => decoded.push(b' '),
+                    (b, _) => decoded.push(b),
+                }
+                i += 1;
+            }
+            String::from_utf8_lossy(&decoded).into_owned()
+        }
+
+        let expected: Vec<(String, String)> = text
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (reference(name), reference(value))
+            })
+            .collect();
+        let mut value = text.clone();
+        let pairs: Vec<(String, String)> = Replacinator::new_in(&mut value, |src| {
+            http::decode_form_urlencoded(src)
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect()
+        });
+        prop_assert_eq!(pairs, expected);
+    }
+
+    #[test]
+    fn fixed_width_reader_matches_fields(
+        text in "[ a\u{e9}]{0,24}",
+        widths in prop::collection::vec(0..6usize, 0..6),
+        chars in any::<bool>(),
+    ) {
+        use replacinator::fixed_width::{self, Unit};
+
+        let unit = if chars { Unit::Chars } else { Unit::Bytes };
+        let mut record = text.clone();
+        let mut fields = fixed_width::fields(&mut record, &widths, unit);
+        let expected: Vec<String> = fields.by_ref().map(|field| field.to_string()).collect();
+        let expected_rest = fields.into_remainder().to_string();
+        let mut value = text.clone();
+        let (read, rest) = Replacinator::new_in(&mut value, |src| {
+            let read: Vec<String> = fixed_width::reader(src, &widths, unit)
+                .trim_pad(' ')
+                .map(|field| field.to_string())
+                .collect();
+            (read, src.remainder().to_string())
+        });
+        prop_assert_eq!(read, expected);
+        prop_assert_eq!(rest, expected_rest);
+    }
+
+    #[test]
+    fn reading_exact_bytes_matches_slicing(text in "[a\u{e9}]{0,12}", n in 0..32usize) {
+        let mut value = text.clone();
+        let read = Replacinator::new_in(&mut value, |src| {
+            src.read_exact_bytes(n).map(str::to_string).ok()
+        });
+        prop_assert_eq!(read.as_deref(), text.get(..n));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn scoped_threads_transform_every_chunk(text in "[a-z\u{e9} \n]{0,64}", len in 0..16usize) {
+        let mut value = text.clone();
+        let lens = Replacinator::scope(&mut value, |scope| {
+            let mut threads = Vec::new();
+            while scope.remaining_len() > 0 {
+                threads.push(scope.spawn(len, Boundary::Char, |src| {
+                    src.make_ascii_uppercase_remaining();
+                    src.skip_while(|_| true);
+                    src.take_start().len()
+                }));
+            }
+            threads.into_iter().map(|thread| thread.join().unwrap()).collect::<Vec<_>>()
+        });
+        prop_assert_eq!(lens.iter().sum::<usize>(), text.len());
+        prop_assert!(lens.iter().all(|&chunk| chunk >= len.max(1) || chunk == *lens.last().unwrap()));
+        prop_assert_eq!(value, text.to_ascii_uppercase());
+    }
+
+    #[test]
+    fn dirty_range_covers_every_change(text in ".{0,24}", ops in prop::collection::vec(op(), 0..24)) {
+        let mut value = text.clone();
+        let len = value.len();
+        let (dirty, is_dirty) = Replacinator::new_in(&mut value, |src| {
+            let _ = catch_unwind(AssertUnwindSafe(|| apply(src, &ops, len)));
+            (src.dirty_range(), src.is_dirty())
+        });
+        prop_assert_eq!(is_dirty, dirty.is_some());
+        let changed: Vec<usize> = (0..len)
+            .filter(|&i| value.as_bytes()[i] != text.as_bytes()[i])
+            .collect();
+        let changed = changed.first().map(|&first| first..changed[changed.len() - 1] + 1);
+        let compacted = cfg!(feature = "compact_on_take") && ops.iter().any(|op| matches!(op, Op::TakeStart));
+        if compacted || ops.iter().any(|op| matches!(op, Op::Rewind)) {
+            // Bytes written before rewinding, or moved by compaction, are still counted, even if
+            // they are changed back later
+            if let Some(changed) = changed {
+                let dirty = dirty.unwrap();
+                prop_assert!(dirty.start <= changed.start && changed.end <= dirty.end);
+            }
+        } else {
+            prop_assert_eq!(dirty, changed);
+        }
+    }
+
+    #[test]
+    fn buffers_of_any_type_are_transformed_in_place(text in ".{0,24}", ops in prop::collection::vec(op(), 0..24)) {
+        let ops: Vec<Op> = ops.into_iter().filter(|op| !matches!(op, Op::Panic)).collect();
+        let transform = |src: &mut Replacinator<'_>| {
+            let _ = catch_unwind(AssertUnwindSafe(|| apply(src, &ops, text.len())));
+        };
+        let mut slice = text.clone();
+        Replacinator::new_in(slice.as_mut_str(), transform);
+        let mut string = text.clone();
+        Replacinator::new_in(&mut string, transform);
+        let mut boxed = text.clone().into_boxed_str();
+        Replacinator::new_in(&mut boxed, transform);
+        let mut backing = text.clone();
+        let mut arena = ArenaString(&mut backing);
+        Replacinator::new_in(&mut arena, transform);
+        prop_assert_eq!(&string, &slice);
+        prop_assert_eq!(&*boxed, &slice);
+        prop_assert_eq!(&backing, &slice);
+    }
+}
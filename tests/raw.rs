@@ -0,0 +1,78 @@
+//! Tests of the checked UTF-8 conversions
+
+use std::panic::catch_unwind;
+
+use replacinator::{raw, Replacinator};
+
+#[test]
+fn conversions_keep_valid_utf8() {
+    let mut bytes = *b"caf\xC3\xA9";
+    // Safety: The bytes are valid UTF-8
+    assert_eq!(unsafe { raw::unchecked_from_utf8(&bytes) }, "café");
+    let s = unsafe { raw::unchecked_from_utf8_mut(&mut bytes) };
+    s.make_ascii_uppercase();
+    assert_eq!(&bytes, b"CAF\xC3\xA9");
+    assert_eq!(unsafe { raw::unchecked_from_utf8(&[]) }, "");
+}
+
+#[test]
+fn checked_conversions_reject_invalid_utf8() {
+    if !raw::SAFETY_CHECKS {
+        // Without the checks, this would be undefined behaviour
+        return;
+    }
+    // Safety: The safety checks are enabled, so invalid UTF-8 panics
+    assert!(catch_unwind(|| unsafe { raw::unchecked_from_utf8(b"\xC3") }.len()).is_err());
+    let mut bytes = *b"a\xFF";
+    assert!(
+        catch_unwind(move || unsafe { raw::unchecked_from_utf8_mut(&mut bytes) }.len()).is_err()
+    );
+}
+
+// `paranoid` (or `force_safety_checks`) overrides both `disable_safety_checks` and
+// `REPLACINATOR_UNCHECKED`, so this holds whichever of those are also used
+#[cfg(feature = "paranoid")]
+#[test]
+fn paranoid_keeps_the_safety_checks_on() {
+    assert_eq!((raw::PARANOID, raw::SAFETY_CHECKS), (true, true));
+}
+
+#[cfg(not(feature = "paranoid"))]
+#[test]
+fn safety_checks_follow_the_features() {
+    let checked = option_env!("REPLACINATOR_UNCHECKED").is_none()
+        && (cfg!(debug_assertions) || !cfg!(feature = "disable_safety_checks"));
+    assert_eq!((raw::PARANOID, raw::SAFETY_CHECKS), (false, checked));
+}
+
+/// Whether a `Replacinator` created by `new_in_unchecked_fastpath` checks its views, after
+/// checking that it still transforms the text correctly
+fn fastpath_checks_utf8() -> bool {
+    let mut text = String::from("aé  b");
+    let checks = Replacinator::new_in_unchecked_fastpath(&mut text, |src| {
+        while let Some(c) = src.read_char() {
+            if c != ' ' {
+                src.write_char(c);
+            }
+        }
+        assert_eq!(src.start(), "aéb");
+        src.checks_utf8()
+    });
+    assert_eq!(text, "aéb  ");
+    checks
+}
+
+// `new_in_unchecked_fastpath` can't turn the checks off for a dependent which enabled `paranoid`
+#[cfg(feature = "paranoid")]
+#[test]
+fn paranoid_keeps_the_checks_on_the_unchecked_fastpath() {
+    assert!(fastpath_checks_utf8());
+}
+
+#[cfg(not(feature = "paranoid"))]
+#[test]
+fn the_unchecked_fastpath_turns_the_checks_off() {
+    assert!(!fastpath_checks_utf8());
+    let mut text = String::from("a");
+    assert!(Replacinator::new_in_checked(&mut text, |src| src.checks_utf8()));
+}
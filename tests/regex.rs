@@ -0,0 +1,101 @@
+//! Tests of replacing the matches of a regular expression in place
+#![cfg(feature = "regex")]
+
+use regex_automata::meta::Regex;
+use replacinator::{
+    regex::{replace_all_shrinking, ErrorKind},
+    Replacinator,
+};
+
+#[test]
+fn matches_are_replaced_by_shorter_text() {
+    let regex = Regex::new(r"(\w+)@(\w+)\.com").unwrap();
+    let mut text = String::from("mail ann@host.com or bé@x.com");
+    let report = Replacinator::new_in(&mut text, |src| {
+        replace_all_shrinking(src, &regex, |captures, haystack, out| {
+            let user = &haystack[captures.get_group(1).unwrap()];
+            let host = &haystack[captures.get_group(2).unwrap()];
+            write!(out, "{}@{}", host, user)
+        })
+        .unwrap()
+    });
+    assert_eq!(text, "mail host@ann or x@bé        ");
+    assert_eq!(
+        (
+            report.read_len(),
+            report.written_len(),
+            report.replacements()
+        ),
+        (30, 22, 2)
+    );
+}
+
+#[test]
+fn assertions_see_the_original_text() {
+    let regex = Regex::new(r"\bab").unwrap();
+    let mut text = String::from("ababab ab");
+    Replacinator::new_in(&mut text, |src| {
+        replace_all_shrinking(src, &regex, |_, _, out| out.write_str("x")).unwrap()
+    });
+    assert_eq!(text, "xabab x  ");
+    let regex = Regex::new(r"(?m)^-").unwrap();
+    let mut text = String::from("--a\n--b");
+    Replacinator::new_in(&mut text, |src| {
+        replace_all_shrinking(src, &regex, |_, _, _| Ok(())).unwrap()
+    });
+    assert_eq!(text, "-a\n-b  ");
+}
+
+#[test]
+fn empty_matches_are_not_repeated() {
+    let regex = Regex::new(r"a*").unwrap();
+    let mut text = String::from("baaé");
+    let report = Replacinator::new_in(&mut text, |src| {
+        replace_all_shrinking(src, &regex, |_, _, _| Ok(())).unwrap()
+    });
+    assert_eq!(text, "bé  ");
+    assert_eq!(report.replacements(), 3);
+    let mut text = String::new();
+    let report = Replacinator::new_in(&mut text, |src| {
+        replace_all_shrinking(src, &regex, |_, _, _| Ok(())).unwrap()
+    });
+    assert_eq!(report.replacements(), 1);
+}
+
+#[test]
+fn longer_replacements_are_errors() {
+    let regex = Regex::new(r"[0-9]+").unwrap();
+    let mut text = String::from("a1 b22 c3 d");
+    Replacinator::new_in(&mut text, |src| {
+        let error = replace_all_shrinking(src, &regex, |captures, haystack, out| {
+            let digits = &haystack[captures.get_match().unwrap().range()];
+            out.write_str(if digits.len() > 1 { "#" } else { "##" })
+        })
+        .unwrap_err();
+        assert_eq!(
+            (error.kind(), error.position().byte()),
+            (ErrorKind::TooLong, 1)
+        );
+        assert_eq!(
+            error.to_string(),
+            "replacement is longer than its match at byte 1"
+        );
+        assert_eq!(src.remainder(), "1 b22 c3 d");
+        src.skip_char();
+        let error = replace_all_shrinking(src, &regex, |captures, haystack, out| {
+            let digits = &haystack[captures.get_match().unwrap().range()];
+            out.write_str(if digits.len() > 1 { "#" } else { "##" })
+        })
+        .unwrap_err();
+        assert_eq!(error.position().byte(), 8);
+        assert_eq!(src.start(), "a1 b# c");
+        assert_eq!(src.remainder(), "3 d");
+    });
+    let mut text = String::from("1");
+    Replacinator::new_in(&mut text, |src| {
+        let error =
+            replace_all_shrinking(src, &regex, |_, _, _| Err(core::fmt::Error)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Format);
+        assert_eq!(src.remainder(), "1");
+    });
+}
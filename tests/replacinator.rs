@@ -0,0 +1,1322 @@
+//! Tests of the cursor methods of `Replacinator`
+
+use replacinator::{
+    transforms::{CommentSyntax, EmptyMarker},
+    FillStrategy, IgnoreAsciiCase, Overflow, OverflowPolicy, ReplaceErrorKind, Replacinator,
+};
+
+#[test]
+fn reader_yields_the_remainder() {
+    let mut text = String::from("héllo");
+    let read: String = Replacinator::new_in(&mut text, |src| src.reader().collect());
+    assert_eq!(read, "héllo");
+    // Nothing was written back, so the string is padding
+    assert_eq!(text, "      ");
+}
+
+#[test]
+fn reader_can_write_whilst_iterating() {
+    let mut text = String::from("a1b2c3");
+    Replacinator::new_in(&mut text, |src| {
+        let mut reader = src.reader();
+        while let Some(c) = reader.next() {
+            if c.is_ascii_alphabetic() {
+                reader.replacinator().write_char(c);
+            }
+        }
+    });
+    assert_eq!(text, "abc   ");
+}
+
+#[test]
+fn reader_size_hint_bounds_the_characters() {
+    let mut text = String::from("aé€😀");
+    Replacinator::new_in(&mut text, |src| {
+        let reader = src.reader();
+        let (low, high) = reader.size_hint();
+        assert!(low <= 4);
+        assert!(high.unwrap() >= 4);
+    });
+}
+
+#[test]
+fn reader_of_empty_string_is_empty() {
+    let mut text = String::new();
+    Replacinator::new_in(&mut text, |src| {
+        let mut reader = src.reader();
+        assert_eq!(reader.next(), None);
+        assert_eq!(reader.next(), None);
+    });
+}
+
+#[test]
+fn rewind_reads_again_and_forgets_writes() {
+    let mut text = String::from("abcdef");
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_char();
+        let checkpoint = src.checkpoint();
+        src.read_char();
+        src.read_char();
+        assert!(src.rewind(checkpoint));
+        assert_eq!(src.remainder(), "bcdef");
+        assert_eq!(src.start(), "a");
+        while src.skip_char().is_some() {}
+    });
+    assert_eq!(text, "abcdef");
+}
+
+#[test]
+fn rewind_can_be_used_to_backtrack_twice() {
+    let mut text = String::from("abc");
+    Replacinator::new_in(&mut text, |src| {
+        let checkpoint = src.checkpoint();
+        src.read_char();
+        assert!(src.rewind(checkpoint));
+        src.read_char();
+        assert!(src.rewind(checkpoint));
+        assert_eq!(src.remainder(), "abc");
+    });
+}
+
+#[test]
+fn rewind_refuses_after_overwriting_the_checkpoint() {
+    let mut text = String::from("abcdef");
+    Replacinator::new_in(&mut text, |src| {
+        src.read_char();
+        let checkpoint = src.checkpoint();
+        src.read_char();
+        src.write_char('x');
+        src.write_char('y');
+        assert!(!src.rewind(checkpoint));
+        assert_eq!(src.start(), "xy");
+        assert_eq!(src.remainder(), "cdef");
+    });
+}
+
+#[test]
+fn rewind_refuses_checkpoints_from_before_take_start() {
+    let mut text = String::from("abcdef");
+    Replacinator::new_in(&mut text, |src| {
+        let checkpoint = src.checkpoint();
+        src.skip_char();
+        src.take_start();
+        assert!(!src.rewind(checkpoint));
+        assert_eq!(src.remainder(), "bcdef");
+    });
+}
+
+#[test]
+fn peeking_does_not_read() {
+    let mut text = String::from("aé€x");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.peek(), Some('a'));
+        assert_eq!(src.peek_nth(0), Some('a'));
+        assert_eq!(src.peek_nth(2), Some('€'));
+        assert_eq!(src.peek_nth(4), None);
+        assert_eq!(src.peek_str(0), Some(""));
+        assert_eq!(src.peek_str(3), Some("aé€"));
+        assert_eq!(src.peek_str(4), Some("aé€x"));
+        assert_eq!(src.peek_str(5), None);
+        assert!(src.starts_with("aé"));
+        assert!(!src.starts_with("é"));
+        assert_eq!(src.remainder(), "aé€x");
+        src.skip_char();
+        assert_eq!(src.peek_str(1), Some("é"));
+        assert!(src.starts_with(""));
+    });
+    assert_eq!(text, "aé€x");
+}
+
+#[test]
+fn peeking_an_empty_remainder() {
+    let mut text = String::new();
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.peek(), None);
+        assert_eq!(src.peek_nth(0), None);
+        assert_eq!(src.peek_str(0), Some(""));
+        assert_eq!(src.peek_str(1), None);
+        assert!(src.starts_with(""));
+    });
+}
+
+#[test]
+fn skip_while_moves_matching_characters_after_a_gap() {
+    let mut text = String::from("  héllo world");
+    Replacinator::new_in(&mut text, |src| {
+        src.read_char();
+        src.read_char();
+        assert_eq!(src.skip_while(char::is_alphabetic), 6);
+        assert_eq!(src.start(), "héllo");
+        assert_eq!(src.remainder(), " world");
+        assert_eq!(src.skip_while(char::is_alphabetic), 0);
+        assert_eq!(src.skip_while(|_| true), 6);
+    });
+    assert_eq!(text, "héllo world  ");
+}
+
+#[test]
+fn skip_ascii_whitespace_matches_skip_while() {
+    let mut text = String::from(" \t\r\n            \u{a0}x");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.skip_ascii_whitespace(), 16);
+        assert_eq!(src.remainder(), "\u{a0}x");
+        assert_eq!(src.skip_ascii_whitespace(), 0);
+    });
+}
+
+#[test]
+fn skip_str_only_skips_a_full_match() {
+    let mut text = String::from("foobar");
+    Replacinator::new_in(&mut text, |src| {
+        assert!(!src.skip_str("fob"));
+        assert!(src.skip_str("foo"));
+        assert!(src.skip_str(""));
+        assert!(!src.skip_str("barbaz"));
+        assert_eq!(src.start(), "foo");
+        assert_eq!(src.remainder(), "bar");
+    });
+}
+
+#[test]
+fn discard_char_drops_one_character() {
+    let mut text = String::from("éa");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.discard_char(), Some('é'));
+        src.skip_char();
+        assert_eq!(src.discard_char(), None);
+    });
+    assert_eq!(text, "a  ");
+}
+
+#[test]
+fn discard_n_stops_at_the_end_of_the_remainder() {
+    let mut text = String::from("a€bc");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.discard_n(0), 0);
+        assert_eq!(src.discard_n(2), 2);
+        assert_eq!(src.remainder(), "bc");
+        assert_eq!(src.discard_n(5), 2);
+        assert_eq!(src.remainder(), "");
+        assert_eq!(src.discard_n(1), 0);
+    });
+    assert_eq!(text, "      ");
+}
+
+#[test]
+fn discard_while_returns_the_bytes_discarded() {
+    let mut text = String::from("ééx");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.discard_while(|c| c == 'é'), 4);
+        assert_eq!(src.discard_while(|c| c == 'é'), 0);
+        src.skip_char();
+    });
+    assert_eq!(text, "x    ");
+}
+
+#[test]
+fn map_chars_replaces_and_removes_characters() {
+    let mut text = String::from("a-bé-c");
+    Replacinator::new_in(&mut text, |src| {
+        let mapped = src.map_chars(|c| match c {
+            '-' => None,
+            'é' => Some('e'),
+            c => Some(c.to_ascii_uppercase()),
+        });
+        assert_eq!(mapped, "ABeC");
+    });
+    assert_eq!(text, "ABeC   ");
+}
+
+#[test]
+fn map_chars_stops_before_a_replacement_which_does_not_fit() {
+    let mut text = String::from("ab-c");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.map_chars(|c| Some(if c == 'b' { 'é' } else { c })), "a");
+        assert_eq!(src.remainder(), "b-c");
+    });
+    // Removing a character makes room for a wider replacement
+    let mut text = String::from("a-b");
+    Replacinator::new_in(&mut text, |src| {
+        let mapped = src.map_chars(|c| match c {
+            '-' => None,
+            'b' => Some('é'),
+            c => Some(c),
+        });
+        assert_eq!(mapped, "aé");
+    });
+}
+
+#[test]
+fn map_chars_of_empty_remainder() {
+    let mut text = String::new();
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.map_chars(|_| unreachable!()), "");
+    });
+}
+
+#[test]
+fn take_marked_splits_off_each_token() {
+    let mut text = String::from("ab, cd");
+    let tokens = Replacinator::new_in(&mut text, |src| {
+        let mut tokens = Vec::new();
+        while src.peek().is_some() {
+            src.discard_while(|c| c == ',' || c == ' ');
+            let mark = src.mark();
+            src.skip_while(|c| c.is_alphabetic());
+            tokens.push(
+                src.take_marked(mark)
+                    .expect("The mark was made from this Replacinator"),
+            );
+        }
+        tokens
+    });
+    assert_eq!(tokens, ["ab", "cd"]);
+}
+
+#[test]
+fn take_marked_includes_only_text_written_since_the_mark() {
+    let mut text = String::from("key=value");
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_while(|c| c != '=');
+        src.discard_char();
+        let mark = src.mark();
+        src.skip_while(|_| true);
+        let value = src.take_marked(mark).unwrap();
+        value.make_ascii_uppercase();
+        assert_eq!(value, "VALUE");
+        assert_eq!(src.start(), "");
+    });
+    assert_eq!(text, "keyVALUE ");
+}
+
+#[test]
+fn take_marked_refuses_stale_marks() {
+    let mut text = String::from("abcdef");
+    Replacinator::new_in(&mut text, |src| {
+        let mark = src.mark();
+        src.skip_char();
+        src.take_start();
+        assert_eq!(src.take_marked(mark), None);
+        src.discard_char();
+        let checkpoint = src.checkpoint();
+        src.skip_char();
+        let mark = src.mark();
+        assert!(src.rewind(checkpoint));
+        assert_eq!(src.take_marked(mark), None);
+        assert_eq!(src.remainder(), "cdef");
+    });
+}
+
+#[test]
+fn take_remainder_leaves_the_first_section() {
+    let mut text = String::from("head: tail");
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_while(|c| c != ':');
+        src.discard_n(2);
+        let tail = src.take_remainder();
+        assert_eq!(src.remainder(), "");
+        assert_eq!(src.read_char(), None);
+        tail.make_ascii_uppercase();
+        assert_eq!(tail, "TAIL");
+        assert_eq!(src.start(), "head");
+        src.write_char('!');
+    });
+    assert_eq!(text, "head! TAIL");
+}
+
+#[test]
+fn take_remainder_of_exhausted_replacinator_is_empty() {
+    let mut text = String::from("ab");
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_while(|_| true);
+        assert_eq!(src.take_remainder(), "");
+        assert_eq!(src.take_remainder(), "");
+        assert_eq!(src.start(), "ab");
+    });
+}
+
+#[test]
+fn split_at_gives_independent_halves() {
+    let mut text = String::from("xkey=value");
+    Replacinator::new_in(&mut text, |src| {
+        src.discard_char();
+        let (mut left, mut right) = src.split_at_char('=').unwrap();
+        assert_eq!(src.remainder(), "");
+        right.with(|src| src.map_chars(|c| Some(c.to_ascii_uppercase())).len());
+        left.with(|src| src.skip_while(|_| true));
+        assert_eq!(left.get().position().byte(), 4);
+        assert_eq!(right.get().position().byte(), 10);
+        assert_eq!(left.finish(), "key");
+        assert_eq!(right.finish(), "VALUE");
+    });
+    assert_eq!(text, " key=VALUE");
+}
+
+#[test]
+fn split_at_requires_a_character_boundary() {
+    let mut text = String::from("éa");
+    Replacinator::new_in(&mut text, |src| {
+        assert!(src.split_at(1).is_none());
+        assert!(src.split_at_char('b').is_none());
+        assert_eq!(src.remainder(), "éa");
+        let (left, right) = src.split_at(3).unwrap();
+        assert_eq!(left.get().remainder(), "éa");
+        assert_eq!(right.get().remainder(), "");
+    });
+}
+
+#[test]
+fn levenshtein_measures_the_logical_content() {
+    let mut text = String::from("k-itten");
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_char();
+        src.discard_char();
+        let mut row = [0; 8];
+        assert_eq!(src.levenshtein("kitten", &mut row), Some(0));
+        assert_eq!(src.levenshtein("sitting", &mut row), Some(3));
+        assert_eq!(src.levenshtein("", &mut row), Some(6));
+        assert_eq!(src.similarity("kitten", &mut row), Some(1.0));
+        assert_eq!(src.similarity("kitteñ", &mut row), Some(1.0 - 1.0 / 6.0));
+    });
+}
+
+#[test]
+fn levenshtein_needs_a_long_enough_row() {
+    let mut text = String::new();
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.levenshtein("abc", &mut [0; 3]), None);
+        assert_eq!(src.similarity("abc", &mut [0; 3]), None);
+        assert_eq!(src.levenshtein("abc", &mut [0; 4]), Some(3));
+        assert_eq!(src.similarity("", &mut [0; 1]), Some(1.0));
+        assert_eq!(src.similarity("ab", &mut [0; 3]), Some(0.0));
+    });
+}
+
+#[test]
+fn fold_bytes_skips_the_gap_and_empty_sections() {
+    let mut text = String::from("ab--cd");
+    Replacinator::new_in(&mut text, |src| {
+        let mut calls = Vec::new();
+        src.fold_bytes(|bytes| calls.push(bytes.to_vec()));
+        assert_eq!(calls, [b"ab--cd".to_vec()]);
+        src.skip_str("ab");
+        src.discard_n(2);
+        let mut calls = Vec::new();
+        src.fold_bytes(|bytes| calls.push(bytes.to_vec()));
+        assert_eq!(calls, [b"ab".to_vec(), b"cd".to_vec()]);
+        src.skip_while(|_| true);
+        let mut calls = Vec::new();
+        src.fold_bytes(|bytes| calls.push(bytes.to_vec()));
+        assert_eq!(calls, [b"abcd".to_vec()]);
+    });
+    let mut text = String::new();
+    Replacinator::new_in(&mut text, |src| {
+        src.fold_bytes(|_| panic!("There are no bytes to fold"));
+    });
+}
+
+#[test]
+fn case_conversion_changes_only_the_remainder() {
+    let mut text = String::from("Ab Cd");
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_char();
+        src.make_ascii_uppercase_remaining();
+        assert_eq!(src.remainder(), "B CD");
+        src.make_ascii_lowercase_remaining();
+        assert_eq!(src.remainder(), "b cd");
+        assert_eq!(src.start(), "A");
+        src.skip_while(|_| true);
+    });
+    assert_eq!(text, "Ab cd");
+}
+
+#[test]
+fn case_conversion_skips_characters_which_change_length() {
+    let mut text = String::from("straße ǅ ſ é");
+    Replacinator::new_in(&mut text, |src| {
+        // 'ß' uppercases to "SS", and 'ſ' to 'S', which is shorter
+        assert_eq!(src.make_uppercase_remaining(), 2);
+        assert_eq!(src.remainder(), "STRAßE Ǆ ſ É");
+        assert_eq!(src.make_lowercase_remaining(), 0);
+        assert_eq!(src.remainder(), "straße ǆ ſ é");
+    });
+    let mut text = String::from("İ");
+    Replacinator::new_in(&mut text, |src| {
+        // 'İ' lowercases to two characters
+        assert_eq!(src.make_lowercase_remaining(), 1);
+        assert_eq!(src.remainder(), "İ");
+    });
+}
+
+#[test]
+fn consume_balanced_skips_nested_and_escaped_delimiters() {
+    let mut text = String::from(r"{a{b}\}c}rest");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(
+            src.consume_balanced('{', '}', Some('\\')).as_deref(),
+            Some(r"{a{b}\}c}")
+        );
+        assert_eq!(src.remainder(), "rest");
+    });
+    let mut text = String::from("(é(中))");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(
+            src.consume_balanced('(', ')', None).as_deref(),
+            Some("(é(中))")
+        );
+        assert_eq!(src.consume_balanced('(', ')', None), None);
+    });
+}
+
+#[test]
+fn consume_balanced_with_the_same_delimiters_stops_at_the_second() {
+    let mut text = String::from(r#""a\"b"c""#);
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(
+            src.consume_balanced('"', '"', Some('\\')).as_deref(),
+            Some(r#""a\"b""#)
+        );
+        assert_eq!(src.remainder(), r#"c""#);
+    });
+}
+
+#[test]
+fn consume_balanced_needs_an_opening_delimiter() {
+    let mut text = String::from("x{}");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.consume_balanced('{', '}', None), None);
+        assert_eq!(src.remainder(), "x{}");
+    });
+}
+
+#[test]
+fn consume_balanced_skips_an_unbalanced_remainder() {
+    for unbalanced in ["{a{b}", r"{a\}", r"{a\"] {
+        let mut text = String::from(unbalanced);
+        Replacinator::new_in(&mut text, |src| {
+            assert_eq!(src.consume_balanced('{', '}', Some('\\')), None);
+            assert_eq!(src.remainder(), "");
+        });
+    }
+}
+
+#[test]
+fn skip_ignorable_skips_whitespace_and_comments() {
+    let mut text = String::from(" // line\n\t/* block\n */ /**/x /* y */");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.skip_ignorable(CommentSyntax::C).unwrap(), 27);
+        assert_eq!(src.remainder(), "x /* y */");
+        assert_eq!(src.skip_ignorable(CommentSyntax::C).unwrap(), 0);
+        src.skip_char();
+        assert_eq!(src.skip_ignorable(CommentSyntax::C).unwrap(), 8);
+        assert_eq!(src.remainder(), "");
+        assert_eq!(src.start(), " // line\n\t/* block\n */ /**/x /* y */");
+    });
+}
+
+#[test]
+fn skip_ignorable_uses_the_given_syntax() {
+    let mut text = String::from("# a\n// b");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.skip_ignorable(CommentSyntax::HASH).unwrap(), 4);
+        assert_eq!(src.remainder(), "// b");
+    });
+}
+
+#[test]
+fn skip_ignorable_skips_an_unterminated_block_comment() {
+    let mut text = String::from("/* é");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.skip_ignorable(CommentSyntax::C).unwrap(), 5);
+        assert_eq!(src.remainder(), "");
+    });
+}
+
+#[test]
+fn skip_ignorable_rejects_an_empty_marker() {
+    let syntax = CommentSyntax {
+        line: &[""],
+        ..CommentSyntax::HASH
+    };
+    let mut text = String::from(" # a");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.skip_ignorable(syntax), Err(EmptyMarker));
+        assert_eq!(src.remainder(), " # a");
+    });
+}
+
+#[test]
+fn match_keyword_skips_the_longest_entry() {
+    let table = ["<", "<<", "<<=", "<=", "=", "=="];
+    let mut text = String::from("<<< <=x==");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.match_keyword(&table), Some(1));
+        assert_eq!(src.match_keyword(&table), Some(0));
+        assert_eq!(src.match_keyword(&table), None);
+        src.skip_char();
+        assert_eq!(src.match_keyword(&table), Some(3));
+        assert_eq!(src.match_keyword(&table), None);
+        src.skip_char();
+        assert_eq!(src.match_keyword(&table), Some(5));
+        assert_eq!(src.match_keyword(&table), None);
+    });
+}
+
+#[test]
+fn match_keyword_handles_empty_and_multibyte_entries() {
+    let mut text = String::from("éa");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.match_keyword(&[]), None);
+        assert_eq!(src.match_keyword(&["", "é", "éb"]), Some(1));
+        assert_eq!(src.match_keyword(&["", "b"]), Some(0));
+        assert_eq!(src.remainder(), "a");
+        assert_eq!(src.match_keyword(&["a", "ab"]), Some(0));
+        assert_eq!(src.match_keyword(&["a"]), None);
+    });
+}
+
+#[test]
+fn match_keyword_with_an_unsorted_table_never_skips_a_non_match() {
+    for table in [&["ab", "a"][..], &["b", "ab", "a"], &["abc", "", "a"]] {
+        let mut text = String::from("ab");
+        Replacinator::new_in(&mut text, |src| {
+            if let Some(index) = src.match_keyword(table) {
+                assert_eq!(src.start(), table[index]);
+            }
+        });
+    }
+}
+
+#[test]
+fn offsets_and_len_follow_the_cursor() {
+    let mut text = String::from("ab cé");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(
+            (src.read_offset(), src.write_offset(), src.len()),
+            (0, 0, 6)
+        );
+        src.skip_char();
+        src.discard_n(2);
+        assert_eq!(
+            (src.read_offset(), src.write_offset(), src.len()),
+            (3, 1, 4)
+        );
+        assert!(!src.is_empty());
+        src.discard_n(2);
+        src.take_start();
+        // Compacting moves the remainder, and so the write position, towards the start
+        let write_offset = if cfg!(feature = "compact_on_take") {
+            1
+        } else {
+            6
+        };
+        assert_eq!(
+            (src.read_offset(), src.write_offset(), src.len()),
+            (6, write_offset, 0)
+        );
+        assert!(src.is_empty());
+    });
+}
+
+#[test]
+fn advance_read_to_skips_to_an_offset_in_the_original_string() {
+    let mut text = String::from("xé, yz");
+    Replacinator::new_in(&mut text, |src| {
+        src.discard_char();
+        assert!(src.advance_read_to(3));
+        assert_eq!(src.start(), "é");
+        assert!(src.advance_read_to(3));
+        assert!(!src.advance_read_to(2));
+        assert!(!src.advance_read_to(8));
+        assert_eq!(src.remainder(), ", yz");
+        assert!(src.advance_read_to(7));
+        assert_eq!((src.start(), src.remainder()), ("é, yz", ""));
+    });
+    let mut text = String::from("é");
+    Replacinator::new_in(&mut text, |src| {
+        assert!(!src.advance_read_to(1));
+        assert_eq!(src.read_offset(), 0);
+    });
+}
+
+#[test]
+fn edits_through_start_mut_keep_marks_and_checkpoints_usable() {
+    let mut text = String::from("aé b-c");
+    Replacinator::new_in(&mut text, |src| {
+        let mark = src.mark();
+        src.skip_while(|c| c != ' ');
+        src.discard_char();
+        let checkpoint = src.checkpoint();
+        src.skip_char();
+        src.start_mut().make_ascii_uppercase();
+        assert_eq!(src.start(), "AéB");
+        assert!(src.rewind(checkpoint));
+        assert_eq!((src.start(), src.remainder()), ("Aé", "b-c"));
+        src.skip_while(|c| c != '-');
+        src.start_mut().make_ascii_uppercase();
+        let token = src.take_marked(mark).unwrap();
+        assert_eq!(token, "AéB");
+        assert_eq!(src.start_mut(), "");
+    });
+    let expected = if cfg!(feature = "compact_on_take") {
+        "AéB-c "
+    } else {
+        "AéB -c"
+    };
+    assert_eq!(text, expected);
+}
+
+#[test]
+fn replace_literal_replaces_every_match() {
+    let mut text = String::from("a---b----c-");
+    let report = Replacinator::new_in(&mut text, |src| src.replace_literal("---", "—").unwrap());
+    assert_eq!(text, "a—b—-c-");
+    assert_eq!(
+        (
+            report.read_len(),
+            report.written_len(),
+            report.replacements()
+        ),
+        (11, 11, 2)
+    );
+    let mut text = String::from("Ünïcode; ünïcode");
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_char();
+        src.replace_literal(['ï', ';'], "i").unwrap()
+    });
+    assert_eq!(text, "Ünicodei ünicode  ");
+}
+
+#[test]
+fn replace_literal_without_matches_reads_the_remainder() {
+    let mut text = String::from("abc");
+    let report = Replacinator::new_in(&mut text, |src| {
+        let report = src.replace_literal(IgnoreAsciiCase("X"), "").unwrap();
+        assert_eq!((src.start(), src.remainder()), ("abc", ""));
+        report
+    });
+    assert_eq!(text, "abc");
+    assert_eq!((report.written_len(), report.replacements()), (3, 0));
+    let mut text = String::from("aBAb");
+    Replacinator::new_in(&mut text, |src| {
+        src.replace_literal_ignore_ascii_case("ab", "-").unwrap()
+    });
+    assert_eq!(text, "--  ");
+}
+
+#[test]
+fn replace_literal_with_a_longer_replacement_is_an_error() {
+    let mut text = String::from("xa");
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_char();
+        let error = src.replace_literal('a', "aa").unwrap_err();
+        let kind = ReplaceErrorKind::TooLong {
+            match_len: 1,
+            replacement_len: 2,
+        };
+        assert_eq!((error.kind(), error.position().byte()), (kind, 1));
+        assert_eq!(src.remainder(), "a");
+    });
+    assert_eq!(text, "xa");
+}
+
+#[test]
+fn replace_literal_checks_every_match_before_replacing() {
+    // The first match could be replaced, but the second is too short
+    let mut text = String::from("é, a, é");
+    let error = Replacinator::new_in(&mut text, |src| {
+        src.replace_literal(|c: char| c.is_alphabetic(), "ä")
+            .unwrap_err()
+    });
+    assert_eq!(error.position().byte(), 4);
+    assert_eq!(
+        error.to_string(),
+        "a replacement of 2 bytes is longer than the match of 1 bytes at byte 4"
+    );
+    assert_eq!(text, "é, a, é");
+}
+
+#[test]
+#[cfg(feature = "case_folding")]
+fn replace_literal_case_folded_checks_matches_shorter_than_from() {
+    // `k` matches U+212A KELVIN SIGN, so the second match is two bytes shorter than `from`
+    let mut text = String::from("\u{212A}x kx");
+    let error = Replacinator::new_in(&mut text, |src| {
+        src.replace_literal_case_folded("\u{212A}x", "abcd")
+            .unwrap_err()
+    });
+    let kind = ReplaceErrorKind::TooLong {
+        match_len: 2,
+        replacement_len: 4,
+    };
+    assert_eq!((error.kind(), error.position().byte()), (kind, 5));
+    assert_eq!(text, "\u{212A}x kx");
+    Replacinator::new_in(&mut text, |src| {
+        src.replace_literal_case_folded("\u{212A}x", "ab").unwrap()
+    });
+    assert_eq!(text, "ab ab  ");
+}
+
+#[test]
+fn replace_literal_of_the_empty_string_is_an_error() {
+    let mut text = String::from("a");
+    let error = Replacinator::new_in(&mut text, |src| src.replace_literal("", "").unwrap_err());
+    assert_eq!(error.kind(), ReplaceErrorKind::EmptyMatch);
+    assert_eq!(
+        error.to_string(),
+        "cannot replace the empty string at byte 0"
+    );
+    assert_eq!(text, "a");
+}
+
+#[test]
+fn truncate_buffer_backs_up_to_a_char_boundary() {
+    let mut text = String::from("aé中b");
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_char();
+        assert_eq!(src.truncate_buffer(5), 3);
+        assert_eq!((src.start(), src.remainder()), ("aé", ""));
+    });
+    assert_eq!(text, "aé    ");
+    let mut text = String::from("aé-中b");
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_while(|c| c != '-');
+        src.discard_char();
+        src.skip_char();
+        assert_eq!(src.start(), "aé中");
+        assert_eq!(src.truncate_buffer(4), 3);
+        assert_eq!((src.start(), src.remainder()), ("aé", ""));
+    });
+    assert_eq!(text, "aé     ");
+}
+
+#[test]
+fn truncate_buffer_to_a_longer_length_changes_nothing() {
+    let mut text = String::from("ab c");
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_char();
+        src.discard_char();
+        assert_eq!(src.truncate_buffer(3), 3);
+        assert_eq!(src.truncate_buffer(usize::MAX), 3);
+        assert_eq!(src.remainder(), " c");
+        assert_eq!(src.truncate_buffer(0), 0);
+        assert_eq!((src.start(), src.remainder()), ("", ""));
+    });
+    assert_eq!(text, "    ");
+}
+
+#[test]
+fn headroom_allows_replacements_which_grow_the_text() {
+    let mut text = String::from("    a&b&c");
+    let escaped = Replacinator::new_in_with_headroom(&mut text, 4, |src| {
+        assert_eq!((src.position().byte(), src.remainder()), (4, "a&b&c"));
+        assert_eq!(src.gap_len(), 4);
+        while let Some(c) = src.read_char() {
+            if c == '&' {
+                src.write_str("&&&");
+            } else {
+                src.write_char(c);
+            }
+        }
+        src.take_start().to_owned()
+    })
+    .unwrap();
+    assert_eq!(escaped, "a&&&b&&&c");
+    assert_eq!(text, escaped);
+}
+
+#[test]
+fn headroom_of_zero_is_new_in() {
+    let mut text = String::from("ab");
+    Replacinator::new_in_with_headroom(&mut text, 0, |src| {
+        assert_eq!((src.gap_len(), src.remainder()), (0, "ab"));
+        src.discard_char();
+    })
+    .unwrap();
+    assert_eq!(text, " b");
+}
+
+#[test]
+fn headroom_must_end_on_a_char_boundary() {
+    let mut text = String::from("éa");
+    for gap in [1, 4] {
+        let error = Replacinator::new_in_with_headroom(&mut text, gap, |_| unreachable!());
+        assert_eq!(error.unwrap_err().gap(), gap);
+    }
+    assert_eq!(text, "éa");
+}
+
+#[test]
+fn extend_writes_characters_and_strings() {
+    let mut text = String::from("Hello, World!");
+    Replacinator::new_in(&mut text, |src| {
+        let read: Vec<char> = src.reader().collect();
+        src.extend(
+            read.iter()
+                .filter(|c| c.is_alphabetic())
+                .map(char::to_ascii_lowercase),
+        );
+        src.extend(["-", "", "x"]);
+        assert_eq!(src.take_start(), "helloworld-x");
+    });
+}
+
+#[test]
+fn write_chars_follows_the_overflow_mode() {
+    let mut text = String::from("abcdef");
+    Replacinator::new_in(&mut text, |src| {
+        src.discard_n(3);
+        assert_eq!(src.write_chars("xé中yz".chars(), Overflow::Skip), 2);
+        assert_eq!(src.start(), "xé");
+        src.discard_n(3);
+        let mut chars = "中yz".chars();
+        assert_eq!(src.write_chars(chars.by_ref(), Overflow::Stop), 1);
+        // The character which didn't fit was consumed, but the rest were not
+        assert_eq!(chars.as_str(), "z");
+        assert_eq!(src.write_chars("".chars(), Overflow::Panic), 0);
+    });
+    assert_eq!(text, "xé中");
+}
+
+#[test]
+fn write_chars_counts_only_what_the_overflow_policy_wrote() {
+    let mut text = String::from("abcdef");
+    Replacinator::new_in(&mut text, |src| {
+        src.set_overflow_policy(OverflowPolicy::Error);
+        src.discard_n(2);
+        assert_eq!(src.write_chars("x中y".chars(), Overflow::Panic), 2);
+        assert_eq!(src.start(), "xy");
+        assert!(src.overflowed());
+        src.set_overflow_policy(OverflowPolicy::Saturate);
+        src.discard_n(2);
+        src.extend("中é".chars());
+        assert_eq!(src.start(), "xyé");
+    });
+    assert_eq!(text, "xyéef");
+}
+
+#[test]
+#[should_panic]
+fn write_chars_can_panic_on_overflow() {
+    let mut text = String::from("ab");
+    Replacinator::new_in(&mut text, |src| {
+        src.discard_char();
+        src.extend("xy".chars());
+    });
+}
+
+#[test]
+#[cfg(feature = "unicode-segmentation")]
+fn graphemes_are_read_whole() {
+    let family = "👨\u{200D}👩\u{200D}👧";
+    let mut text = format!("e\u{301}{}x\r\n", family);
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.peek_grapheme(), Some("e\u{301}"));
+        assert_eq!(src.read_grapheme(), Some("e\u{301}"));
+        assert_eq!(src.skip_grapheme(), Some(family));
+        assert_eq!(src.start(), family);
+        assert_eq!(src.skip_grapheme(), Some("x"));
+        assert_eq!(src.read_grapheme(), Some("\r\n"));
+        assert_eq!(src.peek_grapheme(), None);
+        assert_eq!(src.read_grapheme(), None);
+        assert_eq!(src.skip_grapheme(), None);
+    });
+    assert!(text.starts_with(family));
+}
+
+#[test]
+fn lines_are_read_with_their_endings() {
+    let mut text = String::from("one\r\ntwo\n\nlast\r");
+    Replacinator::new_in(&mut text, |src| {
+        let lines: Vec<_> = src.remainder_lines().collect();
+        assert_eq!(lines, ["one", "two", "", "last\r"]);
+        assert_eq!(src.read_line(), Some("one"));
+        assert_eq!(src.skip_line(), Some("two"));
+        assert_eq!(src.skip_line(), Some(""));
+        assert_eq!(src.start(), "two\n\n");
+        assert_eq!(src.read_line(), Some("last\r"));
+        assert_eq!(src.read_line(), None);
+        assert_eq!(src.skip_line(), None);
+    });
+    assert!(text.starts_with("two\n\n"));
+}
+
+#[test]
+#[cfg(feature = "unicode-segmentation")]
+fn words_are_read_between_word_boundaries() {
+    let mut text = String::from("can't  stop, 3.5é");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.skip_word(), Some("can't"));
+        assert_eq!(src.read_word(), Some("  "));
+        assert_eq!(src.skip_word(), Some("stop"));
+        assert_eq!(src.skip_word(), Some(","));
+        assert_eq!(src.read_word(), Some(" "));
+        assert_eq!(src.skip_word(), Some("3.5é"));
+        assert_eq!(src.read_word(), None);
+        assert_eq!(src.skip_word(), None);
+        assert_eq!(src.start(), "can'tstop,3.5é");
+    });
+}
+
+#[test]
+fn ascii_whitespace_is_skipped_across_whole_words() {
+    for len in [0, 1, 7, 8, 9, 16, 23] {
+        let mut text = " \t\n\r\x0C".repeat(5)[..len].to_owned() + "\x0Bx";
+        Replacinator::new_in(&mut text, |src| {
+            assert_eq!(src.skip_ascii_whitespace(), len);
+            assert_eq!(src.remainder(), "\x0Bx");
+            assert_eq!(src.skip_ascii_whitespace(), 0);
+        });
+    }
+    let whitespace = "\u{A0}  \n\t\t\t\t\t\t\t\t é";
+    let mut text = format!("{0}{0}", whitespace);
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_char();
+        assert_eq!(src.discard_ascii_whitespace(), 12);
+        assert_eq!(src.skip_char(), Some('é'));
+        src.skip_char();
+        assert_eq!(src.discard_ascii_whitespace(), 12);
+        assert_eq!(src.remainder(), "é");
+        assert_eq!(src.start(), "\u{A0}é\u{A0}");
+    });
+}
+
+#[test]
+fn skip_while_passes_every_character_to_the_predicate() {
+    let mut text = String::from("ab中déf!");
+    Replacinator::new_in(&mut text, |src| {
+        let mut seen = String::new();
+        let len = src.skip_while(|c| {
+            seen.push(c);
+            c != '!'
+        });
+        assert_eq!((len, seen.as_str()), (9, "ab中déf!"));
+        assert_eq!(src.discard_while(|c| c == '!'), 1);
+        assert_eq!(src.skip_while(|_| true), 0);
+    });
+}
+
+#[test]
+fn the_second_section_is_filled_using_the_given_strategy() {
+    let mut text = String::from("abcdefgh");
+    Replacinator::new_in(&mut text, |src| {
+        src.discard_char();
+        src.discard_char();
+        src.synchronise_with(FillStrategy::Nul);
+        assert_eq!(src.write_offset(), src.read_offset());
+        src.skip_char();
+        src.discard_char();
+        src.discard_char();
+        src.discard_char();
+        src.synchronise_with(FillStrategy::Char('é'));
+        src.discard_char();
+        src.synchronise();
+        assert_eq!(src.fill(), FillStrategy::DEFAULT);
+    });
+    assert_eq!(text, "\0\0cé  h");
+}
+
+#[test]
+fn compaction_moves_the_remainder_to_the_first_section() {
+    let mut text = String::from("ab cd");
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_char();
+        src.discard_char();
+        src.discard_char();
+        src.compact();
+        assert_eq!((src.start(), src.remainder()), ("a", "cd"));
+        assert_eq!(
+            (src.read_offset(), src.write_offset(), src.len()),
+            (3, 1, 3)
+        );
+        src.discard_char();
+        src.synchronise_with(FillStrategy::Compact);
+        assert_eq!((src.start(), src.remainder()), ("a", "d"));
+    });
+    assert_eq!(text, "ad   ");
+}
+
+#[test]
+fn the_fill_strategy_is_used_when_dropped() {
+    let mut text = String::from("a, b");
+    Replacinator::new_in(&mut text, |src| {
+        src.set_fill(FillStrategy::Char('-'));
+        src.skip_char();
+        src.discard_char();
+        assert_eq!(src.fill(), FillStrategy::Char('-'));
+    });
+    assert_eq!(text, "a- b");
+    let mut text = String::from("a, b");
+    Replacinator::new_in(&mut text, |src| {
+        src.set_fill(FillStrategy::Compact);
+        src.skip_char();
+        src.discard_char();
+        src.discard_char();
+    });
+    assert_eq!(text, "ab  ");
+}
+
+#[test]
+fn padding_can_be_recognised_and_trimmed() {
+    assert_eq!(FillStrategy::DEFAULT, FillStrategy::default());
+    assert_eq!(FillStrategy::POISONED.pad_char(), ' ');
+    assert_eq!(FillStrategy::Compact.pad_char(), ' ');
+    assert_eq!(FillStrategy::Nul.pad_char(), '\0');
+    let fill = FillStrategy::Char('é');
+    assert!(fill.is_padding('é') && fill.is_padding(' ') && !fill.is_padding('e'));
+    assert!(!FillStrategy::Nul.is_padding('x'));
+    let mut text = String::from("ab, c");
+    Replacinator::new_in(&mut text, |src| {
+        src.set_fill(fill);
+        src.discard_while(|c| c != 'c');
+    });
+    assert_eq!(text, "ééc");
+    assert_eq!(text.trim_start_matches(|c| fill.is_padding(c)), "c");
+}
+
+#[test]
+fn taken_slices_are_adjacent_when_compacting_on_take() {
+    let mut text = String::from("a, bé; c");
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_char();
+        src.discard_n(2);
+        let first = src.take_start();
+        src.skip_str("bé");
+        src.discard_n(2);
+        let second = src.take_start();
+        let adjacent = first.as_ptr() as usize + first.len() == second.as_ptr() as usize;
+        assert_eq!((&*first, &*second), ("a", "bé"));
+        assert_eq!(adjacent, cfg!(feature = "compact_on_take"));
+        assert_eq!(src.remainder(), "c");
+    });
+    let expected = if cfg!(feature = "compact_on_take") {
+        "abéc    "
+    } else {
+        "a  bé  c"
+    };
+    assert_eq!(text, expected);
+}
+
+#[test]
+fn take_compacted_returns_the_content_and_its_padding() {
+    let mut text = String::from("x, y, z");
+    let mut string = text.clone();
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_char();
+        src.discard_char();
+        src.skip_str(" y");
+        src.compact();
+        assert_eq!(src.remainder(), ", z");
+        src.discard_n(2);
+        let (content, padding) = src.take_compacted();
+        assert_eq!((&*content, padding), ("x yz", 3));
+        assert_eq!(src.remainder(), "");
+    });
+    assert_eq!(text, "x yz   ");
+    let len = Replacinator::new_in(&mut string, |src| src.take_compacted().0.len());
+    assert_eq!((len, string.as_str()), (7, "x, y, z"));
+}
+
+#[test]
+fn lengths_give_how_much_of_the_string_is_meaningful() {
+    let mut text = String::from("a--b--cé");
+    let len = Replacinator::new_in(&mut text, |src| {
+        src.skip_char();
+        let report = src.replace_literal("--", "-").unwrap();
+        assert_eq!(
+            (
+                report.read_len(),
+                report.written_len(),
+                report.replacements()
+            ),
+            (8, 6, 2)
+        );
+        assert_eq!((src.written_len(), src.consumed_len()), (7, 9));
+        assert_eq!((src.gap_len(), src.remaining_len()), (2, 0));
+        src.written_len()
+    });
+    text.truncate(len);
+    assert_eq!(text, "a-b-cé");
+    let mut text = String::from("ab|cd");
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_char();
+        src.discard_char();
+        src.skip_char();
+        src.take_start();
+        assert_eq!((src.written_len(), src.consumed_len()), (0, 0));
+        src.discard_char();
+        assert_eq!(
+            (src.written_len(), src.consumed_len(), src.remaining_len()),
+            (0, 1, 1)
+        );
+    });
+}
+
+#[test]
+fn byte_buffers_are_validated_once() {
+    let mut bytes = *b"a-b\xc3\xa9";
+    Replacinator::from_utf8_in(&mut bytes, |src| {
+        src.skip_char();
+        src.discard_char();
+        let taken = src.take_remainder();
+        assert_eq!(taken, "bé");
+        taken.make_ascii_uppercase();
+    })
+    .unwrap();
+    assert!(std::str::from_utf8(&bytes).is_ok());
+    assert_eq!(&bytes, b"a B\xc3\xa9");
+
+    let mut bytes = *b"ab\xc3";
+    let error = Replacinator::from_utf8_in(&mut bytes, |_| ()).unwrap_err();
+    assert_eq!((error.valid_up_to(), error.error_len()), (2, None));
+    let mut bytes = *b"\xff";
+    assert!(Replacinator::from_utf8_in(&mut bytes, |_| ()).is_err());
+    assert!(Replacinator::from_utf8_in(&mut [], |_| ()).is_ok());
+}
+
+#[test]
+fn byte_buffers_are_valid_after_the_replacinator_is_dropped() {
+    let mut bytes = *b"abc\xc3\xa9";
+    Replacinator::from_utf8_in(&mut bytes, |src| {
+        src.read_char();
+        src.read_char();
+        src.write_char('x');
+    })
+    .unwrap();
+    assert_eq!(std::str::from_utf8(&bytes).unwrap(), "x c\u{e9}");
+}
+
+#[test]
+fn swapped_byte_buffer_replacinators_are_both_restored() {
+    // An owned `Replacinator` could be swapped into a scoped one, then forgotten with its gap
+    let mut first = String::from("\u{e9}xyz");
+    let mut bytes = *b"\xc3\xa9xyz";
+    let mut buffer = Some(&mut bytes[..]);
+    Replacinator::new_in(&mut first, |outer| {
+        Replacinator::from_utf8_in(buffer.take().unwrap(), |inner| {
+            inner.discard_char();
+            inner.write_char('a');
+            outer.discard_char();
+            outer.write_char('b');
+            std::mem::swap(outer, inner);
+        })
+        .unwrap();
+    });
+    assert_eq!(first, "b xyz");
+    assert_eq!(std::str::from_utf8(&bytes).unwrap(), "a xyz");
+
+    let mut bytes = *b"\xc3\xa9\xffz";
+    let mut buffer = Some(&mut bytes[..]);
+    Replacinator::new_in(&mut first, |outer| {
+        Replacinator::from_utf8_lossy_in_place(buffer.take().unwrap(), |inner| {
+            inner.discard_char();
+            inner.write_char('c');
+            std::mem::swap(outer, inner);
+        })
+    });
+    assert_eq!(std::str::from_utf8(&bytes).unwrap(), "c ?z");
+}
+
+#[test]
+fn invalid_bytes_are_repaired_in_place() {
+    fn repaired(bytes: &[u8]) -> String {
+        let mut bytes = bytes.to_vec();
+        let remainder =
+            Replacinator::from_utf8_lossy_in_place(&mut bytes, |src| src.remainder().to_string());
+        assert_eq!(std::str::from_utf8(&bytes).unwrap(), remainder);
+        remainder
+    }
+
+    assert_eq!(repaired(b"a\xffb"), "a?b");
+    assert_eq!(repaired(b"ab\xe2\x82"), "ab? ");
+    assert_eq!(repaired(b"\xf0\x9f\x98"), "\u{FFFD}");
+    assert_eq!(repaired(b"\xf0\x9f\x98z"), "\u{FFFD}z");
+    // Each byte of an encoded surrogate is a separate invalid sequence
+    assert_eq!(repaired(b"\xed\xa0\x80"), "???");
+    assert_eq!(repaired(b"\xc3\xa9\x80\xc3\xa9"), "é?é");
+    assert_eq!(repaired(b""), "");
+}
+
+#[test]
+fn repaired_bytes_can_be_rewritten() {
+    let mut bytes = *b"x\xff,y";
+    Replacinator::from_utf8_lossy_in_place(&mut bytes, |src| {
+        src.skip_while(|c| c != ',');
+        src.discard_char();
+        assert_eq!(src.take_remainder(), "y");
+    });
+    assert_eq!(&bytes, b"x? y");
+}
+
+#[test]
+fn debug_shows_the_three_sections() {
+    let mut text = String::from("ab-\"é\"");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(
+            format!("{:?}", src),
+            r#"Replacinator { start: "", gap: 0 bytes, remainder: "ab-\"é\"" }"#
+        );
+        src.skip_str("ab");
+        src.discard_char();
+        assert_eq!(
+            format!("{:?}", src),
+            r#"Replacinator { start: "ab", gap: 1 bytes, remainder: "\"é\"" }"#
+        );
+        src.take_start();
+        assert_eq!(
+            format!("{:?}", src),
+            r#"Replacinator { start: "", gap: 0 bytes, remainder: "\"é\"" }"#
+        );
+    });
+}
+
+#[test]
+fn display_shows_the_logical_text() {
+    let mut text = String::from("a\t-é");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.to_string(), "a\t-é");
+        src.skip_char();
+        src.discard_n(2);
+        src.write_char('+');
+        assert_eq!(src.to_string(), "a+é");
+        assert_eq!(format!("[{:>2}]", src), "[a+é]");
+    });
+    let mut empty = String::new();
+    Replacinator::new_in(&mut empty, |src| assert_eq!(src.to_string(), ""));
+}
+
+#[test]
+fn dirty_range_spans_the_first_to_the_last_change() {
+    let dirty = |text: &str, f: fn(&mut Replacinator<'_>)| {
+        let mut text = String::from(text);
+        Replacinator::new_in(&mut text, |src| {
+            f(src);
+            src.dirty_range()
+        })
+    };
+    fn rewrite(src: &mut Replacinator<'_>, with: &str) {
+        src.discard_n(4);
+        src.write_str(with);
+    }
+    assert_eq!(dirty("abcd", |src| rewrite(src, "abcd")), None);
+    assert_eq!(dirty("abcd", |src| rewrite(src, "Abcd")), Some(0..1));
+    assert_eq!(dirty("abcd", |src| rewrite(src, "abCd")), Some(2..3));
+    // The unchanged bytes between the changes are included
+    assert_eq!(dirty("abcd", |src| rewrite(src, "AbcD")), Some(0..4));
+    // Moving text over identical text doesn't change those bytes
+    assert_eq!(
+        dirty("aab", |src| {
+            src.discard_n(1);
+            src.skip_str("ab");
+        }),
+        Some(1..3)
+    );
+    assert_eq!(
+        dirty("aB-cD", |src| src.make_ascii_lowercase_remaining()),
+        Some(1..5)
+    );
+    assert_eq!(
+        dirty("ab-cd", |src| src.make_ascii_lowercase_remaining()),
+        None
+    );
+}
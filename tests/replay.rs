@@ -0,0 +1,115 @@
+//! Tests of recording and replaying the operations on a [`Replacinator`]
+#![cfg(feature = "std")]
+
+use replacinator::{
+    replay::{self, ErrorKind, Operation, Recorder},
+    FillStrategy, Replacinator,
+};
+
+#[test]
+fn a_recording_replays_to_the_same_result() {
+    let original = "ab, é-c; d";
+    let mut text = String::from(original);
+    let (taken, operations) = Replacinator::new_in_observed(&mut text, Recorder::new(), |src| {
+        src.skip_char();
+        src.discard_char();
+        src.write_char('B');
+        let checkpoint = src.checkpoint();
+        src.discard_char();
+        assert!(src.rewind(checkpoint));
+        src.skip_while(|c| c != '-');
+        src.synchronise_with(FillStrategy::Char('_'));
+        src.discard_char();
+        let start = src.take_start().to_string();
+        src.skip_char();
+        let remainder = src.take_remainder().to_string();
+        let operations = src.observer().operations().to_vec();
+        ((start, remainder), operations)
+    });
+    assert!(operations.contains(&Operation::Synchronise(FillStrategy::Char('_'))));
+    assert!(operations.contains(&Operation::TakeStart));
+
+    let decoded = replay::decode(&replay::encode(&operations)).unwrap();
+    assert_eq!(decoded, operations);
+    let mut copy = String::from(original);
+    let replayed = Replacinator::new_in(&mut copy, |src| {
+        let slices = replay::replay(src, &decoded).unwrap();
+        assert_eq!(slices.len(), 2);
+        (slices[0].to_string(), slices[1].to_string())
+    });
+    assert_eq!(replayed, taken);
+    assert_eq!(copy, text);
+}
+
+#[test]
+fn operations_are_encoded_one_per_line() {
+    let operations = [
+        Operation::Read("é\n".into()),
+        Operation::Write("".into()),
+        Operation::Synchronise(FillStrategy::Space),
+        Operation::Synchronise(FillStrategy::Char('·')),
+        Operation::Rewind {
+            read_offset: 3,
+            write_offset: 1,
+        },
+        Operation::TakeStart,
+        Operation::TakeRemainder,
+    ];
+    let encoded = replay::encode(&operations);
+    assert_eq!(
+        encoded,
+        "read 3:é\n\nwrite 0:\nsynchronise space\nsynchronise char 2:·\nrewind 3 1\n\
+         take_start\ntake_remainder\n"
+    );
+    assert_eq!(replay::decode(&encoded).unwrap(), operations);
+    assert_eq!(replay::decode("").unwrap(), []);
+}
+
+#[test]
+fn malformed_logs_are_rejected_at_the_bad_line() {
+    for (log, position) in [
+        ("read 4:ab\n", 0),
+        ("take_start\njump\n", 11),
+        ("take_start", 0),
+        ("synchronise char 2:ab\n", 0),
+        ("synchronise fast\n", 0),
+        ("take_start\nrewind 1\n", 11),
+        ("rewind x 1\n", 0),
+    ] {
+        let error = replay::decode(log).unwrap_err();
+        assert_eq!(
+            (error.kind(), error.position()),
+            (ErrorKind::Malformed, position)
+        );
+    }
+    assert_eq!(
+        replay::decode("jump\n").unwrap_err().to_string(),
+        "malformed operation log at byte 0"
+    );
+}
+
+#[test]
+fn replaying_against_different_text_diverges() {
+    let mut text = String::from("abc");
+    Replacinator::new_in(&mut text, |src| {
+        let operations = [Operation::Read("a".into()), Operation::Read("x".into())];
+        let error = replay::replay(src, &operations).unwrap_err();
+        assert_eq!((error.kind(), error.position()), (ErrorKind::Diverged, 1));
+        assert_eq!(
+            error.to_string(),
+            "replay diverged from the recording at byte 1"
+        );
+        // The operations before the divergence have been applied
+        assert_eq!(src.remainder(), "bc");
+
+        let error = replay::replay(src, &[Operation::Write("xy".into())]).unwrap_err();
+        assert_eq!((error.kind(), error.position()), (ErrorKind::Diverged, 0));
+        let rewind = Operation::Rewind {
+            read_offset: 0,
+            write_offset: 0,
+        };
+        let error = replay::replay(src, &[rewind]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Diverged);
+    });
+    assert_eq!(text, " bc");
+}
@@ -0,0 +1,85 @@
+//! Tests of rewriting from right to left
+
+use replacinator::RevReplacinator;
+
+#[test]
+fn suffixes_are_rewritten_without_reading_the_start() {
+    let mut text = String::from("report-é.tar.gz  ");
+    RevReplacinator::new_in(&mut text, |src| {
+        assert_eq!(src.discard_while(|c| c == ' '), 2);
+        assert!(src.skip_str(".gz"));
+        assert!(!src.skip_str(".zip"));
+        src.discard_char();
+        src.read_char();
+        src.read_char();
+        src.write_str("t");
+        assert_eq!((src.remainder(), src.end()), ("report-é.", "t.gz"));
+        assert_eq!((src.read_offset(), src.write_offset()), (10, 14));
+        assert_eq!(src.peek(), Some('.'));
+        assert!(src.ends_with("é."));
+        assert_eq!(src.skip_while(|c| c != '-'), 3);
+        assert_eq!(src.end(), "é.t.gz");
+    });
+    assert_eq!(text, "report-    é.t.gz");
+}
+
+#[test]
+fn headroom_allows_the_text_to_grow_from_the_end() {
+    let mut text = String::from("a<b<c  ");
+    RevReplacinator::new_in_with_headroom(&mut text, 2, |src| {
+        assert_eq!(src.remainder(), "a<b<c");
+        while let Some(c) = src.read_char() {
+            if c == '<' {
+                src.write_str("<<");
+            } else {
+                src.write_char(c);
+            }
+        }
+        assert_eq!(src.take_end(), "a<<b<<c");
+    })
+    .unwrap();
+    assert_eq!(text, "a<<b<<c");
+}
+
+#[test]
+fn taking_the_ends_leaves_the_rest_usable() {
+    let mut text = String::from("ab,cd");
+    RevReplacinator::new_in(&mut text, |src| {
+        src.skip_char();
+        src.discard_char();
+        let end = src.take_end();
+        assert_eq!(end, "d");
+        end.make_ascii_uppercase();
+        src.discard_char();
+        src.skip_char();
+        let start = src.take_remainder();
+        assert_eq!(start, "a");
+        assert_eq!(src.remainder(), "");
+        assert_eq!(src.read_char(), None);
+        src.end_mut().make_ascii_uppercase();
+        assert_eq!((src.read_offset(), src.write_offset()), (1, 2));
+        src.synchronise();
+        assert_eq!(src.write_offset(), 1);
+    });
+    assert_eq!(text, "a B D");
+}
+
+#[test]
+#[should_panic(expected = "Cannot write more bytes than have been read")]
+fn writing_more_than_was_read_panics() {
+    let mut text = String::from("ab");
+    RevReplacinator::new_in(&mut text, |src| {
+        src.read_char();
+        src.write_str("xy");
+    });
+}
+
+#[test]
+fn headroom_must_start_on_a_char_boundary() {
+    let mut text = String::from("aé");
+    for gap in [1, 4] {
+        let error = RevReplacinator::new_in_with_headroom(&mut text, gap, |_| unreachable!());
+        assert_eq!(error.unwrap_err().gap(), gap);
+    }
+    assert_eq!(text, "aé");
+}
@@ -0,0 +1,112 @@
+//! Tests of driving a character-by-character rewriter over a `Replacinator`
+
+use std::ops::ControlFlow;
+
+use replacinator::{
+    rewrite::{NoRoom, Outcome, Rewriter, Sink},
+    Replacinator,
+};
+
+fn escape_quotes(c: char, out: &mut Sink<'_>) -> ControlFlow<()> {
+    let _ = match c {
+        '"' => out.write_str("\\\""),
+        c => out.write_char(c),
+    };
+    ControlFlow::Continue(())
+}
+
+/// A rewriter which removes runs of `-`, writing how many were removed at the end
+#[derive(Default)]
+struct CountDashes {
+    removed: usize,
+}
+
+impl Rewriter for CountDashes {
+    fn rewrite(&mut self, c: char, out: &mut Sink<'_>) -> ControlFlow<()> {
+        if c == '-' {
+            self.removed += 1;
+        } else {
+            let _ = out.write_char(c);
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn finish(&mut self, out: &mut Sink<'_>) {
+        let _ = out.write_str(&format!("({})", self.removed));
+    }
+}
+
+#[test]
+fn output_can_grow_into_freed_space() {
+    let mut text = String::from("--a\"é");
+    Replacinator::new_in(&mut text, |src| {
+        src.discard_n(2);
+        let outcome = src.drive(&mut escape_quotes);
+        assert_eq!(outcome, Outcome::Finished);
+        assert_eq!((src.start(), src.remainder()), ("a\\\"é", ""));
+    });
+    assert_eq!(text, "a\\\"é ");
+}
+
+#[test]
+fn output_which_does_not_fit_leaves_the_character_unread() {
+    let mut text = String::from("ab\"cd");
+    Replacinator::new_in(&mut text, |src| {
+        let outcome = src.drive(&mut escape_quotes);
+        match outcome {
+            Outcome::NoRoom(position) => assert_eq!(position.byte(), 2),
+            outcome => panic!("unexpected outcome {:?}", outcome),
+        }
+        assert_eq!((src.start(), src.remainder()), ("ab", "\"cd"));
+    });
+    assert_eq!(text, "ab\"cd");
+}
+
+#[test]
+fn rewriters_can_stop_early() {
+    let mut text = String::from("ab.cd");
+    Replacinator::new_in(&mut text, |src| {
+        let mut upper_to_dot = |c: char, out: &mut Sink<'_>| {
+            let _ = out.write_char(c.to_ascii_uppercase());
+            if c == '.' {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        };
+        assert_eq!(src.drive(&mut upper_to_dot), Outcome::Stopped);
+        assert_eq!((src.start(), src.remainder()), ("AB.", "cd"));
+    });
+}
+
+#[test]
+fn finishing_output_is_written_if_it_fits() {
+    let mut text = String::from("a---b");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.drive(&mut CountDashes::default()), Outcome::Finished);
+        assert_eq!(src.start(), "ab(3)");
+    });
+    let mut text = String::from("a-b");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.drive(&mut CountDashes::default()), Outcome::Finished);
+        // `(1)` needs three bytes, but only one was freed
+        assert_eq!(src.start(), "ab");
+    });
+    assert_eq!(text, "ab ");
+}
+
+#[test]
+fn sinks_report_the_room_available() {
+    let mut text = String::from("xy");
+    Replacinator::new_in(&mut text, |src| {
+        let mut rooms = Vec::new();
+        let mut record = |_: char, out: &mut Sink<'_>| {
+            rooms.push(out.available());
+            assert_eq!(out.write_str("too long"), Err(NoRoom));
+            assert_eq!(out.write_str(""), Ok(()));
+            ControlFlow::Continue(())
+        };
+        assert!(matches!(src.drive(&mut record), Outcome::NoRoom(_)));
+        assert_eq!(rooms, [1]);
+    });
+}
@@ -0,0 +1,261 @@
+//! Tests of the sanitising passes
+
+use replacinator::sanitize;
+use replacinator::Replacinator;
+
+#[test]
+fn filename_replaces_forbidden_characters() {
+    let mut name = String::from("a/b\\c:d*e?f\"g<h>i|j\0k");
+    assert_eq!(sanitize::filename(&mut name, 100), "a_b_c_d_e_f_g_h_i_j_k");
+}
+
+#[test]
+fn filename_replaces_trailing_dots_and_reserved_names() {
+    let mut name = String::from("notes. .");
+    assert_eq!(sanitize::filename(&mut name, 100), "notes___");
+    let mut name = String::from("..");
+    assert_eq!(sanitize::filename(&mut name, 100), "__");
+    let mut name = String::from("Lpt1.tar.gz");
+    assert_eq!(sanitize::filename(&mut name, 100), "____.tar.gz");
+    let mut name = String::from("console.txt");
+    assert_eq!(sanitize::filename(&mut name, 100), "console.txt");
+}
+
+#[test]
+fn filename_truncates_on_a_character_boundary() {
+    let mut name = String::from("ab/cdéf.txt");
+    assert_eq!(sanitize::filename(&mut name, 5), "ab_cd");
+    assert_eq!(name, format!("ab_cd{}", " ".repeat(7)));
+    let mut name = String::from("ab/cdéf.txt");
+    assert_eq!(sanitize::filename(&mut name, 6), "ab_cd");
+    assert_eq!(name, format!("ab_cd{}", " ".repeat(7)));
+    // Truncation can leave a trailing dot, which is then replaced
+    let mut name = String::from("a.b");
+    assert_eq!(sanitize::filename(&mut name, 2), "a_");
+    assert_eq!(name, "a_ ");
+}
+
+#[test]
+fn filename_of_empty_name_is_empty() {
+    let mut name = String::from("abc");
+    assert_eq!(sanitize::filename(&mut name, 0), "");
+    assert_eq!(name, "   ");
+    let mut name = String::new();
+    assert_eq!(sanitize::filename(&mut name, 10), "");
+}
+
+fn sanitized(text: &str, allowed: &[char], replacement: Option<char>) -> String {
+    let mut text = String::from(text);
+    Replacinator::new_in(&mut text, |src| {
+        sanitize::text(src, allowed, replacement).to_owned()
+    })
+}
+
+#[test]
+fn text_removes_a_bom_and_control_characters() {
+    assert_eq!(
+        sanitized(
+            "\u{feff}a\0b\tc\r\n\u{7f}\u{85}d",
+            sanitize::LINE_CONTROLS,
+            None
+        ),
+        "ab\tc\r\nd"
+    );
+    assert_eq!(sanitized("a\u{feff}b", &[], None), "a\u{feff}b");
+    assert_eq!(sanitized("a\tb\n", &[], None), "ab");
+    assert_eq!(sanitized("", &[], None), "");
+}
+
+#[test]
+fn text_replacements_only_fit_where_space_was_freed() {
+    assert_eq!(sanitized("a\x01b", &[], Some('?')), "a?b");
+    // U+FFFD is three bytes, so only fits once the BOM has been removed
+    assert_eq!(
+        sanitized("\u{feff}\x01\x02", &[], Some('\u{fffd}')),
+        "\u{fffd}"
+    );
+    let mut text = String::from("\x01\x02");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(sanitize::text(src, &[], Some('\u{fffd}')), "");
+        assert_eq!(src.remainder(), "\x01\x02");
+    });
+}
+
+fn c_string(text: &str, marker: Option<char>) -> (String, String) {
+    let mut text = String::from(text);
+    Replacinator::new_in(&mut text, |src| {
+        let scrubbed = sanitize::c_string(src, marker).to_owned();
+        (scrubbed, src.remainder().to_owned())
+    })
+}
+
+#[test]
+fn c_string_removes_nuls_and_replacement_characters() {
+    assert_eq!(
+        c_string("a\0b\u{FFFD}c\0", None),
+        ("abc".to_owned(), String::new())
+    );
+    assert_eq!(
+        c_string("\u{FFFD}x\0", Some('?')),
+        ("?x?".to_owned(), String::new())
+    );
+    assert_eq!(c_string("", None), (String::new(), String::new()));
+}
+
+#[test]
+fn c_string_markers_only_fit_where_space_was_freed() {
+    // A three byte marker fits in place of U+FFFD, but not in place of a NUL
+    assert_eq!(
+        c_string("\u{FFFD}a\0b", Some('€')),
+        ("€a".to_owned(), "\0b".to_owned())
+    );
+    assert_eq!(
+        c_string("\0\0b", Some('€')),
+        (String::new(), "\0\0b".to_owned())
+    );
+}
+
+fn masked(
+    text: &str,
+    ranges: &[(usize, usize)],
+    mask: char,
+) -> (Result<String, sanitize::Error>, String) {
+    let mut text = String::from(text);
+    let result = Replacinator::new_in(&mut text, |src| {
+        sanitize::mask_ranges(src, ranges.iter().map(|&(start, end)| start..end), mask)
+            .map(|s| s.to_owned())
+    });
+    (result, text)
+}
+
+#[test]
+fn mask_ranges_keeps_the_length_of_the_text() {
+    let (result, text) = masked("card 1234 pin é9 ok", &[(5, 9), (14, 17)], '#');
+    assert_eq!(result.unwrap(), "card #### pin **# ok");
+    assert_eq!(text, "card #### pin **# ok");
+    let (result, _) = masked("aéb", &[(0, 4)], 'é');
+    assert_eq!(result.unwrap(), "*é*");
+    let (result, _) = masked("abc", &[], '#');
+    assert_eq!(result.unwrap(), "abc");
+    let (result, _) = masked("abc", &[(1, 1), (3, 3)], '#');
+    assert_eq!(result.unwrap(), "abc");
+}
+
+#[test]
+fn mask_ranges_is_relative_to_the_original_string() {
+    let mut text = String::from("id=42;pw=hunter2");
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_str("id=42;");
+        let value = src.find("hunter2").unwrap();
+        let masked = sanitize::mask_ranges(src, [value.bytes()], '*').unwrap();
+        assert_eq!(masked, "pw=*******");
+    });
+    assert_eq!(text, "id=42;pw=*******");
+}
+
+#[test]
+fn mask_ranges_rejects_invalid_ranges() {
+    let (result, text) = masked("abcdef", &[(0, 1), (3, 4), (2, 5)], '#');
+    let error = result.unwrap_err();
+    assert_eq!(
+        error.kind(),
+        sanitize::ErrorKind::OutOfOrder { start: 2, end: 5 }
+    );
+    assert_eq!(error.to_string(), "range 2..5 is out of order at byte 4");
+    // The ranges before the invalid one have been masked
+    assert_eq!(text, "#bc#ef");
+
+    let (result, _) = masked("abcdef", &[(3, 1)], '#');
+    assert!(matches!(
+        result.unwrap_err().kind(),
+        sanitize::ErrorKind::OutOfOrder { start: 3, end: 1 }
+    ));
+    let (result, _) = masked("abc", &[(2, 4)], '#');
+    assert_eq!(
+        result.unwrap_err().kind(),
+        sanitize::ErrorKind::OutOfBounds { start: 2, end: 4 }
+    );
+    let (result, _) = masked("aéb", &[(2, 3)], '#');
+    assert_eq!(
+        result.unwrap_err().kind(),
+        sanitize::ErrorKind::NotCharBoundary(2)
+    );
+    let (result, _) = masked("aéb", &[(0, 2)], '#');
+    assert_eq!(
+        result.unwrap_err().kind(),
+        sanitize::ErrorKind::NotCharBoundary(2)
+    );
+}
+
+/// Strip the escape sequences from `text`, returning the stripped text and what was left unread
+fn stripped(text: &str) -> (String, String) {
+    let mut text = String::from(text);
+    Replacinator::new_in(&mut text, |src| {
+        let stripped = sanitize::strip_ansi_escapes(src).to_owned();
+        (stripped, src.remainder().to_owned())
+    })
+}
+
+#[test]
+fn strip_ansi_escapes_removes_control_sequences() {
+    assert_eq!(
+        stripped("\x1b[1;31mred\x1b[0m \x1b[2K\x1b[?25lé"),
+        ("red é".into(), "".into())
+    );
+    // 8-bit control sequence introducer, and escapes with intermediate bytes
+    assert_eq!(
+        stripped("a\u{9b}32mb\x1b(Bc\x1b7d"),
+        ("abcd".into(), "".into())
+    );
+    assert_eq!(stripped("plain"), ("plain".into(), "".into()));
+    assert_eq!(stripped(""), ("".into(), "".into()));
+}
+
+#[test]
+fn strip_ansi_escapes_removes_control_strings() {
+    assert_eq!(
+        stripped("\x1b]0;title\x07a\x1b]8;;http://x\x1b\\link\x1b]8;;\x1b\\"),
+        ("alink".into(), "".into())
+    );
+    assert_eq!(
+        stripped("\x1bPdata\u{9c}b\u{9d}osc\x07c"),
+        ("bc".into(), "".into())
+    );
+    // An `ESC` which doesn't end the string starts the next sequence
+    assert_eq!(stripped("\x1b]title\x1b[1mx"), ("x".into(), "".into()));
+}
+
+#[test]
+fn strip_ansi_escapes_keeps_the_character_which_makes_a_sequence_malformed() {
+    assert_eq!(stripped("\x1b[1\u{e9}x"), ("\u{e9}x".into(), "".into()));
+    assert_eq!(stripped("\x1b\u{e9}"), ("\u{e9}".into(), "".into()));
+}
+
+#[test]
+fn strip_ansi_escapes_leaves_incomplete_sequences_unread() {
+    assert_eq!(stripped("ab\x1b[1;3"), ("ab".into(), "\x1b[1;3".into()));
+    assert_eq!(stripped("a\x1b]title"), ("a".into(), "\x1b]title".into()));
+    assert_eq!(stripped("\x1b"), ("".into(), "\x1b".into()));
+}
+
+#[test]
+fn strip_ansi_escapes_completes_sequences_split_between_chunks() {
+    use replacinator::stream::ReplacinatorStream;
+
+    let mut buf = [0; 16];
+    let mut stream = ReplacinatorStream::new(&mut buf);
+    let mut output = String::new();
+    let chunks: [&[u8]; 3] = [b"a\x1b[3", b"1mb\x1b", b"[0m"];
+    for (i, chunk) in chunks.iter().enumerate() {
+        stream.spare()[..chunk.len()].copy_from_slice(chunk);
+        stream.fill(chunk.len());
+        let (stripped, rest) = stream
+            .process(i == chunks.len() - 1, |src| {
+                sanitize::strip_ansi_escapes(src).to_owned()
+            })
+            .unwrap();
+        assert_eq!(rest, "");
+        output.push_str(&stripped);
+    }
+    assert_eq!(output, "ab");
+}
@@ -0,0 +1,178 @@
+//! Tests of searching the remainder of a `Replacinator`
+
+use std::ops::Range;
+
+use replacinator::{Pattern, Replacinator};
+
+#[test]
+fn find_char_and_find_str_give_offsets_in_the_remainder() {
+    let mut text = String::from("é=1; b=2");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.find_char('='), Some(2));
+        assert_eq!(src.find_str("; "), Some(4));
+        src.skip_char();
+        assert_eq!(src.find_char('='), Some(0));
+        assert_eq!(src.find_str("b="), Some(4));
+        assert_eq!(src.find_char('x'), None);
+        assert_eq!(src.find_str("=3"), None);
+        assert_eq!(src.find_str(""), Some(0));
+        // Nothing was read by searching
+        assert_eq!(src.remainder(), "=1; b=2");
+    });
+}
+
+#[test]
+fn contains_only_searches_the_remainder() {
+    let mut text = String::from("abc");
+    Replacinator::new_in(&mut text, |src| {
+        assert!(src.contains('a'));
+        src.skip_char();
+        assert!(!src.contains('a'));
+        assert!(src.contains("bc"));
+        assert!(src.contains(""));
+        assert!(src.skip_str("bc"));
+        assert!(!src.contains('c'));
+        assert!(src.contains(""));
+    });
+}
+
+#[test]
+fn read_until_reads_up_to_the_match_without_writing() {
+    let mut text = String::from("key=é=v");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.read_until('='), Some("key"));
+        assert_eq!((src.start(), src.remainder()), ("", "=é=v"));
+        // The match is at the start of the remainder, so nothing more is read
+        assert_eq!(src.read_until('='), Some(""));
+        src.read_char();
+        assert_eq!(src.read_until('='), Some("é"));
+        assert_eq!(src.read_until(';'), None);
+        assert_eq!(src.remainder(), "=v");
+    });
+}
+
+#[test]
+fn skip_until_keeps_the_text_up_to_the_match() {
+    let mut text = String::from("a, bé, c");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.skip_until(','), Some("a"));
+        src.discard_n(2);
+        assert_eq!(src.skip_until(','), Some("bé"));
+        assert_eq!(src.start(), "abé");
+        assert_eq!(src.skip_until('!'), None);
+        assert_eq!((src.start(), src.remainder()), ("abé", ", c"));
+    });
+}
+
+#[test]
+fn patterns_give_the_range_of_their_first_match() {
+    let haystack = "a, é; b";
+    assert_eq!('é'.find_in(haystack), Some(3..5));
+    assert_eq!("; ".find_in(haystack), Some(5..7));
+    #[cfg(feature = "alloc")]
+    assert_eq!((&String::from("b")).find_in(haystack), Some(7..8));
+    assert_eq!([';', ','].find_in(haystack), Some(1..2));
+    assert_eq!((&[';', 'é'][..]).find_in(haystack), Some(3..5));
+    assert_eq!((&['b']).find_in(haystack), Some(7..8));
+    assert_eq!((|c: char| !c.is_ascii()).find_in(haystack), Some(3..5));
+    assert_eq!("".find_in(haystack), Some(0..0));
+    assert_eq!('x'.find_in(haystack), None);
+    assert_eq!([].find_in(haystack), None);
+    assert_eq!("é".find_in(""), None);
+}
+
+/// A pattern which matches a run of ASCII digits
+struct Digits;
+
+impl Pattern for Digits {
+    fn find_in(&mut self, haystack: &str) -> Option<Range<usize>> {
+        let start = haystack.find(|c: char| c.is_ascii_digit())?;
+        let len = haystack[start..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(haystack.len() - start);
+        Some(start..start + len)
+    }
+}
+
+#[test]
+fn methods_accept_any_pattern() {
+    let mut text = String::from("id 1234 é 56");
+    Replacinator::new_in(&mut text, |src| {
+        assert!(src.contains(Digits));
+        let span = src.find(Digits).unwrap();
+        assert_eq!((span.start().byte(), span.end().byte()), (3, 7));
+        assert_eq!(src.skip_until(Digits), Some("id "));
+        let report = src.replace_literal(Digits, "#").unwrap();
+        assert_eq!(report.replacements(), 2);
+    });
+    assert_eq!(text.trim_end(), "id # é #");
+}
+
+#[test]
+fn closures_can_keep_state_between_characters() {
+    let mut text = String::from("a,b,c,d");
+    Replacinator::new_in(&mut text, |src| {
+        let mut commas = 0;
+        let third = |c: char| {
+            commas += usize::from(c == ',');
+            commas == 3
+        };
+        assert_eq!(src.read_until(third), Some("a,b,c"));
+    });
+}
+
+#[test]
+fn split_at_pattern_excludes_the_match() {
+    let mut text = String::from("key = é");
+    Replacinator::new_in(&mut text, |src| {
+        let (key, value) = src.split_at_pattern(" = ").unwrap();
+        assert_eq!(
+            (key.get().remainder(), value.get().remainder()),
+            ("key", "é")
+        );
+        assert_eq!(value.get().position().byte(), 6);
+    });
+    let mut text = String::from("no match");
+    Replacinator::new_in(&mut text, |src| {
+        assert!(src.split_at_pattern(Digits).is_none());
+        assert_eq!(src.remainder(), "no match");
+    });
+}
+
+#[test]
+fn peek_window_views_up_to_n_characters() {
+    let mut text = String::from("aé中b");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(src.peek_window(0), "");
+        assert_eq!(src.peek_window(2), "aé");
+        assert_eq!(src.peek_window(3), "aé中");
+        // Fewer than `n` characters remain, so the whole remainder is returned
+        assert_eq!(src.peek_window(10), "aé中b");
+        assert_eq!(src.peek_str(10), None);
+        src.skip_char();
+        assert_eq!(src.peek_window(1), "é");
+        assert_eq!(src.remainder(), "é中b");
+    });
+    let mut text = String::new();
+    Replacinator::new_in(&mut text, |src| assert_eq!(src.peek_window(1), ""));
+}
+
+#[test]
+fn skip_keyword_requires_a_word_boundary() {
+    let mut text = String::from("if (iffy) if_x ifé if");
+    Replacinator::new_in(&mut text, |src| {
+        assert!(src.skip_keyword("if"));
+        assert!(!src.skip_keyword("if"));
+        assert!(src.skip_str(" ("));
+        assert!(!src.skip_keyword("if"));
+        assert!(src.skip_str("iffy) "));
+        assert!(!src.skip_keyword("if"));
+        assert!(src.skip_str("if_x "));
+        assert!(!src.skip_keyword("if"));
+        assert!(src.skip_str("ifé "));
+        // The end of the text is a boundary
+        assert!(src.skip_keyword("if"));
+        assert!(!src.skip_keyword("if"));
+    });
+    assert_eq!(text, "if (iffy) if_x ifé if");
+}
@@ -0,0 +1,154 @@
+//! Tests of deserializing JSON with its strings unescaped in place
+#![cfg(feature = "serde")]
+
+use std::collections::BTreeMap;
+
+use replacinator::{
+    json,
+    serde::{self as json_serde, ErrorKind, StrDeserializer},
+    Replacinator,
+};
+use serde::Deserialize;
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct User<'a> {
+    name: &'a str,
+    age: u8,
+    #[serde(borrow)]
+    tags: Vec<&'a str>,
+    email: Option<&'a str>,
+}
+
+#[test]
+fn escaped_strings_are_borrowed_from_the_document() {
+    let mut document = String::from(
+        r#" { "name": "Zoë \"Z\"", "age": 30, "tags": ["a\/b", "😀"], "email": null } "#,
+    );
+    let user: User<'_> = json_serde::from_str(&mut document).unwrap();
+    assert_eq!(
+        user,
+        User {
+            name: "Zoë \"Z\"",
+            age: 30,
+            tags: vec!["a/b", "😀"],
+            email: None,
+        }
+    );
+}
+
+#[test]
+fn keys_are_unescaped_in_place() {
+    let mut document = String::from(r#"{"a\tb": 1, "é": 2}"#);
+    let map: BTreeMap<&str, u32> = json_serde::from_str(&mut document).unwrap();
+    assert_eq!(map, BTreeMap::from([("a\tb", 1), ("é", 2)]));
+}
+
+#[test]
+fn numbers_have_the_narrowest_type() {
+    let mut document = String::from("[0, -1, 18446744073709551615, -9223372036854775809, 1.5e2]");
+    let numbers: (u64, i64, u64, f64, f64) = json_serde::from_str(&mut document).unwrap();
+    assert_eq!(numbers, (0, -1, u64::MAX, -9223372036854775809.0, 150.0));
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+enum Shape<'a> {
+    Empty,
+    Circle(f64),
+    Label(&'a str, u8),
+    Rect { w: u32, h: u32 },
+}
+
+#[test]
+fn enums_are_strings_or_single_member_objects() {
+    let mut document = String::from(
+        r#"["Empty", {"Circle": 1.5}, { "Label" : ["x\n", 2] }, {"Rect": {"w": 1, "h": 2}}]"#,
+    );
+    let shapes: Vec<Shape<'_>> = json_serde::from_str(&mut document).unwrap();
+    assert_eq!(
+        shapes,
+        [
+            Shape::Empty,
+            Shape::Circle(1.5),
+            Shape::Label("x\n", 2),
+            Shape::Rect { w: 1, h: 2 }
+        ]
+    );
+}
+
+#[test]
+fn ignored_members_are_skipped() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Name<'a> {
+        name: &'a str,
+    }
+    let mut document = String::from(r#"{"extra": [{"deep": "\n"}, 1e3, true], "name": "n"}"#);
+    let name: Name<'_> = json_serde::from_str(&mut document).unwrap();
+    assert_eq!(name, Name { name: "n" });
+}
+
+#[test]
+fn invalid_json_is_reported() {
+    let error = |document: &str| {
+        let mut document = String::from(document);
+        json_serde::from_str::<Vec<u32>>(&mut document).unwrap_err()
+    };
+    let e = error("[1, 2,]");
+    assert_eq!(
+        (e.kind(), e.position().byte()),
+        (ErrorKind::Json(json::ErrorKind::UnexpectedChar(']')), 6)
+    );
+    assert_eq!(e.to_string(), "unexpected character ']' at byte 6");
+    let e = error("[1 2]");
+    assert_eq!(
+        e.kind(),
+        ErrorKind::Json(json::ErrorKind::UnexpectedChar('2'))
+    );
+    let e = error("[1]x");
+    assert_eq!(
+        (e.kind(), e.position().byte()),
+        (ErrorKind::Json(json::ErrorKind::UnexpectedChar('x')), 3)
+    );
+    assert_eq!(
+        error("[1").kind(),
+        ErrorKind::Json(json::ErrorKind::UnexpectedEnd)
+    );
+}
+
+#[test]
+fn errors_from_the_type_are_located() {
+    let mut document = String::from(r#"{"name": "n", "age": 300, "tags": []}"#);
+    let e = json_serde::from_str::<User<'_>>(&mut document).unwrap_err();
+    assert_eq!((e.kind(), e.position().byte()), (ErrorKind::Custom, 24));
+    let mut document = String::from(r#"{"name": "n", "age": 1}"#);
+    let e = json_serde::from_str::<User<'_>>(&mut document).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::MissingField("tags"));
+    assert_eq!(e.to_string(), "missing field `tags` at byte 22");
+    let mut document = String::from(r#"["a"]"#);
+    let e = json_serde::from_str::<Vec<u32>>(&mut document).unwrap_err();
+    assert_eq!((e.kind(), e.position().byte()), (ErrorKind::InvalidType, 4));
+    let mut document = String::from(r#"[1, 2]"#);
+    let e = json_serde::from_str::<(u32,)>(&mut document).unwrap_err();
+    assert_eq!(
+        e.kind(),
+        ErrorKind::Json(json::ErrorKind::UnexpectedChar(','))
+    );
+    let mut document = String::from(r#""Hexagon""#);
+    let e = json_serde::from_str::<Shape<'_>>(&mut document).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::UnknownVariant);
+}
+
+#[test]
+fn deserializer_reads_one_value_from_the_remainder() {
+    let mut text = String::from(r#"  "a\"b" rest"#);
+    Replacinator::new_in(&mut text, |src| {
+        let mut deserializer = StrDeserializer::new(src);
+        let value = <&str>::deserialize(&mut deserializer).unwrap();
+        assert_eq!(value, "a\"b");
+        let e = deserializer.end().unwrap_err();
+        assert_eq!(
+            e.kind(),
+            ErrorKind::Json(json::ErrorKind::UnexpectedChar('r'))
+        );
+        assert_eq!(src.remainder(), "rest");
+    });
+}
@@ -0,0 +1,121 @@
+//! Tests of transforming text which arrives in chunks
+
+use replacinator::{
+    stream::{ErrorKind, ReplacinatorStream},
+    Replacinator,
+};
+
+/// Feed `chunks` through a stream, removing spaces from each, and collect the output
+fn remove_spaces(buf: &mut [u8], chunks: &[&[u8]]) -> String {
+    let mut stream = ReplacinatorStream::new(buf);
+    let mut output = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        stream.spare()[..chunk.len()].copy_from_slice(chunk);
+        stream.fill(chunk.len());
+        let last = i == chunks.len() - 1;
+        let ((), text) = stream
+            .process(last, |src| {
+                while let Some(c) = src.read_char() {
+                    if c != ' ' {
+                        src.write_char(c);
+                    }
+                }
+            })
+            .unwrap();
+        output.push_str(text);
+    }
+    output
+}
+
+#[test]
+fn chunks_are_transformed_in_turn() {
+    let mut buf = [0; 16];
+    assert_eq!(remove_spaces(&mut buf, &[b"a b ", b"c d", b""]), "abcd");
+}
+
+#[test]
+fn split_characters_are_carried_to_the_next_chunk() {
+    let mut buf = [0; 16];
+    let e_acute = "é".as_bytes();
+    let chunks: &[&[u8]] = &[b"a \xE4", b"\xB8", b"\xAD ", &e_acute[..1], &e_acute[1..]];
+    assert_eq!(remove_spaces(&mut buf, chunks), "a中é");
+}
+
+#[test]
+fn unread_text_is_processed_with_the_next_chunk() {
+    let mut buf = [0; 16];
+    let mut stream = ReplacinatorStream::new(&mut buf);
+    let mut output = String::new();
+    for (chunk, last) in [(&b"a\r"[..], false), (b"\nb\r", false), (b"", true)] {
+        stream.spare()[..chunk.len()].copy_from_slice(chunk);
+        stream.fill(chunk.len());
+        let (_, text) = stream
+            .process(last, |src: &mut Replacinator<'_>| loop {
+                match src.peek() {
+                    // A final `\r` might start a `\r\n`, so leave it for the next chunk
+                    Some('\r') if !last && src.remainder().len() == 1 => break,
+                    Some('\r') => {
+                        src.discard_char();
+                        if src.peek() != Some('\n') {
+                            src.write_char('\n');
+                        }
+                    }
+                    Some(_) => {
+                        src.skip_char();
+                    }
+                    None => break,
+                }
+            })
+            .unwrap();
+        output.push_str(text);
+    }
+    assert_eq!(output, "a\nb\n");
+}
+
+#[test]
+fn spare_shrinks_as_text_is_carried_over() {
+    let mut buf = [0; 4];
+    let mut stream = ReplacinatorStream::new(&mut buf);
+    assert_eq!(stream.spare().len(), 4);
+    stream.spare()[..3].copy_from_slice(b"abc");
+    stream.fill(3);
+    let (_, text) = stream.process(false, |src| src.skip_char()).unwrap();
+    assert_eq!(text, "a");
+    assert_eq!(stream.spare().len(), 2);
+}
+
+#[test]
+#[should_panic]
+fn fill_beyond_the_spare_space_panics() {
+    let mut buf = [0; 2];
+    ReplacinatorStream::new(&mut buf).fill(3);
+}
+
+#[test]
+fn invalid_utf8_is_reported() {
+    let mut buf = [0; 8];
+    let mut stream = ReplacinatorStream::new(&mut buf);
+    stream.spare()[..3].copy_from_slice(b"ab\xFF");
+    stream.fill(3);
+    let error = stream.process(false, |_| ()).unwrap_err();
+    assert_eq!(
+        (error.kind(), error.position()),
+        (ErrorKind::InvalidUtf8, 2)
+    );
+    assert_eq!(error.to_string(), "invalid UTF-8 at byte 2");
+}
+
+#[test]
+fn a_character_split_at_the_end_of_the_last_chunk_is_reported() {
+    let mut buf = [0; 8];
+    let mut stream = ReplacinatorStream::new(&mut buf);
+    stream.spare()[..2].copy_from_slice(b"a\xC3");
+    stream.fill(2);
+    let (_, text) = stream.process(false, |src| src.skip_char()).unwrap();
+    assert_eq!(text, "a");
+    let error = stream.process(true, |_| ()).unwrap_err();
+    assert_eq!(
+        (error.kind(), error.position()),
+        (ErrorKind::IncompleteChar, 1)
+    );
+}
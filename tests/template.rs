@@ -0,0 +1,212 @@
+//! Tests of template substitution
+
+use replacinator::{
+    template::{self, ErrorKind},
+    Replacinator,
+};
+
+fn lookup(key: &str) -> Option<&'static str> {
+    match key {
+        "name" => Some("Ann"),
+        "long" => Some("a long value"),
+        "empty" => Some(""),
+        _ => None,
+    }
+}
+
+/// Render `template`, returning the rendered text
+fn rendered(template: &str) -> Result<String, template::Error> {
+    let mut template = String::from(template);
+    let len = template::render_in_place(&mut template, lookup)?;
+    assert!(template[len..].bytes().all(|b| b == b' '));
+    template.truncate(len);
+    Ok(template)
+}
+
+#[test]
+fn render_in_place_substitutes_placeholders() {
+    assert_eq!(rendered("Hi {name}!{empty}").unwrap(), "Hi Ann!");
+    assert_eq!(
+        rendered("{{name}} } {").unwrap_err().kind(),
+        ErrorKind::Unclosed
+    );
+    assert_eq!(rendered("{{name}} }").unwrap(), "{name} }");
+    assert_eq!(rendered("").unwrap(), "");
+}
+
+#[test]
+fn render_in_place_uses_space_freed_by_earlier_placeholders() {
+    assert_eq!(rendered("{long}").unwrap_err().kind(), ErrorKind::TooLong);
+    assert_eq!(rendered("{empty}{empty}{long}").unwrap(), "a long value");
+}
+
+#[test]
+fn render_in_place_reports_errors() {
+    let error = rendered("ab {missing}").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::UnknownKey);
+    assert_eq!(error.position().byte(), 3);
+    let error = rendered("{name} {long}").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::TooLong);
+    assert_eq!(error.position().byte(), 7);
+}
+
+/// Render the fixed-width slots in `template` after skipping `skip` characters, returning the
+/// text which was skipped and the rendered text
+fn rendered_slots(template: &str, skip: usize) -> Result<(String, String), template::Error> {
+    let mut template = String::from(template);
+    Replacinator::new_in(&mut template, |src| {
+        for _ in 0..skip {
+            src.skip_char();
+        }
+        let skipped = src.start().to_string();
+        let rendered = template::render(src, lookup)?;
+        Ok((skipped, rendered.to_string()))
+    })
+}
+
+#[test]
+fn render_pads_values_to_the_width_of_their_slot() {
+    let (_, text) = rendered_slots("Hi {name___}! {empty}", 0).unwrap();
+    assert_eq!(text, "Hi Ann      !        ");
+    let (_, text) = rendered_slots("{name}", 0).unwrap();
+    assert_eq!(text, "Ann   ");
+    let (_, text) = rendered_slots("no slots, just } braces", 0).unwrap();
+    assert_eq!(text, "no slots, just } braces");
+    let (_, text) = rendered_slots("", 0).unwrap();
+    assert_eq!(text, "");
+}
+
+#[test]
+fn render_keeps_the_text_around_each_slot_in_place() {
+    let template = "é {long________} é";
+    let (_, text) = rendered_slots(template, 0).unwrap();
+    assert_eq!(text.len(), template.len());
+    assert_eq!(text, "é a long value   é");
+    // A value may fill the whole slot, including its braces
+    let (_, text) = rendered_slots("{long______}|", 0).unwrap();
+    assert_eq!(text, "a long value|");
+}
+
+#[test]
+fn render_returns_only_the_text_after_the_first_section() {
+    let (skipped, text) = rendered_slots("ab {name}", 2).unwrap();
+    assert_eq!((skipped.as_str(), text.as_str()), ("ab", " Ann   "));
+}
+
+#[test]
+fn render_reports_errors() {
+    let error = rendered_slots("a {name", 0).unwrap_err();
+    assert_eq!(
+        (error.kind(), error.position().byte()),
+        (ErrorKind::Unclosed, 2)
+    );
+    let error = rendered_slots("{name} {missing}", 0).unwrap_err();
+    assert_eq!(
+        (error.kind(), error.position().byte()),
+        (ErrorKind::UnknownKey, 7)
+    );
+    let error = rendered_slots("{}", 0).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::UnknownKey);
+    let error = rendered_slots("é {long}", 0).unwrap_err();
+    assert_eq!(
+        (error.kind(), error.position().byte()),
+        (ErrorKind::TooLong, 3)
+    );
+}
+
+/// Expand the variables in `text`, returning the expanded text and the outcome
+fn expanded(
+    text: &str,
+    unresolved: template::Unresolved,
+) -> Result<(String, template::Expansion), template::Error> {
+    let mut text = String::from(text);
+    let expansion = template::expand_vars(&mut text, lookup, unresolved)?;
+    assert!(text[expansion.len()..].bytes().all(|b| b == b' '));
+    text.truncate(expansion.len());
+    Ok((text, expansion))
+}
+
+#[test]
+fn expand_vars_substitutes_both_forms() {
+    let (text, expansion) = expanded("$name: ${name}$empty.", template::Unresolved::Keep).unwrap();
+    assert_eq!(text, "Ann: Ann.");
+    assert_eq!(expansion.unresolved(), 0);
+    assert!(!expansion.is_empty());
+}
+
+#[test]
+fn expand_vars_leaves_dollars_which_are_not_references() {
+    let (text, expansion) = expanded("$5 $ $-x $", template::Unresolved::Strip).unwrap();
+    assert_eq!(text, "$5 $ $-x $");
+    assert_eq!(expansion.unresolved(), 0);
+}
+
+#[test]
+fn expand_vars_keeps_or_strips_unresolved_references() {
+    let text = "[$missing][${long}][$name_2]";
+    let (kept, expansion) = expanded(text, template::Unresolved::Keep).unwrap();
+    assert_eq!(kept, text);
+    assert_eq!(expansion.unresolved(), 3);
+    let (stripped, expansion) = expanded(text, template::Unresolved::Strip).unwrap();
+    // Stripping `$missing` frees enough space for the value of `long`
+    assert_eq!(stripped, "[][a long value][]");
+    assert_eq!(expansion.unresolved(), 2);
+}
+
+#[test]
+fn expand_vars_reports_unclosed_braces() {
+    let error = expanded("ab ${name", template::Unresolved::Keep).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Unclosed);
+    assert_eq!(error.position().byte(), 3);
+    let (text, expansion) = expanded("", template::Unresolved::Keep).unwrap();
+    assert_eq!(text, "");
+    assert!(expansion.is_empty());
+}
+
+/// Expand the variables in `text` after `headroom` spaces, returning the expanded text
+fn expanded_in_place(text: &str, headroom: usize) -> Result<String, template::Error> {
+    let mut text = format!("{:1$}{2}", "", headroom, text);
+    replacinator::Replacinator::new_in_with_headroom(&mut text, headroom, |src| {
+        template::expand_vars_in_place(src, lookup).map(|s| s.to_owned())
+    })
+    .unwrap()
+}
+
+#[test]
+fn expand_vars_in_place_substitutes_references() {
+    assert_eq!(
+        expanded_in_place("$name: ${name}$empty $5", 0).unwrap(),
+        "Ann: Ann $5"
+    );
+    assert_eq!(expanded_in_place("${long}", 5).unwrap(), "a long value");
+    assert_eq!(expanded_in_place("", 0).unwrap(), "");
+}
+
+#[test]
+fn expand_vars_in_place_reports_unresolved_references() {
+    let error = expanded_in_place("ab $missing", 0).unwrap_err();
+    assert_eq!(
+        (error.kind(), error.position().byte()),
+        (ErrorKind::UnknownKey, 3)
+    );
+    let error = expanded_in_place("ab ${name", 0).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Unclosed);
+}
+
+#[test]
+fn expand_vars_in_place_reports_how_much_room_is_needed() {
+    let error = expanded_in_place("x $long", 2).unwrap_err();
+    let (reference, extra) = match error.kind() {
+        ErrorKind::ValueTooLong { reference, extra } => (reference, extra),
+        kind => panic!("unexpected error {:?}", kind),
+    };
+    assert_eq!((reference.bytes(), extra), (4..9, 5));
+    assert_eq!(
+        error.to_string(),
+        "value needs 5 more bytes than are available at byte 4"
+    );
+    assert_eq!(
+        expanded_in_place("x $long", 2 + extra).unwrap(),
+        "x a long value"
+    );
+}
@@ -0,0 +1,39 @@
+//! Tests of checking transforms against a reference implementation
+#![cfg(feature = "std")]
+
+use replacinator::{testing::verify_transform, transforms};
+
+#[test]
+fn agreeing_transforms_pass() {
+    for input in ["", "a\r\nb\rc", "é\r"] {
+        verify_transform(input, transforms::normalize_newlines, |text| {
+            text.replace("\r\n", "\n").replace('\r', "\n")
+        });
+    }
+    verify_transform(
+        "ab",
+        |src| {
+            src.skip_char();
+            src.take_remainder()
+        },
+        |text| text[1..].to_string(),
+    );
+}
+
+#[test]
+#[should_panic(expected = "The transform disagreed with the reference for \"a\\r\\n\"")]
+fn disagreeing_transforms_panic() {
+    verify_transform("a\r\n", transforms::normalize_newlines, |text| {
+        text.to_string()
+    });
+}
+
+#[test]
+#[should_panic(expected = "The result is not in the string for \"x\"")]
+fn results_from_elsewhere_panic() {
+    verify_transform(
+        "x",
+        |_| Box::leak(String::from("x").into_boxed_str()),
+        |text| text.to_string(),
+    );
+}
@@ -0,0 +1,137 @@
+//! Tests of the general purpose text transformations
+
+use replacinator::text::{self, Terminator, Terminators};
+
+fn slug(text: &str) -> String {
+    let mut text = String::from(text);
+    let slug = text::slugify_in_place(&mut text).to_owned();
+    assert!(text[slug.len()..].bytes().all(|b| b == b' '));
+    slug
+}
+
+#[test]
+fn slugify_lowercases_and_separates_words() {
+    assert_eq!(slug("Hello, World!"), "hello-world");
+    assert_eq!(slug("  Crème Brûlée: A Recipe!"), "creme-brulee-a-recipe");
+    assert_eq!(slug("snake_case and 3 numbers"), "snake-case-and-3-numbers");
+}
+
+#[test]
+fn slugify_removes_other_characters() {
+    assert_eq!(slug("a→b 日本 c"), "ab-c");
+    assert_eq!(slug("---"), "");
+    assert_eq!(slug(""), "");
+}
+
+fn query(text: &str, stop_words: &[&str]) -> String {
+    let mut text = String::from(text);
+    let query = text::normalize_query(&mut text, |word| stop_words.contains(&word)).to_owned();
+    assert!(text[query.len()..].bytes().all(|b| b == b' '));
+    query
+}
+
+#[test]
+fn normalize_query_lowercases_and_separates_words() {
+    assert_eq!(
+        query("The Rust Borrow-Checker!", &["the"]),
+        "rust borrow checker"
+    );
+    assert_eq!(query("  Ünïcode   wörds 42 ", &[]), "ünïcode wörds 42");
+}
+
+#[test]
+fn normalize_query_keeps_letters_which_lowercase_to_longer_text() {
+    // 'İ' lowercases to two characters, so is kept unchanged
+    assert_eq!(query("İSTANBUL", &[]), "İstanbul");
+}
+
+#[test]
+fn normalize_query_removes_stop_words_anywhere() {
+    assert_eq!(query("a b a c a", &["a"]), "b c");
+    assert_eq!(query("a a", &["a"]), "");
+    assert_eq!(query("?!", &[]), "");
+}
+
+fn lines(text: &str, terminators: Terminators) -> Vec<(String, Terminator)> {
+    let mut text = String::from(text);
+    text::lines(&mut text, terminators)
+        .map(|line| (line.text().to_owned(), line.terminator()))
+        .collect()
+}
+
+#[test]
+fn lines_report_each_terminator() {
+    use Terminator::*;
+    let text = "a\r\nb\n\r\nc\rd";
+    let expected = [
+        ("a".to_owned(), CarriageReturnLineFeed),
+        ("b".to_owned(), LineFeed),
+        ("".to_owned(), CarriageReturnLineFeed),
+        ("c\rd".to_owned(), None),
+    ];
+    assert_eq!(lines(text, Terminators::Strip), expected);
+    let kept = lines(text, Terminators::Keep);
+    let joined: String = kept.iter().map(|(line, _)| line.as_str()).collect();
+    assert_eq!(joined, text);
+    assert_eq!(kept[0].0, "a\r\n");
+    let rebuilt: String = lines(text, Terminators::Strip)
+        .iter()
+        .flat_map(|(line, terminator)| [line.as_str(), terminator.as_str()])
+        .collect();
+    assert_eq!(rebuilt, text);
+}
+
+#[test]
+fn lines_skip_an_empty_final_line() {
+    assert_eq!(lines("", Terminators::Keep), []);
+    assert_eq!(
+        lines("\n", Terminators::Strip),
+        [("".to_owned(), Terminator::LineFeed)]
+    );
+    assert_eq!(
+        lines("é\r", Terminators::Strip),
+        [("é\r".to_owned(), Terminator::None)]
+    );
+}
+
+#[test]
+fn lines_can_be_edited_in_place() {
+    let mut text = String::from("ab\ncd\r\n");
+    for line in text::lines(&mut text, Terminators::Strip) {
+        line.into_text().make_ascii_uppercase();
+    }
+    assert_eq!(text, "AB\nCD\r\n");
+    let mut lines = text::lines(&mut text, Terminators::Keep);
+    assert_eq!(lines.nth(2).map(|line| line.text().len()), None);
+    assert!(lines.next().is_none());
+}
+
+/// Encode `text` as UTF-8, decode it as Latin-1, and encode it as UTF-8 again
+fn double_encode(text: &str) -> String {
+    text.bytes().map(char::from).collect()
+}
+
+#[test]
+fn double_encoding_is_detected() {
+    assert!(text::is_double_encoded(&double_encode("café")));
+    assert!(text::is_double_encoded("It\u{e2}\u{20ac}\u{2122}s"));
+    assert!(!text::is_double_encoded("café"));
+    assert!(!text::is_double_encoded("plain ASCII"));
+    assert!(!text::is_double_encoded(""));
+    // Every non-ASCII character must be part of a repairable sequence
+    let mixed = double_encode("é") + "é";
+    assert!(!text::is_double_encoded(&mixed));
+}
+
+#[test]
+fn every_layer_of_encoding_is_repaired() {
+    let mut twice = double_encode("naïve 中文");
+    assert_eq!(text::repair_double_encoding(&mut twice), "naïve 中文");
+    let mut three_times = double_encode(&double_encode("😀!"));
+    let len = three_times.len();
+    assert_eq!(text::repair_double_encoding(&mut three_times), "😀!");
+    assert_eq!(three_times.len(), len);
+    assert!(three_times["😀!".len()..].bytes().all(|b| b == b' '));
+    let mut correct = String::from("café");
+    assert_eq!(text::repair_double_encoding(&mut correct), "café");
+}
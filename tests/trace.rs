@@ -0,0 +1,146 @@
+//! Tests of tracing the cursor operations of a `Replacinator`
+#![cfg(feature = "trace")]
+
+use replacinator::{
+    trace::{Event, Tracer},
+    FillStrategy, Replacinator,
+};
+
+type Record<'e> = &'e mut dyn FnMut(&Event<'_>);
+
+/// Run `with` on a traced `Replacinator` for `text`, returning each event as text
+fn traced(text: &str, with: impl FnMut(&mut Replacinator<'_, Tracer<Record<'_>>>)) -> Vec<String> {
+    let mut events = Vec::new();
+    let mut record = |event: &Event<'_>| events.push(event.to_string());
+    let mut text = String::from(text);
+    Replacinator::with_tracer(&mut text, &mut record as Record<'_>, with);
+    events
+}
+
+#[test]
+fn reads_and_writes_are_traced_with_their_offsets() {
+    let events = traced("aé-b", |src| {
+        src.skip_char();
+        src.read_char();
+        src.write_char('e');
+        src.discard_char();
+        src.skip_char();
+    });
+    assert_eq!(
+        events,
+        [
+            "read \"a\" at byte 0",
+            "write \"a\" at byte 0",
+            "read \"é\" at byte 1",
+            "write \"e\" at byte 1",
+            "read \"-\" at byte 3",
+            "read \"b\" at byte 4",
+            "write \"b\" at byte 2",
+        ]
+    );
+}
+
+#[test]
+fn synchronising_and_rewinding_are_traced() {
+    let events = traced("abcd", |src| {
+        src.discard_char();
+        let checkpoint = src.checkpoint();
+        src.skip_char();
+        assert!(src.rewind(checkpoint));
+        src.synchronise_with(FillStrategy::Char('_'));
+    });
+    assert_eq!(
+        &events[3..],
+        [
+            "rewind to reading at byte 1 and writing at byte 0",
+            "synchronise with Char('_'), reading at byte 1 and writing at byte 1",
+        ]
+    );
+}
+
+#[test]
+fn taking_is_traced() {
+    let events = traced("a,b", |src| {
+        src.skip_char();
+        src.discard_char();
+        src.take_start();
+        src.skip_char();
+        src.take_remainder();
+    });
+    assert_eq!(
+        &events[2..],
+        [
+            "read \",\" at byte 1",
+            "take start \"a\"",
+            "read \"b\" at byte 2",
+            if cfg!(feature = "compact_on_take") {
+                "write \"b\" at byte 1"
+            } else {
+                "write \"b\" at byte 2"
+            },
+            "take remainder \"\"",
+        ]
+    );
+}
+
+#[test]
+fn events_are_passed_to_the_callback() {
+    let mut reads = Vec::new();
+    let mut text = String::from("xé");
+    Replacinator::with_tracer(
+        &mut text,
+        |event: &Event<'_>| {
+            if let Event::Read { offset, text } = *event {
+                reads.push((offset, text.to_owned()));
+            }
+        },
+        |src| {
+            src.skip_char();
+            src.discard_char();
+        },
+    );
+    assert_eq!(reads, [(0, "x".into()), (1, "é".into())]);
+}
+
+#[test]
+#[cfg(feature = "log")]
+fn events_can_be_forwarded_to_log() {
+    use std::sync::Mutex;
+
+    use log::{Log, Metadata, Record};
+
+    /// A logger which keeps the messages logged with the `replacinator` target
+    struct Capture(Mutex<Vec<String>>);
+
+    impl Log for Capture {
+        fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+            metadata.target() == "replacinator"
+        }
+
+        fn log(&self, record: &Record<'_>) {
+            if self.enabled(record.metadata()) {
+                let message = format!("{} {}", record.level(), record.args());
+                self.0.lock().unwrap().push(message);
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+    log::set_logger(&CAPTURE).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+    let mut text = String::from("aé");
+    Replacinator::with_tracer(&mut text, replacinator::trace::log, |src| {
+        src.skip_char();
+        src.discard_char();
+    });
+    assert_eq!(
+        *CAPTURE.0.lock().unwrap(),
+        [
+            "TRACE read \"a\" at byte 0",
+            "TRACE write \"a\" at byte 0",
+            "TRACE read \"é\" at byte 1",
+        ]
+    );
+}
@@ -0,0 +1,577 @@
+//! Tests of the general purpose transforms
+
+use replacinator::transforms::{self, Align, CommentSyntax, StringSyntax, Trim, Whitespace};
+use replacinator::{ReplaceErrorKind, Replacinator};
+
+/// Apply `transform` to the whole of `text`, returning the text it returns
+fn apply(
+    text: &str,
+    mut transform: impl for<'a> FnMut(&mut Replacinator<'a>) -> &'a mut str,
+) -> String {
+    let mut text = String::from(text);
+    Replacinator::new_in(&mut text, |src| transform(src).to_owned())
+}
+
+#[test]
+fn collapse_whitespace_collapses_runs_and_trims() {
+    let collapse = |text| {
+        apply(text, |src| {
+            transforms::collapse_whitespace(src, Whitespace::Ascii)
+        })
+    };
+    assert_eq!(collapse("  a \t\n b  c "), "a b c");
+    assert_eq!(collapse("one"), "one");
+    assert_eq!(collapse(" \n "), "");
+    assert_eq!(collapse(""), "");
+}
+
+#[test]
+fn collapse_whitespace_can_use_unicode_whitespace() {
+    let text = "a\u{a0}\u{2003} b";
+    assert_eq!(
+        apply(text, |src| transforms::collapse_whitespace(
+            src,
+            Whitespace::Ascii
+        )),
+        "a\u{a0}\u{2003} b"
+    );
+    assert_eq!(
+        apply(text, |src| transforms::collapse_whitespace(
+            src,
+            Whitespace::Unicode
+        )),
+        "a b"
+    );
+}
+
+#[test]
+fn trim_in_place_removes_whitespace_from_the_chosen_ends() {
+    let trim = |text, ends| {
+        apply(text, |src| {
+            transforms::trim_in_place(src, ends, Whitespace::Ascii)
+        })
+    };
+    assert_eq!(trim(" \t a b \n", Trim::Start), "a b \n");
+    assert_eq!(trim(" \t a b \n", Trim::End), " \t a b");
+    assert_eq!(trim(" \t a b \n", Trim::Both), "a b");
+    assert_eq!(trim("   ", Trim::Both), "");
+    assert_eq!(
+        apply("\u{a0}a\u{2003}", |src| {
+            transforms::trim_in_place(src, Trim::Both, Whitespace::Unicode)
+        }),
+        "a"
+    );
+}
+
+#[test]
+fn squeeze_collapses_runs_of_the_same_match() {
+    let squeeze = |text| apply(text, |src| transforms::squeeze(src, [' ', '-']));
+    assert_eq!(squeeze("a  b--c -d"), "a b-c -d");
+    assert_eq!(squeeze("  -- "), " - ");
+    assert_eq!(squeeze("abc"), "abc");
+    assert_eq!(squeeze(""), "");
+}
+
+#[test]
+fn squeeze_handles_longer_and_empty_matches() {
+    assert_eq!(
+        apply("abababc ab", |src| transforms::squeeze(src, "ab")),
+        "abc ab"
+    );
+    assert_eq!(
+        apply("\u{e9}\u{e9}\u{e9}x", |src| transforms::squeeze(
+            src, '\u{e9}'
+        )),
+        "\u{e9}x"
+    );
+    assert_eq!(
+        apply("aa  bb", |src| transforms::squeeze(src, "")),
+        "aa  bb"
+    );
+}
+
+#[test]
+fn pad_to_fills_the_freed_space() {
+    let pad = |text, width, align| {
+        let mut text = String::from(text);
+        Replacinator::new_in(&mut text, |src| {
+            src.discard_while(|c| c == ' ');
+            let (padded, missed) = transforms::pad_to(src, width, align, '.');
+            (padded.to_owned(), missed)
+        })
+    };
+    assert_eq!(pad("    ab", 5, Align::Left), ("ab...".to_owned(), 0));
+    assert_eq!(pad("    ab", 5, Align::Right), ("...ab".to_owned(), 0));
+    assert_eq!(pad("    ab", 5, Align::Center), (".ab..".to_owned(), 0));
+    assert_eq!(pad("    ab", 8, Align::Right), ("....ab".to_owned(), 2));
+    assert_eq!(pad("ab", 4, Align::Left), ("ab".to_owned(), 2));
+    assert_eq!(pad("  abc", 2, Align::Left), ("abc".to_owned(), 0));
+}
+
+#[test]
+fn strip_comments_keeps_strings_and_separates_blocks() {
+    let strip = |text| {
+        apply(text, |src| {
+            transforms::strip_comments(src, CommentSyntax::C).unwrap()
+        })
+    };
+    assert_eq!(
+        strip("a/* x */b // line\n\"/* s\\\" */\" '//' c"),
+        "a b \n\"/* s\\\" */\" '//' c"
+    );
+    assert_eq!(strip("x /* unterminated"), "x  ");
+    assert_eq!(
+        strip("\"unterminated // string"),
+        "\"unterminated // string"
+    );
+    assert_eq!(strip(""), "");
+}
+
+#[test]
+fn strip_comments_uses_the_given_syntax() {
+    let hash = |text| {
+        apply(text, |src| {
+            transforms::strip_comments(src, CommentSyntax::HASH).unwrap()
+        })
+    };
+    assert_eq!(hash("key = 'C:\\' # path\n# whole line"), "key = 'C:\\' \n");
+    let sql = CommentSyntax {
+        line: &["--"],
+        block: &[("{-", "-}")],
+        strings: &[StringSyntax {
+            quote: '\'',
+            escape: None,
+        }],
+    };
+    let text = "select '--' {- a -}-- b\n1";
+    assert_eq!(
+        apply(text, |src| transforms::strip_comments(src, sql).unwrap()),
+        "select '--'  \n1"
+    );
+}
+
+#[test]
+fn strip_comments_rejects_empty_markers() {
+    let empty_line = CommentSyntax {
+        line: &["//", ""],
+        ..CommentSyntax::C
+    };
+    let empty_block = CommentSyntax {
+        block: &[("", "*/")],
+        ..CommentSyntax::C
+    };
+    for syntax in [empty_line, empty_block] {
+        let mut text = String::from("a // b");
+        Replacinator::new_in(&mut text, |src| {
+            let error = transforms::strip_comments(src, syntax).unwrap_err();
+            assert_eq!(error, transforms::EmptyMarker);
+            assert_eq!(src.remainder(), "a // b");
+        });
+        assert_eq!(text, "a // b");
+    }
+    let bare = CommentSyntax {
+        line: &[""],
+        block: &[("", "")],
+        strings: &[],
+    };
+    let mut text = String::from("a");
+    Replacinator::new_in(&mut text, |src| {
+        assert!(transforms::strip_comments(src, bare).is_err());
+    });
+}
+
+#[test]
+fn normalize_newlines_converts_crlf_and_lone_cr() {
+    let normalize = |text| apply(text, transforms::normalize_newlines);
+    assert_eq!(normalize("a\r\nb\rc\nd"), "a\nb\nc\nd");
+    assert_eq!(normalize("\r\r\n\n\r"), "\n\n\n\n");
+    assert_eq!(normalize("é\r\n中"), "é\n中");
+}
+
+#[test]
+fn normalize_newlines_leaves_text_without_carriage_returns() {
+    let mut text = String::from("no\nreturns");
+    let normalized = Replacinator::new_in(&mut text, |src| {
+        let normalized = transforms::normalize_newlines(src);
+        assert_eq!(src.remainder(), "");
+        normalized.to_owned()
+    });
+    assert_eq!(normalized, "no\nreturns");
+    assert_eq!(text, "no\nreturns");
+    assert_eq!(apply("", transforms::normalize_newlines), "");
+}
+
+#[test]
+fn normalize_newlines_pads_the_freed_space() {
+    let mut text = String::from("a\r\nb\r\n");
+    Replacinator::new_in(&mut text, |src| {
+        assert_eq!(transforms::normalize_newlines(src), "a\nb\n");
+    });
+    assert_eq!(text.len(), 6);
+    assert!(text.starts_with("a\nb\n"));
+}
+
+#[test]
+fn replace_many_prefers_the_longest_then_the_earliest_pattern() {
+    let pairs = [
+        ("ab", "1"),
+        ("abc", "2"),
+        ("ab", "3"),
+        ("é", "e"),
+        ("b", ""),
+    ];
+    let mut text = String::from("abcabxébé");
+    let report = Replacinator::new_in(&mut text, |src| {
+        transforms::replace_many_in_place(src, &pairs).unwrap()
+    });
+    assert_eq!(text, "21xee      ");
+    assert_eq!(
+        (
+            report.read_len(),
+            report.written_len(),
+            report.replacements()
+        ),
+        (11, 5, 5)
+    );
+}
+
+#[test]
+fn replace_many_without_matches_reads_the_remainder() {
+    let mut text = String::from("xyz");
+    let report = Replacinator::new_in(&mut text, |src| {
+        src.skip_char();
+        let report = transforms::replace_many_in_place(src, &[("x", "")]).unwrap();
+        assert_eq!(src.remainder(), "");
+        report
+    });
+    assert_eq!(text, "xyz");
+    assert_eq!((report.read_len(), report.replacements()), (2, 0));
+    let mut text = String::new();
+    let report = Replacinator::new_in(&mut text, |src| {
+        transforms::replace_many_in_place(src, &[]).unwrap()
+    });
+    assert_eq!(report.replacements(), 0);
+}
+
+#[test]
+fn replace_many_checks_every_pair_first() {
+    let mut text = String::from("xa");
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_char();
+        let error = transforms::replace_many_in_place(src, &[("a", ""), ("b", "bb")]).unwrap_err();
+        let kind = ReplaceErrorKind::TooLong {
+            match_len: 1,
+            replacement_len: 2,
+        };
+        assert_eq!((error.kind(), error.position().byte()), (kind, 1));
+        assert_eq!(src.remainder(), "a");
+    });
+    assert_eq!(text, "xa");
+}
+
+#[test]
+fn replace_many_of_the_empty_string_is_an_error() {
+    let mut text = String::from("a");
+    let error = Replacinator::new_in(&mut text, |src| {
+        transforms::replace_many_in_place(src, &[("a", ""), ("", "")]).unwrap_err()
+    });
+    assert_eq!(error.kind(), ReplaceErrorKind::EmptyMatch);
+    assert_eq!(text, "a");
+}
+
+fn punctuation_spacing(text: &str) -> (String, usize) {
+    let mut text = String::from(text);
+    Replacinator::new_in(&mut text, |src| {
+        let (text, missed) = transforms::fix_punctuation_spacing(src);
+        (text.to_owned(), missed)
+    })
+}
+
+#[test]
+fn punctuation_spacing_removes_spaces_before_punctuation() {
+    assert_eq!(
+        punctuation_spacing("Well  ,  yes ;it is\t!  Done . \n  Next   line  \r\nend"),
+        ("Well, yes; it is! Done.\n  Next line\r\nend".to_owned(), 0)
+    );
+}
+
+#[test]
+fn punctuation_spacing_adds_spaces_only_where_there_is_room() {
+    assert_eq!(punctuation_spacing("a,b;c"), ("a,b;c".to_owned(), 2));
+    assert_eq!(punctuation_spacing("a  ,b"), ("a, b".to_owned(), 0));
+    // `.` and digits don't get a space after them
+    assert_eq!(punctuation_spacing("x  .y 1 ,2"), ("x.y 1,2".to_owned(), 0));
+    assert_eq!(punctuation_spacing(""), (String::new(), 0));
+}
+
+#[test]
+fn downgrade_punctuation_uses_ascii_equivalents() {
+    let text = "\u{201C}It\u{2019}s\u{201D} \u{2014} 1\u{2013}2\u{2026} \u{201A}x\u{2032}";
+    let downgraded = apply(text, transforms::downgrade_punctuation);
+    assert_eq!(downgraded, "\"It's\" -- 1-2... 'x'");
+}
+
+#[test]
+fn downgrade_punctuation_leaves_other_text_unchanged() {
+    let text = "plain 'ascii' - \"text\" with é and «guillemets»";
+    assert_eq!(apply(text, transforms::downgrade_punctuation), text);
+    assert_eq!(apply("", transforms::downgrade_punctuation), "");
+    let mut text = String::from("\u{2026}\u{2026}");
+    Replacinator::new_in(&mut text, |src| {
+        src.skip_char();
+        transforms::downgrade_punctuation(src);
+    });
+    assert_eq!(text, "\u{2026}...");
+}
+
+#[test]
+fn expand_ligatures_spells_out_each_ligature() {
+    let text = "e\u{FB03}cient \u{FB02}oor \u{FB00} \u{FB01} \u{FB04} \u{FB05} \u{FB06}";
+    assert_eq!(
+        apply(text, transforms::expand_ligatures),
+        "efficient floor ff fi ffl \u{17F}t st"
+    );
+    let mut text = String::from("\u{FB01}");
+    Replacinator::new_in(&mut text, |src| transforms::expand_ligatures(src).len());
+    assert_eq!(text, "fi ");
+}
+
+#[test]
+fn expand_ligatures_leaves_other_presentation_forms() {
+    // The Armenian and Hebrew ligatures in the same block are kept
+    let text = "\u{FB13} \u{FB4F} fi æ";
+    assert_eq!(apply(text, transforms::expand_ligatures), text);
+    assert_eq!(apply("", transforms::expand_ligatures), "");
+}
+
+#[test]
+fn strip_invisible_removes_formatting_characters() {
+    let text = "soft\u{AD}ware\u{200B} \u{FEFF}x\u{2062}y\u{34F}\u{2060}";
+    assert_eq!(apply(text, transforms::strip_invisible), "software xy");
+}
+
+#[test]
+fn strip_invisible_keeps_joiners_and_visible_text() {
+    let text = "👩\u{200D}💻 \u{200C}ab\u{2065}";
+    assert_eq!(apply(text, transforms::strip_invisible), text);
+    assert_eq!(apply("\u{AD}\u{AD}", transforms::strip_invisible), "");
+}
+
+#[test]
+#[cfg(feature = "latin_composition")]
+fn compose_latin_composes_letters_and_marks() {
+    let text = "e\u{301}te\u{301} Z\u{30C}a\u{300}";
+    assert_eq!(apply(text, transforms::compose_latin), "été Žà");
+    assert_eq!(apply("", transforms::compose_latin), "");
+    // Only the first mark is next to the letter
+    assert_eq!(
+        apply("a\u{301}\u{301}", transforms::compose_latin),
+        "á\u{301}"
+    );
+}
+
+#[test]
+#[cfg(feature = "latin_composition")]
+fn compose_latin_leaves_what_it_cannot_compose() {
+    // In NFC, these become `ẹ`, `ẹ́` and `Å`, but they are outside what `compose_latin` handles
+    for text in [
+        "e\u{323}",
+        "é\u{323}",
+        "\u{212B}",
+        "\u{301}a",
+        "q\u{301}",
+        "plain",
+    ] {
+        assert_eq!(apply(text, transforms::compose_latin), text);
+    }
+}
+
+#[test]
+#[cfg(feature = "unicode-normalization")]
+fn normalize_nfc_in_place_composes_reorders_and_replaces_singletons() {
+    let normalize = |text| apply(text, |src| transforms::normalize_nfc_in_place(src).unwrap());
+    assert_eq!(normalize("e\u{301}te\u{301} Z\u{30C}"), "été Ž");
+    // Outside of Latin-1 and Latin Extended-A
+    assert_eq!(normalize("e\u{323}\u{301}"), "\u{1EB9}\u{301}");
+    // Marks are put in canonical order before composing
+    assert_eq!(normalize("a\u{301}\u{323}"), "\u{1EA1}\u{301}");
+    assert_eq!(normalize("\u{212B} \u{2126}"), "Å Ω");
+    assert_eq!(normalize("\u{1100}\u{1161}\u{11A8}"), "\u{AC01}");
+    assert_eq!(normalize("\u{301}a plain é"), "\u{301}a plain é");
+    assert_eq!(normalize(""), "");
+}
+
+#[test]
+#[cfg(feature = "unicode-normalization")]
+fn normalize_nfc_in_place_reports_text_which_would_grow() {
+    // U+0958 is excluded from composition, so NFC decomposes it into two characters
+    let mut text = String::from("e\u{301} \u{958}x \u{958}\u{93C}");
+    let positions = Replacinator::new_in(&mut text, |src| {
+        let error = transforms::normalize_nfc_in_place(src).unwrap_err();
+        assert_eq!(src.take_start(), "é \u{958}x \u{958}\u{93C}");
+        error
+            .positions()
+            .iter()
+            .map(|position| (position.byte(), position.char()))
+            .collect::<Vec<_>>()
+    });
+    // Each run starts at the space, as NFC changes U+0958, so a run can't start with it
+    assert_eq!(positions, [(3, 2), (8, 5)]);
+    assert_eq!(text, "é \u{958}x \u{958}\u{93C} ");
+}
+
+#[test]
+#[cfg(feature = "deunicode")]
+fn deunicode_in_place_replaces_latin_letters_and_reports_the_rest() {
+    let deunicode = |text| {
+        let mut text = String::from(text);
+        let mut unmapped = Vec::new();
+        let text = Replacinator::new_in(&mut text, |src| {
+            transforms::deunicode_in_place(src, |c, position| {
+                unmapped.push((c, position.byte(), position.char()))
+            })
+            .to_owned()
+        });
+        (text, unmapped)
+    };
+    assert_eq!(
+        deunicode("Cr\u{e8}me Stra\u{df}e \u{c6}sir"),
+        ("Creme Strasse AEsir".to_owned(), vec![])
+    );
+    assert_eq!(deunicode("e\u{301}\u{142}"), ("el".to_owned(), vec![]));
+    assert_eq!(
+        deunicode("\u{3b1}\u{e9} \u{1f600}"),
+        (
+            "\u{3b1}e \u{1f600}".to_owned(),
+            vec![('\u{3b1}', 0, 0), ('\u{1f600}', 5, 3)]
+        )
+    );
+    assert_eq!(deunicode(""), (String::new(), vec![]));
+}
+
+#[test]
+fn repair_mojibake_restores_the_intended_characters() {
+    let text = "It\u{e2}\u{20ac}\u{2122}s caf\u{c3}\u{a9} \u{f0}\u{178}\u{2dc}\u{20ac}!";
+    assert_eq!(apply(text, transforms::repair_mojibake), "It’s café 😀!");
+    // Bytes which Windows-1252 leaves undefined were decoded as C1 controls
+    assert_eq!(apply("\u{c3}\u{81}", transforms::repair_mojibake), "Á");
+}
+
+#[test]
+fn repair_mojibake_leaves_other_text_unchanged() {
+    let text = "plain ASCII, Ã alone, é, and ’";
+    assert_eq!(apply(text, transforms::repair_mojibake), text);
+    // A lead byte followed by too few continuation bytes isn't repaired
+    assert_eq!(
+        apply("\u{e2}\u{20ac}x", transforms::repair_mojibake),
+        "\u{e2}\u{20ac}x"
+    );
+    assert_eq!(apply("", transforms::repair_mojibake), "");
+}
+
+#[test]
+fn pipeline_matches_applying_each_pass_in_turn() {
+    use transforms::Pass;
+
+    let text = " \u{201C}e\u{FB03}cient\u{201D}\r\n\r\u{AD} \u{2026}x\u{200B}  ";
+    let passes = [
+        Pass::StripInvisible,
+        Pass::ExpandLigatures,
+        Pass::DowngradePunctuation,
+        Pass::NormalizeNewlines,
+        Pass::CollapseWhitespace(Whitespace::Ascii),
+    ];
+    let mut sequential = String::from(text);
+    for pass in passes {
+        sequential = apply(&sequential, |src| {
+            transforms::pipeline(src, &[pass]).unwrap()
+        });
+    }
+    assert_eq!(sequential, "\"efficient\" ...x");
+    assert_eq!(
+        apply(text, |src| transforms::pipeline(src, &passes).unwrap()),
+        sequential
+    );
+}
+
+#[test]
+fn pipeline_passes_apply_in_order() {
+    use transforms::Pass;
+
+    let collapse = Pass::CollapseWhitespace(Whitespace::Ascii);
+    let text = "a\r\n\r\nb";
+    assert_eq!(
+        apply(text, |src| transforms::pipeline(
+            src,
+            &[Pass::NormalizeNewlines, collapse]
+        )
+        .unwrap()),
+        "a b"
+    );
+    let text = "a\u{AD} b";
+    assert_eq!(
+        apply(text, |src| transforms::pipeline(
+            src,
+            &[collapse, Pass::StripInvisible]
+        )
+        .unwrap()),
+        "a b"
+    );
+    assert_eq!(
+        apply(text, |src| transforms::pipeline(src, &[]).unwrap()),
+        text
+    );
+    assert_eq!(
+        apply("", |src| transforms::pipeline(src, &[collapse]).unwrap()),
+        ""
+    );
+}
+
+#[test]
+fn pipeline_of_too_many_passes_is_an_error() {
+    let passes = [transforms::Pass::StripInvisible; transforms::MAX_PASSES + 1];
+    let mut text = String::from("a\u{AD}");
+    Replacinator::new_in(&mut text, |src| {
+        let error = transforms::pipeline(src, &passes).unwrap_err();
+        assert_eq!(error.passes(), transforms::MAX_PASSES + 1);
+        assert_eq!(src.remainder(), "a\u{AD}");
+    });
+    assert_eq!(text, "a\u{AD}");
+}
+
+fn truncated(text: &str, max_bytes: usize) -> (String, usize, String) {
+    let mut text = String::from(text);
+    let (result, cut) = Replacinator::new_in(&mut text, |src| {
+        let (result, cut) = transforms::truncate_with_ellipsis(src, max_bytes);
+        (result.to_owned(), cut)
+    });
+    (result, cut, text)
+}
+
+#[test]
+fn truncate_with_ellipsis_marks_where_text_was_cut() {
+    assert_eq!(
+        truncated("hello world", 8),
+        ("hello…".into(), 6, "hello…   ".into())
+    );
+    assert_eq!(truncated("ééé", 5), ("é…".into(), 4, "é… ".into()));
+    assert_eq!(truncated("ééé", 6), ("ééé".into(), 0, "ééé".into()));
+    assert_eq!(truncated("", 0), ("".into(), 0, "".into()));
+}
+
+#[test]
+fn truncate_with_ellipsis_omits_it_when_there_is_no_room() {
+    assert_eq!(truncated("ééé", 2), ("é".into(), 4, "é    ".into()));
+    assert_eq!(truncated("abcd", 3), ("…".into(), 4, "… ".into()));
+    assert_eq!(truncated("abc", 0), ("".into(), 3, "   ".into()));
+}
+
+#[test]
+fn truncate_with_ellipsis_keeps_combining_marks_with_segmentation() {
+    let (result, cut, _) = truncated("ae\u{301}bc", 5);
+    if cfg!(feature = "unicode-segmentation") {
+        assert_eq!((result.as_str(), cut), ("a…", 5));
+    } else {
+        assert_eq!((result.as_str(), cut), ("ae…", 4));
+    }
+}
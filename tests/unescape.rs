@@ -0,0 +1,230 @@
+//! Tests of escape sequence decoding
+
+use replacinator::unescape::{self, ErrorKind, UnicodeEscape};
+use replacinator::Replacinator;
+
+/// Read one escape of the form `escape` from the start of `text`, returning it and what remains
+fn read(escape: UnicodeEscape<'_>, text: &str) -> (Result<char, unescape::Error>, String) {
+    let mut text = String::from(text);
+    Replacinator::new_in(&mut text, |src| {
+        (escape.read(src), src.remainder().to_owned())
+    })
+}
+
+#[test]
+fn unicode_escape_reads_a_fixed_number_of_digits() {
+    assert_eq!(read(UnicodeEscape::new(2), "e9z"), (Ok('é'), "z".into()));
+    assert_eq!(read(UnicodeEscape::new(4), "20AC1"), (Ok('€'), "1".into()));
+    assert_eq!(
+        read(UnicodeEscape::new(8), "0001f600"),
+        (Ok('😀'), "".into())
+    );
+}
+
+#[test]
+fn unicode_escape_combines_surrogate_pairs() {
+    assert_eq!(
+        read(UnicodeEscape::JSON, r"d83d\ude00!"),
+        (Ok('😀'), "!".into())
+    );
+    let escape = UnicodeEscape::new(4).with_surrogate_pairs("%u");
+    assert_eq!(read(escape, "D83D%uDE00"), (Ok('😀'), "".into()));
+}
+
+#[test]
+fn unicode_escape_rejects_lone_surrogates() {
+    let (result, _) = read(UnicodeEscape::JSON, r"d83d!");
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::LoneSurrogate(0xd83d));
+    let (result, _) = read(UnicodeEscape::JSON, r"d83dA");
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::LoneSurrogate(0xd83d));
+    let (result, _) = read(UnicodeEscape::JSON, r"de00");
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::LoneSurrogate(0xde00));
+    // Without surrogate pairs, a high surrogate is also lone
+    let (result, _) = read(UnicodeEscape::new(4), r"d83d\ude00");
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::LoneSurrogate(0xd83d));
+}
+
+#[test]
+fn unicode_escape_reports_invalid_digits_and_code_points() {
+    let (result, rest) = read(UnicodeEscape::new(4), "12x4");
+    let error = result.unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidDigit('x'));
+    assert_eq!(error.position().byte(), 2);
+    assert_eq!(rest, "x4");
+    let (result, _) = read(UnicodeEscape::new(4), "12");
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::UnexpectedEnd);
+    let (result, _) = read(UnicodeEscape::new(8), "00110000");
+    assert_eq!(
+        result.unwrap_err().kind(),
+        ErrorKind::InvalidCodepoint(0x110000)
+    );
+    let (result, _) = read(UnicodeEscape::new(9), "fffffffff");
+    assert_eq!(
+        result.unwrap_err().kind(),
+        ErrorKind::InvalidCodepoint(u32::MAX)
+    );
+}
+
+/// Unescape `text` with `unescape`, returning the result and what remains
+fn unescaped(
+    text: &str,
+    mut unescape: impl for<'a> FnMut(&mut Replacinator<'a>) -> Result<&'a mut str, unescape::Error>,
+) -> (Result<String, unescape::Error>, String) {
+    let mut text = String::from(text);
+    Replacinator::new_in(&mut text, |src| {
+        let result = unescape(src).map(|s| s.to_owned());
+        (result, src.remainder().to_owned())
+    })
+}
+
+#[test]
+fn unescape_json_decodes_escapes_until_a_quote() {
+    let (result, rest) = unescaped(r#"a\"\\\/\b\f\n\r\té😀" tail"#, unescape::unescape_json);
+    assert_eq!(result.unwrap(), "a\"\\/\x08\x0c\n\r\té😀");
+    assert_eq!(rest, "\" tail");
+    let (result, rest) = unescaped("", unescape::unescape_json);
+    assert_eq!(result.unwrap(), "");
+    assert_eq!(rest, "");
+}
+
+#[test]
+fn unescape_json_reports_errors() {
+    let (result, _) = unescaped(r"ab\q", unescape::unescape_json);
+    let error = result.unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidEscape('q'));
+    assert_eq!(error.position().byte(), 4);
+    let (result, _) = unescaped("a\nb", unescape::unescape_json);
+    assert_eq!(
+        result.unwrap_err().kind(),
+        ErrorKind::ControlCharacter('\n')
+    );
+    let (result, _) = unescaped(r"a\", unescape::unescape_json);
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::UnexpectedEnd);
+    // Characters from U+007F onwards don't need escaping
+    let (result, _) = unescaped("\u{7f}\u{85}", unescape::unescape_json);
+    assert_eq!(result.unwrap(), "\u{7f}\u{85}");
+}
+
+#[test]
+fn unescape_c_decodes_octal_hex_and_universal_names() {
+    let (result, _) = unescaped(
+        r#"\a\v\?\'\0\101\1012\x41é\U0001F600"#,
+        unescape::unescape_c,
+    );
+    assert_eq!(result.unwrap(), "\x07\x0b?'\0AA2Aé😀");
+    let (result, _) = unescaped(r"\777", unescape::unescape_c);
+    assert_eq!(result.unwrap(), "\u{1ff}");
+    let (result, _) = unescaped(r"\x4", unescape::unescape_c);
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::UnexpectedEnd);
+    let (result, _) = unescaped(r"\8", unescape::unescape_c);
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidEscape('8'));
+}
+
+#[test]
+fn unescape_backslashes_removes_each_backslash() {
+    let (result, rest) = unescaped(r#"a\b\\c\"d"e"#, unescape::unescape_backslashes);
+    assert_eq!(result.unwrap(), r#"ab\c"d"#);
+    assert_eq!(rest, "\"e");
+    let (result, _) = unescaped(r"a\", unescape::unescape_backslashes);
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::UnexpectedEnd);
+}
+
+#[test]
+fn consume_quoted_removes_both_quotes() {
+    let (result, rest) = unescaped(r#""a\"é" tail"#, |src| {
+        unescape::consume_quoted(src, '"', unescape::unescape_json)
+    });
+    assert_eq!(result.unwrap(), "a\"é");
+    assert_eq!(rest, " tail");
+    let (result, rest) = unescaped("'it''s''',", |src| {
+        unescape::consume_quoted(src, '\'', |src| unescape::unescape_doubled(src, '\''))
+    });
+    assert_eq!(result.unwrap(), "it's'");
+    assert_eq!(rest, ",");
+}
+
+#[test]
+fn consume_quoted_reports_missing_quotes() {
+    let json = |src: &mut Replacinator<'_>| {
+        unescape::consume_quoted(src, '"', unescape::unescape_json).map(|s| s.len())
+    };
+    let mut text = String::from("a\"");
+    let error = Replacinator::new_in(&mut text, json).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::ExpectedQuote('"'));
+    assert_eq!(error.position().byte(), 0);
+    assert_eq!(error.to_string(), "expected opening '\"' at byte 0");
+    let mut text = String::from("\"abc");
+    let error = Replacinator::new_in(&mut text, json).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Unterminated);
+    assert_eq!(error.position().byte(), 4);
+    let mut text = String::from(r#""a\q""#);
+    let error = Replacinator::new_in(&mut text, json).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidEscape('q'));
+}
+
+#[test]
+fn unescape_doubled_stops_at_a_single_quote() {
+    let (result, rest) = unescaped(r#"a""b"c"#, |src| unescape::unescape_doubled(src, '"'));
+    assert_eq!(result.unwrap(), r#"a"b"#);
+    assert_eq!(rest, "\"c");
+    let (result, rest) = unescaped("中''", |src| unescape::unescape_doubled(src, '\''));
+    assert_eq!(result.unwrap(), "中'");
+    assert_eq!(rest, "");
+    let (result, rest) = unescaped("", |src| unescape::unescape_doubled(src, '\''));
+    assert_eq!(result.unwrap(), "");
+    assert_eq!(rest, "");
+}
+
+const CARET: unescape::EscapeTable<'static> = replacinator::unescape_rules!('^' => {
+    "n" => '\n',
+    "^" => '^',
+    "l" => 'l',
+    "lt" => '<',
+    "e9" => 'é',
+});
+
+#[test]
+fn escape_tables_decode_the_longest_sequence() {
+    let (result, remainder) = unescaped("a^lt^l^^b^e9^n", |src| CARET.unescape(src));
+    assert_eq!(result.unwrap(), "a<l^bé\n");
+    assert_eq!(remainder, "");
+    let (result, _) = unescaped("plain", |src| CARET.unescape(src));
+    assert_eq!(result.unwrap(), "plain");
+    let (result, _) = unescaped("", |src| CARET.unescape(src));
+    assert_eq!(result.unwrap(), "");
+}
+
+#[test]
+fn escape_tables_stop_at_the_terminator() {
+    let table = CARET.terminated_by('"');
+    let (result, remainder) = unescaped("a^^\"b^n", |src| table.unescape(src));
+    assert_eq!(result.unwrap(), "a^");
+    assert_eq!(remainder, "\"b^n");
+    // An escaped terminator doesn't stop decoding
+    let table = replacinator::unescape_rules!('\\' => { "\"" => '"' }).terminated_by('"');
+    let (result, remainder) = unescaped(r#"a\"b"c"#, |src| table.unescape(src));
+    assert_eq!(result.unwrap(), "a\"b");
+    assert_eq!(remainder, "\"c");
+}
+
+#[test]
+fn escape_tables_report_unknown_escapes() {
+    let (result, _) = unescaped("ab^x", |src| CARET.unescape(src));
+    let error = result.unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidEscape('x'));
+    assert_eq!(error.position().byte(), 3);
+    let (result, _) = unescaped("ab^", |src| CARET.unescape(src));
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::UnexpectedEnd);
+}
+
+#[test]
+#[should_panic(expected = "An escape sequence cannot decode to a longer character")]
+fn escape_tables_cannot_grow_the_text() {
+    unescape::EscapeTable::new('^', &[("x", '€')]);
+}
+
+#[test]
+#[should_panic(expected = "An escape sequence cannot be empty")]
+fn escape_tables_cannot_have_empty_sequences() {
+    unescape::EscapeTable::new('^', &[("", 'x')]);
+}